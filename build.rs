@@ -0,0 +1,48 @@
+//! Generates the fixture list for the `test-corpus` feature.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    if env::var_os("CARGO_FEATURE_TEST_CORPUS").is_none() {
+        return;
+    }
+
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let fixtures = Path::new(&manifest_dir).join("tests").join("fixtures");
+    println!("cargo:rerun-if-changed={}", fixtures.display());
+
+    // Entries are sorted so the list is the same on every platform
+    let mut entries = Vec::new();
+    let mut dirs: Vec<_> = fs::read_dir(&fixtures)
+        .expect("failed to read tests/fixtures")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort();
+    for dir in dirs {
+        println!("cargo:rerun-if-changed={}", dir.display());
+        let hint = dir.file_name().unwrap().to_str().unwrap().to_string();
+        let mut files: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "mmd"))
+            .collect();
+        files.sort();
+        for file in files {
+            let stem = file.file_stem().unwrap().to_str().unwrap();
+            entries.push(format!(
+                "    CorpusEntry {{ name: {:?}, diagram_type_hint: {:?}, source: include_str!({:?}) }},\n",
+                format!("{}/{}", hint, stem),
+                hint,
+                file.display().to_string()
+            ));
+        }
+    }
+
+    let code = format!("static ENTRIES: &[CorpusEntry] = &[\n{}];\n", entries.concat());
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("corpus.rs");
+    fs::write(out, code).unwrap();
+}
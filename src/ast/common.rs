@@ -2,6 +2,9 @@
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use super::LineIndex;
 
 /// A span in the source code (byte offsets).
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
@@ -56,6 +59,23 @@ impl Span {
     pub fn text<'a>(&self, source: &'a str) -> &'a str {
         &source[self.start..self.end.min(source.len())]
     }
+
+    /// Returns the LSP position `(line, character)` of the span start.
+    ///
+    /// Both values are 0-based and the character counts UTF-16 code units,
+    /// as required by the Language Server Protocol.
+    ///
+    /// This indexes `source` on every call; to convert many spans, build a
+    /// [`LineIndex`] once and use [`LineIndex::lsp_position`].
+    pub fn to_lsp_position(&self, source: &str) -> (usize, usize) {
+        LineIndex::new(source).lsp_position(source, self.start)
+    }
+
+    /// Returns the LSP start and end positions of the span.
+    pub fn to_lsp_range(&self, source: &str) -> ((usize, usize), (usize, usize)) {
+        let index = LineIndex::new(source);
+        (index.lsp_position(source, self.start), index.lsp_position(source, self.end))
+    }
 }
 
 /// Kind of AST node.
@@ -109,6 +129,8 @@ pub enum NodeKind {
     Relationship,
     /// Generic statement.
     Statement,
+    /// A statement that could not be parsed.
+    Error,
     /// Unknown/other node type.
     Other(String),
 }
@@ -128,6 +150,53 @@ impl NodeKind {
     }
 }
 
+/// Property names shared by many node kinds.
+///
+/// Accepted wherever a property name is, so the typed accessors on
+/// [`AstNode`] and the code setting the properties name them the same way.
+/// Other properties are still set and read by their string names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PropertyKey {
+    /// `id`: the identifier of a node, participant, task or other element.
+    Id,
+    /// `label`: the displayed label.
+    Label,
+    /// `text`: the text of a message, note or similar statement.
+    Text,
+    /// `from`: the source of an edge, message or relationship.
+    From,
+    /// `to`: the target of an edge, message or relationship.
+    To,
+    /// `type`: the statement type, or the declared type of an element.
+    Type,
+}
+
+impl PropertyKey {
+    /// Returns the property name, as serialized.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PropertyKey::Id => "id",
+            PropertyKey::Label => "label",
+            PropertyKey::Text => "text",
+            PropertyKey::From => "from",
+            PropertyKey::To => "to",
+            PropertyKey::Type => "type",
+        }
+    }
+}
+
+impl AsRef<str> for PropertyKey {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<PropertyKey> for String {
+    fn from(key: PropertyKey) -> Self {
+        key.as_str().to_string()
+    }
+}
+
 /// A node in the AST.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AstNode {
@@ -135,6 +204,11 @@ pub struct AstNode {
     pub kind: NodeKind,
     /// The span in the source code.
     pub span: Span,
+    /// The node's position in source order among all nodes of its [`Ast`],
+    /// counting from 0. Set by [`Ast::new`]; identical input always gets
+    /// the same numbering.
+    #[serde(default)]
+    pub order: u32,
     /// The raw text of this node (optional).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub text: Option<String>,
@@ -155,6 +229,7 @@ impl AstNode {
         Self {
             kind,
             span,
+            order: 0,
             text: None,
             children: Vec::new(),
             fields: HashMap::new(),
@@ -167,6 +242,7 @@ impl AstNode {
         Self {
             kind,
             span,
+            order: 0,
             text: Some(text.into()),
             children: Vec::new(),
             fields: HashMap::new(),
@@ -205,8 +281,39 @@ impl AstNode {
     }
 
     /// Gets a property by name.
-    pub fn get_property(&self, name: &str) -> Option<&str> {
-        self.properties.get(name).map(|s| s.as_str())
+    pub fn get_property(&self, name: impl AsRef<str>) -> Option<&str> {
+        self.properties.get(name.as_ref()).map(|s| s.as_str())
+    }
+
+    /// Returns the `id` property.
+    pub fn id(&self) -> Option<&str> {
+        self.get_property(PropertyKey::Id)
+    }
+
+    /// Returns the `label` property.
+    pub fn label(&self) -> Option<&str> {
+        self.get_property(PropertyKey::Label)
+    }
+
+    /// Returns the `text` property, falling back to the `label` property.
+    pub fn text_or_label(&self) -> Option<&str> {
+        self.get_property(PropertyKey::Text).or_else(|| self.label())
+    }
+
+    /// Returns the `from` property.
+    pub fn from(&self) -> Option<&str> {
+        self.get_property(PropertyKey::From)
+    }
+
+    /// Returns the `to` property.
+    pub fn to(&self) -> Option<&str> {
+        self.get_property(PropertyKey::To)
+    }
+
+    /// Returns the `type` property: a statement's type, such as `click` or
+    /// `linkStyle`, or an element's declared type.
+    pub fn statement_type(&self) -> Option<&str> {
+        self.get_property(PropertyKey::Type)
     }
 }
 
@@ -218,17 +325,55 @@ pub struct Ast {
     /// The source text (for reference).
     #[serde(skip)]
     pub source: String,
+    /// The line index of `source`, built on first use.
+    #[serde(skip)]
+    line_index: OnceLock<LineIndex>,
 }
 
 impl Ast {
-    /// Creates a new AST with the given root node.
+    /// Creates a new AST with the given root node, numbering its nodes in
+    /// source order (see [`AstNode::order`]).
+    ///
+    /// Nodes are numbered by where their spans start. A node comes before
+    /// the nodes nested in it that start at the same offset, and otherwise
+    /// ties keep the order of the tree.
     pub fn new(root: AstNode, source: impl Into<String>) -> Self {
+        let mut root = root;
+        let mut starts = Vec::new();
+        visit_mut(&mut root, &mut |node| starts.push(node.span.start));
+        let mut by_start: Vec<usize> = (0..starts.len()).collect();
+        by_start.sort_by_key(|&i| starts[i]);
+        let mut orders = vec![0; starts.len()];
+        for (order, i) in by_start.into_iter().enumerate() {
+            orders[i] = order as u32;
+        }
+        let mut orders = orders.into_iter();
+        visit_mut(&mut root, &mut |node| node.order = orders.next().unwrap_or_default());
         Self {
             root,
             source: source.into(),
+            line_index: OnceLock::new(),
         }
     }
 
+    /// Returns the line index of the source, building it on the first call.
+    pub fn line_index(&self) -> &LineIndex {
+        self.line_index.get_or_init(|| LineIndex::new(&self.source))
+    }
+
+    /// Returns every node of the tree, the root included, sorted by
+    /// [`AstNode::order`].
+    pub fn iter_source_order(&self) -> impl Iterator<Item = &AstNode> {
+        fn collect<'a>(node: &'a AstNode, nodes: &mut Vec<&'a AstNode>) {
+            nodes.push(node);
+            node.children.iter().chain(node.fields.values()).for_each(|child| collect(child, nodes));
+        }
+        let mut nodes = Vec::new();
+        collect(&self.root, &mut nodes);
+        nodes.sort_by_key(|node| node.order);
+        nodes.into_iter()
+    }
+
     /// Gets the text for a span.
     pub fn text_for_span(&self, span: &Span) -> &str {
         span.text(&self.source)
@@ -250,7 +395,7 @@ impl Ast {
         for child in &node.children {
             self.walk_node(child, depth + 1, visitor);
         }
-        for (_, field) in &node.fields {
+        for field in node.fields.values() {
             self.walk_node(field, depth + 1, visitor);
         }
     }
@@ -263,10 +408,73 @@ impl Ast {
     }
 }
 
+/// Calls `visit` on `node` and then its descendants, children before fields
+/// and fields by name.
+fn visit_mut(node: &mut AstNode, visit: &mut impl FnMut(&mut AstNode)) {
+    visit(node);
+    for child in &mut node.children {
+        visit_mut(child, visit);
+    }
+    let mut fields: Vec<_> = node.fields.iter_mut().collect();
+    fields.sort_by(|a, b| a.0.cmp(b.0));
+    for (_, field) in fields {
+        visit_mut(field, visit);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_typed_accessors_match_string_properties() {
+        let mut node = AstNode::new(NodeKind::Message, Span::new(0, 0));
+        node.add_property("id", "m1");
+        node.add_property("from", "A");
+        node.add_property("to", "B");
+        node.add_property("type", "message");
+        node.add_property("label", "Hi");
+        assert_eq!(node.id(), Some("m1"));
+        assert_eq!((node.from(), node.to()), (Some("A"), Some("B")));
+        assert_eq!(node.statement_type(), Some("message"));
+        assert_eq!(node.label(), Some("Hi"));
+        assert_eq!(node.text_or_label(), Some("Hi"));
+        node.add_property("text", "Hello");
+        assert_eq!(node.text_or_label(), Some("Hello"));
+    }
+
+    #[test]
+    fn test_property_keys_keep_serialized_names() {
+        let mut node = AstNode::new(NodeKind::Node, Span::new(0, 0));
+        node.add_property(PropertyKey::Id, "A");
+        node.add_property(PropertyKey::Label, "Start");
+        assert_eq!(node.get_property("id"), Some("A"));
+        let json = serde_json::to_value(&node).unwrap();
+        assert_eq!(json["properties"], serde_json::json!({"id": "A", "label": "Start"}));
+    }
+
+    #[test]
+    fn test_lsp_position_counts_utf16_units() {
+        let source = "graph TD\n    A[😀] --> ???";
+        let offset = source.find("???").unwrap();
+        let span = Span::new(offset, offset + 3);
+        let line_start = source.find('\n').unwrap() + 1;
+        let line_prefix = &source[line_start..offset];
+
+        // The 4-byte emoji is 1 char but 2 UTF-16 code units.
+        assert_eq!(line_prefix.len(), 16);
+        assert_eq!(line_prefix.chars().count(), 13);
+        assert_eq!(span.to_lsp_position(source), (1, 14));
+        assert_eq!(span.to_lsp_range(source), ((1, 14), (1, 17)));
+    }
+
+    #[test]
+    fn test_lsp_position_ascii() {
+        let source = "graph TD\n    A --> B";
+        assert_eq!(Span::new(0, 5).to_lsp_position(source), (0, 0));
+        assert_eq!(Span::new(13, 14).to_lsp_position(source), (1, 4));
+    }
+
     #[test]
     fn test_span() {
         let span = Span::new(0, 10);
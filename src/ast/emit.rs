@@ -0,0 +1,285 @@
+//! Re-emitting AST nodes as Mermaid source.
+//!
+//! Each supported node kind emits its canonical fragment: spacing is
+//! normalized, but labels are emitted as written (`raw_label`), so escapes
+//! and entity codes survive. Kinds without an emitter produce a Mermaid
+//! comment instead, so the output stays parseable.
+
+use super::{AstNode, NodeKind};
+
+impl AstNode {
+    /// Emits the canonical Mermaid fragment for this node.
+    ///
+    /// Supports the flowchart and sequence diagram node kinds. Containers
+    /// emit only their opening line, except a sequence `box` or `rect`,
+    /// which owns its statements. Other kinds emit `%% unsupported: <kind>`.
+    pub fn to_mermaid(&self) -> String {
+        let prop = |key| self.get_property(key).unwrap_or_default();
+        match &self.kind {
+            NodeKind::Root => {
+                let mut lines = Vec::new();
+                for child in &self.children {
+                    let fragment = child.to_mermaid();
+                    if child.kind == NodeKind::DiagramDeclaration {
+                        lines.push(fragment);
+                    } else {
+                        lines.extend(fragment.lines().map(|line| format!("    {}", line)));
+                    }
+                }
+                lines.join("\n")
+            }
+            NodeKind::DiagramDeclaration => {
+                let keyword = self.text.as_deref().unwrap_or_default();
+                match self.get_property("direction").and_then(direction_keyword) {
+                    Some(direction) => format!("{} {}", keyword, direction),
+                    None => keyword.to_string(),
+                }
+            }
+            NodeKind::Node if self.get_property("metadata").is_some() => {
+                format!("{}@{{ {} }}", prop("id"), prop("metadata"))
+            }
+            NodeKind::Node => {
+                let id = prop("id");
+                let label = self.get_property("raw_label").or(self.get_property("label"));
+                let node = match (label, shape_delimiters(prop("shape"))) {
+                    (Some(label), Some((open, close))) => format!("{}{}{}{}", id, open, label, close),
+                    _ => id.to_string(),
+                };
+                match self.get_property("class") {
+                    Some(class) => format!("{}:::{}", node, class),
+                    None => node,
+                }
+            }
+            NodeKind::Edge if self.get_property("link_type").is_some() => {
+                let mut link = String::new();
+                if let Some(edge_id) = self.get_property("edge_id") {
+                    link.push_str(&format!("{}@", edge_id));
+                }
+                link.push_str(link_arrow(prop("link_type")));
+                match self.get_property("arrow_head") {
+                    Some("circle") => link.replace_range(link.len() - 1.., "o"),
+                    Some("cross") => link.replace_range(link.len() - 1.., "x"),
+                    _ => {}
+                }
+                if let Some(label) = self.get_property("label") {
+                    link.push_str(&format!("|{}|", label.replace('|', "\\|")));
+                }
+                let targets = node_list(&self.children);
+                if targets.is_empty() {
+                    link
+                } else {
+                    format!("{} {}", link, targets)
+                }
+            }
+            NodeKind::Edge => {
+                let links = self.children.iter().filter(|c| c.kind != NodeKind::Node).map(AstNode::to_mermaid);
+                std::iter::once(node_list(&self.children))
+                    .chain(links)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            }
+            NodeKind::Subgraph => {
+                let id = prop("id");
+                match self.get_property("raw_label") {
+                    Some(label) if label != id => format!("subgraph {} [{}]", id, label),
+                    _ => format!("subgraph {}", id),
+                }
+            }
+            NodeKind::Style => format!("style {} {}", prop("node_id"), prop("styles")),
+            NodeKind::ClassDef => format!("classDef {} {}", prop("name"), prop("styles")),
+            NodeKind::Participant => {
+                let mut participant = match self.get_property("metadata") {
+                    Some(metadata) => format!("{} {}@{{ {} }}", prop("keyword"), prop("id"), metadata),
+                    None => format!("{} {}", prop("type"), prop("id")),
+                };
+                if let Some(alias) = self.get_property("alias") {
+                    participant.push_str(&format!(" as {}", alias));
+                }
+                participant
+            }
+            NodeKind::Message => {
+                let activation = match self.get_property("activation") {
+                    Some("activate") => "+",
+                    Some("deactivate") => "-",
+                    _ => "",
+                };
+                format!(
+                    "{}{}{}{}: {}",
+                    prop("from"),
+                    message_arrow(prop("arrow_type")),
+                    activation,
+                    prop("to"),
+                    prop("text")
+                )
+            }
+            NodeKind::Note => format!("Note {}: {}", prop("position"), prop("text")),
+            NodeKind::Activation => format!("{} {}", prop("action"), prop("participant")),
+            NodeKind::Loop => with_label("loop", prop("label")),
+            NodeKind::Alt => with_label("alt", prop("label")),
+            NodeKind::Statement => self.statement_to_mermaid(),
+            kind => unsupported(kind),
+        }
+    }
+
+    /// Emits a `Statement` node, keyed by its `type` property.
+    fn statement_to_mermaid(&self) -> String {
+        let prop = |key| self.get_property(key).unwrap_or_default();
+        match prop("type") {
+            "end" => "end".to_string(),
+            "direction" => format!("direction {}", prop("direction")),
+            "linkStyle" => format!("linkStyle {} {}", prop("indices"), prop("styles")),
+            "click" => format!("click {} {}", prop("node_id"), prop("definition")),
+            "edge_data" => format!("{}@{{ {} }}", prop("edge_id"), prop("data")),
+            "node_list" => node_list(&self.children),
+            // Skipped as unknown, so kept as written
+            "unknown" => self.text.clone().unwrap_or_default(),
+            "title" => with_label("title", prop("value")),
+            "autonumber" => with_label("autonumber", prop("value")),
+            "accTitle" => format!("accTitle: {}", prop("value")),
+            "accDescr" if prop("value").contains('\n') => {
+                format!("accDescr {{\n{}\n}}", prop("value"))
+            }
+            "accDescr" => format!("accDescr: {}", prop("value")),
+            block @ ("box" | "rect") => {
+                let header = format!("{} {}", prop("color"), prop("label"));
+                let mut lines = vec![with_label(block, &header)];
+                for child in &self.children {
+                    lines.extend(child.to_mermaid().lines().map(|line| format!("    {}", line)));
+                }
+                lines.push("end".to_string());
+                lines.join("\n")
+            }
+            block @ ("opt" | "par" | "and" | "else" | "critical" | "option" | "break") => {
+                with_label(block, prop("label"))
+            }
+            _ => unsupported(&self.kind),
+        }
+    }
+}
+
+/// Emits the `Node` nodes among `children`, joined by `&`.
+fn node_list(children: &[AstNode]) -> String {
+    children
+        .iter()
+        .filter(|c| c.kind == NodeKind::Node)
+        .map(AstNode::to_mermaid)
+        .collect::<Vec<_>>()
+        .join(" & ")
+}
+
+/// Joins a keyword and an optional trailing label.
+fn with_label(keyword: &str, label: &str) -> String {
+    let label = label.trim();
+    if label.is_empty() {
+        keyword.to_string()
+    } else {
+        format!("{} {}", keyword, label)
+    }
+}
+
+fn unsupported(kind: &NodeKind) -> String {
+    format!("%% unsupported: {:?}", kind)
+}
+
+/// Maps a flowchart `Direction` name to its keyword.
+fn direction_keyword(name: &str) -> Option<&'static str> {
+    match name {
+        "TopToBottom" => Some("TB"),
+        "BottomToTop" => Some("BT"),
+        "LeftToRight" => Some("LR"),
+        "RightToLeft" => Some("RL"),
+        _ => None,
+    }
+}
+
+/// Maps a flowchart `NodeShape` name to its opening and closing delimiters.
+fn shape_delimiters(name: &str) -> Option<(&'static str, &'static str)> {
+    Some(match name {
+        "Rectangle" => ("[", "]"),
+        "RoundedRect" => ("(", ")"),
+        "Stadium" => ("([", "])"),
+        "Subroutine" => ("[[", "]]"),
+        "Cylindrical" => ("[(", ")]"),
+        "Circle" => ("((", "))"),
+        "Asymmetric" => (">", "]"),
+        "Rhombus" => ("{", "}"),
+        "Hexagon" => ("{{", "}}"),
+        "Parallelogram" => ("[/", "/]"),
+        "ParallelogramAlt" => ("[\\", "\\]"),
+        "Trapezoid" => ("[/", "\\]"),
+        "TrapezoidAlt" => ("[\\", "/]"),
+        "DoubleCircle" => ("(((", ")))"),
+        _ => return None,
+    })
+}
+
+/// Maps a flowchart `LinkType` name to its arrow.
+fn link_arrow(name: &str) -> &'static str {
+    match name {
+        "Open" => "---",
+        "Dotted" => "-.-",
+        "DottedArrow" => "-.->",
+        "Thick" => "===",
+        "ThickArrow" => "==>",
+        "Invisible" => "~~~",
+        _ => "-->",
+    }
+}
+
+/// Maps a sequence `ArrowType` name to its arrow.
+fn message_arrow(name: &str) -> &'static str {
+    match name {
+        "Dotted" => "-->>",
+        "SolidLine" => "->",
+        "DottedLine" => "-->",
+        "SolidCross" => "-x",
+        "DottedCross" => "--x",
+        "SolidAsync" => "-)",
+        "DottedAsync" => "--)",
+        _ => "->>",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    /// Parses `code` and returns the emitted fragment and source text of
+    /// each top-level statement after the declaration.
+    fn round_trip(code: &str) -> Vec<(String, String)> {
+        let result = crate::parse(code, None);
+        let ast = result.ast.expect("parse failed");
+        ast.root.children[1..]
+            .iter()
+            .map(|node| (node.to_mermaid(), node.span.text(code).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_flowchart_round_trip() {
+        let code = "flowchart LR\n    A[Start] -->|go| B((Mid)) -.-> C\n    D{\"Say #quot;hi#quot;\"} ==> E([Done])\n    E e1@~~~ F@{ shape: card }\n    G:::foo ~~~ H[Hidden]:::my-class\n    subgraph s1 [Title]\n    direction TB\n    end";
+        for (emitted, source) in round_trip(code) {
+            assert_eq!(emitted, source);
+        }
+    }
+
+    #[test]
+    fn test_sequence_round_trip() {
+        let code = "sequenceDiagram\n    participant A as Alice\n    actor B\n    A->>+B: Hello\n    B--)A: Hi\n    Note right of A: Thinking\n    loop Every minute\n    end\n    deactivate B";
+        for (emitted, source) in round_trip(code) {
+            assert_eq!(emitted, source);
+        }
+    }
+
+    #[test]
+    fn test_declaration_and_root() {
+        let result = crate::parse("graph TD\n    A-->B", None);
+        let ast = result.ast.unwrap();
+        assert_eq!(ast.root.to_mermaid(), "graph TB\n    A --> B");
+    }
+
+    #[test]
+    fn test_unsupported_kind() {
+        let result = crate::parse("stateDiagram-v2\n    [*] --> A", None);
+        let transition = result.ast.unwrap().root.children[1].clone();
+        assert!(transition.to_mermaid().starts_with("%% unsupported:"));
+    }
+}
@@ -0,0 +1,111 @@
+//! Line and column lookups for byte offsets.
+
+use super::{Position, Range, Span};
+
+/// The offsets where each line of a source starts, for turning byte offsets
+/// into lines and columns without rescanning the source for every lookup.
+///
+/// Building the index is one pass over the source. A lookup binary-searches
+/// for the line and then only scans that line up to the offset, so resolving
+/// many diagnostics stays linear in the size of the source.
+///
+/// ```rust
+/// use mermaid_linter::LineIndex;
+///
+/// let source = "graph TD\n    A --> B";
+/// let index = LineIndex::new(source);
+/// let position = index.position(source, 14);
+/// assert_eq!((position.line, position.column), (2, 6));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineIndex {
+    /// The byte offset of the start of each line; the first is always 0.
+    line_starts: Vec<usize>,
+    /// The length of the source.
+    len: usize,
+}
+
+impl LineIndex {
+    /// Indexes the lines of `source`.
+    pub fn new(source: &str) -> Self {
+        let newlines = source.bytes().enumerate().filter(|&(_, b)| b == b'\n').map(|(i, _)| i + 1);
+        Self {
+            line_starts: std::iter::once(0).chain(newlines).collect(),
+            len: source.len(),
+        }
+    }
+
+    /// Returns the number of lines, counting an empty line after a final
+    /// newline.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Returns the 0-based line holding `offset`. Offsets past the end are
+    /// on the last line.
+    pub fn line(&self, offset: usize) -> usize {
+        self.line_starts.partition_point(|&start| start <= offset.min(self.len)) - 1
+    }
+
+    /// Returns the span of the 0-based `line`, without its newline.
+    pub fn line_span(&self, line: usize) -> Span {
+        let start = self.line_starts.get(line).copied().unwrap_or(self.len);
+        let end = self.line_starts.get(line + 1).map_or(self.len, |next| next - 1);
+        Span::new(start, end)
+    }
+
+    /// Returns the 1-based line and column of `offset`, counting columns in
+    /// characters. `source` must be the text the index was built from.
+    pub fn position(&self, source: &str, offset: usize) -> Position {
+        let offset = offset.min(self.len);
+        let line = self.line(offset);
+        let column = self.line_prefix(source, line, offset).chars().count() + 1;
+        Position::new(line + 1, column, offset)
+    }
+
+    /// Returns the positions of the start and end of `span`.
+    pub fn range(&self, source: &str, span: Span) -> Range {
+        Range::new(self.position(source, span.start), self.position(source, span.end))
+    }
+
+    /// Returns the LSP position `(line, character)` of `offset`: both 0-based,
+    /// with the character counted in UTF-16 code units.
+    pub fn lsp_position(&self, source: &str, offset: usize) -> (usize, usize) {
+        let line = self.line(offset);
+        let column = self.line_prefix(source, line, offset.min(self.len)).encode_utf16().count();
+        (line, column)
+    }
+
+    /// Returns the text of `line` before `offset`, including a character
+    /// that `offset` falls inside of.
+    fn line_prefix<'a>(&self, source: &'a str, line: usize, offset: usize) -> &'a str {
+        let mut end = offset;
+        while !source.is_char_boundary(end) {
+            end += 1;
+        }
+        &source[self.line_starts[line]..end]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookups_match_a_linear_scan() {
+        let source = "graph TD\n    A[😀] --> B\n\n    B --> C\n";
+        let index = LineIndex::new(source);
+        assert_eq!(index.line_count(), 5);
+        assert_eq!(index.line_span(1).text(source), "    A[😀] --> B");
+        assert_eq!(index.line_span(2), Span::new(27, 27));
+        assert_eq!(index.line_span(4), Span::new(source.len(), source.len()));
+
+        let arrow = source.find("-->").unwrap();
+        assert_eq!(index.position(source, arrow), Position::new(2, 10, arrow));
+        assert_eq!(index.lsp_position(source, arrow), (1, 10));
+        assert_eq!(index.position(source, 8), Position::new(1, 9, 8));
+        assert_eq!(index.position(source, 9), Position::new(2, 1, 9));
+        assert_eq!(index.position(source, 1000), Position::new(5, 1, source.len()));
+        assert_eq!(LineIndex::new("").position("", 0), Position::new(1, 1, 0));
+    }
+}
@@ -0,0 +1,289 @@
+//! Complexity metrics computed from an AST.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Ast, AstNode, NodeKind, Span};
+use crate::diagnostic::{Diagnostic, DiagnosticCode};
+
+/// Size and complexity counts for a parsed diagram.
+///
+/// Computed by counting node kinds, so the same metrics apply to every
+/// diagram type; counts that don't apply to a diagram are zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct DiagramMetrics {
+    /// Distinct nodes: flowchart nodes, states, classes, entities, tasks
+    /// and commits. Repeated references to the same id count once.
+    pub nodes: usize,
+    /// Connections: flowchart links, relationships and transitions.
+    pub edges: usize,
+    /// Distinct sequence diagram participants and actors.
+    pub participants: usize,
+    /// Sequence diagram messages.
+    pub messages: usize,
+    /// Subgraphs, sections, namespaces and other containers.
+    pub subgraphs: usize,
+    /// Deepest nesting level below the root.
+    pub depth: usize,
+    /// Deepest nesting of subgraphs, namespaces and other containers; one
+    /// for containers that hold no others.
+    pub subgraph_depth: usize,
+    /// Deepest nesting of composite states; one for composite states that
+    /// hold no others.
+    pub composite_depth: usize,
+    /// Total number of AST nodes, including the root.
+    pub total: usize,
+}
+
+impl DiagramMetrics {
+    /// Computes the metrics of an AST.
+    pub fn from_ast(ast: &Ast) -> Self {
+        let mut metrics = Self::default();
+        let mut node_ids = HashSet::new();
+        let mut participant_ids = HashSet::new();
+
+        ast.walk(|node, depth| {
+            metrics.total += 1;
+            metrics.depth = metrics.depth.max(depth);
+
+            match &node.kind {
+                NodeKind::Node | NodeKind::State | NodeKind::Class => {
+                    metrics.nodes += usize::from(counts_once(node, &mut node_ids));
+                }
+                NodeKind::Other(kind) if kind == "Entity" => {
+                    metrics.nodes += usize::from(counts_once(node, &mut node_ids));
+                }
+                NodeKind::Participant => {
+                    metrics.participants += usize::from(counts_once(node, &mut participant_ids));
+                }
+                // A flowchart edge statement wraps one `Edge` per link
+                NodeKind::Edge if !node.children.iter().any(|c| c.kind == NodeKind::Edge) => {
+                    metrics.edges += 1;
+                }
+                NodeKind::Relationship | NodeKind::Transition => metrics.edges += 1,
+                NodeKind::Message => metrics.messages += 1,
+                NodeKind::Subgraph => metrics.subgraphs += 1,
+                _ => {}
+            }
+        });
+        metrics.subgraph_depth = subgraph_depth(&ast.root.children);
+        metrics.composite_depth = composite_depth(&ast.root);
+
+        metrics
+    }
+}
+
+/// Returns the deepest subgraph nesting among `nodes`. Flowchart subgraphs
+/// are siblings of their contents, closed by an `end` statement; namespaces
+/// hold their contents as children, and gantt and journey sections do not
+/// nest.
+fn subgraph_depth(nodes: &[AstNode]) -> usize {
+    let mut open: usize = 0;
+    let mut deepest = 0;
+    for node in nodes {
+        let own = match (&node.kind, node.statement_type()) {
+            (NodeKind::Subgraph, None) => {
+                open += 1;
+                0
+            }
+            (NodeKind::Subgraph, Some(_)) => 1,
+            (NodeKind::Statement, Some("end")) => {
+                open = open.saturating_sub(1);
+                0
+            }
+            _ => 0,
+        };
+        deepest = deepest.max(open + own + subgraph_depth(&node.children));
+    }
+    deepest
+}
+
+/// Returns the deepest composite state nesting below `node`.
+fn composite_depth(node: &AstNode) -> usize {
+    node.children
+        .iter()
+        .map(|child| {
+            let own = usize::from(child.get_property("is_composite") == Some("true"));
+            own + composite_depth(child)
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Limits on a diagram's [`DiagramMetrics`], for the opt-in budget lints.
+///
+/// Each budget is a rule named as in the CLI (`max-nodes`, ...); a diagram
+/// over a budget gets one warning for it. No budget is set by default; set
+/// them with [`ComplexityBudgets::set`] or by assigning the fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct ComplexityBudgets {
+    /// Most distinct nodes (`max-nodes`).
+    pub max_nodes: Option<usize>,
+    /// Most edges (`max-edges`).
+    pub max_edges: Option<usize>,
+    /// Most sequence diagram participants (`max-participants`).
+    pub max_participants: Option<usize>,
+    /// Most sequence diagram messages (`max-messages`).
+    pub max_messages: Option<usize>,
+    /// Deepest subgraph nesting (`max-subgraph-depth`).
+    pub max_subgraph_depth: Option<usize>,
+    /// Deepest composite state nesting (`max-composite-depth`).
+    pub max_composite_depth: Option<usize>,
+}
+
+/// A budget a diagram exceeds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BudgetExcess {
+    /// The budget's rule name, such as `max-nodes`.
+    pub rule: &'static str,
+    /// What the diagram counts.
+    pub count: usize,
+    /// The budget.
+    pub limit: usize,
+}
+
+impl ComplexityBudgets {
+    /// The rule names, in the order budgets are checked.
+    pub const RULES: &'static [&'static str] = &[
+        "max-nodes",
+        "max-edges",
+        "max-participants",
+        "max-messages",
+        "max-subgraph-depth",
+        "max-composite-depth",
+    ];
+
+    /// Sets the budget for a rule, returning false if there is no rule of
+    /// that name.
+    pub fn set(&mut self, rule: &str, limit: usize) -> bool {
+        let budget = match rule {
+            "max-nodes" => &mut self.max_nodes,
+            "max-edges" => &mut self.max_edges,
+            "max-participants" => &mut self.max_participants,
+            "max-messages" => &mut self.max_messages,
+            "max-subgraph-depth" => &mut self.max_subgraph_depth,
+            "max-composite-depth" => &mut self.max_composite_depth,
+            _ => return false,
+        };
+        *budget = Some(limit);
+        true
+    }
+
+    /// Returns the budgets `metrics` exceeds, in [`Self::RULES`] order.
+    pub fn exceeded(&self, metrics: &DiagramMetrics) -> Vec<BudgetExcess> {
+        [
+            (self.max_nodes, metrics.nodes),
+            (self.max_edges, metrics.edges),
+            (self.max_participants, metrics.participants),
+            (self.max_messages, metrics.messages),
+            (self.max_subgraph_depth, metrics.subgraph_depth),
+            (self.max_composite_depth, metrics.composite_depth),
+        ]
+        .into_iter()
+        .zip(Self::RULES)
+        .filter_map(|((limit, count), rule)| {
+            let limit = limit?;
+            (count > limit).then_some(BudgetExcess { rule, count, limit })
+        })
+        .collect()
+    }
+
+    /// Warns once per exceeded budget, at the diagram declaration.
+    pub(crate) fn validate(&self, ast: &Ast) -> Vec<Diagnostic> {
+        if *self == Self::default() {
+            return Vec::new();
+        }
+        let span = ast
+            .root
+            .children
+            .iter()
+            .find(|node| node.kind == NodeKind::DiagramDeclaration)
+            .map_or(Span::new(0, 0), |node| node.span);
+        self.exceeded(&DiagramMetrics::from_ast(ast))
+            .into_iter()
+            .map(|excess| {
+                Diagnostic::warning(
+                    DiagnosticCode::ConstraintViolation,
+                    format!(
+                        "Diagram has {} {}, over the {} budget of {}",
+                        excess.count,
+                        excess.noun(),
+                        excess.rule,
+                        excess.limit
+                    ),
+                    span,
+                )
+                .with_note("split the diagram into smaller ones, or raise the budget")
+            })
+            .collect()
+    }
+}
+
+impl BudgetExcess {
+    /// What the rule counts, in the plural.
+    fn noun(&self) -> &'static str {
+        match self.rule {
+            "max-nodes" => "nodes",
+            "max-edges" => "edges",
+            "max-participants" => "participants",
+            "max-messages" => "messages",
+            "max-subgraph-depth" => "levels of nested subgraphs",
+            _ => "levels of nested composite states",
+        }
+    }
+}
+
+/// Returns true the first time a node's id is seen. Nodes without an id
+/// always count.
+fn counts_once(node: &AstNode, seen: &mut HashSet<String>) -> bool {
+    match node.id().or(node.get_property("name")) {
+        Some(id) => seen.insert(id.to_string()),
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(code: &str) -> DiagramMetrics {
+        let result = crate::parse(code, None);
+        DiagramMetrics::from_ast(result.ast.as_ref().expect("parse failed"))
+    }
+
+    #[test]
+    fn test_flowchart_metrics() {
+        let m = metrics("graph TD\n    A --> B --> C\n    A --> C\n    subgraph one\n    D\n    end");
+        assert_eq!(m.nodes, 4);
+        assert_eq!(m.edges, 3);
+        assert_eq!(m.subgraphs, 1);
+        assert_eq!(m.participants, 0);
+    }
+
+    #[test]
+    fn test_sequence_metrics() {
+        let m = metrics("sequenceDiagram\n    participant A\n    participant B\n    A->>B: Hi\n    B->>A: Hello");
+        assert_eq!(m.participants, 2);
+        assert_eq!(m.messages, 2);
+        assert_eq!(m.nodes, 0);
+    }
+
+    #[test]
+    fn test_nesting_depths() {
+        let m = metrics("graph TD\n    subgraph a\n    subgraph b\n    X\n    end\n    end\n    subgraph c\n    Y\n    end");
+        assert_eq!(m.subgraph_depth, 2);
+        let m = metrics("gantt\n    section One\n    Task :t1, 2024-01-01, 1d\n    section Two\n    Task :t2, 2024-01-02, 1d");
+        assert_eq!(m.subgraph_depth, 1);
+        let m = metrics("stateDiagram-v2\n    state Outer {\n        state Inner {\n            [*] --> X\n        }\n    }\n    state Other {\n        [*] --> Y\n    }");
+        assert_eq!(m.composite_depth, 2);
+    }
+
+    #[test]
+    fn test_state_depth() {
+        let flat = metrics("stateDiagram-v2\n    [*] --> A");
+        let nested = metrics("stateDiagram-v2\n    state Outer {\n        state Inner {\n            [*] --> X\n        }\n    }");
+        assert!(nested.depth > flat.depth);
+    }
+}
@@ -1,9 +1,14 @@
 //! Abstract Syntax Tree (AST) definitions for Mermaid diagrams.
 
 mod common;
+mod emit;
+mod line_index;
+mod metrics;
 mod typed;
 
-pub use common::{Ast, AstNode, NodeKind, Span};
+pub use common::{Ast, AstNode, NodeKind, PropertyKey, Span};
+pub use line_index::LineIndex;
+pub use metrics::{BudgetExcess, ComplexityBudgets, DiagramMetrics};
 pub use typed::*;
 
 use serde::{Deserialize, Serialize};
@@ -63,38 +68,18 @@ impl Range {
     }
 
     /// Creates a range from byte offsets and source text.
+    ///
+    /// This indexes `source` on every call; to convert many offsets, build a
+    /// [`LineIndex`] once and use [`LineIndex::range`].
     pub fn from_offsets(source: &str, start_offset: usize, end_offset: usize) -> Self {
-        let start = offset_to_position(source, start_offset);
-        let end = offset_to_position(source, end_offset);
-        Self { start, end }
+        LineIndex::new(source).range(source, Span::new(start_offset, end_offset))
     }
 }
 
 /// Converts a byte offset to a position (line, column).
+#[cfg(test)]
 fn offset_to_position(source: &str, offset: usize) -> Position {
-    let offset = offset.min(source.len());
-    let mut line = 1;
-    let mut column = 1;
-    let mut current_offset = 0;
-
-    for (idx, ch) in source.char_indices() {
-        if idx >= offset {
-            break;
-        }
-        current_offset = idx;
-        if ch == '\n' {
-            line += 1;
-            column = 1;
-        } else {
-            column += 1;
-        }
-    }
-
-    Position {
-        line,
-        column,
-        offset: current_offset,
-    }
+    LineIndex::new(source).position(source, offset)
 }
 
 #[cfg(test)]
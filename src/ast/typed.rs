@@ -3,9 +3,11 @@
 //! These provide more specific type information than the generic AST
 //! for diagrams that need semantic validation.
 
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
-use super::Span;
+use super::{Ast, AstNode, NodeKind, Span};
 
 // ============================================================================
 // Flowchart AST
@@ -22,6 +24,7 @@ pub enum FlowDirection {
 
 impl FlowDirection {
     /// Parses a direction from a string.
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_uppercase().as_str() {
             "TB" | "TD" => Some(FlowDirection::TopToBottom),
@@ -52,17 +55,86 @@ pub enum NodeShape {
     DoubleCircle,
 }
 
+impl NodeShape {
+    /// Parses a shape from the name recorded in the AST `shape` property.
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s {
+            "Rectangle" => Some(NodeShape::Rectangle),
+            "RoundedRect" => Some(NodeShape::RoundedRect),
+            "Stadium" => Some(NodeShape::Stadium),
+            "Subroutine" => Some(NodeShape::Subroutine),
+            "Cylindrical" => Some(NodeShape::Cylindrical),
+            "Circle" => Some(NodeShape::Circle),
+            "Asymmetric" => Some(NodeShape::Asymmetric),
+            "Rhombus" => Some(NodeShape::Rhombus),
+            "Hexagon" => Some(NodeShape::Hexagon),
+            "Parallelogram" => Some(NodeShape::Parallelogram),
+            "ParallelogramAlt" => Some(NodeShape::ParallelogramAlt),
+            "Trapezoid" => Some(NodeShape::Trapezoid),
+            "TrapezoidAlt" => Some(NodeShape::TrapezoidAlt),
+            "DoubleCircle" => Some(NodeShape::DoubleCircle),
+            _ => None,
+        }
+    }
+}
+
 /// A node in a flowchart.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FlowNode {
     pub id: String,
+    /// The label text, with escapes and entity codes resolved.
     pub label: Option<String>,
-    pub shape: NodeShape,
+    /// The label as written in the source.
+    pub raw_label: Option<String>,
+    /// The declared shape, or `None` if the node was only referenced bare.
+    pub shape: Option<NodeShape>,
     pub span: Span,
 }
 
+/// Extracts one [`FlowNode`] per id from a flowchart AST.
+///
+/// Bare references are consolidated with the occurrence that declared a
+/// shape or label, whose span is used; ids that are never declared keep
+/// the span of their first reference and have no shape.
+pub fn flow_nodes(ast: &Ast) -> Vec<FlowNode> {
+    let mut nodes: Vec<FlowNode> = Vec::new();
+    ast.walk(|node, _| {
+        if node.kind != NodeKind::Node {
+            return;
+        }
+        let Some(id) = node.id() else {
+            return;
+        };
+        let label = node.label().map(String::from);
+        let raw_label = node.get_property("raw_label").map(String::from);
+        let shape = label
+            .as_ref()
+            .and_then(|_| node.get_property("shape"))
+            .and_then(NodeShape::from_name);
+
+        match nodes.iter_mut().find(|n| n.id == id) {
+            Some(existing) => {
+                if existing.shape.is_none() && shape.is_some() {
+                    existing.shape = shape;
+                    existing.label = label;
+                    existing.raw_label = raw_label;
+                    existing.span = node.span;
+                }
+            }
+            None => nodes.push(FlowNode {
+                id: id.to_string(),
+                label,
+                raw_label,
+                shape,
+                span: node.span,
+            }),
+        }
+    });
+    nodes
+}
+
 /// Type of edge in a flowchart.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum EdgeType {
     Arrow,
     Open,
@@ -70,6 +142,23 @@ pub enum EdgeType {
     Thick,
     DottedArrow,
     ThickArrow,
+    Invisible,
+}
+
+impl EdgeType {
+    /// Parses an edge type from the name recorded in the AST `link_type` property.
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s {
+            "Arrow" => Some(EdgeType::Arrow),
+            "Open" => Some(EdgeType::Open),
+            "Dotted" => Some(EdgeType::Dotted),
+            "Thick" => Some(EdgeType::Thick),
+            "DottedArrow" => Some(EdgeType::DottedArrow),
+            "ThickArrow" => Some(EdgeType::ThickArrow),
+            "Invisible" => Some(EdgeType::Invisible),
+            _ => None,
+        }
+    }
 }
 
 /// An edge in a flowchart.
@@ -79,9 +168,55 @@ pub struct FlowEdge {
     pub to: String,
     pub edge_type: EdgeType,
     pub label: Option<String>,
+    /// Position of the link in source order, as counted by `linkStyle`.
+    pub index: usize,
+    /// The id given with `id@`, if any.
+    pub id: Option<String>,
+    /// From the start of the source node to the end of the target node.
     pub span: Span,
 }
 
+/// Extracts every link of a flowchart AST as a [`FlowEdge`], in source order.
+///
+/// A chain `A --> B --> C` yields one edge per link, and a link between
+/// `&` lists one edge per pair: `A & B --> C` is `A --> C` and `B --> C`.
+pub fn flow_edges(ast: &Ast) -> Vec<FlowEdge> {
+    let mut edges = Vec::new();
+    ast.walk(|stmt, _| {
+        if stmt.kind != NodeKind::Edge {
+            return;
+        }
+        // Only edge statements start with their source nodes
+        let mut from: Vec<&AstNode> = stmt.children.iter().take_while(|c| c.kind == NodeKind::Node).collect();
+        if from.is_empty() {
+            return;
+        }
+        for link in stmt.children.iter().filter(|c| c.kind == NodeKind::Edge) {
+            let to: Vec<&AstNode> = link.children.iter().filter(|c| c.kind == NodeKind::Node).collect();
+            if to.is_empty() {
+                continue;
+            }
+            let edge_type = link.get_property("link_type").and_then(EdgeType::from_name);
+            let first_index = link.get_property("index").and_then(|i| i.parse().ok());
+            for (pair, (source, target)) in from.iter().flat_map(|f| to.iter().map(move |t| (f, t))).enumerate() {
+                if let (Some(from_id), Some(to_id), Some(edge_type)) = (source.id(), target.id(), edge_type) {
+                    edges.push(FlowEdge {
+                        from: from_id.to_string(),
+                        to: to_id.to_string(),
+                        edge_type,
+                        label: link.label().map(String::from),
+                        index: first_index.map_or(edges.len(), |index: usize| index + pair),
+                        id: link.get_property("edge_id").map(String::from),
+                        span: Span::new(source.span.start, target.span.end),
+                    });
+                }
+            }
+            from = to;
+        }
+    });
+    edges
+}
+
 /// A subgraph in a flowchart.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FlowSubgraph {
@@ -182,15 +317,54 @@ pub enum RelationType {
     Dependency,
     Realization,
     Link,
+    DashedLink,
+}
+
+impl RelationType {
+    /// Parses a relation type from the name recorded in the AST `relation_type` property.
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s {
+            "Inheritance" => Some(RelationType::Inheritance),
+            "Composition" => Some(RelationType::Composition),
+            "Aggregation" => Some(RelationType::Aggregation),
+            "Association" => Some(RelationType::Association),
+            "Dependency" => Some(RelationType::Dependency),
+            "Realization" => Some(RelationType::Realization),
+            "Link" => Some(RelationType::Link),
+            "DashedLink" => Some(RelationType::DashedLink),
+            _ => None,
+        }
+    }
+}
+
+/// The end of a relation arrow that carries the arrowhead or marker, as
+/// written in the source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HeadSide {
+    Left,
+    Right,
 }
 
 /// Cardinality of a relationship.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Cardinality {
     pub min: Option<String>,
     pub max: Option<String>,
 }
 
+impl Cardinality {
+    /// Reads a cardinality as written between the quotes: a range such as
+    /// `0..*` gives its bounds, and any other text (`1`, `many`) is both
+    /// the minimum and the maximum.
+    pub fn parse(text: &str) -> Self {
+        let bound = |b: &str| Some(b.trim().to_string()).filter(|b| !b.is_empty());
+        match text.split_once("..") {
+            Some((min, max)) => Self { min: bound(min), max: bound(max) },
+            None => Self { min: bound(text), max: bound(text) },
+        }
+    }
+}
+
 /// A class in a class diagram.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClassDef {
@@ -202,17 +376,64 @@ pub struct ClassDef {
 }
 
 /// A relationship between classes.
+///
+/// `from` and `to` are semantic rather than textual: `to` is the class at the
+/// arrowhead or marker (the supertype, the whole of a composition or
+/// aggregation, the target of a dependency), so `Animal <|-- Dog` and
+/// `Dog --|> Animal` both give `from: Dog, to: Animal`. Relations without a
+/// head keep source order. The cardinalities are oriented the same way.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClassRelation {
     pub from: String,
     pub to: String,
     pub relation_type: RelationType,
+    /// Where the arrowhead was written; `None` for plain links.
+    pub head_side: Option<HeadSide>,
     pub label: Option<String>,
     pub from_cardinality: Option<Cardinality>,
     pub to_cardinality: Option<Cardinality>,
     pub span: Span,
 }
 
+/// Extracts every relationship of a class diagram AST, in source order.
+pub fn class_relations(ast: &Ast) -> Vec<ClassRelation> {
+    let mut relations = Vec::new();
+    ast.walk(|node, _| {
+        if node.kind != NodeKind::Relationship {
+            return;
+        }
+        let relation_type = node.get_property("relation_type").and_then(RelationType::from_name);
+        let (Some(from), Some(to), Some(relation_type)) = (
+            node.get_property("semantic_from"),
+            node.get_property("semantic_to"),
+            relation_type,
+        ) else {
+            return;
+        };
+        let head_side = match node.get_property("head_side") {
+            Some("left") => Some(HeadSide::Left),
+            Some("right") => Some(HeadSide::Right),
+            _ => None,
+        };
+        let cardinality = |key: &str| node.get_property(key).map(Cardinality::parse);
+        let (mut from_cardinality, mut to_cardinality) = (cardinality("from_cardinality"), cardinality("to_cardinality"));
+        if head_side == Some(HeadSide::Left) {
+            std::mem::swap(&mut from_cardinality, &mut to_cardinality);
+        }
+        relations.push(ClassRelation {
+            from: from.to_string(),
+            to: to.to_string(),
+            relation_type,
+            head_side,
+            label: node.label().map(String::from),
+            from_cardinality,
+            to_cardinality,
+            span: node.span,
+        });
+    });
+    relations
+}
+
 // ============================================================================
 // State Diagram AST
 // ============================================================================
@@ -248,6 +469,96 @@ pub struct StateTransition {
     pub span: Span,
 }
 
+// ============================================================================
+// Gantt Chart AST
+// ============================================================================
+
+/// A task in a gantt chart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GanttTask {
+    pub name: String,
+    pub id: Option<String>,
+    pub section: Option<String>,
+    /// Ids listed after `after`, as written.
+    pub after: Vec<String>,
+    /// Recognized modifiers (`done`, `active`, `crit`, `milestone`, `vert`).
+    pub modifiers: Vec<String>,
+    pub span: Span,
+}
+
+/// The dependency graph of a gantt chart.
+///
+/// Task ids resolve globally, regardless of section or declaration order.
+/// When an id is declared more than once the last declaration wins, as in
+/// Mermaid.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GanttGraph {
+    /// Tasks in declaration order.
+    pub tasks: Vec<GanttTask>,
+    /// Edges `(task, dependency)` as indices into `tasks`: `task` comes after
+    /// `dependency`.
+    pub edges: Vec<(usize, usize)>,
+    /// `after` references that match no task id, as `(task, id)`.
+    pub unresolved: Vec<(usize, String)>,
+}
+
+impl GanttGraph {
+    /// Returns the indices of the tasks that `task` comes after.
+    pub fn dependencies(&self, task: usize) -> impl Iterator<Item = usize> + '_ {
+        self.edges
+            .iter()
+            .filter(move |(from, _)| *from == task)
+            .map(|(_, to)| *to)
+    }
+}
+
+/// Builds the dependency graph of a gantt chart AST.
+pub fn gantt_graph(ast: &Ast) -> GanttGraph {
+    let mut graph = GanttGraph::default();
+    let mut section = None;
+
+    for node in &ast.root.children {
+        match (&node.kind, node.statement_type()) {
+            (NodeKind::Subgraph, Some("section")) => {
+                section = node.get_property("name").map(str::to_string);
+            }
+            (NodeKind::Node, Some("task")) => graph.tasks.push(GanttTask {
+                name: node.get_property("name").unwrap_or_default().to_string(),
+                id: node.id().map(str::to_string),
+                section: section.clone(),
+                after: node
+                    .get_property("after")
+                    .map(|after| after.split_whitespace().map(str::to_string).collect())
+                    .unwrap_or_default(),
+                modifiers: node
+                    .get_property("modifiers")
+                    .map(|modifiers| modifiers.split(',').map(str::to_string).collect())
+                    .unwrap_or_default(),
+                span: node.span,
+            }),
+            _ => {}
+        }
+    }
+
+    let mut by_id = HashMap::new();
+    for (index, task) in graph.tasks.iter().enumerate() {
+        if let Some(id) = &task.id {
+            by_id.insert(id.as_str(), index);
+        }
+    }
+
+    for (index, task) in graph.tasks.iter().enumerate() {
+        for id in &task.after {
+            match by_id.get(id.as_str()) {
+                Some(&dependency) => graph.edges.push((index, dependency)),
+                None => graph.unresolved.push((index, id.clone())),
+            }
+        }
+    }
+
+    graph
+}
+
 // ============================================================================
 // Packet Diagram AST (requires semantic validation)
 // ============================================================================
@@ -301,6 +612,97 @@ mod tests {
         assert_eq!(FlowDirection::from_str("invalid"), None);
     }
 
+    #[test]
+    fn test_flow_nodes_shape_only_when_declared() {
+        let result = crate::parse("graph TD\n    A --> B\n    B(Round) --> C", None);
+        let nodes = flow_nodes(result.ast.as_ref().unwrap());
+        let shape_of = |id: &str| nodes.iter().find(|n| n.id == id).unwrap().shape;
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(shape_of("A"), None);
+        assert_eq!(shape_of("B"), Some(NodeShape::RoundedRect));
+        assert_eq!(shape_of("C"), None);
+    }
+
+    #[test]
+    fn test_flow_edges_follow_chains() {
+        let code = "graph TD\n    A --> B -.->|maybe| C\n    C ~~~ A";
+        let result = crate::parse(code, None);
+        let edges = flow_edges(result.ast.as_ref().unwrap());
+        let summary: Vec<_> = edges
+            .iter()
+            .map(|e| (e.from.as_str(), e.to.as_str(), e.edge_type, e.label.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("A", "B", EdgeType::Arrow, None),
+                ("B", "C", EdgeType::DottedArrow, Some("maybe")),
+                ("C", "A", EdgeType::Invisible, None),
+            ]
+        );
+        assert_eq!(edges[1].span.text(code), "B -.->|maybe| C");
+    }
+
+    #[test]
+    fn test_flow_edges_index_and_id() {
+        let code = "graph TD\n    A --> B --> C\n    C e1@--> D";
+        let result = crate::parse(code, None);
+        let edges = flow_edges(result.ast.as_ref().unwrap());
+        let indices: Vec<_> = edges.iter().map(|e| e.index).collect();
+        assert_eq!(indices, vec![0, 1, 2]);
+        assert_eq!(edges[1].id, None);
+        assert_eq!(edges[2].id.as_deref(), Some("e1"));
+    }
+
+    #[test]
+    fn test_flow_edges_expand_node_lists() {
+        let code = "flowchart TD\n    A & B --> C & D --> E\n    E --> F";
+        let result = crate::parse(code, None);
+        let edges = flow_edges(result.ast.as_ref().unwrap());
+        let summary: Vec<_> = edges.iter().map(|e| (e.from.as_str(), e.to.as_str(), e.index)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("A", "C", 0),
+                ("A", "D", 1),
+                ("B", "C", 2),
+                ("B", "D", 3),
+                ("C", "E", 4),
+                ("D", "E", 5),
+                ("E", "F", 6),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_class_relations_are_oriented() {
+        let semantic = |code: &str| {
+            let result = crate::parse(code, None);
+            class_relations(result.ast.as_ref().unwrap())
+                .into_iter()
+                .map(|r| (r.from, r.to, r.relation_type))
+                .collect::<Vec<_>>()
+        };
+        let expected = vec![
+            ("B".to_string(), "A".to_string(), RelationType::Inheritance),
+            ("Part".to_string(), "Whole".to_string(), RelationType::Composition),
+        ];
+        assert_eq!(semantic("classDiagram\n    A <|-- B\n    Whole *-- Part"), expected);
+        assert_eq!(semantic("classDiagram\n    B --|> A\n    Part --* Whole"), expected);
+    }
+
+    #[test]
+    fn test_gantt_graph_resolves_across_sections() {
+        let code = "gantt\n    section A\n    First :a1, 2024-01-01, 3d\n    Second :after b1 a1, 2d\n    section B\n    Third :b1, 2024-01-02, 1d\n    Fourth :after zz, 1d";
+        let result = crate::parse(code, None);
+        let graph = gantt_graph(result.ast.as_ref().unwrap());
+
+        assert_eq!(graph.tasks.len(), 4);
+        assert_eq!(graph.tasks[2].section.as_deref(), Some("B"));
+        assert_eq!(graph.dependencies(1).collect::<Vec<_>>(), vec![2, 0]);
+        assert_eq!(graph.unresolved, vec![(3, "zz".to_string())]);
+    }
+
     #[test]
     fn test_packet_field_validation() {
         let valid = PacketField {
@@ -2,13 +2,22 @@
 //!
 //! A command-line tool for linting Mermaid diagrams.
 
+use std::collections::HashMap;
 use std::fs;
 use std::io::{self, Read};
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 
-use clap::{Parser, Subcommand};
-use mermaid_linter::{parse, validate, detect_type, ParseResult};
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use mermaid_linter::extract::{extract_mermaid_adoc, extract_mermaid_rst};
+use mermaid_linter::preprocess::normalize_text;
+use mermaid_linter::{fix, output_schema, parse, rename, validate, detect_type, ComplexityBudgets, ConfigSource, DiagramMetrics, DiagramType, Diagnostic, DiagnosticCode, LineIndex, OptInCheck, ParseOptions, ParseResult, Severity, Span};
+use serde::{Deserialize, Serialize};
 
 /// Mermaid diagram syntax linter
 #[derive(Parser)]
@@ -22,8 +31,8 @@ struct Cli {
     #[arg(value_name = "FILE")]
     files: Vec<PathBuf>,
 
-    /// Output format (text, json)
-    #[arg(short, long, default_value = "text")]
+    /// Output format
+    #[arg(short, long, default_value = "text", value_parser = ["text", "json", "ndjson"])]
     format: String,
 
     /// Only validate, don't output AST
@@ -34,9 +43,66 @@ struct Cli {
     #[arg(short, long)]
     quiet: bool,
 
+    /// Print only the totals: files, files ok and failed, errors and
+    /// warnings (as a JSON object with `--format json` or `ndjson`)
+    #[arg(long, global = true)]
+    count: bool,
+
     /// Show AST output
     #[arg(long)]
     ast: bool,
+
+    /// Override the severity of a diagnostic code, e.g. `E307=warning`, or
+    /// turn it off with `E307=off` (repeatable)
+    #[arg(
+        long = "severity",
+        value_name = "CODE=LEVEL",
+        value_parser = parse_severity_override,
+        global = true
+    )]
+    severity: Vec<(DiagnosticCode, Option<Severity>)>,
+
+    /// Don't report the diagnostics recorded in a baseline file, or record
+    /// them with `--write-baseline`
+    #[arg(long, value_name = "FILE", global = true)]
+    baseline: Option<PathBuf>,
+
+    /// Record the diagnostics to the `--baseline` file instead of suppressing them
+    #[arg(long, requires = "baseline", global = true)]
+    write_baseline: bool,
+
+    /// Print the diagnostics suppressed by `--baseline FILE`, marked as baselined
+    #[arg(long, global = true)]
+    show_baselined: bool,
+
+    /// Only check the syntax, skipping the semantic validation passes
+    #[arg(long, global = true)]
+    syntax_only: bool,
+
+    /// Link diagnostic codes to pages under this URL instead of the
+    /// built-in rules page
+    #[arg(long, value_name = "URL", global = true)]
+    docs_url: Option<String>,
+
+    /// Print where each configuration value came from: the frontmatter or
+    /// a directive, in merge order
+    #[arg(long, global = true)]
+    explain_config: bool,
+
+    /// Warn when a diagram exceeds a complexity budget, e.g.
+    /// `max-nodes=150` (repeatable); over-budget diagrams are listed after
+    /// the results
+    #[arg(long = "budget", value_name = "RULE=LIMIT", value_parser = parse_budget, global = true)]
+    budgets: Vec<(&'static str, usize)>,
+
+    /// Number of files to lint in parallel
+    #[arg(short, long, default_value_t = 1, global = true)]
+    jobs: usize,
+
+    /// Write man pages for mermaid-lint and each of its subcommands to
+    /// this directory
+    #[arg(long, value_name = "DIR", hide = true)]
+    generate_manpage: Option<PathBuf>,
 }
 
 #[derive(Subcommand)]
@@ -47,8 +113,8 @@ enum Commands {
         #[arg(value_name = "FILE")]
         files: Vec<PathBuf>,
 
-        /// Output format (text, json)
-        #[arg(short, long, default_value = "text")]
+        /// Output format
+        #[arg(short, long, default_value = "text", value_parser = ["text", "json", "ndjson"])]
         format: String,
     },
 
@@ -72,59 +138,515 @@ enum Commands {
         #[arg(value_name = "FILE")]
         file: Option<PathBuf>,
 
-        /// Output format (json, yaml)
-        #[arg(short, long, default_value = "json")]
+        /// Output format
+        #[arg(short, long, default_value = "json", value_parser = ["json", "yaml"])]
+        format: String,
+    },
+
+    /// Apply unambiguous fixes and rewrite the files
+    Fix {
+        /// Input files
+        #[arg(value_name = "FILE")]
+        files: Vec<PathBuf>,
+
+        /// Print the fixes without writing the files
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Rename an identifier wherever it is used as an id
+    Rename {
+        /// Input files
+        #[arg(value_name = "FILE")]
+        files: Vec<PathBuf>,
+
+        /// The identifier to rename
+        #[arg(long)]
+        from: String,
+
+        /// The new identifier
+        #[arg(long)]
+        to: String,
+
+        /// Write the renamed files instead of only printing the occurrences
+        #[arg(long)]
+        write: bool,
+    },
+
+    /// Parse files and report the parse time, diagram type and size of
+    /// each diagram, slowest first
+    Stats {
+        /// Input files
+        #[arg(value_name = "FILE")]
+        files: Vec<PathBuf>,
+
+        /// Output format
+        #[arg(short, long, default_value = "text", value_parser = ["text", "json"])]
         format: String,
     },
+
+    /// List all diagnostic rules and opt-in checks with their default
+    /// state and severity
+    Rules {
+        /// Output format
+        #[arg(short, long, default_value = "text", value_parser = ["text", "json"])]
+        format: String,
+    },
+
+    /// List the diagram types and whether each is fully parsed
+    ListTypes {
+        /// Output format
+        #[arg(short, long, default_value = "text", value_parser = ["text", "json"])]
+        format: String,
+    },
+
+    /// Print a shell completion script
+    Completions {
+        /// The shell to complete in
+        shell: Shell,
+    },
+
+    /// Print the JSON Schema of the `json` and `ndjson` output
+    Schema,
 }
 
 fn main() {
     env_logger::init();
 
     let cli = Cli::parse();
+    if let Some(dir) = &cli.generate_manpage {
+        process::exit(write_manpages(dir));
+    }
+
+    let mut options = ParseOptions::default();
+    for (code, severity) in cli.severity {
+        if let Some(severity) = severity {
+            options.severity_overrides.insert(code, severity);
+        } else {
+            options.disabled_codes.insert(code);
+        }
+    }
+    for (rule, limit) in cli.budgets {
+        options.budgets.set(rule, limit);
+    }
+    options.syntax_only = cli.syntax_only;
+    options.docs_base_url = cli.docs_url;
+    options.trace_config = cli.explain_config;
+
+    let mut baseline = match cli.baseline {
+        None => None,
+        Some(path) if cli.write_baseline => Some(BaselineMode::Write(path, Vec::new())),
+        Some(path) => match BaselineMode::load(&path, cli.show_baselined) {
+            Ok(mode) => Some(mode),
+            Err(e) => {
+                eprintln!("Error reading baseline {}: {}", path.display(), e);
+                process::exit(1);
+            }
+        },
+    };
 
     let exit_code = match cli.command {
-        Some(Commands::Lint { files, format }) => lint_files(&files, &format, false),
+        Some(Commands::Lint { files, format }) => {
+            lint_files(&files, &format, false, cli.count, &options, &mut baseline, cli.jobs)
+        }
         Some(Commands::Detect { file }) => detect_file(file),
-        Some(Commands::Check { files }) => check_files(&files),
-        Some(Commands::Parse { file, format }) => parse_file(file, &format),
+        Some(Commands::Check { files }) => check_files(&files, &options),
+        Some(Commands::Parse { file, format }) => parse_file(file, &format, &options),
+        Some(Commands::Fix { files, dry_run }) => fix_files(&files, dry_run, &options),
+        Some(Commands::Rename { files, from, to, write }) => rename_files(&files, &from, &to, write),
+        Some(Commands::Stats { files, format }) => stats_files(&files, &format, &options),
+        Some(Commands::Rules { format }) => list_rules(&format),
+        Some(Commands::ListTypes { format }) => list_types(&format),
+        Some(Commands::Completions { shell }) => {
+            clap_complete::generate(shell, &mut Cli::command(), "mermaid-lint", &mut io::stdout());
+            0
+        }
+        Some(Commands::Schema) => {
+            println!("{}", serde_json::to_string_pretty(&output_schema()).unwrap_or_default());
+            0
+        }
         None => {
             if cli.files.is_empty() {
                 // Read from stdin
-                lint_stdin(&cli.format, cli.check, cli.quiet, cli.ast)
+                lint_stdin(&cli.format, cli.check, cli.quiet, cli.count, cli.ast, &options)
             } else {
-                lint_files(&cli.files, &cli.format, cli.quiet)
+                lint_files(&cli.files, &cli.format, cli.quiet, cli.count, &options, &mut baseline, cli.jobs)
             }
         }
     };
 
+    let exit_code = match baseline {
+        Some(BaselineMode::Write(path, mut entries)) => {
+            // Files may finish in any order; each file's entries stay in order
+            entries.sort_by(|a, b| a.file.cmp(&b.file));
+            let count = entries.len();
+            let json = serde_json::to_string_pretty(&BaselineFile { entries }).unwrap_or_default();
+            match fs::write(&path, json + "\n") {
+                Ok(()) => {
+                    eprintln!("Recorded {} diagnostic(s) in {}", count, path.display());
+                    0
+                }
+                Err(e) => {
+                    eprintln!("Error writing baseline {}: {}", path.display(), e);
+                    1
+                }
+            }
+        }
+        _ => exit_code,
+    };
+
     process::exit(exit_code);
 }
 
-fn lint_files(files: &[PathBuf], format: &str, quiet: bool) -> i32 {
+/// The baseline file written by `--baseline FILE --write-baseline`.
+#[derive(Serialize, Deserialize)]
+struct BaselineFile {
+    entries: Vec<BaselineEntry>,
+}
+
+/// A recorded diagnostic. The code and message are kept for readers of the
+/// file; only the file and fingerprint are matched.
+#[derive(Serialize, Deserialize)]
+struct BaselineEntry {
+    file: String,
+    fingerprint: String,
+    code: String,
+    message: String,
+}
+
+/// How `--baseline` applies to a lint run.
+enum BaselineMode {
+    /// Record every diagnostic, then write them to the file.
+    Write(PathBuf, Vec<BaselineEntry>),
+    /// Don't report recorded diagnostics, each at most as many times as it
+    /// was recorded in the file. The flag prints them as notes.
+    Suppress(HashMap<(String, String), usize>, bool),
+}
+
+impl BaselineMode {
+    fn load(path: &Path, show_baselined: bool) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let file: BaselineFile = serde_json::from_str(&text).map_err(|e| e.to_string())?;
+        let mut counts = HashMap::new();
+        for entry in file.entries {
+            *counts.entry((baseline_path(Path::new(&entry.file)), entry.fingerprint)).or_insert(0) += 1;
+        }
+        Ok(BaselineMode::Suppress(counts, show_baselined))
+    }
+
+    /// Records or filters the diagnostics of one diagram of `file`. Returns
+    /// the baselined diagnostics to print as notes.
+    fn apply(&mut self, file: &Path, result: &mut ParseResult, source: &str) -> Vec<Diagnostic> {
+        let file = baseline_path(file);
+        let fingerprint = |d: &Diagnostic| d.fingerprint(result.diagram_type, source);
+        match self {
+            BaselineMode::Write(_, entries) => {
+                entries.extend(result.diagnostics.iter().map(|d| BaselineEntry {
+                    file: file.clone(),
+                    fingerprint: fingerprint(d),
+                    code: d.code.as_str().to_string(),
+                    message: d.message.clone(),
+                }));
+                Vec::new()
+            }
+            BaselineMode::Suppress(counts, show) => {
+                let (baselined, kept): (Vec<_>, Vec<_>) =
+                    std::mem::take(&mut result.diagnostics).into_iter().partition(|d| {
+                        match counts.get_mut(&(file.clone(), fingerprint(d))) {
+                            Some(count) if *count > 0 => {
+                                *count -= 1;
+                                true
+                            }
+                            _ => false,
+                        }
+                    });
+                result.ok = !kept.iter().any(|d| d.severity.is_error());
+                result.diagnostics = kept;
+                if *show { baselined } else { Vec::new() }
+            }
+        }
+    }
+}
+
+/// Returns `file` as a baseline records it: without `.` components and with
+/// `/` separators, so `./docs/a.mmd` and `docs/a.mmd` match.
+fn baseline_path(file: &Path) -> String {
+    let file: PathBuf = file.components().filter(|part| !matches!(part, Component::CurDir)).collect();
+    file.to_string_lossy().replace('\\', "/")
+}
+
+/// Parses a `CODE=LEVEL` severity override; `None` turns the code off.
+fn parse_severity_override(value: &str) -> Result<(DiagnosticCode, Option<Severity>), String> {
+    let (code, level) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected CODE=LEVEL, found '{}'", value))?;
+    let code = DiagnosticCode::from_code(code.trim())
+        .ok_or_else(|| format!("unknown diagnostic code '{}'", code))?;
+    let severity = Severity::parse_level(level).map_err(|e| e.to_string())?;
+    Ok((code, severity))
+}
+
+/// Writes `mermaid-lint.1` and a `mermaid-lint-<subcommand>.1` page per
+/// subcommand to `dir`, generated from the command-line definitions.
+fn write_manpages(dir: &Path) -> i32 {
+    match fs::create_dir_all(dir).and_then(|()| clap_mangen::generate_to(Cli::command(), dir)) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("Error writing man pages to {}: {}", dir.display(), e);
+            1
+        }
+    }
+}
+
+/// Parses a `RULE=LIMIT` complexity budget.
+fn parse_budget(value: &str) -> Result<(&'static str, usize), String> {
+    let (rule, limit) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected RULE=LIMIT, found '{}'", value))?;
+    let rule = ComplexityBudgets::RULES
+        .iter()
+        .find(|r| **r == rule.trim())
+        .ok_or_else(|| format!("unknown budget '{}'; expected one of {}", rule, ComplexityBudgets::RULES.join(", ")))?;
+    let limit = limit.trim().parse().map_err(|_| format!("invalid limit '{}'", limit))?;
+    Ok((rule, limit))
+}
+
+/// Describes the budgets a diagram exceeds, one line each.
+fn over_budget(label: &str, result: &ParseResult, options: &ParseOptions) -> Vec<String> {
+    options
+        .budgets
+        .exceeded(&result.metrics())
+        .into_iter()
+        .map(|excess| format!("{}: {} ({} > {})", label, excess.rule, excess.count, excess.limit))
+        .collect()
+}
+
+/// Prints the diagrams over a complexity budget, if any, after the results.
+fn print_budget_summary(lines: &[String]) {
+    if !lines.is_empty() {
+        eprintln!("Over budget:");
+        for line in lines {
+            eprintln!("  {}", line);
+        }
+    }
+}
+
+/// Lints files on up to `jobs` threads.
+///
+/// Results print in the order the files were given, whatever order they
+/// finish in. The `ndjson` format instead streams each file's lines as soon
+/// as it finishes, with a `sequence` field holding the file's input index.
+/// With `count`, only the totals are printed, after all files finish.
+fn lint_files(
+    files: &[PathBuf],
+    format: &str,
+    quiet: bool,
+    count: bool,
+    options: &ParseOptions,
+    baseline: &mut Option<BaselineMode>,
+    jobs: usize,
+) -> i32 {
+    let baseline = Mutex::new(baseline);
+    let streaming = format == "ndjson" && !count;
+    let reports = run_indexed(files.len(), jobs, |index| {
+        let report = lint_file(index, &files[index], format, quiet || count, options, &baseline);
+        if streaming {
+            report.print();
+        }
+        report
+    });
+
     let mut has_errors = false;
+    let mut over_budget = Vec::new();
+    let mut summary = Summary::default();
+    for report in reports {
+        if !streaming {
+            report.print();
+        }
+        has_errors |= report.has_errors;
+        summary.add_file(&report);
+        over_budget.extend(report.over_budget);
+    }
+    if count {
+        summary.print(format);
+    } else if !quiet {
+        print_budget_summary(&over_budget);
+    }
+    if has_errors { 1 } else { 0 }
+}
 
-    for file in files {
-        match fs::read_to_string(file) {
-            Ok(content) => {
-                let result = parse(&content, None);
-                has_errors |= !result.ok;
+/// The totals printed by `--count`.
+#[derive(Debug, Default, Serialize)]
+struct Summary {
+    files: usize,
+    ok: usize,
+    failed: usize,
+    errors: usize,
+    warnings: usize,
+}
+
+impl Summary {
+    fn add_file(&mut self, report: &FileReport) {
+        self.files += 1;
+        if report.has_errors {
+            self.failed += 1;
+        } else {
+            self.ok += 1;
+        }
+        self.errors += report.errors;
+        self.warnings += report.warnings;
+    }
+
+    fn print(&self, format: &str) {
+        match format {
+            "json" | "ndjson" => println!("{}", serde_json::to_string(self).unwrap_or_default()),
+            _ => println!(
+                "{} file(s): {} ok, {} failed, {} error(s), {} warning(s)",
+                self.files, self.ok, self.failed, self.errors, self.warnings
+            ),
+        }
+    }
+}
+
+/// The output of linting one file, buffered until it can be printed.
+#[derive(Default)]
+struct FileReport {
+    stdout: String,
+    stderr: String,
+    has_errors: bool,
+    /// The number of error and warning diagnostics reported, for `--count`.
+    errors: usize,
+    warnings: usize,
+    /// The budgets the file's diagrams exceed, for the summary.
+    over_budget: Vec<String>,
+}
+
+impl FileReport {
+    fn print(&self) {
+        eprint!("{}", self.stderr);
+        print!("{}", self.stdout);
+    }
+
+    fn count_diagnostics(&mut self, result: &ParseResult) {
+        for diagnostic in &result.diagnostics {
+            match diagnostic.severity {
+                Severity::Error => self.errors += 1,
+                Severity::Warning => self.warnings += 1,
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Lints one file. `index` is its position among the input files.
+fn lint_file(
+    index: usize,
+    file: &Path,
+    format: &str,
+    quiet: bool,
+    options: &ParseOptions,
+    baseline: &Mutex<&mut Option<BaselineMode>>,
+) -> FileReport {
+    let mut report = FileReport::default();
+    match fs::read_to_string(file) {
+        Ok(content) => {
+            let diagrams = diagrams_in(file, content);
+            if diagrams.is_empty() && !quiet {
+                report.stderr = format!("{}: no Mermaid diagrams found\n", file.display());
+            }
+            for (label, code) in diagrams {
+                let mut result = parse(&code, Some(options.clone()));
+                let baselined = match baseline.lock().unwrap().as_mut() {
+                    Some(baseline) => baseline.apply(file, &mut result, &code),
+                    None => Vec::new(),
+                };
+                report.has_errors |= !result.ok;
+                report.count_diagnostics(&result);
+                report.stderr.push_str(&explain_config(&label, &result, &code));
+                report.over_budget.extend(over_budget(&label, &result, options));
 
                 if !quiet {
-                    print_result(file.to_string_lossy().as_ref(), &result, format, &content);
+                    report
+                        .stdout
+                        .push_str(&render_result(&label, &result, format, &code, &baselined, index, options.docs_base_url()));
                 }
             }
-            Err(e) => {
-                eprintln!("Error reading {}: {}", file.display(), e);
-                has_errors = true;
-            }
+        }
+        Err(e) => {
+            report.stderr = format!("Error reading {}: {}\n", file.display(), e);
+            report.has_errors = true;
         }
     }
+    report
+}
 
-    if has_errors { 1 } else { 0 }
+/// Renders a diagram's config trace for `--explain-config`, one line per
+/// field set, with the line the frontmatter or directive starts on. Empty
+/// when tracing is off.
+fn explain_config(label: &str, result: &ParseResult, code: &str) -> String {
+    if result.config_trace.is_empty() {
+        return String::new();
+    }
+    // Trace spans index into the source with normalized line endings
+    let normalized = normalize_text(code);
+    let mut out = format!("{}: config\n", label);
+    for change in &result.config_trace {
+        let location = match change.source {
+            ConfigSource::Base => String::new(),
+            ConfigSource::Frontmatter(span) | ConfigSource::Directive(span) => {
+                format!(", line {}", span.to_lsp_position(&normalized).0 + 1)
+            }
+        };
+        out.push_str(&format!("  {} = {} ({}{})\n", change.key, change.value, change.source, location));
+    }
+    out
+}
+
+/// Runs `task` for each index in `0..count` on up to `jobs` threads, and
+/// returns the results in index order.
+fn run_indexed<T: Send>(count: usize, jobs: usize, task: impl Fn(usize) -> T + Sync) -> Vec<T> {
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<Option<T>> = (0..count).map(|_| None).collect();
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, count.max(1)) {
+            let (sender, next, task) = (sender.clone(), &next, &task);
+            scope.spawn(move || loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                if index >= count || sender.send((index, task(index))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+        for (index, result) in receiver {
+            results[index] = Some(result);
+        }
+    });
+    results.into_iter().map(|result| result.expect("every index is run")).collect()
+}
+
+/// Returns the diagrams to lint in a file, each with its output label.
+///
+/// reStructuredText (`.rst`) and AsciiDoc (`.adoc`) documents yield one
+/// diagram per embedded block, labeled with the line the block starts on.
+/// Any other file is a single diagram.
+fn diagrams_in(file: &Path, content: String) -> Vec<(String, String)> {
+    let extract: fn(&str) -> Vec<(Span, String)> = match file.extension().and_then(|e| e.to_str()) {
+        Some("rst") => extract_mermaid_rst,
+        Some("adoc" | "asciidoc") => extract_mermaid_adoc,
+        _ => return vec![(file.display().to_string(), content)],
+    };
+    extract(&content)
+        .into_iter()
+        .map(|(span, code)| {
+            let line = content[..span.start].matches('\n').count() + 1;
+            (format!("{}:{}", file.display(), line), code)
+        })
+        .collect()
 }
 
-fn lint_stdin(format: &str, check_only: bool, quiet: bool, show_ast: bool) -> i32 {
+fn lint_stdin(format: &str, check_only: bool, quiet: bool, count: bool, show_ast: bool, options: &ParseOptions) -> i32 {
     let mut content = String::new();
     if let Err(e) = io::stdin().read_to_string(&mut content) {
         eprintln!("Error reading stdin: {}", e);
@@ -132,7 +654,7 @@ fn lint_stdin(format: &str, check_only: bool, quiet: bool, show_ast: bool) -> i3
     }
 
     if check_only {
-        let valid = validate(&content, None);
+        let valid = validate(&content, Some(options.clone()));
         if !quiet {
             if valid {
                 println!("Valid");
@@ -143,10 +665,17 @@ fn lint_stdin(format: &str, check_only: bool, quiet: bool, show_ast: bool) -> i3
         return if valid { 0 } else { 1 };
     }
 
-    let result = parse(&content, None);
+    let result = parse(&content, Some(options.clone()));
+    eprint!("{}", explain_config("<stdin>", &result, &content));
 
-    if !quiet {
-        print_result("<stdin>", &result, format, &content);
+    if count {
+        let mut report = FileReport { has_errors: !result.ok, ..FileReport::default() };
+        report.count_diagnostics(&result);
+        let mut summary = Summary::default();
+        summary.add_file(&report);
+        summary.print(format);
+    } else if !quiet {
+        print!("{}", render_result("<stdin>", &result, format, &content, &[], 0, options.docs_base_url()));
 
         if show_ast && result.ok {
             if let Some(ast) = &result.ast {
@@ -154,6 +683,7 @@ fn lint_stdin(format: &str, check_only: bool, quiet: bool, show_ast: bool) -> i3
                 println!("{}", serde_json::to_string_pretty(ast).unwrap_or_default());
             }
         }
+        print_budget_summary(&over_budget("<stdin>", &result, options));
     }
 
     if result.ok { 0 } else { 1 }
@@ -190,18 +720,23 @@ fn detect_file(file: Option<PathBuf>) -> i32 {
     }
 }
 
-fn check_files(files: &[PathBuf]) -> i32 {
+fn check_files(files: &[PathBuf], options: &ParseOptions) -> i32 {
     let mut has_errors = false;
 
     for file in files {
         match fs::read_to_string(file) {
             Ok(content) => {
-                let valid = validate(&content, None);
-                if valid {
-                    println!("{}: OK", file.display());
-                } else {
-                    println!("{}: FAIL", file.display());
-                    has_errors = true;
+                let diagrams = diagrams_in(file, content);
+                if diagrams.is_empty() {
+                    eprintln!("{}: no Mermaid diagrams found", file.display());
+                }
+                for (label, code) in diagrams {
+                    if validate(&code, Some(options.clone())) {
+                        println!("{}: OK", label);
+                    } else {
+                        println!("{}: FAIL", label);
+                        has_errors = true;
+                    }
                 }
             }
             Err(e) => {
@@ -214,7 +749,7 @@ fn check_files(files: &[PathBuf]) -> i32 {
     if has_errors { 1 } else { 0 }
 }
 
-fn parse_file(file: Option<PathBuf>, format: &str) -> i32 {
+fn parse_file(file: Option<PathBuf>, format: &str, options: &ParseOptions) -> i32 {
     let content = match file {
         Some(path) => match fs::read_to_string(&path) {
             Ok(c) => c,
@@ -233,7 +768,7 @@ fn parse_file(file: Option<PathBuf>, format: &str) -> i32 {
         }
     };
 
-    let result = parse(&content, None);
+    let result = parse(&content, Some(options.clone()));
 
     if !result.ok {
         for diag in &result.diagnostics {
@@ -253,44 +788,323 @@ fn parse_file(file: Option<PathBuf>, format: &str) -> i32 {
     0
 }
 
-fn print_result(file: &str, result: &ParseResult, format: &str, source: &str) {
+fn fix_files(files: &[PathBuf], dry_run: bool, options: &ParseOptions) -> i32 {
+    let mut has_errors = false;
+
+    for file in files {
+        let content = match fs::read_to_string(file) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Error reading {}: {}", file.display(), e);
+                has_errors = true;
+                continue;
+            }
+        };
+
+        let fixed = match fix::fix(&content, Some(options.clone())) {
+            Ok(fixed) => fixed,
+            Err(e) => {
+                eprintln!("{}: not fixed, {}", file.display(), e);
+                has_errors = true;
+                continue;
+            }
+        };
+
+        if fixed.skipped > 0 {
+            println!(
+                "{}: {} fix(es) could not be applied to text preprocessing changed",
+                file.display(),
+                fixed.skipped
+            );
+        }
+        if fixed.applied.is_empty() {
+            if fixed.skipped == 0 {
+                println!("{}: nothing to fix", file.display());
+            }
+            continue;
+        }
+        let index = LineIndex::new(&content);
+        for applied in &fixed.applied {
+            let (line, col) = index.lsp_position(&content, applied.edit.span.start);
+            println!(
+                "{}:{}:{}: {} (replaced {:?} with {:?})",
+                file.display(),
+                line + 1,
+                col + 1,
+                applied.message,
+                applied.edit.span.text(&content),
+                applied.edit.new_text
+            );
+        }
+
+        if !dry_run {
+            if let Err(e) = fs::write(file, &fixed.code) {
+                eprintln!("Error writing {}: {}", file.display(), e);
+                has_errors = true;
+            }
+        }
+    }
+
+    if has_errors { 1 } else { 0 }
+}
+
+fn rename_files(files: &[PathBuf], from: &str, to: &str, write: bool) -> i32 {
+    let mut has_errors = false;
+
+    for file in files {
+        let content = match fs::read_to_string(file) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Error reading {}: {}", file.display(), e);
+                has_errors = true;
+                continue;
+            }
+        };
+
+        let renamed = match rename::rename_identifier(&content, None, from, to) {
+            Ok(renamed) => renamed,
+            Err(diagnostics) => {
+                for diagnostic in diagnostics.iter().filter(|d| d.severity.is_error()) {
+                    eprintln!("{}: not renamed, {}", file.display(), diagnostic.message);
+                }
+                has_errors = true;
+                continue;
+            }
+        };
+
+        let index = LineIndex::new(&content);
+        for span in &renamed.occurrences {
+            let (line, col) = index.lsp_position(&content, span.start);
+            println!("{}:{}:{}: renamed '{}' to '{}'", file.display(), line + 1, col + 1, from, to);
+        }
+
+        if write {
+            if let Err(e) = fs::write(file, &renamed.code) {
+                eprintln!("Error writing {}: {}", file.display(), e);
+                has_errors = true;
+            }
+        }
+    }
+
+    if has_errors { 1 } else { 0 }
+}
+
+/// The parse time and size of one diagram, for `stats`.
+struct DiagramStats {
+    label: String,
+    duration: Duration,
+    diagram_type: Option<DiagramType>,
+    ok: bool,
+    metrics: DiagramMetrics,
+}
+
+fn stats_files(files: &[PathBuf], format: &str, options: &ParseOptions) -> i32 {
+    let mut has_errors = false;
+    let mut stats = Vec::new();
+
+    for file in files {
+        let content = match fs::read_to_string(file) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Error reading {}: {}", file.display(), e);
+                has_errors = true;
+                continue;
+            }
+        };
+        for (label, code) in diagrams_in(file, content) {
+            let started = Instant::now();
+            let result = parse(&code, Some(options.clone()));
+            let duration = started.elapsed();
+            stats.push(DiagramStats {
+                label,
+                duration,
+                diagram_type: result.diagram_type,
+                ok: result.ok,
+                metrics: result.metrics(),
+            });
+        }
+    }
+    stats.sort_by_key(|s| std::cmp::Reverse(s.duration));
+
+    match format {
+        "json" => {
+            let output: Vec<_> = stats
+                .iter()
+                .map(|s| {
+                    serde_json::json!({
+                        "file": s.label,
+                        "duration_ms": s.duration.as_secs_f64() * 1000.0,
+                        "diagram_type": s.diagram_type.map(|t| t.as_str()),
+                        "ok": s.ok,
+                        "metrics": s.metrics,
+                    })
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&output).unwrap_or_default());
+        }
+        _ => {
+            for s in &stats {
+                println!(
+                    "{:>9.3}ms  {:<14}  {:>5} nodes  {:>5} edges  {}",
+                    s.duration.as_secs_f64() * 1000.0,
+                    s.diagram_type.map_or("unknown", |t| t.as_str()),
+                    s.metrics.nodes,
+                    s.metrics.edges,
+                    s.label
+                );
+            }
+        }
+    }
+
+    if has_errors { 1 } else { 0 }
+}
+
+fn list_rules(format: &str) -> i32 {
+    let codes = DiagnosticCode::all();
+    let checks = OptInCheck::all();
+    let defaults = ParseOptions::default();
+    let state = |enabled: bool| if enabled { "on" } else { "off" };
+    match format {
+        "json" => {
+            let mut output: Vec<_> = codes
+                .iter()
+                .map(|code| {
+                    serde_json::json!({
+                        "code": code.as_str(),
+                        "name": format!("{:?}", code),
+                        "category": code.category(),
+                        "severity": code.default_severity().as_str(),
+                        "enabled": !defaults.disabled_codes.contains(code),
+                        "description": code.description(),
+                    })
+                })
+                .collect();
+            output.extend(checks.iter().map(|check| {
+                serde_json::json!({
+                    "code": check.code().as_str(),
+                    "name": check.name(),
+                    "category": check.code().category(),
+                    "severity": check.severity().as_str(),
+                    "enabled": check.is_enabled(&defaults),
+                    "option": check.option(),
+                    "description": check.description(),
+                })
+            }));
+            println!("{}", serde_json::to_string_pretty(&output).unwrap_or_default());
+        }
+        _ => {
+            let width = codes
+                .iter()
+                .map(|code| code.as_str().len())
+                .chain(checks.iter().map(|check| check.name().len()))
+                .max()
+                .unwrap_or_default();
+            for code in codes {
+                println!(
+                    "{:<width$}  {:<3}  {:<7}  {:<21}  {}",
+                    code.as_str(),
+                    state(!defaults.disabled_codes.contains(code)),
+                    code.default_severity().as_str(),
+                    code.category(),
+                    code.description()
+                );
+            }
+            for check in checks {
+                println!(
+                    "{:<width$}  {:<3}  {:<7}  {:<21}  {} (reported as {}, enabled by ParseOptions::{})",
+                    check.name(),
+                    state(check.is_enabled(&defaults)),
+                    check.severity().as_str(),
+                    check.code().category(),
+                    check.description(),
+                    check.code(),
+                    check.option()
+                );
+            }
+        }
+    }
+
+    0
+}
+
+fn list_types(format: &str) -> i32 {
+    let types: Vec<_> = DiagramType::all().iter().filter(|t| !t.is_pseudo()).collect();
     match format {
         "json" => {
-            let output = serde_json::json!({
-                "file": file,
-                "ok": result.ok,
-                "diagram_type": result.diagram_type.map(|t| t.as_str()),
-                "title": result.title,
-                "diagnostics": result.diagnostics.iter().map(|d| {
+            let output: Vec<_> = types
+                .iter()
+                .map(|t| {
                     serde_json::json!({
-                        "code": d.code.as_str(),
-                        "message": d.message,
-                        "severity": d.severity.as_str(),
-                        "range": {
-                            "start": d.span.start,
-                            "end": d.span.end,
-                        }
+                        "type": t.as_str(),
+                        "name": t.display_name(),
+                        "fully_parsed": t.is_fully_parsed(),
                     })
-                }).collect::<Vec<_>>()
-            });
+                })
+                .collect();
             println!("{}", serde_json::to_string_pretty(&output).unwrap_or_default());
         }
+        _ => {
+            for t in types {
+                let support = if t.is_fully_parsed() { "parsed" } else { "stub" };
+                println!("{:<14}  {:<6}  {}", t.as_str(), support, t.display_name());
+            }
+        }
+    }
+
+    0
+}
+
+/// Renders a lint result. `baselined` diagnostics, already removed from the
+/// result, are rendered as notes. `sequence` is the input index of the file,
+/// reported by the `ndjson` format. Codes link to pages under `docs_base`.
+fn render_result(
+    file: &str,
+    result: &ParseResult,
+    format: &str,
+    source: &str,
+    baselined: &[Diagnostic],
+    sequence: usize,
+    docs_base: &str,
+) -> String {
+    match format {
+        "json" | "ndjson" => {
+            let mut output = result.to_json_value_with_docs_base(source, docs_base);
+            output["file"] = file.into();
+            if let Some(diagnostics) = output["diagnostics"].as_array_mut() {
+                diagnostics.extend(baselined.iter().map(|d| {
+                    let mut json = d.to_json_value(result.diagram_type, source, docs_base);
+                    json["baselined"] = serde_json::Value::Bool(true);
+                    json
+                }));
+            }
+            if format == "ndjson" {
+                output["sequence"] = sequence.into();
+                return format!("{}\n", output);
+            }
+            format!("{}\n", serde_json::to_string_pretty(&output).unwrap_or_default())
+        }
         _ => {
             // Text format
+            let index = LineIndex::new(source);
+            let mut lines = Vec::new();
             if result.ok {
-                println!("{}: OK", file);
+                lines.push(format!("{}: OK", file));
                 if let Some(diagram_type) = result.diagram_type {
-                    println!("  Type: {}", diagram_type);
+                    lines.push(format!("  Type: {}", diagram_type));
                 }
                 if let Some(title) = &result.title {
-                    println!("  Title: {}", title);
+                    lines.push(format!("  Title: {}", title));
                 }
             } else {
-                println!("{}: FAIL", file);
+                lines.push(format!("{}: FAIL", file));
                 for diag in &result.diagnostics {
-                    println!("{}", diag.format(source));
+                    lines.push(format!("{}\n  see: {}", diag.format_with_index(source, &index), diag.code_url(docs_base)));
                 }
             }
+            for diag in baselined {
+                lines.push(format!("note: baselined {}", diag.format_with_index(source, &index)));
+            }
+            lines.iter().map(|line| format!("{}\n", line)).collect()
         }
     }
 }
@@ -0,0 +1,225 @@
+//! Fluent construction of [`ParseOptions`].
+
+use crate::ast::ComplexityBudgets;
+use crate::diagnostic::{DiagnosticCode, Severity};
+
+use super::{MermaidConfig, MermaidVersion, ParseOptions};
+
+/// Builds [`ParseOptions`] one setting at a time; see
+/// [`ParseOptions::builder`].
+///
+/// Every setting starts at its default, and a setting made twice keeps the
+/// later value.
+///
+/// ```
+/// use mermaid_linter::{DiagnosticCode, ParseOptions, Severity};
+///
+/// let options = ParseOptions::builder()
+///     .recover(true)
+///     .warn_duplicate_edges(true)
+///     .severity_override(DiagnosticCode::DuplicateDefinition, Severity::Error)
+///     .build();
+/// assert!(options.recover && options.warn_duplicate_edges);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptionsBuilder {
+    options: ParseOptions,
+}
+
+impl ParseOptionsBuilder {
+    /// Sets the base configuration that frontmatter and directives are
+    /// merged into.
+    pub fn base_config(mut self, config: MermaidConfig) -> Self {
+        self.options.base_config = Some(config);
+        self
+    }
+
+    /// Sets whether to suppress errors and return ok=false instead.
+    pub fn suppress_errors(mut self, suppress: bool) -> Self {
+        self.options.suppress_errors = suppress;
+        self
+    }
+
+    /// Sets whether to return the preprocessed source in the parse result.
+    pub fn include_preprocessed(mut self, include: bool) -> Self {
+        self.options.include_preprocessed = include;
+        self
+    }
+
+    /// Sets whether to skip the semantic validation passes.
+    pub fn syntax_only(mut self, syntax_only: bool) -> Self {
+        self.options.syntax_only = syntax_only;
+        self
+    }
+
+    /// Sets whether to keep the partial AST of a failed parse.
+    pub fn recover(mut self, recover: bool) -> Self {
+        self.options.recover = recover;
+        self
+    }
+
+    /// Sets whether to report legacy diagram types as their v2 equivalents.
+    pub fn normalize_diagram_types(mut self, normalize: bool) -> Self {
+        self.options.normalize_diagram_types = normalize;
+        self
+    }
+
+    /// Reports `code` at `severity` instead of its built-in severity. A
+    /// code disabled earlier is reported again.
+    pub fn severity_override(mut self, code: DiagnosticCode, severity: Severity) -> Self {
+        self.options.disabled_codes.remove(&code);
+        self.options.severity_overrides.insert(code, severity);
+        self
+    }
+
+    /// Stops reporting `code`, dropping any earlier severity override.
+    pub fn disable_code(mut self, code: DiagnosticCode) -> Self {
+        self.options.severity_overrides.remove(&code);
+        self.options.disabled_codes.insert(code);
+        self
+    }
+
+    /// Sets whether to warn about exactly duplicated flowchart edges.
+    pub fn warn_duplicate_edges(mut self, warn: bool) -> Self {
+        self.options.warn_duplicate_edges = warn;
+        self
+    }
+
+    /// Sets whether to suggest a `classDef` for repeated flowchart styles.
+    pub fn hint_repeated_styles(mut self, hint: bool) -> Self {
+        self.options.hint_repeated_styles = hint;
+        self
+    }
+
+    /// Sets whether to warn about flowchart link endpoints that are not a
+    /// declared node or a subgraph.
+    pub fn warn_undeclared_endpoints(mut self, warn: bool) -> Self {
+        self.options.warn_undeclared_endpoints = warn;
+        self
+    }
+
+    /// Sets whether to warn about flowchart classes that are never applied.
+    pub fn warn_unused_class_defs(mut self, warn: bool) -> Self {
+        self.options.warn_unused_class_defs = warn;
+        self
+    }
+
+    /// Sets whether to warn about sequence participants that are never used.
+    pub fn warn_unused_participants(mut self, warn: bool) -> Self {
+        self.options.warn_unused_participants = warn;
+        self
+    }
+
+    /// Sets whether to flag class diagram `link` and `callback` statements.
+    pub fn prefer_click_syntax(mut self, prefer: bool) -> Self {
+        self.options.prefer_click_syntax = prefer;
+        self
+    }
+
+    /// Sets whether to warn about ids that differ only by case or invisible
+    /// characters.
+    pub fn warn_confusable_identifiers(mut self, warn: bool) -> Self {
+        self.options.warn_confusable_identifiers = warn;
+        self
+    }
+
+    /// Sets the ER attribute types to allow.
+    pub fn er_attribute_types<I, S>(mut self, types: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.options.er_attribute_types = Some(types.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets the complexity budgets to warn about.
+    pub fn budgets(mut self, budgets: ComplexityBudgets) -> Self {
+        self.options.budgets = budgets;
+        self
+    }
+
+    /// Sets whether to compute the element ids Mermaid renders.
+    pub fn compute_render_ids(mut self, compute: bool) -> Self {
+        self.options.compute_render_ids = compute;
+        self
+    }
+
+    /// Sets the Mermaid release whose render id scheme to follow.
+    pub fn mermaid_version(mut self, version: MermaidVersion) -> Self {
+        self.options.mermaid_version = version;
+        self
+    }
+
+    /// Sets where diagnostic codes are documented.
+    pub fn docs_base_url(mut self, url: impl Into<String>) -> Self {
+        self.options.docs_base_url = Some(url.into());
+        self
+    }
+
+    /// Sets whether to record the configuration fields set while merging.
+    pub fn trace_config(mut self, trace: bool) -> Self {
+        self.options.trace_config = trace;
+        self
+    }
+
+    /// Returns the options.
+    pub fn build(self) -> ParseOptions {
+        self.options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_defaults() {
+        let options = ParseOptions::builder().build();
+        let default = ParseOptions::default();
+        assert_eq!(options.recover, default.recover);
+        assert_eq!(options.warn_duplicate_edges, default.warn_duplicate_edges);
+        assert_eq!(options.er_attribute_types, default.er_attribute_types);
+        assert_eq!(options.mermaid_version, default.mermaid_version);
+        assert_eq!(options.docs_base_url(), default.docs_base_url());
+        assert!(options.severity_overrides.is_empty() && options.disabled_codes.is_empty());
+    }
+
+    #[test]
+    fn test_later_settings_win() {
+        let options = ParseOptions::builder()
+            .recover(true)
+            .recover(false)
+            .severity_override(DiagnosticCode::DuplicateDefinition, Severity::Error)
+            .severity_override(DiagnosticCode::DuplicateDefinition, Severity::Hint)
+            .disable_code(DiagnosticCode::InvalidEscape)
+            .severity_override(DiagnosticCode::InvalidEscape, Severity::Info)
+            .disable_code(DiagnosticCode::UnvalidatedSyntax)
+            .build();
+        assert!(!options.recover);
+        assert_eq!(
+            options.severity_overrides.get(&DiagnosticCode::DuplicateDefinition),
+            Some(&Severity::Hint)
+        );
+        assert_eq!(options.severity_overrides.get(&DiagnosticCode::InvalidEscape), Some(&Severity::Info));
+        assert!(!options.disabled_codes.contains(&DiagnosticCode::InvalidEscape));
+        assert!(options.disabled_codes.contains(&DiagnosticCode::UnvalidatedSyntax));
+    }
+
+    #[test]
+    fn test_builder_matches_with_methods() {
+        let built = ParseOptions::builder()
+            .base_config(MermaidConfig::default())
+            .er_attribute_types(["string"])
+            .compute_render_ids(true)
+            .mermaid_version(MermaidVersion::V10)
+            .build();
+        let chained = ParseOptions::with_config(MermaidConfig::default())
+            .with_er_attribute_types(["string"])
+            .with_render_ids(MermaidVersion::V10);
+        assert!(built.base_config.is_some());
+        assert_eq!(built.er_attribute_types, chained.er_attribute_types);
+        assert_eq!(built.compute_render_ids, chained.compute_render_ids);
+        assert_eq!(built.mermaid_version, chained.mermaid_version);
+    }
+}
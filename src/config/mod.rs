@@ -1,31 +1,275 @@
 //! Configuration types for Mermaid parsing.
 
+mod builder;
+
+use std::collections::{HashMap, HashSet};
+
 use serde::{Deserialize, Serialize};
 
+use crate::ast::{ComplexityBudgets, Span};
+use crate::detector::DiagramType;
+use crate::diagnostic::{Diagnostic, DiagnosticCode, Severity, DEFAULT_DOCS_BASE_URL};
+
+pub use builder::ParseOptionsBuilder;
+
+/// Layout engines Mermaid accepts for the `layout` setting.
+const LAYOUT_ENGINES: &[&str] = &["dagre", "elk"];
+
 /// Options for parsing a Mermaid diagram.
+///
+/// Fields are added as new checks are, so the struct can't be built with a
+/// literal outside this crate: use [`ParseOptions::builder`], or start from
+/// [`ParseOptions::default`] and chain the `with_*` methods.
+///
+/// ```
+/// use mermaid_linter::{DiagnosticCode, ParseOptions, Severity};
+///
+/// let options = ParseOptions::builder()
+///     .recover(true)
+///     .warn_duplicate_edges(true)
+///     .severity_override(DiagnosticCode::DuplicateDefinition, Severity::Error)
+///     .build();
+/// assert!(options.recover && options.warn_duplicate_edges);
+/// ```
 #[derive(Debug, Clone, Default)]
+#[non_exhaustive]
 pub struct ParseOptions {
     /// Base configuration to use for parsing.
     pub base_config: Option<MermaidConfig>,
     /// Whether to suppress errors and return ok=false instead of throwing.
     pub suppress_errors: bool,
+    /// Whether to return the preprocessed source in the parse result.
+    pub include_preprocessed: bool,
+    /// Whether to only check the syntax, skipping the semantic validation
+    /// passes (undefined references, duplicate definitions and the opt-in
+    /// lints). Faster when only whether a diagram parses matters.
+    pub syntax_only: bool,
+    /// Whether to return the partial AST when parsing fails. The semantic
+    /// validation passes also run on the partial AST, and their warnings are
+    /// reported alongside the parse errors.
+    pub recover: bool,
+    /// Whether to report legacy diagram types as their v2 equivalents in
+    /// `ParseResult::diagram_type`. Parser selection is unaffected.
+    pub normalize_diagram_types: bool,
+    /// Severities that replace the built-in severity of a diagnostic code.
+    ///
+    /// Applied to parser and validation diagnostics before `ok` is computed,
+    /// so an error downgraded to a warning no longer fails the parse and a
+    /// warning promoted to an error does. Parsers without error recovery
+    /// still return no AST after an error, so downgrading their errors
+    /// cannot make the parse succeed.
+    pub severity_overrides: HashMap<DiagnosticCode, Severity>,
+    /// Diagnostic codes that are not reported at all. Like severity
+    /// overrides, applied before `ok` is computed.
+    pub disabled_codes: HashSet<DiagnosticCode>,
+    /// Whether to warn about flowchart edges that exactly repeat an earlier
+    /// edge (same source, target, link type and label). Off by default.
+    pub warn_duplicate_edges: bool,
+    /// Whether to suggest a `classDef` when three or more flowchart `style`
+    /// statements apply the same styles. Off by default.
+    pub hint_repeated_styles: bool,
+    /// Whether to warn about flowchart link endpoints that are neither a
+    /// declared node nor a subgraph id, which are often typos. Off by
+    /// default.
+    pub warn_undeclared_endpoints: bool,
+    /// Whether to warn about flowchart `classDef` definitions that no node
+    /// uses (`unused-classdef`). `classDef default` is never reported. Off
+    /// by default.
+    pub warn_unused_class_defs: bool,
+    /// Whether to warn about sequence participants that no message, note
+    /// or activation refers to (`unused-participant`). Off by default.
+    pub warn_unused_participants: bool,
+    /// Whether to flag class diagram `link` and `callback` statements,
+    /// with the equivalent `click` statement as a fix
+    /// (`prefer-click-syntax`). Off by default.
+    pub prefer_click_syntax: bool,
+    /// Whether to warn about ids that differ only by case or invisible
+    /// characters, such as `UserService` and `Userservice`, in flowcharts,
+    /// state, class and sequence diagrams (`confusable-identifiers`). Off by
+    /// default.
+    pub warn_confusable_identifiers: bool,
+    /// The ER attribute types to allow; an attribute of any other type gets
+    /// a warning, with generic types checked on their base name. `None`,
+    /// the default, allows any type.
+    pub er_attribute_types: Option<Vec<String>>,
+    /// Limits on the diagram's size and nesting; each exceeded budget gets
+    /// a warning at the diagram declaration. None are set by default.
+    pub budgets: ComplexityBudgets,
+    /// Whether to set a `render_id` property on flowchart nodes and links
+    /// and sequence participants, holding the element id Mermaid would give
+    /// them in the rendered SVG. Best effort; off by default.
+    pub compute_render_ids: bool,
+    /// The Mermaid release whose render id scheme to follow.
+    pub mermaid_version: MermaidVersion,
+    /// Where diagnostic codes are documented, for teams hosting their own
+    /// docs; see [`DiagnosticCode::doc_url`]. `None` links to this crate's
+    /// rules page.
+    pub docs_base_url: Option<String>,
+    /// Whether to record every configuration field set by the base config,
+    /// frontmatter and directives, in merge order, in
+    /// `ParseResult::config_trace`. Off by default.
+    pub trace_config: bool,
+}
+
+/// A Mermaid release, for behavior that differs between releases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MermaidVersion {
+    V10,
+    #[default]
+    V11,
 }
 
 impl ParseOptions {
+    /// Returns a builder starting from the default options.
+    pub fn builder() -> ParseOptionsBuilder {
+        ParseOptionsBuilder::default()
+    }
+
     /// Creates new parse options with a base configuration.
     pub fn with_config(config: MermaidConfig) -> Self {
         Self {
             base_config: Some(config),
-            suppress_errors: false,
+            ..Default::default()
         }
     }
+
+    /// Enables returning the preprocessed source alongside the parse result.
+    pub fn with_preprocessed(mut self) -> Self {
+        self.include_preprocessed = true;
+        self
+    }
+
+    /// Skips the semantic validation passes.
+    pub fn with_syntax_only(mut self) -> Self {
+        self.syntax_only = true;
+        self
+    }
+
+    /// Enables recovery mode, keeping the partial AST of a failed parse.
+    pub fn with_recovery(mut self) -> Self {
+        self.recover = true;
+        self
+    }
+
+    /// Enables reporting a single canonical type for legacy and v2 diagrams.
+    pub fn with_normalized_types(mut self) -> Self {
+        self.normalize_diagram_types = true;
+        self
+    }
+
+    /// Enables warnings for exactly duplicated flowchart edges.
+    pub fn with_duplicate_edge_warnings(mut self) -> Self {
+        self.warn_duplicate_edges = true;
+        self
+    }
+
+    /// Enables hints for flowchart styles that could be a `classDef`.
+    pub fn with_repeated_style_hints(mut self) -> Self {
+        self.hint_repeated_styles = true;
+        self
+    }
+
+    /// Enables warnings for flowchart link endpoints that are not a
+    /// declared node or a subgraph.
+    pub fn with_undeclared_endpoint_warnings(mut self) -> Self {
+        self.warn_undeclared_endpoints = true;
+        self
+    }
+
+    /// Enables warnings for flowchart classes that are defined but never
+    /// applied.
+    pub fn with_unused_class_def_warnings(mut self) -> Self {
+        self.warn_unused_class_defs = true;
+        self
+    }
+
+    /// Enables warnings for sequence participants that are declared but
+    /// never used.
+    pub fn with_unused_participant_warnings(mut self) -> Self {
+        self.warn_unused_participants = true;
+        self
+    }
+
+    /// Enables warnings for class diagram `link` and `callback` statements
+    /// that could be written as `click`.
+    pub fn with_click_syntax_warnings(mut self) -> Self {
+        self.prefer_click_syntax = true;
+        self
+    }
+
+    /// Enables warnings for ids that differ only by case or invisible
+    /// characters.
+    pub fn with_confusable_identifier_warnings(mut self) -> Self {
+        self.warn_confusable_identifiers = true;
+        self
+    }
+
+    /// Enables warnings for ER attributes whose type is not one of `types`.
+    pub fn with_er_attribute_types<I, S>(mut self, types: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.er_attribute_types = Some(types.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets the complexity budgets to warn about.
+    pub fn with_budgets(mut self, budgets: ComplexityBudgets) -> Self {
+        self.budgets = budgets;
+        self
+    }
+
+    /// Enables render ids following the scheme of the given Mermaid release.
+    pub fn with_render_ids(mut self, version: MermaidVersion) -> Self {
+        self.compute_render_ids = true;
+        self.mermaid_version = version;
+        self
+    }
+
+    /// Links diagnostic codes to pages under `url`.
+    pub fn with_docs_base_url(mut self, url: impl Into<String>) -> Self {
+        self.docs_base_url = Some(url.into());
+        self
+    }
+
+    /// Returns the base for documentation URLs: the configured one, or
+    /// [`DEFAULT_DOCS_BASE_URL`].
+    pub fn docs_base_url(&self) -> &str {
+        self.docs_base_url.as_deref().unwrap_or(DEFAULT_DOCS_BASE_URL)
+    }
+
+    /// Enables recording where each configuration value came from.
+    pub fn with_config_trace(mut self) -> Self {
+        self.trace_config = true;
+        self
+    }
+
+    /// Reports diagnostics with the given code at `severity`. A code
+    /// disabled earlier is reported again.
+    pub fn with_severity_override(mut self, code: DiagnosticCode, severity: Severity) -> Self {
+        self.disabled_codes.remove(&code);
+        self.severity_overrides.insert(code, severity);
+        self
+    }
+
+    /// Stops reporting diagnostics with the given code, dropping any earlier
+    /// severity override.
+    pub fn with_code_disabled(mut self, code: DiagnosticCode) -> Self {
+        self.severity_overrides.remove(&code);
+        self.disabled_codes.insert(code);
+        self
+    }
 }
 
 /// Mermaid configuration.
 ///
 /// This mirrors relevant parts of Mermaid's configuration that affect parsing behavior.
+/// It and its per-diagram parts are non-exhaustive: build them from
+/// `Default` or by deserializing.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[non_exhaustive]
 pub struct MermaidConfig {
     /// Flowchart-specific configuration.
     #[serde(default)]
@@ -47,9 +291,15 @@ pub struct MermaidConfig {
     #[serde(default)]
     pub wrap: bool,
 
-    /// General layout engine.
+    /// General layout engine: "dagre" or "elk".
     #[serde(default)]
     pub layout: Option<String>,
+
+    /// The diagram type to assume when the text has no recognized header,
+    /// as a header keyword (`sequenceDiagram`) or a type name (`sequence`).
+    /// Not a Mermaid setting; see [`MermaidConfig::diagram_type_hint`].
+    #[serde(default)]
+    pub diagram_type: Option<String>,
 }
 
 impl MermaidConfig {
@@ -83,12 +333,131 @@ impl MermaidConfig {
         if other.layout.is_some() {
             self.layout = other.layout.clone();
         }
+        if other.diagram_type.is_some() {
+            self.diagram_type = other.diagram_type.clone();
+        }
+    }
+
+    /// Returns the fields `merge` would copy from this config, as
+    /// `(key, value)` pairs with the keys spelled as in a config file.
+    pub(crate) fn set_fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = Vec::new();
+        let optional = [
+            ("flowchart.defaultRenderer", &self.flowchart.default_renderer),
+            ("class.defaultRenderer", &self.class.default_renderer),
+            ("state.defaultRenderer", &self.state.default_renderer),
+            ("gantt.displayMode", &self.gantt.display_mode),
+        ];
+        for (key, value) in optional {
+            if let Some(value) = value {
+                fields.push((key, value.clone()));
+            }
+        }
+        if self.wrap {
+            fields.push(("wrap", "true".to_string()));
+        }
+        for (key, value) in [("layout", &self.layout), ("diagramType", &self.diagram_type)] {
+            if let Some(value) = value {
+                fields.push((key, value.clone()));
+            }
+        }
+        fields
+    }
+
+    /// Returns the diagram type `diagram_type` names, if any.
+    ///
+    /// Detection falls back to it for text without a recognized header; a
+    /// header always wins.
+    pub fn diagram_type_hint(&self) -> Option<DiagramType> {
+        let hint = self.diagram_type.as_deref()?.trim();
+        DiagramType::from_header_keyword(hint)
+            .or_else(|| DiagramType::all().iter().copied().find(|t| t.as_str().eq_ignore_ascii_case(hint)))
+            .filter(|t| !matches!(t, DiagramType::Error | DiagramType::BadFrontmatter))
+    }
+
+    /// Checks values Mermaid would reject or ignore.
+    ///
+    /// The settings come from frontmatter or directives, which are removed
+    /// before parsing, so the diagnostics have an empty span at the start of
+    /// the diagram.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        if let Some(layout) = self.layout.as_deref() {
+            if !LAYOUT_ENGINES.contains(&layout) {
+                diagnostics.push(
+                    Diagnostic::warning(
+                        DiagnosticCode::InvalidValue,
+                        format!("Unknown layout '{}'", layout),
+                        Span::default(),
+                    )
+                    .with_note(format!("supported layouts are {}", LAYOUT_ENGINES.join(" and "))),
+                );
+            }
+        }
+        if let Some(hint) = self.diagram_type.as_deref().filter(|_| self.diagram_type_hint().is_none()) {
+            diagnostics.push(
+                Diagnostic::warning(
+                    DiagnosticCode::InvalidValue,
+                    format!("Unknown diagram type '{}' in diagramType", hint),
+                    Span::default(),
+                )
+                .with_note("use a header keyword such as 'sequenceDiagram', or a type name such as 'sequence'"),
+            );
+        }
+        diagnostics
+    }
+}
+
+/// Where a configuration value came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigSource {
+    /// [`ParseOptions::base_config`].
+    Base,
+    /// The frontmatter block.
+    Frontmatter(Span),
+    /// An `init` or `wrap` directive.
+    Directive(Span),
+}
+
+impl std::fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigSource::Base => write!(f, "base config"),
+            ConfigSource::Frontmatter(_) => write!(f, "frontmatter"),
+            ConfigSource::Directive(_) => write!(f, "directive"),
+        }
+    }
+}
+
+/// A configuration field set while merging the base config, frontmatter
+/// and directives; see [`ParseOptions::trace_config`].
+///
+/// Spans index into the source with line endings normalized to `\n`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigChange {
+    /// The field, spelled as in a config file (`flowchart.defaultRenderer`).
+    pub key: String,
+    /// The value it was set to.
+    pub value: String,
+    /// Where the value came from.
+    pub source: ConfigSource,
+}
+
+impl ConfigChange {
+    /// Returns one change per field set in `config`.
+    pub(crate) fn from_config(config: &MermaidConfig, source: ConfigSource) -> Vec<ConfigChange> {
+        config
+            .set_fields()
+            .into_iter()
+            .map(|(key, value)| ConfigChange { key: key.to_string(), value, source })
+            .collect()
     }
 }
 
 /// Flowchart-specific configuration.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[non_exhaustive]
 pub struct FlowchartConfig {
     /// Default renderer for flowcharts.
     /// Can be "dagre-d3", "dagre-wrapper", or "elk".
@@ -99,6 +468,7 @@ pub struct FlowchartConfig {
 /// Class diagram-specific configuration.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[non_exhaustive]
 pub struct ClassConfig {
     /// Default renderer for class diagrams.
     #[serde(default)]
@@ -108,6 +478,7 @@ pub struct ClassConfig {
 /// State diagram-specific configuration.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[non_exhaustive]
 pub struct StateConfig {
     /// Default renderer for state diagrams.
     #[serde(default)]
@@ -117,6 +488,7 @@ pub struct StateConfig {
 /// Gantt chart-specific configuration.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[non_exhaustive]
 pub struct GanttConfig {
     /// Display mode for Gantt charts.
     #[serde(default)]
@@ -154,6 +526,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_options_defaults_and_ordering() {
+        let options = ParseOptions::default();
+        assert!(options.base_config.is_none());
+        assert!(!options.recover && !options.syntax_only && !options.trace_config);
+        assert!(options.severity_overrides.is_empty() && options.disabled_codes.is_empty());
+        assert_eq!(options.budgets, ComplexityBudgets::default());
+        assert_eq!(options.docs_base_url(), DEFAULT_DOCS_BASE_URL);
+
+        // Later calls win, and each method only touches its own fields
+        let code = DiagnosticCode::DuplicateDefinition;
+        let options = ParseOptions::with_config(MermaidConfig::default())
+            .with_severity_override(code, Severity::Warning)
+            .with_render_ids(MermaidVersion::V10)
+            .with_severity_override(code, Severity::Error)
+            .with_render_ids(MermaidVersion::V11)
+            .with_code_disabled(DiagnosticCode::InvalidValue);
+        assert!(options.base_config.is_some());
+        assert_eq!(options.severity_overrides.get(&code), Some(&Severity::Error));
+        assert_eq!(options.mermaid_version, MermaidVersion::V11);
+        assert!(options.compute_render_ids);
+        assert!(options.disabled_codes.contains(&DiagnosticCode::InvalidValue));
+        assert!(!options.recover);
+    }
+
+    #[test]
+    fn test_validate_layout() {
+        let config = |layout: &str| MermaidConfig {
+            layout: Some(layout.to_string()),
+            ..Default::default()
+        };
+        assert!(config("elk").validate().is_empty());
+        assert!(MermaidConfig::default().validate().is_empty());
+
+        let diagnostics = config("grid").validate();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, DiagnosticCode::InvalidValue);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+    }
+
     #[test]
     fn test_config_deserialize() {
         let json = r#"{
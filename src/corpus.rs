@@ -0,0 +1,31 @@
+//! The fixture corpus from `tests/fixtures`, available with the
+//! `test-corpus` feature.
+//!
+//! The list is generated at build time, so downstream crates, benches and
+//! property tests can run over the same diagrams as the golden tests.
+//!
+//! ```
+//! for entry in mermaid_linter::corpus::all() {
+//!     let _ = mermaid_linter::parse(entry.source, None);
+//! }
+//! ```
+
+/// One fixture diagram.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CorpusEntry {
+    /// The fixture path below `tests/fixtures`, without the `.mmd`
+    /// extension, e.g. `flowchart/simple`.
+    pub name: &'static str,
+    /// The fixture directory, which names the diagram type the fixture is
+    /// for (`flowchart`, `sequence`, ...) or a group of special cases.
+    pub diagram_type_hint: &'static str,
+    /// The diagram source.
+    pub source: &'static str,
+}
+
+include!(concat!(env!("OUT_DIR"), "/corpus.rs"));
+
+/// Returns every fixture, sorted by name.
+pub fn all() -> &'static [CorpusEntry] {
+    ENTRIES
+}
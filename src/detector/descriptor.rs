@@ -0,0 +1,228 @@
+//! The diagram type table.
+//!
+//! Every diagram type has one [`DiagramDescriptor`], listed in Mermaid.js
+//! detection order. Detection, [`DiagramType::all`], the per-type predicates
+//! and header keyword lookups all read this table, so adding a diagram type
+//! means adding one entry.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::DiagramType;
+use crate::config::MermaidConfig;
+
+/// How a descriptor recognizes its diagram in the preprocessed text.
+#[derive(Debug)]
+enum Matcher {
+    /// The whole text is the keyword, ignoring case.
+    WholeText,
+    /// The text starts with the keyword.
+    Prefix,
+    /// The text starts with one of the keywords as a whole word, ignoring
+    /// case.
+    Header(Regex),
+}
+
+/// Describes a diagram type: its header keywords and properties.
+#[derive(Debug)]
+pub struct DiagramDescriptor {
+    /// The diagram type.
+    pub diagram_type: DiagramType,
+    /// The string identifier returned by [`DiagramType::as_str`].
+    pub name: &'static str,
+    /// The keywords that start a diagram of this type.
+    pub header_keywords: &'static [&'static str],
+    /// Whether the diagram requires entity encoding.
+    pub needs_entity_encoding: bool,
+    /// Whether Mermaid parses the diagram with a Langium grammar.
+    pub uses_langium: bool,
+    /// Whether the diagram is a "large feature" in Mermaid.
+    pub is_large_feature: bool,
+    matcher: Matcher,
+    /// Picks the detected type from the config, for keywords whose renderer
+    /// can be overridden.
+    resolve: Option<fn(&MermaidConfig) -> DiagramType>,
+}
+
+impl DiagramDescriptor {
+    /// Returns the detected type if `text` (trimmed, preprocessed code)
+    /// starts with this descriptor's header.
+    pub(super) fn detect(&self, text: &str, config: &MermaidConfig) -> Option<DiagramType> {
+        let matched = match &self.matcher {
+            Matcher::WholeText => self.header_keywords.iter().any(|k| text.eq_ignore_ascii_case(k)),
+            Matcher::Prefix => self.header_keywords.iter().any(|k| text.starts_with(k)),
+            Matcher::Header(regex) => regex.is_match(text),
+        };
+        matched.then(|| self.resolve.map_or(self.diagram_type, |resolve| resolve(config)))
+    }
+}
+
+/// Returns the descriptors of all diagram types, in detection order.
+pub fn descriptors() -> &'static [DiagramDescriptor] {
+    &DESCRIPTORS
+}
+
+/// Returns every header keyword, in detection order.
+pub fn header_keywords() -> impl Iterator<Item = &'static str> {
+    DESCRIPTORS.iter().flat_map(|d| d.header_keywords.iter().copied())
+}
+
+static DESCRIPTORS: Lazy<Vec<DiagramDescriptor>> = Lazy::new(|| {
+    use DiagramType::*;
+
+    vec![
+        special(Error, &["error"], Matcher::WholeText),
+        special(BadFrontmatter, &["---"], Matcher::Prefix),
+        // Large features; these are typically enabled by config, but we
+        // always support them
+        DiagramDescriptor {
+            needs_entity_encoding: true,
+            is_large_feature: true,
+            ..header(FlowchartElk, "flowchart-elk", &["flowchart-elk"])
+        },
+        DiagramDescriptor {
+            is_large_feature: true,
+            ..header(Mindmap, "mindmap", &["mindmap"])
+        },
+        DiagramDescriptor {
+            uses_langium: true,
+            is_large_feature: true,
+            ..header(Architecture, "architecture", &["architecture", "architecture-beta"])
+        },
+        header(
+            C4,
+            "c4",
+            &["C4Context", "C4Container", "C4Component", "C4Dynamic", "C4Deployment"],
+        ),
+        header(Kanban, "kanban", &["kanban"]),
+        // Class diagrams: v2 first, then legacy
+        header(ClassDiagram, "classDiagram", &["classDiagram-v2"]),
+        DiagramDescriptor {
+            resolve: Some(resolve_class),
+            ..header(Class, "class", &["classDiagram"])
+        },
+        header(Er, "er", &["erDiagram"]),
+        header(Gantt, "gantt", &["gantt"]),
+        DiagramDescriptor {
+            uses_langium: true,
+            ..header(Info, "info", &["info"])
+        },
+        DiagramDescriptor {
+            uses_langium: true,
+            ..header(Pie, "pie", &["pie"])
+        },
+        header(Requirement, "requirement", &["requirement", "requirementDiagram"]),
+        header(Sequence, "sequence", &["sequenceDiagram"]),
+        // Flowcharts: the 'flowchart' keyword first, then 'graph'
+        DiagramDescriptor {
+            needs_entity_encoding: true,
+            resolve: Some(resolve_flowchart),
+            ..header(FlowchartV2, "flowchart-v2", &["flowchart"])
+        },
+        DiagramDescriptor {
+            needs_entity_encoding: true,
+            resolve: Some(resolve_graph),
+            ..header(Flowchart, "flowchart", &["graph"])
+        },
+        header(Timeline, "timeline", &["timeline"]),
+        DiagramDescriptor {
+            uses_langium: true,
+            ..header(GitGraph, "gitGraph", &["gitGraph"])
+        },
+        // State diagrams: v2 first, then legacy
+        header(StateDiagram, "stateDiagram", &["stateDiagram-v2"]),
+        DiagramDescriptor {
+            resolve: Some(resolve_state),
+            ..header(State, "state", &["stateDiagram"])
+        },
+        header(Journey, "journey", &["journey"]),
+        header(QuadrantChart, "quadrantChart", &["quadrantChart"]),
+        header(Sankey, "sankey", &["sankey", "sankey-beta"]),
+        DiagramDescriptor {
+            uses_langium: true,
+            ..header(Packet, "packet", &["packet", "packet-beta"])
+        },
+        header(XyChart, "xychart", &["xychart", "xychart-beta"]),
+        header(Block, "block", &["block", "block-beta"]),
+        DiagramDescriptor {
+            uses_langium: true,
+            ..header(Radar, "radar", &["radar", "radar-beta"])
+        },
+        DiagramDescriptor {
+            uses_langium: true,
+            ..header(Treemap, "treemap", &["treemap"])
+        },
+    ]
+});
+
+/// A pseudo-diagram recognized by `matcher` rather than a header.
+fn special(diagram_type: DiagramType, keywords: &'static [&'static str], matcher: Matcher) -> DiagramDescriptor {
+    DiagramDescriptor {
+        matcher,
+        ..header(diagram_type, keywords[0], keywords)
+    }
+}
+
+/// A diagram detected by its header keywords, with every property off.
+fn header(
+    diagram_type: DiagramType,
+    name: &'static str,
+    header_keywords: &'static [&'static str],
+) -> DiagramDescriptor {
+    let alternatives: Vec<_> = header_keywords.iter().map(|k| regex::escape(k)).collect();
+    let pattern = format!(r"(?i)^\s*(?:{})\b", alternatives.join("|"));
+    DiagramDescriptor {
+        diagram_type,
+        name,
+        header_keywords,
+        needs_entity_encoding: false,
+        uses_langium: false,
+        is_large_feature: false,
+        matcher: Matcher::Header(Regex::new(&pattern).unwrap()),
+        resolve: None,
+    }
+}
+
+fn resolve_class(config: &MermaidConfig) -> DiagramType {
+    if config.class.default_renderer.as_deref() == Some("dagre-wrapper") {
+        log::debug!("'classDiagram' matched; class.defaultRenderer = dagre-wrapper selects classDiagram-v2");
+        return DiagramType::ClassDiagram;
+    }
+    log::debug!("'classDiagram' matched; no renderer override, using legacy class");
+    DiagramType::Class
+}
+
+fn resolve_flowchart(config: &MermaidConfig) -> DiagramType {
+    if config.flowchart.default_renderer.as_deref() == Some("elk") || config.layout.as_deref() == Some("elk") {
+        log::debug!("'flowchart' matched; elk renderer/layout selects flowchart-elk");
+        return DiagramType::FlowchartElk;
+    }
+    log::debug!("'flowchart' matched; using flowchart-v2");
+    DiagramType::FlowchartV2
+}
+
+fn resolve_graph(config: &MermaidConfig) -> DiagramType {
+    match config.flowchart.default_renderer.as_deref() {
+        Some("elk") => {
+            log::debug!("'graph' matched; flowchart.defaultRenderer = elk selects flowchart-elk");
+            DiagramType::FlowchartElk
+        }
+        Some("dagre-wrapper") => {
+            log::debug!("'graph' matched; flowchart.defaultRenderer = dagre-wrapper selects flowchart-v2");
+            DiagramType::FlowchartV2
+        }
+        _ => {
+            log::debug!("'graph' matched; no renderer override, using legacy flowchart");
+            DiagramType::Flowchart
+        }
+    }
+}
+
+fn resolve_state(config: &MermaidConfig) -> DiagramType {
+    if config.state.default_renderer.as_deref() == Some("dagre-wrapper") {
+        log::debug!("'stateDiagram' matched; state.defaultRenderer = dagre-wrapper selects stateDiagram-v2");
+        return DiagramType::StateDiagram;
+    }
+    log::debug!("'stateDiagram' matched; no renderer override, using legacy state");
+    DiagramType::State
+}
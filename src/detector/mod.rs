@@ -3,10 +3,14 @@
 //! This module detects the type of Mermaid diagram from the source text.
 //! The detection order matches Mermaid.js to ensure compatibility.
 
+mod descriptor;
 mod detectors;
 
+pub use descriptor::{descriptors, header_keywords, DiagramDescriptor};
 pub use detectors::detect_type;
+pub(crate) use detectors::detect_header;
 
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 
 /// Supported Mermaid diagram types.
@@ -80,104 +84,110 @@ pub enum DiagramType {
 }
 
 impl DiagramType {
+    /// Returns the descriptor of this diagram type.
+    pub fn descriptor(&self) -> &'static DiagramDescriptor {
+        descriptors()
+            .iter()
+            .find(|d| d.diagram_type == *self)
+            .expect("every diagram type has a descriptor")
+    }
+
+    /// Returns the diagram type a header keyword such as `sequenceDiagram`
+    /// or `graph` starts, ignoring case.
+    ///
+    /// Keywords whose type depends on the config resolve to their default:
+    /// `graph` is `Flowchart` and `classDiagram` is `Class`.
+    pub fn from_header_keyword(keyword: &str) -> Option<DiagramType> {
+        descriptors()
+            .iter()
+            .find(|d| d.header_keywords.iter().any(|k| k.eq_ignore_ascii_case(keyword)))
+            .map(|d| d.diagram_type)
+    }
+
     /// Returns the string identifier for this diagram type.
     pub fn as_str(&self) -> &'static str {
+        self.descriptor().name
+    }
+
+    /// Returns a human-readable name, such as "Entity-relationship diagram".
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            DiagramType::Error => "Error",
+            DiagramType::BadFrontmatter => "Bad frontmatter",
+            DiagramType::Flowchart => "Flowchart",
+            DiagramType::FlowchartV2 => "Flowchart v2",
+            DiagramType::FlowchartElk => "Flowchart (ELK layout)",
+            DiagramType::Sequence => "Sequence diagram",
+            DiagramType::Class => "Class diagram",
+            DiagramType::ClassDiagram => "Class diagram v2",
+            DiagramType::State => "State diagram",
+            DiagramType::StateDiagram => "State diagram v2",
+            DiagramType::Er => "Entity-relationship diagram",
+            DiagramType::Gantt => "Gantt chart",
+            DiagramType::Journey => "User journey",
+            DiagramType::Requirement => "Requirement diagram",
+            DiagramType::GitGraph => "Git graph",
+            DiagramType::XyChart => "XY chart",
+            DiagramType::QuadrantChart => "Quadrant chart",
+            DiagramType::C4 => "C4 diagram",
+            DiagramType::Packet => "Packet diagram",
+            DiagramType::Treemap => "Treemap",
+            DiagramType::Sankey => "Sankey diagram",
+            DiagramType::Kanban => "Kanban board",
+            DiagramType::Block => "Block diagram",
+            DiagramType::Radar => "Radar chart",
+            DiagramType::Pie => "Pie chart",
+            DiagramType::Info => "Info",
+            DiagramType::Timeline => "Timeline",
+            DiagramType::Mindmap => "Mindmap",
+            DiagramType::Architecture => "Architecture diagram",
+        }
+    }
+
+    /// Returns true for the `Error` and `BadFrontmatter` pseudo-diagrams,
+    /// which mark unusable input rather than a kind of diagram.
+    pub fn is_pseudo(&self) -> bool {
+        matches!(self, DiagramType::Error | DiagramType::BadFrontmatter)
+    }
+
+    /// Returns true if the linter has a parser for this type. Types without
+    /// one are accepted as long as they are detected, with a stub AST, no
+    /// syntax checks and an info diagnostic saying so.
+    pub fn is_fully_parsed(&self) -> bool {
+        crate::parser::parser_for(*self).is_some_and(|parser| parser.name() != "stub")
+    }
+
+    /// Returns the canonical type, collapsing legacy variants into their v2
+    /// equivalents (`Flowchart`, `Class` and `State`).
+    pub fn canonical(&self) -> DiagramType {
         match self {
-            DiagramType::Error => "error",
-            DiagramType::BadFrontmatter => "---",
-            DiagramType::Flowchart => "flowchart",
-            DiagramType::FlowchartV2 => "flowchart-v2",
-            DiagramType::FlowchartElk => "flowchart-elk",
-            DiagramType::Sequence => "sequence",
-            DiagramType::Class => "class",
-            DiagramType::ClassDiagram => "classDiagram",
-            DiagramType::State => "state",
-            DiagramType::StateDiagram => "stateDiagram",
-            DiagramType::Er => "er",
-            DiagramType::Gantt => "gantt",
-            DiagramType::Journey => "journey",
-            DiagramType::Requirement => "requirement",
-            DiagramType::GitGraph => "gitGraph",
-            DiagramType::XyChart => "xychart",
-            DiagramType::QuadrantChart => "quadrantChart",
-            DiagramType::C4 => "c4",
-            DiagramType::Packet => "packet",
-            DiagramType::Treemap => "treemap",
-            DiagramType::Sankey => "sankey",
-            DiagramType::Kanban => "kanban",
-            DiagramType::Block => "block",
-            DiagramType::Radar => "radar",
-            DiagramType::Pie => "pie",
-            DiagramType::Info => "info",
-            DiagramType::Timeline => "timeline",
-            DiagramType::Mindmap => "mindmap",
-            DiagramType::Architecture => "architecture",
+            DiagramType::Flowchart => DiagramType::FlowchartV2,
+            DiagramType::Class => DiagramType::ClassDiagram,
+            DiagramType::State => DiagramType::StateDiagram,
+            other => *other,
         }
     }
 
     /// Returns true if this diagram type requires entity encoding.
     pub fn needs_entity_encoding(&self) -> bool {
-        matches!(
-            self,
-            DiagramType::Flowchart | DiagramType::FlowchartV2 | DiagramType::FlowchartElk
-        )
+        self.descriptor().needs_entity_encoding
     }
 
     /// Returns true if this is a "large feature" diagram in Mermaid.
     pub fn is_large_feature(&self) -> bool {
-        matches!(
-            self,
-            DiagramType::FlowchartElk | DiagramType::Mindmap | DiagramType::Architecture
-        )
+        self.descriptor().is_large_feature
     }
 
     /// Returns true if this diagram uses Langium grammar (vs Jison).
     pub fn uses_langium(&self) -> bool {
-        matches!(
-            self,
-            DiagramType::Pie
-                | DiagramType::Info
-                | DiagramType::Packet
-                | DiagramType::GitGraph
-                | DiagramType::Radar
-                | DiagramType::Architecture
-                | DiagramType::Treemap
-        )
-    }
-
-    /// Returns all supported diagram types.
+        self.descriptor().uses_langium
+    }
+
+    /// Returns all supported diagram types, in detection order.
     pub fn all() -> &'static [DiagramType] {
-        &[
-            DiagramType::Error,
-            DiagramType::BadFrontmatter,
-            DiagramType::Flowchart,
-            DiagramType::FlowchartV2,
-            DiagramType::FlowchartElk,
-            DiagramType::Sequence,
-            DiagramType::Class,
-            DiagramType::ClassDiagram,
-            DiagramType::State,
-            DiagramType::StateDiagram,
-            DiagramType::Er,
-            DiagramType::Gantt,
-            DiagramType::Journey,
-            DiagramType::Requirement,
-            DiagramType::GitGraph,
-            DiagramType::XyChart,
-            DiagramType::QuadrantChart,
-            DiagramType::C4,
-            DiagramType::Packet,
-            DiagramType::Treemap,
-            DiagramType::Sankey,
-            DiagramType::Kanban,
-            DiagramType::Block,
-            DiagramType::Radar,
-            DiagramType::Pie,
-            DiagramType::Info,
-            DiagramType::Timeline,
-            DiagramType::Mindmap,
-            DiagramType::Architecture,
-        ]
+        static ALL: Lazy<Vec<DiagramType>> =
+            Lazy::new(|| descriptors().iter().map(|d| d.diagram_type).collect());
+        &ALL
     }
 }
 
@@ -191,6 +201,16 @@ impl std::fmt::Display for DiagramType {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_is_fully_parsed() {
+        assert!(DiagramType::FlowchartV2.is_fully_parsed());
+        assert!(DiagramType::GitGraph.is_fully_parsed());
+        assert!(!DiagramType::Sankey.is_fully_parsed());
+        assert!(!DiagramType::Error.is_fully_parsed());
+        assert!(DiagramType::BadFrontmatter.is_pseudo());
+        assert_eq!(DiagramType::Er.display_name(), "Entity-relationship diagram");
+    }
+
     #[test]
     fn test_diagram_type_str() {
         assert_eq!(DiagramType::Flowchart.as_str(), "flowchart");
@@ -205,6 +225,38 @@ mod tests {
         assert!(!DiagramType::Sequence.needs_entity_encoding());
     }
 
+    #[test]
+    fn test_canonical() {
+        assert_eq!(DiagramType::Flowchart.canonical(), DiagramType::FlowchartV2);
+        assert_eq!(DiagramType::Class.canonical(), DiagramType::ClassDiagram);
+        assert_eq!(DiagramType::State.canonical(), DiagramType::StateDiagram);
+        assert_eq!(DiagramType::FlowchartElk.canonical(), DiagramType::FlowchartElk);
+    }
+
+    #[test]
+    fn test_from_header_keyword() {
+        assert_eq!(DiagramType::from_header_keyword("sequenceDiagram"), Some(DiagramType::Sequence));
+        assert_eq!(DiagramType::from_header_keyword("GRAPH"), Some(DiagramType::Flowchart));
+        assert_eq!(DiagramType::from_header_keyword("classDiagram"), Some(DiagramType::Class));
+        assert_eq!(DiagramType::from_header_keyword("xychart-beta"), Some(DiagramType::XyChart));
+        assert_eq!(DiagramType::from_header_keyword("flowchart-v2"), None);
+    }
+
+    #[test]
+    fn test_every_type_has_one_descriptor() {
+        let all = DiagramType::all();
+        assert_eq!(all.len(), 29);
+        for diagram_type in all {
+            assert_eq!(all.iter().filter(|t| *t == diagram_type).count(), 1);
+            assert_eq!(diagram_type.descriptor().diagram_type, *diagram_type);
+        }
+
+        let keywords: Vec<_> = header_keywords().collect();
+        for keyword in &keywords {
+            assert_eq!(keywords.iter().filter(|k| k.eq_ignore_ascii_case(keyword)).count(), 1, "{}", keyword);
+        }
+    }
+
     #[test]
     fn test_uses_langium() {
         assert!(DiagramType::Pie.uses_langium());
@@ -0,0 +1,109 @@
+//! Fluent construction of [`Diagnostic`]s.
+
+use crate::ast::Span;
+use crate::detector::DiagramType;
+
+use super::{Diagnostic, DiagnosticCode, RelatedDiagnostic, Severity, TextEdit};
+
+/// Builds a [`Diagnostic`] one field at a time; see [`Diagnostic::builder`].
+///
+/// The severity starts at the code's [`DiagnosticCode::default_severity`]
+/// and the span is empty at the start of the source until set.
+///
+/// ```
+/// use mermaid_linter::{Diagnostic, DiagnosticCode, Severity, Span};
+///
+/// let diagnostic = Diagnostic::builder(DiagnosticCode::SemanticError, "Node 'A' is unreachable")
+///     .severity(Severity::Warning)
+///     .span(Span::new(12, 13))
+///     .note("link it from the start node")
+///     .build();
+/// assert_eq!(diagnostic.severity, Severity::Warning);
+/// assert_eq!(diagnostic.notes, ["link it from the start node"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct DiagnosticBuilder {
+    diagnostic: Diagnostic,
+}
+
+impl DiagnosticBuilder {
+    pub(super) fn new(code: DiagnosticCode, message: String) -> Self {
+        Self {
+            diagnostic: Diagnostic::new(code, message, code.default_severity(), Span::default()),
+        }
+    }
+
+    /// Sets the severity.
+    pub fn severity(mut self, severity: Severity) -> Self {
+        self.diagnostic.severity = severity;
+        self
+    }
+
+    /// Sets the location in the source.
+    pub fn span(mut self, span: Span) -> Self {
+        self.diagnostic.span = span;
+        self
+    }
+
+    /// Sets the diagram type.
+    pub fn diagram_type(mut self, diagram_type: DiagramType) -> Self {
+        self.diagnostic.diagram_type = Some(diagram_type);
+        self
+    }
+
+    /// Adds a note.
+    pub fn note(mut self, note: impl Into<String>) -> Self {
+        self.diagnostic.notes.push(note.into());
+        self
+    }
+
+    /// Adds a related location.
+    pub fn related(mut self, related: RelatedDiagnostic) -> Self {
+        self.diagnostic.related.push(related);
+        self
+    }
+
+    /// Adds an edit that fixes the problem.
+    pub fn fix(mut self, fix: TextEdit) -> Self {
+        self.diagnostic.fixes.push(fix);
+        self
+    }
+
+    /// Returns the diagnostic.
+    pub fn build(self) -> Diagnostic {
+        self.diagnostic
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_defaults() {
+        let diagnostic = Diagnostic::builder(DiagnosticCode::DuplicateDefinition, "twice").build();
+        assert_eq!(diagnostic.severity, Severity::Warning);
+        assert_eq!(diagnostic.span, Span::default());
+        assert_eq!(diagnostic.message, "twice");
+        assert!(diagnostic.diagram_type.is_none() && diagnostic.notes.is_empty() && diagnostic.fixes.is_empty());
+    }
+
+    #[test]
+    fn test_builder_matches_constructors() {
+        let built = Diagnostic::builder(DiagnosticCode::InvalidSyntax, "bad")
+            .severity(Severity::Info)
+            .severity(Severity::Hint)
+            .span(Span::new(1, 2))
+            .note("first")
+            .note("second")
+            .related(RelatedDiagnostic::new("here", Span::new(0, 1)))
+            .fix(TextEdit::new(Span::new(1, 2), "x"))
+            .build();
+        let chained = Diagnostic::hint(DiagnosticCode::InvalidSyntax, "bad", Span::new(1, 2))
+            .with_note("first")
+            .with_note("second")
+            .with_related(RelatedDiagnostic::new("here", Span::new(0, 1)))
+            .with_fix(TextEdit::new(Span::new(1, 2), "x"));
+        assert_eq!(format!("{:?}", built), format!("{:?}", chained));
+    }
+}
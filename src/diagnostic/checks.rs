@@ -0,0 +1,161 @@
+//! Checks that are off by default.
+//!
+//! Each check reports under an existing [`DiagnosticCode`], at the severity
+//! it defines here rather than the code's default, and runs only when
+//! enabled on [`ParseOptions`].
+
+use super::{Diagnostic, DiagnosticCode, Severity};
+use crate::ast::Span;
+use crate::config::ParseOptions;
+
+/// A check that runs only when enabled on [`ParseOptions`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OptInCheck {
+    /// Flowchart edges that exactly repeat an earlier edge.
+    DuplicateEdges,
+    /// Flowchart `style` statements that could share a `classDef`.
+    RepeatedStyles,
+    /// Flowchart link endpoints that are neither a node nor a subgraph.
+    UndeclaredEndpoints,
+    /// Flowchart `classDef` definitions that no node uses.
+    UnusedClassDef,
+    /// Sequence participants that nothing refers to.
+    UnusedParticipant,
+    /// Class diagram `link` and `callback` statements.
+    PreferClickSyntax,
+    /// Ids that differ only by case or invisible characters.
+    ConfusableIdentifiers,
+    /// ER attributes of a type outside an allowed list.
+    ErAttributeTypes,
+}
+
+impl OptInCheck {
+    /// Returns every opt-in check, in the order they are documented.
+    pub fn all() -> &'static [OptInCheck] {
+        &[
+            OptInCheck::DuplicateEdges,
+            OptInCheck::RepeatedStyles,
+            OptInCheck::UndeclaredEndpoints,
+            OptInCheck::UnusedClassDef,
+            OptInCheck::UnusedParticipant,
+            OptInCheck::PreferClickSyntax,
+            OptInCheck::ConfusableIdentifiers,
+            OptInCheck::ErAttributeTypes,
+        ]
+    }
+
+    /// Returns the check's name, which is also its anchor in `docs/rules.md`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            OptInCheck::DuplicateEdges => "duplicate-edges",
+            OptInCheck::RepeatedStyles => "repeated-styles",
+            OptInCheck::UndeclaredEndpoints => "undeclared-endpoints",
+            OptInCheck::UnusedClassDef => "unused-classdef",
+            OptInCheck::UnusedParticipant => "unused-participant",
+            OptInCheck::PreferClickSyntax => "prefer-click-syntax",
+            OptInCheck::ConfusableIdentifiers => "confusable-identifiers",
+            OptInCheck::ErAttributeTypes => "er-attribute-types",
+        }
+    }
+
+    /// Returns the code the check reports under.
+    pub fn code(&self) -> DiagnosticCode {
+        match self {
+            OptInCheck::DuplicateEdges | OptInCheck::RepeatedStyles | OptInCheck::ConfusableIdentifiers => {
+                DiagnosticCode::DuplicateDefinition
+            }
+            OptInCheck::UndeclaredEndpoints => DiagnosticCode::UndefinedReference,
+            OptInCheck::UnusedClassDef | OptInCheck::UnusedParticipant => DiagnosticCode::SemanticError,
+            OptInCheck::PreferClickSyntax => DiagnosticCode::InvalidSyntax,
+            OptInCheck::ErAttributeTypes => DiagnosticCode::InvalidValue,
+        }
+    }
+
+    /// Returns the severity the check reports at.
+    pub fn severity(&self) -> Severity {
+        match self {
+            OptInCheck::RepeatedStyles => Severity::Hint,
+            _ => Severity::Warning,
+        }
+    }
+
+    /// Returns the [`ParseOptions`] method that enables the check.
+    pub fn option(&self) -> &'static str {
+        match self {
+            OptInCheck::DuplicateEdges => "with_duplicate_edge_warnings",
+            OptInCheck::RepeatedStyles => "with_repeated_style_hints",
+            OptInCheck::UndeclaredEndpoints => "with_undeclared_endpoint_warnings",
+            OptInCheck::UnusedClassDef => "with_unused_class_def_warnings",
+            OptInCheck::UnusedParticipant => "with_unused_participant_warnings",
+            OptInCheck::PreferClickSyntax => "with_click_syntax_warnings",
+            OptInCheck::ConfusableIdentifiers => "with_confusable_identifier_warnings",
+            OptInCheck::ErAttributeTypes => "with_er_attribute_types",
+        }
+    }
+
+    /// Returns a one-line description of what the check reports.
+    pub fn description(&self) -> &'static str {
+        match self {
+            OptInCheck::DuplicateEdges => "A flowchart edge repeats an earlier edge exactly",
+            OptInCheck::RepeatedStyles => "Flowchart style statements repeat the same styles",
+            OptInCheck::UndeclaredEndpoints => "A flowchart link endpoint is not a declared node or subgraph",
+            OptInCheck::UnusedClassDef => "A flowchart classDef is never applied",
+            OptInCheck::UnusedParticipant => "A sequence participant is never used",
+            OptInCheck::PreferClickSyntax => "A class diagram link or callback could be written as click",
+            OptInCheck::ConfusableIdentifiers => "Ids differ only by case or invisible characters",
+            OptInCheck::ErAttributeTypes => "An ER attribute type is not in the allowed list",
+        }
+    }
+
+    /// Returns whether `options` enable the check.
+    pub fn is_enabled(&self, options: &ParseOptions) -> bool {
+        match self {
+            OptInCheck::DuplicateEdges => options.warn_duplicate_edges,
+            OptInCheck::RepeatedStyles => options.hint_repeated_styles,
+            OptInCheck::UndeclaredEndpoints => options.warn_undeclared_endpoints,
+            OptInCheck::UnusedClassDef => options.warn_unused_class_defs,
+            OptInCheck::UnusedParticipant => options.warn_unused_participants,
+            OptInCheck::PreferClickSyntax => options.prefer_click_syntax,
+            OptInCheck::ConfusableIdentifiers => options.warn_confusable_identifiers,
+            OptInCheck::ErAttributeTypes => options.er_attribute_types.is_some(),
+        }
+    }
+
+    /// Creates a diagnostic for the check, with its code and severity.
+    pub(crate) fn diagnostic(&self, message: impl Into<String>, span: Span) -> Diagnostic {
+        Diagnostic::new(self.code(), message.into(), self.severity(), span)
+    }
+}
+
+impl std::fmt::Display for OptInCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checks_off_by_default() {
+        let options = ParseOptions::default();
+        assert!(OptInCheck::all().iter().all(|check| !check.is_enabled(&options)));
+        let options = ParseOptions::default().with_repeated_style_hints();
+        assert!(OptInCheck::RepeatedStyles.is_enabled(&options));
+        assert_eq!(OptInCheck::RepeatedStyles.severity(), Severity::Hint);
+    }
+
+    #[test]
+    fn test_every_check_documented() {
+        let docs = include_str!("../../docs/rules.md");
+        for check in OptInCheck::all() {
+            assert!(
+                docs.contains(&format!("<a id=\"{}\"></a>\n### {}\n", check, check)),
+                "{} is not documented",
+                check
+            );
+            assert!(docs.contains(check.option()), "{} does not name its option", check);
+        }
+    }
+}
@@ -2,6 +2,15 @@
 
 use serde::{Deserialize, Serialize};
 
+use super::Severity;
+
+/// Page documenting every diagnostic code, with a section for each.
+pub const DOCS_URL: &str = "https://github.com/0xd219b/mermaid-linter/blob/main/docs/rules.md";
+
+/// The default base for [`DiagnosticCode::doc_url`]: [`DOCS_URL`], which
+/// has an anchor for every code's [`DiagnosticCode::kebab_name`].
+pub const DEFAULT_DOCS_BASE_URL: &str = "https://github.com/0xd219b/mermaid-linter/blob/main/docs/rules.md#";
+
 /// Error codes for diagnostics.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum DiagnosticCode {
@@ -12,6 +21,10 @@ pub enum DiagnosticCode {
     UnknownDiagram,
     /// Error during preprocessing.
     PreprocessError,
+    /// The input contains no diagram.
+    EmptyInput,
+    /// The diagram type has no parser, so its syntax is not checked.
+    UnvalidatedSyntax,
 
     // ========================================================================
     // Frontmatter/Directive errors (E1xx)
@@ -119,12 +132,67 @@ pub enum DiagnosticCode {
 }
 
 impl DiagnosticCode {
+    /// Returns every diagnostic code, in code order.
+    pub fn all() -> &'static [DiagnosticCode] {
+        &[
+            DiagnosticCode::UnknownDiagram,
+            DiagnosticCode::PreprocessError,
+            DiagnosticCode::EmptyInput,
+            DiagnosticCode::UnvalidatedSyntax,
+            DiagnosticCode::FrontmatterParseError,
+            DiagnosticCode::DirectiveParseError,
+            DiagnosticCode::InvalidDirective,
+            DiagnosticCode::DirectiveJsonError,
+            DiagnosticCode::LexerError,
+            DiagnosticCode::UnterminatedString,
+            DiagnosticCode::InvalidEscape,
+            DiagnosticCode::ParserError,
+            DiagnosticCode::UnexpectedToken,
+            DiagnosticCode::ExpectedToken,
+            DiagnosticCode::UnexpectedEof,
+            DiagnosticCode::InvalidSyntax,
+            DiagnosticCode::MissingElement,
+            DiagnosticCode::DuplicateDefinition,
+            DiagnosticCode::SemanticError,
+            DiagnosticCode::UndefinedReference,
+            DiagnosticCode::InvalidValue,
+            DiagnosticCode::ConstraintViolation,
+            DiagnosticCode::InvalidDirection,
+            DiagnosticCode::InvalidNodeShape,
+            DiagnosticCode::InvalidEdgeType,
+            DiagnosticCode::SubgraphError,
+            DiagnosticCode::InvalidArrowType,
+            DiagnosticCode::InvalidParticipant,
+            DiagnosticCode::InvalidActivation,
+            DiagnosticCode::InvalidRelationType,
+            DiagnosticCode::InvalidVisibility,
+            DiagnosticCode::InvalidMember,
+            DiagnosticCode::InvalidStateType,
+            DiagnosticCode::InvalidTransition,
+            DiagnosticCode::PacketInvalidBitRange,
+            DiagnosticCode::PacketNonContiguous,
+            DiagnosticCode::TreemapInvalidStructure,
+            DiagnosticCode::GanttInvalidDate,
+        ]
+    }
+
+    /// Looks up a code by its string code (`E307`) or its name
+    /// (`DuplicateDefinition`).
+    pub fn from_code(code: &str) -> Option<DiagnosticCode> {
+        Self::all()
+            .iter()
+            .copied()
+            .find(|c| c.as_str().eq_ignore_ascii_case(code) || format!("{:?}", c) == code)
+    }
+
     /// Returns the string code for this diagnostic.
     pub fn as_str(&self) -> &'static str {
         match self {
             // General errors
             DiagnosticCode::UnknownDiagram => "E001",
             DiagnosticCode::PreprocessError => "E002",
+            DiagnosticCode::EmptyInput => "E003",
+            DiagnosticCode::UnvalidatedSyntax => "E004",
 
             // Frontmatter/Directive errors
             DiagnosticCode::FrontmatterParseError => "E101",
@@ -180,10 +248,94 @@ impl DiagnosticCode {
         }
     }
 
+    /// Returns the variant name in kebab case, such as
+    /// `duplicate-definition` for `DuplicateDefinition`.
+    pub fn kebab_name(&self) -> String {
+        let mut name = String::new();
+        for (i, c) in format!("{:?}", self).char_indices() {
+            if c.is_ascii_uppercase() && i > 0 {
+                name.push('-');
+            }
+            name.push(c.to_ascii_lowercase());
+        }
+        name
+    }
+
+    /// Returns the documentation URL for this code under `base`, such as
+    /// [`DEFAULT_DOCS_BASE_URL`]: `<base>/<kebab-name>`, or
+    /// `<base><kebab-name>` if `base` ends in `#` and names anchors on a
+    /// single page.
+    pub fn doc_url(&self, base: &str) -> String {
+        if base.ends_with('#') {
+            format!("{}{}", base, self.kebab_name())
+        } else {
+            format!("{}/{}", base.trim_end_matches('/'), self.kebab_name())
+        }
+    }
+
+    /// Returns a one-line description of what this code reports.
+    pub fn description(&self) -> &'static str {
+        match self {
+            DiagnosticCode::UnknownDiagram => "The diagram type could not be detected",
+            DiagnosticCode::PreprocessError => "The source could not be preprocessed",
+            DiagnosticCode::EmptyInput => "The input contains no diagram",
+            DiagnosticCode::UnvalidatedSyntax => "The diagram type has no parser, so only its header is checked",
+            DiagnosticCode::FrontmatterParseError => "The YAML frontmatter is malformed",
+            DiagnosticCode::DirectiveParseError => "A %%{...}%% directive is malformed",
+            DiagnosticCode::InvalidDirective => "A directive has an unknown type",
+            DiagnosticCode::DirectiveJsonError => "A directive contains invalid JSON",
+            DiagnosticCode::LexerError => "An unknown or unexpected character",
+            DiagnosticCode::UnterminatedString => "A string literal is not closed",
+            DiagnosticCode::InvalidEscape => "An escape sequence or entity code is malformed",
+            DiagnosticCode::ParserError => "A statement could not be parsed",
+            DiagnosticCode::UnexpectedToken => "A token appears where it is not allowed",
+            DiagnosticCode::ExpectedToken => "A required token is missing",
+            DiagnosticCode::UnexpectedEof => "The input ends in the middle of a statement",
+            DiagnosticCode::InvalidSyntax => "The syntax is not valid for this diagram type",
+            DiagnosticCode::MissingElement => "A required element, such as a closing keyword, is missing",
+            DiagnosticCode::DuplicateDefinition => "Something is defined more than once",
+            DiagnosticCode::SemanticError => "The diagram is well-formed but inconsistent",
+            DiagnosticCode::UndefinedReference => "A reference to an undefined node, participant or task",
+            DiagnosticCode::InvalidValue => "A field has a value outside its allowed set or range",
+            DiagnosticCode::ConstraintViolation => "A diagram-specific constraint is violated",
+            DiagnosticCode::InvalidDirection => "A flowchart or state direction is not valid",
+            DiagnosticCode::InvalidNodeShape => "A flowchart node shape is not valid",
+            DiagnosticCode::InvalidEdgeType => "A flowchart link type is not valid",
+            DiagnosticCode::SubgraphError => "A subgraph is malformed",
+            DiagnosticCode::InvalidArrowType => "A sequence message arrow is not valid",
+            DiagnosticCode::InvalidParticipant => "A sequence participant declaration is not valid",
+            DiagnosticCode::InvalidActivation => "A sequence activation is not balanced",
+            DiagnosticCode::InvalidRelationType => "A class relationship type is not valid",
+            DiagnosticCode::InvalidVisibility => "A class member visibility modifier is not valid",
+            DiagnosticCode::InvalidMember => "A class member is malformed",
+            DiagnosticCode::InvalidStateType => "A state type annotation is not valid",
+            DiagnosticCode::InvalidTransition => "A state transition is malformed",
+            DiagnosticCode::PacketInvalidBitRange => "A packet field has an invalid bit range",
+            DiagnosticCode::PacketNonContiguous => "Packet fields leave a gap between bits",
+            DiagnosticCode::TreemapInvalidStructure => "A treemap node is not nested correctly",
+            DiagnosticCode::GanttInvalidDate => "A gantt date does not match the date format",
+        }
+    }
+
+    /// Returns the severity this code is reported with by default.
+    ///
+    /// Some checks report a code at a lower severity where the problem is
+    /// harmless, e.g. an unknown entity code is kept as written.
+    pub fn default_severity(&self) -> Severity {
+        match self {
+            DiagnosticCode::DuplicateDefinition | DiagnosticCode::InvalidEscape => Severity::Warning,
+            DiagnosticCode::UnvalidatedSyntax => Severity::Info,
+            _ => Severity::Error,
+        }
+    }
+
     /// Returns a human-readable category for this code.
     pub fn category(&self) -> &'static str {
         match self {
-            DiagnosticCode::UnknownDiagram | DiagnosticCode::PreprocessError => "general",
+            DiagnosticCode::UnknownDiagram
+            | DiagnosticCode::PreprocessError
+            | DiagnosticCode::EmptyInput
+            | DiagnosticCode::UnvalidatedSyntax => "general",
             DiagnosticCode::FrontmatterParseError
             | DiagnosticCode::DirectiveParseError
             | DiagnosticCode::InvalidDirective
@@ -238,6 +390,62 @@ mod tests {
         assert_eq!(DiagnosticCode::SemanticError.category(), "semantic");
     }
 
+    #[test]
+    fn test_all_codes_listed_once() {
+        let all = DiagnosticCode::all();
+        let unique: std::collections::HashSet<_> = all.iter().map(|c| c.as_str()).collect();
+        assert_eq!(unique.len(), all.len());
+        let kebab_names: std::collections::HashSet<_> = all.iter().map(|c| c.kebab_name()).collect();
+        assert_eq!(kebab_names.len(), all.len());
+        for code in all {
+            let name = code.kebab_name();
+            assert!(
+                name.split('-').all(|w| !w.is_empty() && w.bytes().all(|b| b.is_ascii_lowercase())),
+                "{} is not kebab case",
+                name
+            );
+            assert_eq!(name.replace('-', ""), format!("{:?}", code).to_lowercase());
+        }
+        assert!(all.contains(&DiagnosticCode::GanttInvalidDate));
+        assert_eq!(DiagnosticCode::DuplicateDefinition.default_severity(), Severity::Warning);
+        assert_eq!(DiagnosticCode::UnvalidatedSyntax.default_severity(), Severity::Info);
+    }
+
+    #[test]
+    fn test_doc_urls() {
+        assert_eq!(
+            DiagnosticCode::DuplicateDefinition.doc_url(DEFAULT_DOCS_BASE_URL),
+            format!("{}#duplicate-definition", DOCS_URL)
+        );
+        assert_eq!(
+            DiagnosticCode::GanttInvalidDate.doc_url("https://docs.example.com/lint/"),
+            "https://docs.example.com/lint/gantt-invalid-date"
+        );
+    }
+
+    #[test]
+    fn test_every_code_documented() {
+        let docs = include_str!("../../docs/rules.md");
+        for code in DiagnosticCode::all() {
+            assert!(docs.contains(&format!("### {}\n", code)), "{} is not documented", code);
+            assert!(
+                docs.contains(&format!("<a id=\"{}\"></a>\n### {}\n", code.kebab_name(), code)),
+                "{} has no anchor",
+                code
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_code() {
+        assert_eq!(DiagnosticCode::from_code("E307"), Some(DiagnosticCode::DuplicateDefinition));
+        assert_eq!(
+            DiagnosticCode::from_code("UndefinedReference"),
+            Some(DiagnosticCode::UndefinedReference)
+        );
+        assert_eq!(DiagnosticCode::from_code("E999"), None);
+    }
+
     #[test]
     fn test_diagnostic_code_display() {
         let code = DiagnosticCode::ParserError;
@@ -1,12 +1,22 @@
 //! Diagnostic types for reporting errors and warnings.
 
+mod builder;
+mod checks;
 mod codes;
 
-pub use codes::DiagnosticCode;
+pub use builder::DiagnosticBuilder;
+pub use checks::OptInCheck;
+pub use codes::{DiagnosticCode, DEFAULT_DOCS_BASE_URL, DOCS_URL};
 
-use crate::ast::Span;
+use crate::ast::{LineIndex, Range, Span};
 use crate::detector::DiagramType;
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// The widest source line, in characters, that [`Diagnostic::format`]
+/// prints in full; longer lines are cut to this many characters around the
+/// caret.
+const MAX_CONTEXT_WIDTH: usize = 120;
 
 /// Severity level of a diagnostic.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -37,10 +47,57 @@ impl Severity {
             Severity::Hint => "hint",
         }
     }
+
+    /// Parses a severity level as written in configuration or on the
+    /// command line: `error`, `warning`, `info` or `hint`, in any case, or
+    /// `off`, which gives `None`.
+    pub fn parse_level(s: &str) -> Result<Option<Severity>, ParseSeverityError> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "error" => Ok(Some(Severity::Error)),
+            "warning" => Ok(Some(Severity::Warning)),
+            "info" => Ok(Some(Severity::Info)),
+            "hint" => Ok(Some(Severity::Hint)),
+            "off" => Ok(None),
+            _ => Err(ParseSeverityError::Unknown(s.to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for Severity {
+    type Err = ParseSeverityError;
+
+    /// Parses a severity with [`Severity::parse_level`]; `off` is an error
+    /// here, since it turns a code off rather than naming a severity.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Severity::parse_level(s)?.ok_or(ParseSeverityError::Off)
+    }
+}
+
+/// An error from parsing a [`Severity`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ParseSeverityError {
+    /// Not a severity level.
+    #[error("unknown severity '{0}'; expected error, warning, info, hint or off")]
+    Unknown(String),
+    /// `off`, where a severity is required.
+    #[error("'off' turns a diagnostic code off and is not a severity")]
+    Off,
 }
 
 /// A diagnostic message from parsing.
+///
+/// Outside this crate, for example in custom validators, diagnostics are
+/// built with [`Diagnostic::builder`], or with [`Diagnostic::error`] or a
+/// sibling constructor and the `with_*` methods, since fields are added
+/// over time.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct Diagnostic {
     /// Error code identifying the type of error.
     pub code: DiagnosticCode,
@@ -59,6 +116,14 @@ pub struct Diagnostic {
     /// Related diagnostics (e.g., "defined here").
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub related: Vec<RelatedDiagnostic>,
+    /// Edits that fix the problem. A single edit is safe to apply
+    /// automatically.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fixes: Vec<TextEdit>,
+    /// The span as lines and columns. Filled in by [`crate::parse`];
+    /// `None` until [`Diagnostic::resolve_range`] is called.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range: Option<Range>,
 }
 
 impl Diagnostic {
@@ -72,9 +137,16 @@ impl Diagnostic {
             diagram_type: None,
             notes: Vec::new(),
             related: Vec::new(),
+            fixes: Vec::new(),
+            range: None,
         }
     }
 
+    /// Returns a builder for a diagnostic with `code` and `message`.
+    pub fn builder(code: DiagnosticCode, message: impl Into<String>) -> DiagnosticBuilder {
+        DiagnosticBuilder::new(code, message.into())
+    }
+
     /// Creates an error diagnostic.
     pub fn error(code: DiagnosticCode, message: impl Into<String>, span: Span) -> Self {
         Self::new(code, message.into(), Severity::Error, span)
@@ -85,6 +157,16 @@ impl Diagnostic {
         Self::new(code, message.into(), Severity::Warning, span)
     }
 
+    /// Creates an informational diagnostic.
+    pub fn info(code: DiagnosticCode, message: impl Into<String>, span: Span) -> Self {
+        Self::new(code, message.into(), Severity::Info, span)
+    }
+
+    /// Creates a hint diagnostic.
+    pub fn hint(code: DiagnosticCode, message: impl Into<String>, span: Span) -> Self {
+        Self::new(code, message.into(), Severity::Hint, span)
+    }
+
     /// Sets the diagram type.
     pub fn with_diagram_type(mut self, diagram_type: DiagramType) -> Self {
         self.diagram_type = Some(diagram_type);
@@ -103,20 +185,123 @@ impl Diagnostic {
         self
     }
 
+    /// Adds an edit that fixes the problem.
+    pub fn with_fix(mut self, fix: TextEdit) -> Self {
+        self.fixes.push(fix);
+        self
+    }
+
+    /// Returns the documentation URL for this diagnostic's code under
+    /// `base`; see [`DiagnosticCode::doc_url`].
+    pub fn code_url(&self, base: &str) -> String {
+        self.code.doc_url(base)
+    }
+
+    /// Returns the diagnostic as an entry of the `diagnostics` list of
+    /// [`crate::ParseResult::to_json_value`]: its code, documentation URL
+    /// under `docs_base`, message, severity, byte range, fingerprint and,
+    /// once resolved, line and column location. `source` is the code the
+    /// span refers to.
+    pub fn to_json_value(&self, diagram_type: Option<DiagramType>, source: &str, docs_base: &str) -> serde_json::Value {
+        let mut json = serde_json::json!({
+            "code": self.code.as_str(),
+            "codeDescription": {
+                "href": self.code_url(docs_base),
+            },
+            "url": self.code_url(docs_base),
+            "message": self.message,
+            "severity": self.severity.as_str(),
+            "range": {
+                "start": self.span.start,
+                "end": self.span.end,
+            },
+            "fingerprint": self.fingerprint(diagram_type, source),
+        });
+        if let Some(range) = self.range {
+            json["location"] = serde_json::json!({
+                "start": { "line": range.start.line, "column": range.start.column },
+                "end": { "line": range.end.line, "column": range.end.column },
+            });
+        }
+        json
+    }
+
+    /// Returns a fingerprint that identifies this diagnostic across
+    /// unrelated edits, for baselining.
+    ///
+    /// The fingerprint hashes the code, the diagram type, the message and the
+    /// text of the lines the span covers, with whitespace collapsed. Offsets
+    /// and line numbers are left out, so adding a line above the diagnostic
+    /// doesn't change it. `source` is the code the span refers to: the
+    /// code passed to [`crate::parse`].
+    pub fn fingerprint(&self, diagram_type: Option<DiagramType>, source: &str) -> String {
+        let start = self.span.start.min(source.len());
+        let end = self.span.end.clamp(start, source.len());
+        let line_start = source.get(..start).and_then(|s| s.rfind('\n')).map_or(0, |i| i + 1);
+        let line_end = source.get(end..).and_then(|s| s.find('\n')).map_or(source.len(), |i| end + i);
+        let statement = source.get(line_start..line_end).unwrap_or_default();
+
+        let diagram_type = diagram_type.or(self.diagram_type).map_or("", |t| t.as_str());
+        let fields = [
+            self.code.as_str(),
+            diagram_type,
+            &collapse_whitespace(&self.message),
+            &collapse_whitespace(statement),
+        ];
+        // FNV-1a, so fingerprints are the same across platforms and releases
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for byte in fields.join("\0").bytes() {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+        format!("{:016x}", hash)
+    }
+
+    /// Resolves the span to lines and columns of `source`, the text the
+    /// span refers to, and stores them in `range`.
+    pub fn resolve_range(&mut self, source: &str) {
+        self.resolve_range_with_index(source, &LineIndex::new(source));
+    }
+
+    /// Like [`Diagnostic::resolve_range`], with `index` already built from
+    /// `source`.
+    pub fn resolve_range_with_index(&mut self, source: &str, index: &LineIndex) {
+        self.range = Some(index.range(source, self.span));
+    }
+
+    /// Replaces the span of the diagnostic, of its related locations and of
+    /// its fixes with `map(span)`.
+    pub fn map_spans(&mut self, map: impl Fn(Span) -> Span) {
+        self.span = map(self.span);
+        for related in &mut self.related {
+            related.span = map(related.span);
+        }
+        for edit in &mut self.fixes {
+            edit.span = map(edit.span);
+        }
+    }
+
     /// Formats the diagnostic for display.
     pub fn format(&self, source: &str) -> String {
-        let location = self.format_location(source);
+        self.format_with_index(source, &LineIndex::new(source))
+    }
+
+    /// Like [`Diagnostic::format`], with `index` already built from `source`.
+    /// Formatting many diagnostics of one source this way indexes it once.
+    pub fn format_with_index(&self, source: &str, index: &LineIndex) -> String {
+        let position = index.position(source, self.span.start);
         let mut result = format!(
-            "{}: [{}] {}\n  --> {}",
+            "{}: [{}] {}\n  --> {}:{}",
             self.severity.as_str(),
             self.code.as_str(),
             self.message,
-            location
+            position.line,
+            position.column
         );
 
         // Add source context if available
         if !self.span.is_empty() {
-            if let Some(context) = self.get_source_context(source) {
+            if let Some(context) = self.get_source_context(source, index, position.line) {
                 result.push_str(&format!("\n{}", context));
             }
         }
@@ -129,59 +314,62 @@ impl Diagnostic {
         result
     }
 
-    /// Formats the location for display.
-    fn format_location(&self, source: &str) -> String {
-        let (line, col) = self.offset_to_line_col(source, self.span.start);
-        format!("{}:{}", line, col)
-    }
-
-    /// Gets source context around the error.
-    fn get_source_context(&self, source: &str) -> Option<String> {
-        let (line_num, col) = self.offset_to_line_col(source, self.span.start);
-        let lines: Vec<&str> = source.lines().collect();
-
-        if line_num == 0 || line_num > lines.len() {
+    /// Gets source context around the error, on the 1-based line `line_num`.
+    fn get_source_context(&self, source: &str, index: &LineIndex, line_num: usize) -> Option<String> {
+        let line_span = index.line_span(line_num - 1);
+        // The empty line after a final newline has nothing to show
+        if line_span.start >= source.len() {
             return None;
         }
-
-        let line = lines[line_num - 1];
+        let line = line_span.text(source).strip_suffix('\r').unwrap_or(line_span.text(source));
         let line_num_str = format!("{}", line_num);
         let padding = " ".repeat(line_num_str.len());
 
+        // Cut long lines (minified diagrams) to a window that starts a
+        // third of the way before the caret. Only the window is scanned, so
+        // a diagnostic on a long line costs no more than one on a short one.
+        let mut caret = self.span.start.clamp(line_span.start, line_span.start + line.len()) - line_span.start;
+        while !line.is_char_boundary(caret) {
+            caret -= 1;
+        }
+        let (before, after) = line.split_at(caret);
+        let after_count = after.chars().take(MAX_CONTEXT_WIDTH).count();
+        let back = (MAX_CONTEXT_WIDTH / 3).max(MAX_CONTEXT_WIDTH - after_count);
+        let (back, start) = match before.char_indices().rev().take(back).last() {
+            Some((start, _)) => (before[start..].chars().count(), start),
+            None => (0, caret),
+        };
+        let forward = MAX_CONTEXT_WIDTH - back;
+        let end = after.char_indices().nth(forward).map_or(line.len(), |(i, _)| caret + i);
+        let prefix = if start > 0 { "..." } else { "" };
+        let suffix = if end < line.len() { "..." } else { "" };
+
         let mut result = format!("{} |\n", padding);
-        result.push_str(&format!("{} | {}\n", line_num_str, line));
+        result.push_str(&format!("{} | {}{}{}\n", line_num_str, prefix, &line[start..end], suffix));
 
         // Add caret pointing to the error
-        let caret_padding = " ".repeat(col.saturating_sub(1));
-        let caret_len = (self.span.end - self.span.start).min(line.len() - col + 1).max(1);
+        let caret_padding = " ".repeat(prefix.len() + back);
+        let caret_len = (self.span.end - self.span.start).min(after_count.min(forward)).max(1);
         let carets = "^".repeat(caret_len);
         result.push_str(&format!("{} | {}{}", padding, caret_padding, carets));
 
         Some(result)
     }
+}
 
-    /// Converts a byte offset to line and column numbers.
-    fn offset_to_line_col(&self, source: &str, offset: usize) -> (usize, usize) {
-        let offset = offset.min(source.len());
-        let mut line = 1;
-        let mut col = 1;
-
-        for (idx, ch) in source.char_indices() {
-            if idx >= offset {
-                break;
-            }
-            if ch == '\n' {
-                line += 1;
-                col = 1;
-            } else {
-                col += 1;
-            }
-        }
-
-        (line, col)
+/// Returns `text` for quoting in a message, cut to its first 40 characters
+/// and marked with `...` if it is longer.
+pub(crate) fn excerpt(text: &str) -> String {
+    match text.char_indices().nth(40) {
+        Some((cut, _)) => format!("{}...", &text[..cut]),
+        None => text.to_string(),
     }
 }
 
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
 /// A related diagnostic providing additional context.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RelatedDiagnostic {
@@ -201,6 +389,30 @@ impl RelatedDiagnostic {
     }
 }
 
+/// A replacement of the source text in `span`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextEdit {
+    /// The text to replace; empty to insert at `span.start`.
+    pub span: Span,
+    /// The replacement text; empty to delete.
+    pub new_text: String,
+}
+
+impl TextEdit {
+    /// Creates an edit replacing `span` with `new_text`.
+    pub fn new(span: Span, new_text: impl Into<String>) -> Self {
+        Self {
+            span,
+            new_text: new_text.into(),
+        }
+    }
+
+    /// Creates an edit deleting `span`.
+    pub fn delete(span: Span) -> Self {
+        Self::new(span, "")
+    }
+}
+
 /// A collection of diagnostics.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Diagnostics {
@@ -308,6 +520,23 @@ impl<'a> IntoIterator for &'a Diagnostics {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_severity_round_trip() {
+        for severity in [Severity::Error, Severity::Warning, Severity::Info, Severity::Hint] {
+            assert_eq!(severity.to_string(), severity.as_str());
+            assert_eq!(severity.to_string().parse::<Severity>(), Ok(severity));
+            assert_eq!(severity.to_string().to_uppercase().parse::<Severity>(), Ok(severity));
+            let json = serde_json::to_string(&severity).unwrap();
+            assert_eq!(json, format!("\"{}\"", severity));
+        }
+        assert_eq!(Severity::parse_level("Off"), Ok(None));
+        assert_eq!("off".parse::<Severity>(), Err(ParseSeverityError::Off));
+        assert_eq!(
+            "fatal".parse::<Severity>().unwrap_err().to_string(),
+            "unknown severity 'fatal'; expected error, warning, info, hint or off"
+        );
+    }
+
     #[test]
     fn test_diagnostic_creation() {
         let diag = Diagnostic::error(
@@ -332,6 +561,35 @@ mod tests {
         let formatted = diag.format(source);
         assert!(formatted.contains("error"));
         assert!(formatted.contains("unexpected token"));
+        assert!(formatted.ends_with("3 |     invalid\n  |    ^^^^^^^"), "{}", formatted);
+    }
+
+    #[test]
+    fn test_format_cuts_long_lines() {
+        let source = format!("graph TD;{}X-->(;{}", "A-->B;".repeat(5000), "C-->D;".repeat(5000));
+        let start = source.find('(').unwrap();
+        let diag = Diagnostic::error(DiagnosticCode::InvalidSyntax, "unexpected '('", Span::new(start, start + 1));
+
+        let formatted = diag.format(&source);
+        let lines: Vec<&str> = formatted.lines().collect();
+        assert_eq!(lines[1], format!("  --> 1:{}", start + 1));
+        assert!(lines[3].starts_with("1 | ...") && lines[3].ends_with("..."), "{}", lines[3]);
+        assert!(lines[3].len() < MAX_CONTEXT_WIDTH + 20);
+        // The caret still points at the '('
+        let caret = lines[4].find('^').unwrap();
+        assert_eq!(&lines[3][caret..caret + 3], "(;C");
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_position_and_spacing() {
+        let diag = |start| Diagnostic::error(DiagnosticCode::ParserError, "unexpected  token", Span::new(start, start + 7));
+        let source = "graph TD\n    A --> B\n    invalid";
+        let shifted = "graph TD\n    C --> D\n\n    A --> B\n  invalid   ";
+        let fingerprint = diag(24).fingerprint(Some(DiagramType::Flowchart), source);
+        assert_eq!(fingerprint.len(), 16);
+        assert_eq!(diag(36).fingerprint(Some(DiagramType::Flowchart), shifted), fingerprint);
+        assert_ne!(diag(24).fingerprint(Some(DiagramType::Sequence), source), fingerprint);
+        assert_ne!(diag(13).fingerprint(Some(DiagramType::Flowchart), source), fingerprint);
     }
 
     #[test]
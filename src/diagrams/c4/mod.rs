@@ -0,0 +1,49 @@
+//! C4 diagram support.
+//!
+//! There is no full C4 parser yet. Until there is, C4 diagrams go through a
+//! tolerant [`scanner`] that extracts the element and relationship calls
+//! without validating the diagram structure.
+//!
+//! # Syntax
+//!
+//! ```text
+//! C4Context
+//!     Person(customerA, "Banking Customer A", "A customer of the bank")
+//!     System(SystemAA, "Internet Banking System")
+//!     Rel(customerA, SystemAA, "Uses")
+//! ```
+
+pub mod scanner;
+
+pub use scanner::scan;
+
+use crate::ast::Ast;
+use crate::config::MermaidConfig;
+use crate::diagnostic::Diagnostic;
+use crate::parser::traits::DiagramParser;
+
+/// C4 diagram parser, backed by the [`scanner`]. It reports no diagnostics.
+pub struct C4Parser;
+
+impl C4Parser {
+    /// Creates a new C4 diagram parser.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for C4Parser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DiagramParser for C4Parser {
+    fn parse(&self, code: &str, _config: &MermaidConfig) -> Result<Ast, Vec<Diagnostic>> {
+        Ok(scan(code))
+    }
+
+    fn name(&self) -> &'static str {
+        "c4"
+    }
+}
@@ -0,0 +1,167 @@
+//! Tolerant scanner for C4 diagrams.
+//!
+//! Finds `Person`, `System`, `Container` and `Component` calls (including
+//! their `Db`, `Queue` and `_Ext` variants) and `Rel` calls, and records
+//! their leading arguments. Anything else is skipped, so the scan never fails.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::ast::{Ast, AstNode, NodeKind, Span};
+use crate::parser::lexer::Cursor;
+
+/// The diagram keyword on the first line.
+static RE_DECLARATION: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*(C4Context|C4Container|C4Component|C4Dynamic|C4Deployment)\b").unwrap()
+});
+
+/// The start of an element or relationship call, up to its opening paren.
+static RE_CALL: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"\b(?:(?P<element>(?:Person|System|Container|Component)(?:Db|Queue)?(?:_Ext)?)|(?P<rel>BiRel|Rel(?:_Up|_Down|_Left|_Right|_Back|_U|_D|_L|_R)?))\s*\(",
+    )
+    .unwrap()
+});
+
+/// Scans a C4 diagram into an AST of `Node` (element) and `Edge`
+/// (relationship) nodes. The root is marked with `status: scanned`.
+pub fn scan(code: &str) -> Ast {
+    let mut root = AstNode::new(NodeKind::Root, Span::new(0, code.len()));
+    root.add_property("diagram_type", "c4");
+    root.add_property("status", "scanned");
+
+    if let Some(keyword) = RE_DECLARATION.captures(code).and_then(|c| c.get(1)) {
+        let span = Span::new(keyword.start(), keyword.end());
+        root.add_child(AstNode::with_text(NodeKind::DiagramDeclaration, span, keyword.as_str()));
+    }
+
+    let mut cursor = 0;
+    for captures in RE_CALL.captures_iter(code) {
+        let call = captures.get(0).unwrap();
+        // Skip matches inside the arguments of a call already scanned
+        if call.start() < cursor {
+            continue;
+        }
+
+        let (args, end) = split_arguments(code, call.end());
+        cursor = end;
+        let span = Span::new(call.start(), end);
+
+        if let Some(element) = captures.name("element") {
+            let Some(alias) = args.first().filter(|a| !a.is_empty()) else {
+                continue;
+            };
+            let mut node = AstNode::with_text(NodeKind::Node, span, alias);
+            node.add_property("type", element.as_str());
+            node.add_property("alias", alias.clone());
+            if let Some(label) = args.get(1) {
+                node.add_property("label", label.clone());
+            }
+            root.add_child(node);
+        } else if let Some(rel) = captures.name("rel") {
+            let (Some(from), Some(to)) = (args.first(), args.get(1)) else {
+                continue;
+            };
+            let mut node = AstNode::new(NodeKind::Edge, span);
+            node.add_property("type", rel.as_str());
+            node.add_property("from", from.clone());
+            node.add_property("to", to.clone());
+            if let Some(label) = args.get(2) {
+                node.add_property("label", label.clone());
+            }
+            root.add_child(node);
+        }
+    }
+
+    Ast::new(root, code.to_string())
+}
+
+/// Splits the arguments of a call, starting just after its opening paren.
+///
+/// Commas inside quotes or nested parens don't split. An unclosed call ends
+/// at the end of its line. Returns the arguments and the end offset of the call.
+fn split_arguments(code: &str, start: usize) -> (Vec<String>, usize) {
+    let mut args = Vec::new();
+    let mut arg_start = start;
+    let mut depth = 0;
+    let mut cursor = Cursor::with_range(code, start, code.len());
+
+    loop {
+        // Strings stop at the end of their line, like the call
+        if cursor.scan_string('"').is_some() {
+            continue;
+        }
+        let index = cursor.offset();
+        match cursor.bump() {
+            None | Some('\n') => {
+                args.push(argument(code, arg_start, index));
+                return (args, index);
+            }
+            Some('(') => depth += 1,
+            Some(')') if depth > 0 => depth -= 1,
+            Some(')') => {
+                args.push(argument(code, arg_start, index));
+                return (args, index + 1);
+            }
+            Some(',') if depth == 0 => {
+                args.push(argument(code, arg_start, index));
+                arg_start = index + 1;
+            }
+            Some(_) => {}
+        }
+    }
+}
+
+/// Returns the trimmed, unquoted argument in a source range.
+fn argument(code: &str, start: usize, end: usize) -> String {
+    let trimmed = code[start..end].trim();
+    trimmed
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or(trimmed)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_element() {
+        let ast = scan("C4Context\n    Person(customer, \"Customer, Retail\", \"A customer\")");
+        assert_eq!(ast.root.get_property("status"), Some("scanned"));
+
+        let person = &ast.root.children[1];
+        assert_eq!(person.kind, NodeKind::Node);
+        assert_eq!(person.get_property("type"), Some("Person"));
+        assert_eq!(person.get_property("alias"), Some("customer"));
+        assert_eq!(person.get_property("label"), Some("Customer, Retail"));
+    }
+
+    #[test]
+    fn test_scan_relationship() {
+        let ast = scan("C4Context\n    Rel_Back(a, b, \"Reads (cached)\")");
+        let rel = &ast.root.children[1];
+        assert_eq!(rel.kind, NodeKind::Edge);
+        assert_eq!(rel.get_property("type"), Some("Rel_Back"));
+        assert_eq!(rel.get_property("from"), Some("a"));
+        assert_eq!(rel.get_property("to"), Some("b"));
+        assert_eq!(rel.get_property("label"), Some("Reads (cached)"));
+    }
+
+    #[test]
+    fn test_scan_tolerates_unclosed_call() {
+        let ast = scan("C4Context\n    System(sys, \"Broken\"\n    System_Ext(ext, \"External\")");
+        let aliases: Vec<_> = ast.root.children[1..]
+            .iter()
+            .filter_map(|n| n.get_property("alias"))
+            .collect();
+        assert_eq!(aliases, vec!["sys", "ext"]);
+    }
+
+    #[test]
+    fn test_scan_ignores_boundaries() {
+        let ast = scan("C4Context\n    System_Boundary(b1, \"Bank\") {\n    }");
+        assert_eq!(ast.root.children.len(), 1);
+    }
+}
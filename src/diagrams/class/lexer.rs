@@ -2,7 +2,7 @@
 
 use logos::Logos;
 
-use crate::ast::Span;
+use crate::parser::{lexer, quotes};
 
 /// Token types for class diagram parsing.
 #[derive(Logos, Debug, Clone, PartialEq, Eq)]
@@ -42,6 +42,12 @@ pub enum ClassToken {
     #[token("direction", ignore(case))]
     Direction,
 
+    #[token("accTitle", ignore(case))]
+    AccTitle,
+
+    #[token("accDescr", ignore(case))]
+    AccDescr,
+
     // Stereotypes
     #[regex(r"<<[^>]+>>")]
     Stereotype,
@@ -77,6 +83,12 @@ pub enum ClassToken {
     #[token("..|>")]
     RealizationRight,
 
+    #[token("<--")]
+    AssociationLeft,
+
+    #[token("-->")]
+    AssociationRight,
+
     #[token("--")]
     Association,
 
@@ -131,13 +143,18 @@ pub enum ClassToken {
     Newline,
 
     // Quoted strings
-    #[regex(r#""([^"\\]|\\.)*""#)]
+    #[token("\"", quotes::lex_quoted)]
+    #[token("“", quotes::lex_quoted)]
     DoubleQuotedString,
 
     // Identifiers (including generic types)
     #[regex(r"[a-zA-Z_][a-zA-Z0-9_]*(<[^>]+>)?")]
     Identifier,
 
+    // A class name in backticks, which may hold spaces: `Animal Class`
+    #[regex(r"`[^`\n]+`")]
+    BacktickName,
+
     // Numbers
     #[regex(r"[0-9]+")]
     Number,
@@ -148,36 +165,16 @@ pub enum ClassToken {
 
     // Text - lower priority so other patterns match first
     // Note: Excludes spaces so identifiers can be matched separately
-    #[regex(r#"[^\n{}()\[\]:,+\-#~*$" \t]+"#, priority = 1)]
+    #[regex(r#"[^\n{}()\[\]:,+\-#~*$" \t“][^\n{}()\[\]:,+\-#~*$" \t]*"#, priority = 1)]
     Text,
 }
 
-/// A positioned token.
-#[derive(Debug, Clone)]
-pub struct PositionedToken {
-    pub kind: ClassToken,
-    pub span: Span,
-    pub text: String,
-}
-
-/// Tokenize class diagram source code.
-pub fn tokenize(source: &str) -> Vec<PositionedToken> {
-    let mut tokens = Vec::new();
-    let mut lexer = ClassToken::lexer(source);
-
-    while let Some(result) = lexer.next() {
-        if let Ok(kind) = result {
-            let span = lexer.span();
-            let text = lexer.slice().to_string();
-            tokens.push(PositionedToken {
-                kind,
-                span: Span::new(span.start, span.end),
-                text,
-            });
-        }
-    }
+/// A class diagram token with its span and text.
+pub type Token = lexer::Token<ClassToken>;
 
-    tokens
+/// Tokenizes class diagram source, skipping text no token matches.
+pub fn tokenize(source: &str) -> Vec<Token> {
+    lexer::tokenize(source)
 }
 
 #[cfg(test)]
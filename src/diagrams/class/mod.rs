@@ -17,6 +17,7 @@
 
 mod lexer;
 mod parser;
+pub mod validate;
 
 pub use parser::ClassParser;
 
@@ -59,6 +60,7 @@ pub enum RelationType {
 }
 
 impl RelationType {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s {
             "<|--" | "--|>" => Some(RelationType::Inheritance),
@@ -66,7 +68,7 @@ impl RelationType {
             "o--" | "--o" => Some(RelationType::Aggregation),
             "..>" | "<.." => Some(RelationType::Dependency),
             "..|>" | "<|.." => Some(RelationType::Realization),
-            "--" => Some(RelationType::Association),
+            "--" | "-->" | "<--" => Some(RelationType::Association),
             ".." => Some(RelationType::DashedLink),
             _ => None,
         }
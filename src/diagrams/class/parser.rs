@@ -3,11 +3,16 @@
 use crate::ast::{Ast, AstNode, NodeKind, Span};
 use crate::config::MermaidConfig;
 use crate::diagnostic::{Diagnostic, DiagnosticCode};
+use crate::parser::{accessibility, click, finish_parse, quotes, unknown, StatementLoop};
+use crate::parser::lexer::patterns;
 use crate::parser::traits::DiagramParser;
 
-use super::lexer::{tokenize, ClassToken, PositionedToken};
+use super::lexer::{tokenize, ClassToken, Token};
 use super::{RelationType, Visibility};
 
+/// Mermaid statement keywords this parser skips rather than checks.
+const NEW_KEYWORDS: &[&str] = &["style", "classDef"];
+
 /// Class diagram parser.
 pub struct ClassParser;
 
@@ -30,20 +35,31 @@ impl DiagramParser for ClassParser {
         parser.parse()
     }
 
+    /// Also returns the warnings and hints of a successful parse. There is
+    /// no error recovery, so a failed parse has no AST.
+    fn parse_partial(&self, code: &str, _config: &MermaidConfig) -> (Option<Ast>, Vec<Diagnostic>) {
+        let tokens = tokenize(code);
+        let mut parser = ClassParserImpl::new(&tokens, code);
+        match parser.parse() {
+            Ok(ast) => (Some(ast), std::mem::take(&mut parser.diagnostics)),
+            Err(diagnostics) => (None, diagnostics),
+        }
+    }
+
     fn name(&self) -> &'static str {
         "class"
     }
 }
 
 struct ClassParserImpl<'a> {
-    tokens: &'a [PositionedToken],
+    tokens: &'a [Token],
     pos: usize,
     source: &'a str,
     diagnostics: Vec<Diagnostic>,
 }
 
 impl<'a> ClassParserImpl<'a> {
-    fn new(tokens: &'a [PositionedToken], source: &'a str) -> Self {
+    fn new(tokens: &'a [Token], source: &'a str) -> Self {
         Self {
             tokens,
             pos: 0,
@@ -76,6 +92,7 @@ impl<'a> ClassParserImpl<'a> {
 
         // Parse statements
         while !self.is_at_end() {
+            let loop_start = self.pos;
             self.skip_newlines();
 
             if self.is_at_end() {
@@ -87,13 +104,10 @@ impl<'a> ClassParserImpl<'a> {
             } else {
                 self.skip_to_newline();
             }
+            self.ensure_progress(loop_start);
         }
 
-        if self.diagnostics.iter().any(|d| d.severity.is_error()) {
-            Err(std::mem::take(&mut self.diagnostics))
-        } else {
-            Ok(Ast::new(root, self.source.to_string()))
-        }
+        finish_parse(root, self.source, &mut self.diagnostics)
     }
 
     fn parse_statement(&mut self) -> Option<AstNode> {
@@ -103,6 +117,11 @@ impl<'a> ClassParserImpl<'a> {
             return None;
         }
 
+        // Check for accessibility
+        if self.check(&ClassToken::AccTitle) || self.check(&ClassToken::AccDescr) {
+            return self.parse_accessibility();
+        }
+
         if self.check(&ClassToken::Class) {
             return self.parse_class();
         }
@@ -131,10 +150,27 @@ impl<'a> ClassParserImpl<'a> {
             return self.parse_css_class();
         }
 
+        if let Some(node) = self.parse_unknown_statement() {
+            return Some(node);
+        }
+
         // Try to parse a relationship or class member
         self.parse_relationship_or_member()
     }
 
+    /// Skips a statement starting with a keyword-like word, with a warning.
+    fn parse_unknown_statement(&mut self) -> Option<AstNode> {
+        let start = self.current_span().start;
+        let keyword = unknown::leading_keyword(&self.source[start..])?;
+        while !self.is_at_end() && !self.check(&ClassToken::Newline) {
+            self.advance();
+        }
+        let span = Span::new(start, self.previous_span().end);
+        let (node, diagnostic) = unknown::skipped_statement(self.source, span, keyword, NEW_KEYWORDS);
+        self.diagnostics.push(diagnostic);
+        Some(node)
+    }
+
     fn parse_class(&mut self) -> Option<AstNode> {
         let start = self.current_span().start;
         self.advance(); // consume 'class'
@@ -325,9 +361,30 @@ impl<'a> ClassParserImpl<'a> {
         // Parse first identifier
         let first_id = self.expect_identifier()?;
 
+        // A quoted cardinality may come between a class and the arrow, as in
+        // `A "1" o-- "many" B`
+        let first_cardinality = if self.check_cardinality()
+            && self.tokens.get(self.pos + 1).is_some_and(|t| relation_type(&t.kind).is_some())
+        {
+            self.parse_cardinality()
+        } else {
+            None
+        };
+
         // Check for relationship
-        if let Some(rel_type) = self.try_parse_relation_type() {
-            // This is a relationship
+        if let Some((rel_type, head_side)) = self.try_parse_relation_type() {
+            // This is a relationship; a quoted string is the cardinality if a
+            // class name follows it
+            let second_cardinality = if self.check_cardinality()
+                && self
+                    .tokens
+                    .get(self.pos + 1)
+                    .is_some_and(|t| !matches!(t.kind, ClassToken::Newline | ClassToken::Colon))
+            {
+                self.parse_cardinality()
+            } else {
+                None
+            };
             let second_id = self.expect_identifier()?;
 
             // Check for label
@@ -340,9 +397,27 @@ impl<'a> ClassParserImpl<'a> {
 
             let end = self.previous_span().end;
             let mut node = AstNode::new(NodeKind::Relationship, Span::new(start, end));
+            // `semantic_to` is the class at the arrowhead or marker (the
+            // supertype, whole or target), whichever side it is written on
+            let (semantic_from, semantic_to) = match head_side {
+                Some("left") => (second_id.clone(), first_id.clone()),
+                _ => (first_id.clone(), second_id.clone()),
+            };
             node.add_property("from", first_id);
             node.add_property("to", second_id);
             node.add_property("relation_type", format!("{:?}", rel_type));
+            if let Some(side) = head_side {
+                node.add_property("head_side", side);
+            }
+            node.add_property("semantic_from", semantic_from);
+            node.add_property("semantic_to", semantic_to);
+            // Cardinalities follow the textual `from` and `to`
+            if let Some(c) = first_cardinality {
+                node.add_property("from_cardinality", c);
+            }
+            if let Some(c) = second_cardinality {
+                node.add_property("to_cardinality", c);
+            }
 
             if let Some(l) = label {
                 node.add_property("label", l);
@@ -377,18 +452,21 @@ impl<'a> ClassParserImpl<'a> {
         None
     }
 
-    fn try_parse_relation_type(&mut self) -> Option<RelationType> {
-        let rel = match self.peek()?.kind {
-            ClassToken::InheritanceLeft | ClassToken::InheritanceRight => Some(RelationType::Inheritance),
-            ClassToken::CompositionLeft | ClassToken::CompositionRight => Some(RelationType::Composition),
-            ClassToken::AggregationLeft | ClassToken::AggregationRight => Some(RelationType::Aggregation),
-            ClassToken::DependencyLeft | ClassToken::DependencyRight => Some(RelationType::Dependency),
-            ClassToken::RealizationLeft | ClassToken::RealizationRight => Some(RelationType::Realization),
-            ClassToken::Association => Some(RelationType::Association),
-            ClassToken::DashedLine => Some(RelationType::DashedLink),
-            _ => None,
-        };
+    /// Checks for a quoted cardinality: a number or range such as `"0..*"`,
+    /// or any quoted text such as `"many"`.
+    fn check_cardinality(&self) -> bool {
+        self.check(&ClassToken::Cardinality) || self.check(&ClassToken::DoubleQuotedString)
+    }
 
+    /// Consumes a quoted cardinality, returning the text between the quotes.
+    fn parse_cardinality(&mut self) -> Option<String> {
+        self.quoted_text()
+    }
+
+    /// Parses a relation arrow, returning its kind and the side (`left` or
+    /// `right`) carrying the arrowhead or marker, if any.
+    fn try_parse_relation_type(&mut self) -> Option<(RelationType, Option<&'static str>)> {
+        let rel = relation_type(&self.peek()?.kind);
         if rel.is_some() {
             self.advance();
         }
@@ -412,13 +490,26 @@ impl<'a> ClassParserImpl<'a> {
             self.skip_newlines();
 
             while !self.is_at_end() && !self.check(&ClassToken::RBrace) {
+                let loop_start = self.pos;
                 self.skip_newlines();
 
+                if self.check(&ClassToken::RBrace) {
+                    break;
+                }
+
                 if let Some(stmt) = self.parse_statement() {
+                    // A direction inside the body applies to this namespace
+                    if stmt.get_property("type") == Some("direction") {
+                        let direction = stmt.get_property("direction").unwrap_or_default();
+                        if patterns::is_direction_value(direction) {
+                            node.add_property("direction", direction.to_uppercase());
+                        }
+                    }
                     node.add_child(stmt);
                 } else {
                     self.skip_to_newline();
                 }
+                self.ensure_progress(loop_start);
             }
 
             if self.check(&ClassToken::RBrace) {
@@ -467,11 +558,27 @@ impl<'a> ClassParserImpl<'a> {
     }
 
     fn parse_direction(&mut self) -> Option<AstNode> {
-        let start = self.current_span().start;
+        let keyword_span = self.current_span();
+        let start = keyword_span.start;
         self.advance(); // consume 'direction'
 
+        let value_start = self.current_span().start;
         let direction = self.parse_text_until_newline();
 
+        if direction.is_empty() {
+            self.diagnostics.push(Diagnostic::error(
+                DiagnosticCode::InvalidDirection,
+                "Missing value for 'direction'; expected one of TB, BT, LR, RL",
+                keyword_span,
+            ));
+        } else if !patterns::is_direction_value(&direction) {
+            self.diagnostics.push(Diagnostic::error(
+                DiagnosticCode::InvalidDirection,
+                format!("Invalid direction '{}'; expected one of TB, BT, LR, RL", direction),
+                Span::new(value_start, self.previous_span().end),
+            ));
+        }
+
         let end = self.previous_span().end;
         let mut node = AstNode::new(NodeKind::Statement, Span::new(start, end));
         node.add_property("type", "direction");
@@ -485,29 +592,54 @@ impl<'a> ClassParserImpl<'a> {
         self.advance();
 
         let target = self.expect_identifier()?;
-        let rest = self.parse_text_until_newline();
+        let click_span = Span::new(start, self.previous_span().end);
+        let (action, definition) = click::parse_action(
+            self.source,
+            click_span,
+            self.current_span().start,
+            &mut self.diagnostics,
+        );
+        while !self.is_at_end() && !self.check(&ClassToken::Newline) {
+            self.advance();
+        }
 
-        let end = self.previous_span().end;
-        let mut node = AstNode::new(NodeKind::Statement, Span::new(start, end));
+        let mut node = AstNode::new(NodeKind::Statement, Span::new(start, definition.end.max(click_span.end)));
         node.add_property("type", "click");
         node.add_property("target", target);
-        node.add_property("definition", rest);
+        node.add_property("definition", definition.text(self.source));
+        if let Some(action) = action {
+            action.add_properties(&mut node);
+        }
 
         Some(node)
     }
 
+    /// Parses the older `link` and `callback` statements, which store the
+    /// same action properties as `click`.
     fn parse_link_or_callback(&mut self) -> Option<AstNode> {
         let start = self.current_span().start;
         let keyword = self.advance()?.text.clone();
 
         let target = self.expect_identifier()?;
-        let rest = self.parse_text_until_newline();
+        let statement_span = Span::new(start, self.previous_span().end);
+        let (action, definition) = click::parse_legacy_action(
+            self.source,
+            &keyword,
+            statement_span,
+            self.current_span().start,
+            &mut self.diagnostics,
+        );
+        while !self.is_at_end() && !self.check(&ClassToken::Newline) {
+            self.advance();
+        }
 
-        let end = self.previous_span().end;
-        let mut node = AstNode::new(NodeKind::Statement, Span::new(start, end));
+        let mut node = AstNode::new(NodeKind::Statement, Span::new(start, definition.end.max(statement_span.end)));
         node.add_property("type", keyword.to_lowercase());
         node.add_property("target", target);
-        node.add_property("definition", rest);
+        node.add_property("definition", definition.text(self.source));
+        if let Some(action) = action {
+            action.add_properties(&mut node);
+        }
 
         Some(node)
     }
@@ -538,13 +670,23 @@ impl<'a> ClassParserImpl<'a> {
         content.trim().to_string()
     }
 
+    /// Parse accessibility statement.
+    fn parse_accessibility(&mut self) -> Option<AstNode> {
+        let start = self.current_span().start;
+        let (node, end) = accessibility::parse_statement(self.source, start, &mut self.diagnostics)?;
+        while !self.is_at_end() && self.current_span().start < end {
+            self.advance();
+        }
+        Some(node)
+    }
+
     // Helper methods
 
     fn is_at_end(&self) -> bool {
         self.pos >= self.tokens.len()
     }
 
-    fn peek(&self) -> Option<&PositionedToken> {
+    fn peek(&self) -> Option<&Token> {
         self.tokens.get(self.pos)
     }
 
@@ -552,7 +694,7 @@ impl<'a> ClassParserImpl<'a> {
         self.peek().map(|t| &t.kind == kind).unwrap_or(false)
     }
 
-    fn advance(&mut self) -> Option<&PositionedToken> {
+    fn advance(&mut self) -> Option<&Token> {
         if !self.is_at_end() {
             self.pos += 1;
             self.tokens.get(self.pos - 1)
@@ -561,12 +703,20 @@ impl<'a> ClassParserImpl<'a> {
         }
     }
 
+    /// Consumes a quoted string token, returning the text between the
+    /// quotes; see [`quotes::unquote`].
+    fn quoted_text(&mut self) -> Option<String> {
+        let token = self.advance()?.clone();
+        quotes::unquote(&token.text, token.span, &mut self.diagnostics)
+    }
+
     fn expect_identifier(&mut self) -> Option<String> {
         if self.check(&ClassToken::Identifier) {
             Some(self.advance()?.text.clone())
+        } else if self.check(&ClassToken::BacktickName) {
+            Some(self.advance()?.text.trim_matches('`').to_string())
         } else if self.check(&ClassToken::DoubleQuotedString) {
-            let quoted = self.advance()?.text.clone();
-            Some(quoted[1..quoted.len() - 1].to_string())
+            self.quoted_text()
         } else if self.check(&ClassToken::Text) {
             let text = self.advance()?.text.trim().to_string();
             if !text.is_empty() {
@@ -629,6 +779,50 @@ impl<'a> ClassParserImpl<'a> {
     }
 }
 
+impl StatementLoop for ClassParserImpl<'_> {
+    fn position(&self) -> usize {
+        self.pos
+    }
+
+    fn at_end(&self) -> bool {
+        self.is_at_end()
+    }
+
+    fn token_span(&self) -> Span {
+        self.current_span()
+    }
+
+    fn skip_token(&mut self) {
+        self.advance();
+    }
+
+    fn report(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+}
+
+/// Returns the kind of relation an arrow token stands for, and the side
+/// (`left` or `right`) carrying the arrowhead or marker, if any.
+fn relation_type(kind: &ClassToken) -> Option<(RelationType, Option<&'static str>)> {
+    match kind {
+        ClassToken::InheritanceLeft => Some((RelationType::Inheritance, Some("left"))),
+        ClassToken::InheritanceRight => Some((RelationType::Inheritance, Some("right"))),
+        ClassToken::CompositionLeft => Some((RelationType::Composition, Some("left"))),
+        ClassToken::CompositionRight => Some((RelationType::Composition, Some("right"))),
+        ClassToken::AggregationLeft => Some((RelationType::Aggregation, Some("left"))),
+        ClassToken::AggregationRight => Some((RelationType::Aggregation, Some("right"))),
+        ClassToken::DependencyLeft => Some((RelationType::Dependency, Some("left"))),
+        ClassToken::DependencyRight => Some((RelationType::Dependency, Some("right"))),
+        ClassToken::RealizationLeft => Some((RelationType::Realization, Some("left"))),
+        ClassToken::RealizationRight => Some((RelationType::Realization, Some("right"))),
+        ClassToken::AssociationLeft => Some((RelationType::Association, Some("left"))),
+        ClassToken::AssociationRight => Some((RelationType::Association, Some("right"))),
+        ClassToken::Association => Some((RelationType::Association, None)),
+        ClassToken::DashedLine => Some((RelationType::DashedLink, None)),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -698,4 +892,36 @@ mod tests {
         let result = parse(code);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_invalid_direction_value() {
+        let code = "classDiagram\n    direction NESW\n    class Animal";
+        let diagnostics = parse(code).unwrap_err();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, DiagnosticCode::InvalidDirection);
+        assert_eq!(&code[diagnostics[0].span.start..diagnostics[0].span.end], "NESW");
+    }
+
+    #[test]
+    fn test_namespace_direction_recorded() {
+        let code = r#"classDiagram
+    namespace Shapes {
+        direction RL
+        class Square
+    }
+"#;
+        let ast = parse(code).unwrap();
+        let namespace = &ast.root.children[1];
+        assert_eq!(namespace.get_property("direction"), Some("RL"));
+    }
+
+    #[test]
+    fn test_curly_and_unterminated_quotes() {
+        let result = crate::parse("classDiagram\n    class “Foo”\n    “Foo” \"1\" --> \"many Bar", None);
+        assert!(!result.ok);
+        let codes: Vec<_> = result.diagnostics.iter().map(|d| d.code).collect();
+        assert_eq!(codes, [DiagnosticCode::InvalidSyntax, DiagnosticCode::InvalidSyntax, DiagnosticCode::UnterminatedString]);
+        assert_eq!(result.diagnostics[0].fixes[0].new_text, "\"Foo\"");
+        assert!(crate::parse("classDiagram\n    class “Foo”", None).ok);
+    }
 }
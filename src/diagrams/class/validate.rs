@@ -0,0 +1,131 @@
+//! Semantic validation for class diagrams.
+
+use std::collections::HashSet;
+
+use crate::ast::{Ast, AstNode, NodeKind};
+use crate::diagnostic::{Diagnostic, DiagnosticCode, OptInCheck, TextEdit};
+use crate::parser::click::ClickAction;
+
+/// The statements that attach an action to a class.
+const ACTION_STATEMENTS: &[&str] = &["click", "link", "callback"];
+
+/// Validates a parsed class diagram AST.
+///
+/// - A `click`, `link` or `callback` statement whose target is not a class
+///   of the diagram produces a warning; Mermaid ignores it.
+pub fn validate(ast: &Ast) -> Vec<Diagnostic> {
+    let mut classes = HashSet::new();
+    collect_classes(&ast.root, &mut classes);
+
+    action_statements(ast)
+        .into_iter()
+        .filter_map(|node| {
+            let target = node.get_property("target")?;
+            if classes.contains(target) {
+                return None;
+            }
+            let keyword = node.statement_type().unwrap_or_default();
+            Some(
+                Diagnostic::warning(
+                    DiagnosticCode::UndefinedReference,
+                    format!("'{}' is not a class of this diagram; the '{}' has no effect", target, keyword),
+                    node.span,
+                )
+                .with_note("define the class, or use it in a relationship, before attaching an action"),
+            )
+        })
+        .collect()
+}
+
+/// Flags the older standalone `link` and `callback` statements, with the
+/// equivalent `click` statement as the fix.
+///
+/// Mermaid documents `click <class> href "url"` and `click <class> call
+/// name()` as the current forms.
+pub fn prefer_click_syntax(ast: &Ast) -> Vec<Diagnostic> {
+    action_statements(ast)
+        .into_iter()
+        .filter(|node| node.statement_type() != Some("click"))
+        .filter_map(|node| {
+            let keyword = node.statement_type()?;
+            let click = ClickAction::from_properties(node)?.to_click_statement(node.get_property("target")?);
+            Some(
+                OptInCheck::PreferClickSyntax
+                    .diagnostic(format!("'{}' is the older form of 'click'", keyword), node.span)
+                    .with_note(format!("write '{}'", click))
+                    .with_fix(TextEdit::new(node.span, click)),
+            )
+        })
+        .collect()
+}
+
+/// Returns the `click`, `link` and `callback` statements, including those
+/// inside namespaces.
+fn action_statements(ast: &Ast) -> Vec<&AstNode> {
+    fn collect<'a>(node: &'a AstNode, statements: &mut Vec<&'a AstNode>) {
+        for child in &node.children {
+            if child.kind == NodeKind::Statement
+                && ACTION_STATEMENTS.contains(&child.statement_type().unwrap_or_default())
+            {
+                statements.push(child);
+            }
+            collect(child, statements);
+        }
+    }
+    let mut statements = Vec::new();
+    collect(&ast.root, &mut statements);
+    statements
+}
+
+/// Collects the names of the classes a diagram defines: declared classes,
+/// relationship ends and classes given members with `Class : member`.
+fn collect_classes<'a>(node: &'a AstNode, classes: &mut HashSet<&'a str>) {
+    for child in &node.children {
+        let names: &[&str] = match (&child.kind, child.statement_type()) {
+            (NodeKind::Class, _) => &["name"],
+            (NodeKind::Relationship, _) => &["from", "to"],
+            (NodeKind::Statement, Some("class_member")) => &["class"],
+            _ => &[],
+        };
+        classes.extend(names.iter().filter_map(|key| child.get_property(key)));
+        collect_classes(child, classes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MermaidConfig;
+    use crate::diagrams::class::ClassParser;
+    use crate::parser::traits::DiagramParser;
+
+    fn parse(code: &str) -> Ast {
+        ClassParser::new()
+            .parse(code, &MermaidConfig::default())
+            .expect("parse failed")
+    }
+
+    #[test]
+    fn test_action_targets_resolve_the_same_way() {
+        let code = "classDiagram\n    Animal <|-- Duck\n    namespace N {\n        class Fish\n    }\n    Zebra : +run()\n    click Animal href \"a\"\n    link Fish \"b\"\n    callback Zebra \"c\"\n    link Bird \"d\"\n    click Cat call show()";
+        let diagnostics = validate(&parse(code));
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics.iter().all(|d| d.code == DiagnosticCode::UndefinedReference));
+        assert_eq!(diagnostics[0].span.text(code), "link Bird \"d\"");
+        assert_eq!(diagnostics[1].message, "'Cat' is not a class of this diagram; the 'click' has no effect");
+    }
+
+    #[test]
+    fn test_prefer_click_syntax() {
+        let code = "classDiagram\n    class A\n    link A \"https://example.com\" \"Docs\" _blank\n    click A call show()\n    callback A \"show\" \"Run\"";
+        let diagnostics = prefer_click_syntax(&parse(code));
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].message, "'link' is the older form of 'click'");
+        assert_eq!(
+            diagnostics[0].fixes[0].new_text,
+            "click A href \"https://example.com\" \"Docs\" _blank"
+        );
+        assert_eq!(diagnostics[1].fixes[0].span.text(code), "callback A \"show\" \"Run\"");
+        assert_eq!(diagnostics[1].fixes[0].new_text, "click A call show() \"Run\"");
+    }
+}
@@ -2,6 +2,8 @@
 
 use logos::Logos;
 
+use crate::parser::{lexer, quotes};
+
 /// Tokens for ER diagram lexing.
 #[derive(Logos, Debug, Clone, PartialEq)]
 #[logos(skip r"[ \t]+")]
@@ -130,7 +132,8 @@ pub enum ErToken {
     Tilde,
 
     // Quoted strings
-    #[regex(r#""[^"]*""#)]
+    #[token("\"", quotes::lex_quoted)]
+    #[token("“", quotes::lex_quoted)]
     QuotedString,
 
     // Entity/attribute names - alphanumeric with hyphens and underscores
@@ -145,10 +148,6 @@ pub enum ErToken {
     #[regex(r"~[^~]+~")]
     GenericType,
 
-    // Comment text (after keys)
-    #[regex(r#""[^"]*""#, priority = 2)]
-    CommentText,
-
     // Newline
     #[regex(r"\n|\r\n")]
     Newline,
@@ -179,30 +178,12 @@ impl ErToken {
     }
 }
 
-/// A token with its span information.
-#[derive(Debug, Clone)]
-pub struct Token {
-    pub kind: ErToken,
-    pub text: String,
-    pub span: std::ops::Range<usize>,
-}
+/// An ER diagram token with its span and text.
+pub type Token = lexer::Token<ErToken>;
 
-/// Tokenize ER diagram source.
+/// Tokenizes ER diagram source, skipping text no token matches.
 pub fn tokenize(source: &str) -> Vec<Token> {
-    let mut tokens = Vec::new();
-    let mut lexer = ErToken::lexer(source);
-
-    while let Some(result) = lexer.next() {
-        if let Ok(kind) = result {
-            tokens.push(Token {
-                kind,
-                text: lexer.slice().to_string(),
-                span: lexer.span(),
-            });
-        }
-    }
-
-    tokens
+    lexer::tokenize(source)
 }
 
 #[cfg(test)]
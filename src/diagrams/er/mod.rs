@@ -15,6 +15,7 @@
 
 pub mod lexer;
 pub mod parser;
+pub mod validate;
 
 pub use parser::ErParser;
 
@@ -35,6 +36,7 @@ pub enum Cardinality {
 
 impl Cardinality {
     /// Parse cardinality from string.
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
             "||" | "only one" | "1" => Some(Cardinality::OnlyOne),
@@ -73,6 +75,7 @@ pub enum IdentificationType {
 
 impl IdentificationType {
     /// Parse identification type from string.
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s {
             "--" | "to" => Some(IdentificationType::Identifying),
@@ -103,6 +106,7 @@ pub enum AttributeKey {
 
 impl AttributeKey {
     /// Parse from string.
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_uppercase().as_str() {
             "PK" => Some(AttributeKey::PrimaryKey),
@@ -1,22 +1,66 @@
 //! Parser for ER diagrams.
 
+use once_cell::sync::Lazy;
+use regex::Regex;
+
 use crate::ast::{Ast, AstNode, NodeKind, Span};
+use crate::config::MermaidConfig;
 use crate::diagnostic::{Diagnostic, DiagnosticCode, Severity};
+use crate::parser::traits::DiagramParser;
+use crate::parser::{accessibility, finish_parse, quotes, StatementLoop};
 
 use super::lexer::{tokenize, ErToken, Token};
 use super::{Cardinality, IdentificationType};
 
+/// The word forms of a cardinality, as Mermaid's lexer accepts them.
+/// Longer forms come first, so `1+` is not read as `1`.
+static CARDINALITY_WORDS: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)^(?:only\s+one|zero\s+or\s+one|one\s+or\s+zero|(?:zero|one)\s+or\s+(?:more|many)|many\([01]\)|[01]\+|1)")
+        .unwrap()
+});
+
 /// Parser for ER diagrams.
-pub struct ErParser<'a> {
+pub struct ErParser;
+
+impl ErParser {
+    /// Creates a new ER diagram parser.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for ErParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DiagramParser for ErParser {
+    fn parse(&self, code: &str, _config: &MermaidConfig) -> Result<Ast, Vec<Diagnostic>> {
+        ErParserImpl::new(code).parse()
+    }
+
+    /// Also returns the warnings and hints of a successful parse. There is
+    /// no error recovery, so a failed parse has no AST.
+    fn parse_partial(&self, code: &str, _config: &MermaidConfig) -> (Option<Ast>, Vec<Diagnostic>) {
+        ErParserImpl::new(code).parse_partial()
+    }
+
+    fn name(&self) -> &'static str {
+        "er"
+    }
+}
+
+struct ErParserImpl<'a> {
     tokens: Vec<Token>,
     pos: usize,
     source: &'a str,
     diagnostics: Vec<Diagnostic>,
 }
 
-impl<'a> ErParser<'a> {
+impl<'a> ErParserImpl<'a> {
     /// Create a new parser.
-    pub fn new(source: &'a str) -> Self {
+    fn new(source: &'a str) -> Self {
         Self {
             tokens: tokenize(source),
             pos: 0,
@@ -25,8 +69,17 @@ impl<'a> ErParser<'a> {
         }
     }
 
+    /// Parses the ER diagram, also returning the warnings and hints that
+    /// [`Self::parse`] drops on success. A failed parse has no AST.
+    fn parse_partial(&mut self) -> (Option<Ast>, Vec<Diagnostic>) {
+        match self.parse() {
+            Ok(ast) => (Some(ast), std::mem::take(&mut self.diagnostics)),
+            Err(diagnostics) => (None, diagnostics),
+        }
+    }
+
     /// Parse the ER diagram.
-    pub fn parse(&mut self) -> Result<Ast, Vec<Diagnostic>> {
+    fn parse(&mut self) -> Result<Ast, Vec<Diagnostic>> {
         let start_span = Span::new(0, self.source.len());
         let mut root = AstNode::new(NodeKind::Root, start_span);
 
@@ -43,11 +96,13 @@ impl<'a> ErParser<'a> {
                 Severity::Error,
                 self.current_span(),
             ));
-            return Err(self.diagnostics.clone());
+            // Move the diagnostics out instead of cloning them
+            return Err(std::mem::take(&mut self.diagnostics));
         }
 
         // Parse statements
         while !self.is_at_end() {
+            let loop_start = self.pos;
             self.skip_newlines();
             if self.is_at_end() {
                 break;
@@ -59,13 +114,10 @@ impl<'a> ErParser<'a> {
                 // Skip unknown token
                 self.advance();
             }
+            self.ensure_progress(loop_start);
         }
 
-        if self.diagnostics.iter().any(|d| d.severity == Severity::Error) {
-            Err(self.diagnostics.clone())
-        } else {
-            Ok(Ast::new(root, self.source.to_string()))
-        }
+        finish_parse(root, self.source, &mut self.diagnostics)
     }
 
     /// Parse the erDiagram declaration.
@@ -242,45 +294,10 @@ impl<'a> ErParser<'a> {
     /// Parse accessibility statement.
     fn parse_accessibility(&mut self) -> Option<AstNode> {
         let start = self.current_span().start;
-        let acc_type = if self.check(&ErToken::AccTitle) {
-            "accTitle"
-        } else {
-            "accDescr"
-        };
-        self.advance();
-
-        // Skip colon if present
-        if self.check(&ErToken::Colon) {
+        let (node, end) = accessibility::parse_statement(self.source, start, &mut self.diagnostics)?;
+        while !self.is_at_end() && self.current_span().start < end {
             self.advance();
         }
-
-        // Check for multi-line description
-        if self.check(&ErToken::OpenBrace) {
-            self.advance();
-            let mut content = String::new();
-            while !self.check(&ErToken::CloseBrace) && !self.is_at_end() {
-                content.push_str(&self.current_text());
-                content.push(' ');
-                self.advance();
-            }
-            if self.check(&ErToken::CloseBrace) {
-                self.advance();
-            }
-            let end = self.previous_span().end;
-
-            let mut node = AstNode::new(NodeKind::Statement, Span::new(start, end));
-            node.add_property("type", acc_type);
-            node.add_property("value", content.trim().to_string());
-            return Some(node);
-        }
-
-        // Single line
-        let value = self.consume_until_newline();
-        let end = self.previous_span().end;
-
-        let mut node = AstNode::new(NodeKind::Statement, Span::new(start, end));
-        node.add_property("type", acc_type);
-        node.add_property("value", value);
         Some(node)
     }
 
@@ -310,8 +327,8 @@ impl<'a> ErParser<'a> {
             return self.parse_entity_with_attributes(start, entity_a, class_a);
         }
 
-        // Check for relationship
-        if self.current_is_cardinality() {
+        // Check for relationship, with symbolic or word-form cardinalities
+        if self.current_is_cardinality() || self.line_has_relationship_type() {
             return self.parse_relationship(start, entity_a, class_a);
         }
 
@@ -333,10 +350,7 @@ impl<'a> ErParser<'a> {
             self.advance();
             Some(name)
         } else if self.check(&ErToken::QuotedString) {
-            let text = self.current_text();
-            self.advance();
-            // Remove quotes
-            Some(text[1..text.len() - 1].to_string())
+            self.quoted_text()
         } else {
             None
         }
@@ -365,10 +379,20 @@ impl<'a> ErParser<'a> {
                 break;
             }
 
+            let attr_start = self.current_span().start;
             if let Some(attr) = self.parse_attribute() {
                 entity.add_child(attr);
             } else {
-                self.advance(); // Skip unknown token
+                // Report the malformed attribute once and skip the rest of its line
+                while !self.check(&ErToken::Newline) && !self.check(&ErToken::CloseBrace) && !self.is_at_end() {
+                    self.advance();
+                }
+                let end = self.previous_span().end.max(attr_start);
+                self.diagnostics.push(Diagnostic::error(
+                    DiagnosticCode::InvalidSyntax,
+                    "Expected an attribute as '<type> <name>'",
+                    Span::new(attr_start, end),
+                ));
             }
         }
 
@@ -458,9 +482,9 @@ impl<'a> ErParser<'a> {
 
         // Parse comment
         if self.check(&ErToken::QuotedString) {
-            let comment = self.current_text();
-            self.advance();
-            attr.add_property("comment", comment[1..comment.len() - 1].to_string());
+            if let Some(comment) = self.quoted_text() {
+                attr.add_property("comment", comment);
+            }
         }
 
         let end = self.previous_span().end;
@@ -479,19 +503,40 @@ impl<'a> ErParser<'a> {
         let card_a = self.parse_cardinality()?;
 
         // Parse identification type
-        let id_type = if self.check(&ErToken::Identifying) {
+        let id_type = if self.check(&ErToken::Identifying) || self.check(&ErToken::To) {
             self.advance();
             IdentificationType::Identifying
         } else if self.check(&ErToken::NonIdentifying) {
             self.advance();
             IdentificationType::NonIdentifying
+        } else if self.check(&ErToken::Optionally) {
+            self.advance();
+            if !self.check(&ErToken::To) {
+                self.diagnostics.push(Diagnostic::error(
+                    DiagnosticCode::ExpectedToken,
+                    "Expected 'to' after 'optionally'",
+                    self.current_span(),
+                ));
+                self.consume_until_newline();
+                return None;
+            }
+            self.advance();
+            IdentificationType::NonIdentifying
         } else {
             IdentificationType::Identifying
         };
 
+        if self.at_line_end() {
+            return self.report_wrapped_relationship(start);
+        }
+
         // Parse right cardinality
         let card_b = self.parse_cardinality()?;
 
+        if self.at_line_end() {
+            return self.report_wrapped_relationship(start);
+        }
+
         // Parse second entity
         let entity_b = self.parse_entity_name()?;
 
@@ -509,10 +554,22 @@ impl<'a> ErParser<'a> {
             None
         };
 
-        // Parse label (after colon)
+        // Parse label (after colon); the label is optional, but a colon
+        // must be followed by one
         let label = if self.check(&ErToken::Colon) {
+            let colon = self.current_span();
             self.advance();
-            Some(self.consume_until_newline())
+            self.consume_until_newline();
+            let raw = self.source[colon.end..self.previous_span().end.max(colon.end)].trim();
+            if raw.is_empty() {
+                self.diagnostics.push(
+                    Diagnostic::error(DiagnosticCode::InvalidSyntax, "Missing relationship label after ':'", colon)
+                        .with_note("write a label after the colon, or remove the colon"),
+                );
+                None
+            } else {
+                Some(raw.to_string())
+            }
         } else {
             None
         };
@@ -532,14 +589,60 @@ impl<'a> ErParser<'a> {
         if let Some(c) = class_b {
             rel.add_property("classB", c);
         }
-        if let Some(l) = label {
-            rel.add_property("label", l.trim().to_string());
+        if let Some(raw) = label {
+            // Quotes delimit the label; the text between them is kept as written
+            let text = raw
+                .strip_prefix('"')
+                .and_then(|l| l.strip_suffix('"'))
+                .unwrap_or(&raw);
+            rel.add_property("label", text.to_string());
+            rel.add_property("raw_label", raw);
         }
 
         Some(rel)
     }
 
-    /// Parse cardinality.
+    /// Check whether the current statement line has ended.
+    fn at_line_end(&self) -> bool {
+        self.is_at_end() || self.check(&ErToken::Newline) || self.check(&ErToken::Semicolon)
+    }
+
+    /// Report a relationship that was wrapped across lines.
+    ///
+    /// The continuation line (`ENTITY : label`) is consumed as well, so it is
+    /// not misread as a standalone entity.
+    fn report_wrapped_relationship(&mut self, start: usize) -> Option<AstNode> {
+        let end = self.current_span().start;
+        self.diagnostics.push(Diagnostic::error(
+            DiagnosticCode::InvalidSyntax,
+            "Relationship is incomplete; an ER relationship must be written on a single line",
+            Span::new(start, end),
+        ));
+
+        if self.check(&ErToken::Newline) {
+            self.advance();
+            if (self.check(&ErToken::Identifier) || self.check(&ErToken::QuotedString))
+                && self.peek_is(&ErToken::Colon)
+            {
+                self.consume_until_newline();
+            }
+        }
+
+        None
+    }
+
+    /// Check whether the token after the current one has the given kind.
+    fn peek_is(&self, kind: &ErToken) -> bool {
+        self.tokens
+            .get(self.pos + 1)
+            .map(|t| &t.kind == kind)
+            .unwrap_or(false)
+    }
+
+    /// Parse cardinality, in symbolic (`||`) or word (`only one`) form.
+    ///
+    /// Reports an error, and consumes the rest of the line, if neither form
+    /// matches.
     fn parse_cardinality(&mut self) -> Option<Cardinality> {
         if self.check(&ErToken::OnlyOneLeft) {
             self.advance();
@@ -554,10 +657,88 @@ impl<'a> ErParser<'a> {
             self.advance();
             Some(Cardinality::ZeroOrMore)
         } else {
-            None
+            self.parse_cardinality_words()
         }
     }
 
+    /// Parse a word-form cardinality such as `zero or more` or `1+`.
+    fn parse_cardinality_words(&mut self) -> Option<Cardinality> {
+        let start = self.current_span().start;
+        let rest = &self.source[start..];
+        let matched = CARDINALITY_WORDS
+            .find(rest)
+            .filter(|m| !rest[m.end()..].starts_with(|c: char| c.is_alphanumeric() || c == '_'));
+        if let Some(matched) = matched {
+            let end = start + matched.end();
+            while !self.is_at_end() && self.current_span().start < end {
+                self.advance();
+            }
+            let words: Vec<&str> = matched.as_str().split_whitespace().collect();
+            return Cardinality::from_str(&words.join(" "));
+        }
+
+        let end = self.unknown_cardinality_end();
+        let diagnostic = if end == start {
+            Diagnostic::error(
+                DiagnosticCode::MissingElement,
+                format!("Missing cardinality before '{}'", self.current_text()),
+                self.current_span(),
+            )
+        } else {
+            Diagnostic::error(
+                DiagnosticCode::InvalidValue,
+                format!("Unknown cardinality '{}'", &self.source[start..end]),
+                Span::new(start, end),
+            )
+        };
+        self.diagnostics.push(diagnostic.with_note(
+            "use a symbol such as '||' or 'o{', or one of: only one, zero or one, one or more, \
+             zero or more, one or many, zero or many, 1, 1+, 0+, many(1), many(0)",
+        ));
+        self.consume_until_newline();
+        None
+    }
+
+    /// Returns the end offset of the unrecognized cardinality words at the
+    /// current token: they stop at the relationship type, or before the
+    /// entity name that ends the relationship.
+    fn unknown_cardinality_end(&self) -> usize {
+        let mut end = self.current_span().start;
+        for (i, token) in self.tokens.iter().enumerate().skip(self.pos) {
+            let ends_line = match self.tokens.get(i + 1).map(|t| &t.kind) {
+                None => true,
+                Some(kind) => matches!(
+                    kind,
+                    ErToken::Newline | ErToken::Semicolon | ErToken::Colon | ErToken::TripleColon
+                ),
+            };
+            let is_word = match token.kind {
+                ErToken::Identifier | ErToken::QuotedString => !ends_line,
+                ErToken::To | ErToken::Optionally | ErToken::Newline | ErToken::Semicolon | ErToken::Colon => false,
+                ref kind => !kind.is_relationship_line() && !kind.is_cardinality(),
+            };
+            if !is_word {
+                break;
+            }
+            end = token.span.end;
+        }
+        end
+    }
+
+    /// Check whether the current line has a relationship type (`--`, `..`,
+    /// `to` or `optionally to`) before any label or attribute block.
+    fn line_has_relationship_type(&self) -> bool {
+        self.tokens[self.pos.min(self.tokens.len())..]
+            .iter()
+            .take_while(|t| {
+                !matches!(
+                    t.kind,
+                    ErToken::Newline | ErToken::Semicolon | ErToken::Colon | ErToken::OpenBrace
+                )
+            })
+            .any(|t| t.kind.is_relationship_line() || matches!(t.kind, ErToken::To | ErToken::Optionally))
+    }
+
     /// Check if current token is a cardinality marker.
     fn current_is_cardinality(&self) -> bool {
         if let Some(token) = self.current() {
@@ -586,13 +767,21 @@ impl<'a> ErParser<'a> {
         self.tokens.get(self.pos)
     }
 
+    /// Consumes a quoted string token, returning the text between the
+    /// quotes; see [`quotes::unquote`].
+    fn quoted_text(&mut self) -> Option<String> {
+        let (text, span) = (self.current_text(), self.current_span());
+        self.advance();
+        quotes::unquote(&text, span, &mut self.diagnostics)
+    }
+
     fn current_text(&self) -> String {
         self.current().map(|t| t.text.clone()).unwrap_or_default()
     }
 
     fn current_span(&self) -> Span {
         self.current()
-            .map(|t| Span::new(t.span.start, t.span.end))
+            .map(|t| t.span)
             .unwrap_or(Span::new(self.source.len(), self.source.len()))
     }
 
@@ -600,7 +789,7 @@ impl<'a> ErParser<'a> {
         if self.pos > 0 {
             self.tokens
                 .get(self.pos - 1)
-                .map(|t| Span::new(t.span.start, t.span.end))
+                .map(|t| t.span)
                 .unwrap_or(Span::new(0, 0))
         } else {
             Span::new(0, 0)
@@ -628,6 +817,28 @@ impl<'a> ErParser<'a> {
     }
 }
 
+impl StatementLoop for ErParserImpl<'_> {
+    fn position(&self) -> usize {
+        self.pos
+    }
+
+    fn at_end(&self) -> bool {
+        self.is_at_end()
+    }
+
+    fn token_span(&self) -> Span {
+        self.current_span()
+    }
+
+    fn skip_token(&mut self) {
+        self.advance();
+    }
+
+    fn report(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -637,11 +848,62 @@ mod tests {
         let code = r#"erDiagram
     CUSTOMER ||--o{ ORDER : places"#;
 
-        let mut parser = ErParser::new(code);
+        let mut parser = ErParserImpl::new(code);
         let result = parser.parse();
         assert!(result.is_ok(), "Failed: {:?}", result.err());
     }
 
+    #[test]
+    fn test_parse_wrapped_relationship() {
+        let code = "erDiagram\n    CUSTOMER ||--\n    o{ ORDER : places";
+
+        let mut parser = ErParserImpl::new(code);
+        let diagnostics = parser.parse().unwrap_err();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, DiagnosticCode::InvalidSyntax);
+    }
+
+    #[test]
+    fn test_parse_missing_cardinality_word() {
+        let code = "erDiagram\n    CUSTOMER only one optionally ORDER\n    ORDER two to one or more ITEM";
+
+        let mut parser = ErParserImpl::new(code);
+        let diagnostics = parser.parse().unwrap_err();
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].message, "Expected 'to' after 'optionally'");
+        assert_eq!(diagnostics[1].message, "Unknown cardinality 'two'");
+        assert_eq!(diagnostics[1].span.text(code), "two");
+    }
+
+    #[test]
+    fn test_parse_relationship_labels() {
+        let code = "erDiagram\n    CUSTOMER ||--o{ ORDER : \"places  online orders\"\n    ORDER ||--|{ ITEM : ratio: 1:n\n    ORDER ||--|| INVOICE";
+
+        let ast = ErParserImpl::new(code).parse().expect("parse failed");
+        let mut relationships = Vec::new();
+        ast.walk(|node, _| {
+            if node.kind == NodeKind::Relationship {
+                relationships.push(node.clone());
+            }
+        });
+        assert_eq!(relationships.len(), 3);
+        assert_eq!(relationships[0].label(), Some("places  online orders"));
+        assert_eq!(relationships[0].get_property("raw_label"), Some("\"places  online orders\""));
+        assert_eq!(relationships[1].label(), Some("ratio: 1:n"));
+        assert_eq!(relationships[2].label(), None);
+    }
+
+    #[test]
+    fn test_parse_empty_relationship_label() {
+        let code = "erDiagram\n    CUSTOMER ||--o{ ORDER :   \n    ORDER ||--|{ ITEM : contains";
+
+        let diagnostics = ErParserImpl::new(code).parse().unwrap_err();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, DiagnosticCode::InvalidSyntax);
+        assert_eq!(diagnostics[0].span.start, code.find(':').unwrap());
+        assert_eq!(diagnostics[0].span.text(code), ":");
+    }
+
     #[test]
     fn test_parse_with_attributes() {
         let code = r#"erDiagram
@@ -650,7 +912,7 @@ mod tests {
         string custNumber PK
     }"#;
 
-        let mut parser = ErParser::new(code);
+        let mut parser = ErParserImpl::new(code);
         let result = parser.parse();
         assert!(result.is_ok(), "Failed: {:?}", result.err());
     }
@@ -662,7 +924,7 @@ mod tests {
     ORDER ||--|{ LINE-ITEM : contains
     CUSTOMER }|..|{ DELIVERY-ADDRESS : uses"#;
 
-        let mut parser = ErParser::new(code);
+        let mut parser = ErParserImpl::new(code);
         let result = parser.parse();
         assert!(result.is_ok(), "Failed: {:?}", result.err());
     }
@@ -687,7 +949,7 @@ mod tests {
         float pricePerUnit
     }"#;
 
-        let mut parser = ErParser::new(code);
+        let mut parser = ErParserImpl::new(code);
         let result = parser.parse();
         assert!(result.is_ok(), "Failed: {:?}", result.err());
     }
@@ -698,7 +960,7 @@ mod tests {
     direction LR
     CUSTOMER ||--o{ ORDER : places"#;
 
-        let mut parser = ErParser::new(code);
+        let mut parser = ErParserImpl::new(code);
         let result = parser.parse();
         assert!(result.is_ok(), "Failed: {:?}", result.err());
     }
@@ -706,8 +968,19 @@ mod tests {
     #[test]
     fn test_parse_invalid() {
         let code = "not an er diagram";
-        let mut parser = ErParser::new(code);
+        let mut parser = ErParserImpl::new(code);
         let result = parser.parse();
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_curly_and_unterminated_quotes() {
+        let result = crate::parse("erDiagram\n    “Line Item” ||--o{ ORDER : has", None);
+        assert!(result.ok);
+        assert_eq!(result.diagnostics[0].message, "Curly quotes are read as straight quotes");
+
+        let result = crate::parse("erDiagram\n    \"LINE ||--o{ ORDER : has\n    A ||--o{ B : x", None);
+        assert!(!result.ok);
+        assert_eq!(result.diagnostics[0].code, DiagnosticCode::UnterminatedString);
+    }
 }
@@ -0,0 +1,61 @@
+//! Semantic validation for ER diagrams.
+
+use crate::ast::{Ast, NodeKind, Span};
+use crate::diagnostic::{Diagnostic, OptInCheck};
+
+/// Warns about attributes whose type is not in `allowed`, for teams that
+/// restrict attribute types to a known vocabulary.
+///
+/// A generic type such as `list~string~` is checked on its base name
+/// (`list`). Types are compared exactly, so `String` does not match
+/// `string`.
+pub fn attribute_types(ast: &Ast, allowed: &[String]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    ast.walk(|node, _| {
+        if node.kind != NodeKind::Attribute {
+            return;
+        }
+        let Some(attr_type) = node.get_property("type") else {
+            return;
+        };
+        let base = attr_type.split('~').next().unwrap_or_default();
+        if allowed.iter().any(|t| t == base) {
+            return;
+        }
+        // The type is the first token of the attribute
+        let text = ast.text_for_span(&node.span);
+        let len = text.find(char::is_whitespace).unwrap_or(text.len());
+        diagnostics.push(
+            OptInCheck::ErAttributeTypes
+                .diagnostic(
+                    format!("'{}' is not an allowed attribute type", base),
+                    Span::from_len(node.span.start, len),
+                )
+                .with_note(format!("allowed types: {}", allowed.join(", "))),
+        );
+    });
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MermaidConfig;
+    use crate::diagrams::er::ErParser;
+    use crate::parser::traits::DiagramParser;
+
+    #[test]
+    fn test_attribute_types() {
+        let code = "erDiagram\n    CUSTOMER {\n        string name\n        int age\n        varchar email\n        list~string~ tags\n        map~int~ scores\n    }";
+        let ast = ErParser::new().parse(code, &MermaidConfig::default()).expect("parse failed");
+        let allowed: Vec<String> = ["string", "int", "list"].iter().map(|t| t.to_string()).collect();
+
+        let diagnostics = attribute_types(&ast, &allowed);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].message, "'varchar' is not an allowed attribute type");
+        assert_eq!(diagnostics[0].span.text(code), "varchar");
+        assert_eq!(diagnostics[0].notes[0], "allowed types: string, int, list");
+        assert_eq!(diagnostics[1].message, "'map' is not an allowed attribute type");
+        assert_eq!(diagnostics[1].span.text(code), "map~int~");
+    }
+}
@@ -0,0 +1,205 @@
+//! Label text resolution.
+//!
+//! A node, subgraph or link label is stored twice: `raw_label` is the
+//! source text between the delimiters, and `label` is the logical text. To
+//! get the logical text, `\"`, `\'` and `\\` inside quoted strings are
+//! unescaped, and Mermaid entity codes (`#quot;`, `#9829;`) are decoded to
+//! their characters.
+
+use crate::ast::Span;
+use crate::diagnostic::{Diagnostic, DiagnosticCode};
+use crate::parser::quotes;
+
+/// Named entity codes and the characters they stand for.
+const NAMED_ENTITIES: &[(&str, char)] = &[
+    ("quot", '"'), ("amp", '&'), ("lt", '<'), ("gt", '>'), ("apos", '\''),
+    ("nbsp", '\u{a0}'), ("num", '#'), ("semi", ';'), ("colon", ':'), ("comma", ','),
+    ("lpar", '('), ("rpar", ')'), ("lsqb", '['), ("rsqb", ']'), ("lcub", '{'),
+    ("rcub", '}'), ("vert", '|'), ("bsol", '\\'), ("sol", '/'), ("ast", '*'),
+    ("excl", '!'), ("quest", '?'), ("equals", '='), ("plus", '+'), ("minus", '−'),
+    ("times", '×'), ("divide", '÷'), ("deg", '°'), ("plusmn", '±'), ("micro", 'µ'),
+    ("middot", '·'), ("bull", '•'), ("hellip", '…'), ("ndash", '–'), ("mdash", '—'),
+    ("lsquo", '‘'), ("rsquo", '’'), ("ldquo", '“'), ("rdquo", '”'), ("laquo", '«'),
+    ("raquo", '»'), ("copy", '©'), ("reg", '®'), ("trade", '™'), ("sect", '§'),
+    ("para", '¶'), ("cent", '¢'), ("pound", '£'), ("euro", '€'), ("yen", '¥'),
+    ("larr", '←'), ("rarr", '→'), ("uarr", '↑'), ("darr", '↓'), ("harr", '↔'),
+    ("lArr", '⇐'), ("rArr", '⇒'), ("hArr", '⇔'), ("le", '≤'), ("ge", '≥'),
+    ("ne", '≠'), ("infin", '∞'), ("check", '✓'), ("cross", '✗'), ("hearts", '♥'),
+    ("spades", '♠'), ("clubs", '♣'), ("diams", '♦'), ("star", '☆'), ("starf", '★'),
+];
+
+/// A resolved label.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(super) struct Label {
+    /// The logical text.
+    pub text: String,
+    /// The source text.
+    pub raw: String,
+}
+
+impl Label {
+    /// Returns true if the logical text is empty.
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+}
+
+/// Label text under construction, with the source offset of each character.
+#[derive(Debug, Default)]
+pub(super) struct LabelBuilder {
+    chars: Vec<(char, usize)>,
+}
+
+impl LabelBuilder {
+    /// Appends unquoted text starting at `offset`.
+    pub fn push_text(&mut self, text: &str, offset: usize) {
+        self.chars.extend(text.char_indices().map(|(i, c)| (c, offset + i)));
+    }
+
+    /// Appends the content of a quoted string token starting at `offset`,
+    /// unescaping `\"`, `\'` and `\\`. Other backslashes are kept as written.
+    /// An unterminated string runs to the end of the token.
+    pub fn push_quoted(&mut self, token: &str, offset: usize) {
+        let open = token.chars().next().map_or(0, char::len_utf8);
+        let inner = quotes::strip_matching_quotes(token).unwrap_or(&token[open..]);
+        let mut chars = inner.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            if c == '\\' {
+                if let Some(&(_, next @ ('"' | '\'' | '\\'))) = chars.peek() {
+                    chars.next();
+                    self.chars.push((next, offset + open + i));
+                    continue;
+                }
+            }
+            self.chars.push((c, offset + open + i));
+        }
+    }
+
+    /// Trims the text and decodes entity codes, reporting malformed escapes.
+    pub fn finish(self, raw: &str, diagnostics: &mut Vec<Diagnostic>) -> String {
+        let start = self.chars.iter().position(|(c, _)| !c.is_whitespace());
+        let end = self.chars.iter().rposition(|(c, _)| !c.is_whitespace());
+        let chars = match (start, end) {
+            (Some(start), Some(end)) => &self.chars[start..=end],
+            _ => return String::new(),
+        };
+
+        if raw.ends_with('\\') {
+            let offset = chars[chars.len() - 1].1;
+            diagnostics.push(Diagnostic::hint(
+                DiagnosticCode::InvalidEscape,
+                "Trailing backslash in label escapes nothing and is kept as written",
+                Span::new(offset, offset + 1),
+            ));
+        }
+
+        let mut text = String::with_capacity(chars.len());
+        let mut i = 0;
+        while i < chars.len() {
+            let (c, offset) = chars[i];
+            if c == '#' {
+                if let Some(len) = entity_len(&chars[i..]) {
+                    let code: String = chars[i + 1..i + len - 1].iter().map(|(c, _)| c).collect();
+                    let span = Span::new(offset, chars[i + len - 1].1 + 1);
+                    match decode_entity(&code) {
+                        Ok(decoded) => text.push(decoded),
+                        Err(message) => {
+                            diagnostics.push(Diagnostic::warning(DiagnosticCode::InvalidEscape, message, span));
+                            text.push_str(&format!("#{};", code));
+                        }
+                    }
+                    i += len;
+                    continue;
+                }
+            }
+            text.push(c);
+            i += 1;
+        }
+        text
+    }
+}
+
+/// Returns the length in characters of the `#code;` entity at the start of
+/// `chars`, if there is one.
+fn entity_len(chars: &[(char, usize)]) -> Option<usize> {
+    let code_len = chars[1..]
+        .iter()
+        .take_while(|(c, _)| c.is_ascii_alphanumeric() || *c == '_')
+        .count();
+    match chars.get(code_len + 1) {
+        Some((';', _)) if code_len > 0 => Some(code_len + 2),
+        _ => None,
+    }
+}
+
+/// Decodes the code of a `#NNNN;` or `#name;` entity.
+fn decode_entity(code: &str) -> Result<char, String> {
+    if code.chars().all(|c| c.is_ascii_digit()) {
+        return code
+            .parse::<u32>()
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| format!("Invalid character code '#{};'", code));
+    }
+    NAMED_ENTITIES
+        .iter()
+        .find(|(name, _)| *name == code)
+        .map(|(_, c)| *c)
+        .ok_or_else(|| format!("Unknown entity '#{};' is kept as written", code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostic::Severity;
+
+    fn resolve(raw: &str) -> (String, Vec<Diagnostic>) {
+        let mut builder = LabelBuilder::default();
+        if raw.starts_with('"') {
+            builder.push_quoted(raw, 0);
+        } else {
+            builder.push_text(raw, 0);
+        }
+        let mut diagnostics = Vec::new();
+        let text = builder.finish(raw, &mut diagnostics);
+        (text, diagnostics)
+    }
+
+    #[test]
+    fn test_escaped_quotes() {
+        assert_eq!(resolve(r#""She said \"hi\"""#).0, "She said \"hi\"");
+        assert_eq!(resolve(r#""a \\ b""#).0, "a \\ b");
+        assert_eq!(resolve(r#""C:\path""#).0, "C:\\path");
+    }
+
+    #[test]
+    fn test_entity_codes() {
+        assert_eq!(resolve("Say #quot;hi#quot;").0, "Say \"hi\"");
+        assert_eq!(resolve("I #9829; you").0, "I ♥ you");
+        assert_eq!(resolve("a # b; c").0, "a # b; c");
+    }
+
+    #[test]
+    fn test_unknown_entity() {
+        let (text, diagnostics) = resolve("a #quo; b");
+        assert_eq!(text, "a #quo; b");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[0].span, Span::new(2, 7));
+    }
+
+    #[test]
+    fn test_invalid_character_code() {
+        let (text, diagnostics) = resolve("#55296;");
+        assert_eq!(text, "#55296;");
+        assert_eq!(diagnostics[0].code, DiagnosticCode::InvalidEscape);
+    }
+
+    #[test]
+    fn test_trailing_backslash() {
+        let (text, diagnostics) = resolve("path\\");
+        assert_eq!(text, "path\\");
+        assert_eq!(diagnostics[0].severity, Severity::Hint);
+        assert_eq!(diagnostics[0].span, Span::new(4, 5));
+    }
+}
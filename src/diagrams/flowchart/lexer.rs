@@ -3,6 +3,7 @@
 use logos::Logos;
 
 use crate::ast::Span;
+use crate::parser::lexer;
 
 /// Token types for flowchart parsing.
 #[derive(Logos, Debug, Clone, PartialEq, Eq)]
@@ -39,32 +40,43 @@ pub enum FlowToken {
     #[token("linkStyle", ignore(case))]
     LinkStyle,
 
+    #[token("accTitle", ignore(case))]
+    AccTitle,
+
+    #[token("accDescr", ignore(case))]
+    AccDescr,
+
     // Direction keywords
     #[regex(r"(?i)TB|TD|BT|LR|RL")]
     DirectionValue,
 
-    // Arrow types (order matters - longer patterns first)
-    #[token("-->")]
+    // Arrow types. Arrows may end in a circle (`--o`) or a cross (`--x`)
+    // instead of a head, and any link may be drawn longer, as in `---->`
+    // or `-...-`, to span more ranks.
+    #[regex("-{2,}[>ox]")]
     Arrow,
 
-    #[token("---")]
+    #[regex("-{3,}")]
     Line,
 
-    #[token("-.-")]
+    #[regex(r"-\.+-")]
     DottedLine,
 
-    #[token("-.->")]
+    #[regex(r"-\.+-[>ox]")]
     DottedArrow,
 
-    #[token("==>")]
+    #[regex("={2,}[>ox]")]
     ThickArrow,
 
-    #[token("===")]
+    #[regex("={3,}")]
     ThickLine,
 
-    #[token("~~~")]
+    // Any number of tildes past three, as in `A ~~~~ B`
+    #[regex("~~~+")]
     Invisible,
 
+    /// Opens a link with its label inside, closed by `-->` or `---`. A
+    /// closed one becomes a [`FlowToken::LabeledLink`].
     #[token("--")]
     DoubleDash,
 
@@ -142,6 +154,19 @@ pub enum FlowToken {
     #[token("|")]
     Pipe,
 
+    /// A `|label|` right after a link, from its opening pipe through the
+    /// closing one, or to the end of the line if it is not closed. Produced
+    /// by [`tokenize`] rather than by the logos rules.
+    PipeLabel,
+
+    /// A link with its label written inside it, `-- label -->`,
+    /// `-. label .->` or `== label ==>`, from the opening through the closing
+    /// part. Produced by [`tokenize`] rather than by the logos rules.
+    LabeledLink,
+
+    #[token(":::")]
+    TripleColon,
+
     #[token(":")]
     Colon,
 
@@ -168,6 +193,10 @@ pub enum FlowToken {
     #[regex(r#"`([^`])*`"#)]
     BacktickString,
 
+    // Edge ids (`e1@-->`, `e1@{ ... }`)
+    #[regex(r"[a-zA-Z_][a-zA-Z0-9_]*@")]
+    EdgeId,
+
     // Identifiers
     #[regex(r"[a-zA-Z_][a-zA-Z0-9_]*")]
     Identifier,
@@ -182,33 +211,121 @@ pub enum FlowToken {
     Text,
 }
 
-/// A positioned token.
-#[derive(Debug, Clone)]
-pub struct PositionedToken {
-    pub kind: FlowToken,
-    pub span: Span,
-    pub text: String,
+/// A flowchart token with its span and text.
+pub type Token = lexer::Token<FlowToken>;
+
+/// Tokenizes flowchart source, skipping text no token matches.
+///
+/// The label of a link written `-->|label|` is read raw, as one
+/// [`FlowToken::PipeLabel`], so that quotes and brackets in it are not
+/// lexed as strings or node shapes. So is a link with its label inside,
+/// as one [`FlowToken::LabeledLink`], if it is closed on the same line.
+/// An identifier takes in the `-word` parts that follow it, as in
+/// `order-svc`.
+pub fn tokenize(source: &str) -> Vec<Token> {
+    let mut lexer = FlowToken::lexer(source);
+    let mut tokens: Vec<Token> = Vec::new();
+    while let Some(result) = lexer.next() {
+        let Ok(mut kind) = result else {
+            continue;
+        };
+        let rest = lexer.remainder();
+        let line = &rest[..rest.find('\n').unwrap_or(rest.len())];
+        if kind == FlowToken::Pipe && tokens.last().is_some_and(|t| t.kind.is_link()) {
+            lexer.bump(pipe_label_end(line).unwrap_or(line.len()));
+            kind = FlowToken::PipeLabel;
+        } else if kind == FlowToken::Identifier {
+            lexer.bump(hyphenated_len(rest));
+        } else if matches!(kind, FlowToken::DoubleDash | FlowToken::DashDot | FlowToken::DoubleEqual) {
+            if let Some(closer) = link_closer(lexer.slice(), line) {
+                lexer.bump(closer.end);
+                kind = FlowToken::LabeledLink;
+            }
+        }
+        let span = lexer.span();
+        tokens.push(Token::new(kind, Span::new(span.start, span.end), lexer.slice()));
+    }
+    tokens
 }
 
-/// Tokenize flowchart source code.
-pub fn tokenize(source: &str) -> Vec<PositionedToken> {
-    let mut tokens = Vec::new();
-    let mut lexer = FlowToken::lexer(source);
+/// Returns the offset just past the first unescaped `|` in `text`, which
+/// follows the opening pipe of a label. A backslash escapes the character
+/// after it.
+pub(crate) fn pipe_label_end(text: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '|' => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
 
-    while let Some(result) = lexer.next() {
-        if let Ok(kind) = result {
-            let span = lexer.span();
-            let text = lexer.slice().to_string();
-            tokens.push(PositionedToken {
-                kind,
-                span: Span::new(span.start, span.end),
-                text,
-            });
+/// Returns the length of the `-word` parts at the start of `text`, which
+/// continue an identifier. Like Mermaid, a `-` followed by another `-`, a
+/// `.` or a `>` starts a link instead.
+pub(crate) fn hyphenated_len(text: &str) -> usize {
+    let mut len = 0;
+    while let Some(rest) = text[len..].strip_prefix('-') {
+        let word = rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or(rest.len());
+        if word == 0 {
+            break;
         }
-        // Skip invalid tokens
+        len += 1 + word;
     }
+    len
+}
 
-    tokens
+/// Returns the range in `text`, which follows the link opening `opener`
+/// (`--`, `-.` or `==`), of the part closing the link: `-->` or `---` after
+/// `--`, `.->` or `.-` after `-.`, `==>` or `===` after `==`, with any
+/// number of extra dots, dashes or equals signs. The label before it must not be
+/// blank.
+pub(crate) fn link_closer(opener: &str, text: &str) -> Option<std::ops::Range<usize>> {
+    let (find, stroke) = match opener {
+        "--" => ("--", '-'),
+        "-." => (".-", '-'),
+        "==" => ("==", '='),
+        _ => return None,
+    };
+    let found = text.find(find)?;
+    // A longer dotted closer, as in `-. label ..->`, starts at its first dot
+    let start = match opener {
+        "-." => text[..found].trim_end_matches('.').len(),
+        _ => found,
+    };
+    if text[..start].trim().is_empty() {
+        return None;
+    }
+    let after = &text[found + 1..];
+    let strokes = after.len() - after.trim_start_matches(stroke).len();
+    let end = found + 1 + strokes;
+    if text[end..].starts_with('>') {
+        Some(start..end + 1)
+    } else if opener == "-." || strokes >= 2 {
+        Some(start..end)
+    } else {
+        None
+    }
+}
+
+impl FlowToken {
+    /// Whether the token is a complete link that a `|label|` can follow.
+    fn is_link(&self) -> bool {
+        matches!(
+            self,
+            FlowToken::Arrow
+                | FlowToken::Line
+                | FlowToken::DottedLine
+                | FlowToken::DottedArrow
+                | FlowToken::ThickArrow
+                | FlowToken::ThickLine
+                | FlowToken::Invisible
+        )
+    }
 }
 
 #[cfg(test)]
@@ -263,11 +380,85 @@ mod tests {
         assert!(tokens.iter().any(|t| t.kind == FlowToken::DoubleQuotedString));
     }
 
+    #[test]
+    fn test_tokenize_edge_id() {
+        let tokens = tokenize("A e1@--> B");
+
+        assert_eq!(tokens[1].kind, FlowToken::EdgeId);
+        assert_eq!(tokens[1].text, "e1@");
+        assert_eq!(tokens[2].kind, FlowToken::Arrow);
+    }
+
     #[test]
     fn test_tokenize_edge_label() {
         let tokens = tokenize("A -->|label| B");
 
         assert!(tokens.iter().any(|t| t.kind == FlowToken::Arrow));
-        assert!(tokens.iter().filter(|t| t.kind == FlowToken::Pipe).count() >= 1);
+        assert!(tokens.iter().any(|t| t.kind == FlowToken::PipeLabel && t.text == "|label|"));
+    }
+
+    #[test]
+    fn test_long_links() {
+        for (code, kind) in [
+            ("A ----> B", FlowToken::Arrow),
+            ("A ---- B", FlowToken::Line),
+            ("A -...-> B", FlowToken::DottedArrow),
+            ("A -..- B", FlowToken::DottedLine),
+            ("A ====> B", FlowToken::ThickArrow),
+            ("A ==== B", FlowToken::ThickLine),
+            ("A ---o B", FlowToken::Arrow),
+        ] {
+            let tokens = tokenize(code);
+            assert_eq!(tokens.len(), 3, "{}", code);
+            assert_eq!(tokens[1].kind, kind, "{}", code);
+        }
+    }
+
+    #[test]
+    fn test_hyphenated_identifiers() {
+        let ids = |code| -> Vec<String> {
+            tokenize(code).into_iter().filter(|t| t.kind == FlowToken::Identifier).map(|t| t.text).collect()
+        };
+        assert_eq!(ids("order-svc --> order"), ["order-svc", "order"]);
+        assert_eq!(ids("a-b-c-->d"), ["a-b-c", "d"]);
+        assert_eq!(ids("A-.->B"), ["A", "B"]);
+        assert_eq!(ids("A---B"), ["A", "B"]);
+        assert_eq!(hyphenated_len("-x_1-y z"), 6);
+        assert_eq!(hyphenated_len("->B"), 0);
+    }
+
+    #[test]
+    fn test_labeled_links() {
+        for (code, link) in [
+            ("A -- a b --> B", "-- a b -->"),
+            ("A-. dotted .->B", "-. dotted .->"),
+            ("A== thick ==>B", "== thick ==>"),
+            ("A -- open --- B", "-- open ---"),
+            ("A -. \"x-y\" .- B", "-. \"x-y\" .-"),
+            ("A -- long ----> B", "-- long ---->"),
+            ("A -. long ...-> B", "-. long ...->"),
+            ("A == long ====> B", "== long ====>"),
+        ] {
+            let tokens = tokenize(code);
+            assert_eq!(tokens[1].kind, FlowToken::LabeledLink, "{}", code);
+            assert_eq!(tokens[1].text, link);
+        }
+        assert_eq!(tokenize("A -- x\n--> B")[1].kind, FlowToken::DoubleDash);
+        assert_eq!(tokenize("A == x")[1].kind, FlowToken::DoubleEqual);
+        assert_eq!(link_closer("--", " -->"), None);
+        assert_eq!(link_closer("--", " a -- b"), None);
+    }
+
+    #[test]
+    fn test_pipe_label_is_read_raw() {
+        let tokens = tokenize("A -->|a \"[b]\" \\| c| B\nC");
+        let label = tokens.iter().find(|t| t.kind == FlowToken::PipeLabel).unwrap();
+        assert_eq!(label.text, "|a \"[b]\" \\| c|");
+        assert!(!tokens.iter().any(|t| t.kind == FlowToken::LBracket));
+
+        let tokens = tokenize("A -->|open\nB");
+        assert_eq!(tokens[2].text, "|open");
+        assert_eq!(tokens[3].kind, FlowToken::Newline);
+        assert_eq!(pipe_label_end("a\\\\|b|"), Some(4));
     }
 }
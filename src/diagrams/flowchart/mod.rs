@@ -15,8 +15,12 @@
 //!     D --> E
 //! ```
 
+mod label;
 mod lexer;
 mod parser;
+pub mod render_ids;
+mod shapes;
+pub mod validate;
 
 pub use parser::FlowchartParser;
 
@@ -32,6 +36,7 @@ pub enum Direction {
 }
 
 impl Direction {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_uppercase().as_str() {
             "TB" | "TD" => Some(Direction::TopToBottom),
@@ -81,7 +86,8 @@ pub enum LinkType {
 pub struct FlowNode {
     pub id: String,
     pub label: Option<String>,
-    pub shape: NodeShape,
+    /// The declared shape, or `None` if the node was only referenced bare.
+    pub shape: Option<NodeShape>,
     pub span: Span,
 }
 
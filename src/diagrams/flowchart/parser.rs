@@ -1,20 +1,49 @@
 //! Flowchart parser implementation.
 
+use std::collections::HashSet;
+
 use crate::ast::{Ast, AstNode, NodeKind, Span};
 use crate::config::MermaidConfig;
-use crate::diagnostic::{Diagnostic, DiagnosticCode};
+use crate::detector::DiagramType;
+use crate::diagnostic::{excerpt, Diagnostic, DiagnosticCode, TextEdit};
+use crate::parser::{accessibility, click, dangling, quotes, unknown, StatementLoop};
+use crate::parser::lexer::{patterns, Cursor};
 use crate::parser::traits::DiagramParser;
 
-use super::lexer::{tokenize, FlowToken, PositionedToken};
+use super::label::{Label, LabelBuilder};
+use super::lexer::{link_closer, pipe_label_end, tokenize, FlowToken, Token};
+use super::shapes;
 use super::{Direction, LinkType, NodeShape};
 
+/// Statement keywords: a line starting with one doesn't continue a link
+/// split across lines.
+const KEYWORDS: &[&str] = &["end", "subgraph", "style", "classDef", "class", "click", "linkStyle", "direction"];
+
+/// Mermaid statement keywords this parser skips rather than checks. Every
+/// flowchart statement keyword is parsed today.
+const NEW_KEYWORDS: &[&str] = &[];
+
 /// Flowchart parser.
-pub struct FlowchartParser;
+pub struct FlowchartParser {
+    /// Whether the diagram is a legacy `graph` flowchart, where constructs
+    /// that need the `flowchart` keyword produce warnings.
+    legacy: bool,
+}
 
 impl FlowchartParser {
     /// Creates a new flowchart parser.
     pub fn new() -> Self {
-        Self
+        Self { legacy: false }
+    }
+
+    /// Creates a parser for the detected flowchart variant. A legacy
+    /// flowchart ([`DiagramType::Flowchart`]) warns about the constructs
+    /// that only the `flowchart` keyword supports: subgraph `direction`,
+    /// `&` node lists and `@{ ... }` shape metadata.
+    pub fn for_type(diagram_type: DiagramType) -> Self {
+        Self {
+            legacy: diagram_type == DiagramType::Flowchart,
+        }
     }
 }
 
@@ -27,10 +56,16 @@ impl Default for FlowchartParser {
 impl DiagramParser for FlowchartParser {
     fn parse(&self, code: &str, _config: &MermaidConfig) -> Result<Ast, Vec<Diagnostic>> {
         let tokens = tokenize(code);
-        let mut parser = FlowchartParserImpl::new(&tokens, code);
+        let mut parser = FlowchartParserImpl::new(&tokens, code, self.legacy);
         parser.parse()
     }
 
+    fn parse_partial(&self, code: &str, _config: &MermaidConfig) -> (Option<Ast>, Vec<Diagnostic>) {
+        let tokens = tokenize(code);
+        let mut parser = FlowchartParserImpl::new(&tokens, code, self.legacy);
+        parser.parse_partial()
+    }
+
     fn name(&self) -> &'static str {
         "flowchart"
     }
@@ -38,23 +73,61 @@ impl DiagramParser for FlowchartParser {
 
 /// Internal parser implementation.
 struct FlowchartParserImpl<'a> {
-    tokens: &'a [PositionedToken],
+    tokens: &'a [Token],
     pos: usize,
     source: &'a str,
     diagnostics: Vec<Diagnostic>,
+    /// Node ids that appeared with a shape or label at least once.
+    declared_ids: HashSet<String>,
+    /// Number of links parsed so far, used as the next link's `index`.
+    edge_count: usize,
+    /// Ids given to links with `id@`.
+    edge_ids: HashSet<String>,
+    /// Whether this is a legacy `graph` flowchart.
+    legacy: bool,
 }
 
 impl<'a> FlowchartParserImpl<'a> {
-    fn new(tokens: &'a [PositionedToken], source: &'a str) -> Self {
+    fn new(tokens: &'a [Token], source: &'a str, legacy: bool) -> Self {
         Self {
             tokens,
             pos: 0,
             source,
             diagnostics: Vec::new(),
+            declared_ids: HashSet::new(),
+            edge_count: 0,
+            edge_ids: HashSet::new(),
+            legacy,
+        }
+    }
+
+    /// Warns that `feature` is not supported by a legacy `graph` flowchart.
+    fn warn_if_legacy(&mut self, code: DiagnosticCode, feature: &str, span: Span) {
+        if self.legacy {
+            self.diagnostics.push(
+                Diagnostic::warning(
+                    code,
+                    format!(
+                        "{} requires the 'flowchart' keyword or defaultRenderer: dagre-wrapper",
+                        feature
+                    ),
+                    span,
+                )
+                .with_note("'graph' diagrams use the legacy renderer unless flowchart.defaultRenderer is set"),
+            );
         }
     }
 
     fn parse(&mut self) -> Result<Ast, Vec<Diagnostic>> {
+        match self.parse_partial() {
+            (Some(ast), diagnostics) if !diagnostics.iter().any(|d| d.severity.is_error()) => Ok(ast),
+            (_, diagnostics) => Err(diagnostics),
+        }
+    }
+
+    /// Parses the diagram, returning the AST built so far alongside all
+    /// diagnostics. Unparseable statements are kept as `Error` nodes.
+    fn parse_partial(&mut self) -> (Option<Ast>, Vec<Diagnostic>) {
         let start_span = Span::new(0, self.source.len());
         let mut root = AstNode::new(NodeKind::Root, start_span);
 
@@ -70,32 +143,87 @@ impl<'a> FlowchartParserImpl<'a> {
                 "Expected 'graph' or 'flowchart' declaration",
                 Span::new(0, 0),
             ));
-            return Err(std::mem::take(&mut self.diagnostics));
+            return (None, std::mem::take(&mut self.diagnostics));
         }
 
         // Skip newlines
         self.skip_newlines();
 
+        // Indices into `root.children` of the subgraphs currently open
+        let mut open_subgraphs: Vec<usize> = Vec::new();
+
         // Parse statements
         while !self.is_at_end() {
+            let loop_start = self.pos;
             self.skip_newlines();
 
             if self.is_at_end() {
                 break;
             }
 
+            let stmt_start = self.pos;
+            let diagnostic_count = self.diagnostics.len();
+            let closes_subgraph = self.check(&FlowToken::End);
             if let Some(stmt) = self.parse_statement() {
+                if stmt.kind == NodeKind::Subgraph {
+                    open_subgraphs.push(root.children.len());
+                } else if closes_subgraph {
+                    open_subgraphs.pop();
+                } else if stmt.get_property("type") == Some("direction") {
+                    self.scope_direction(&mut root, open_subgraphs.last().copied(), &stmt);
+                }
                 root.add_child(stmt);
             } else {
-                // Skip to next line on error
-                self.skip_to_newline();
+                // Skip to the next statement on error
+                self.skip_statement();
+                root.add_child(self.error_node(stmt_start, diagnostic_count));
             }
+            self.ensure_progress(loop_start);
         }
 
-        if self.diagnostics.iter().any(|d| d.severity.is_error()) {
-            Err(std::mem::take(&mut self.diagnostics))
-        } else {
-            Ok(Ast::new(root, self.source.to_string()))
+        self.mark_declared(&mut root);
+        (
+            Some(Ast::new(root, self.source.to_string())),
+            std::mem::take(&mut self.diagnostics),
+        )
+    }
+
+    /// Records a `direction` statement on its enclosing subgraph, or hints
+    /// that it is ignored when it appears at the top level of a `graph`.
+    fn scope_direction(&mut self, root: &mut AstNode, subgraph: Option<usize>, stmt: &AstNode) {
+        let direction = stmt.get_property("direction").unwrap_or_default();
+        if !patterns::is_direction_value(direction) {
+            return;
+        }
+        let is_graph = root
+            .children
+            .first()
+            .and_then(|decl| decl.text.as_deref())
+            .is_some_and(|keyword| keyword.eq_ignore_ascii_case("graph"));
+
+        if let Some(index) = subgraph {
+            self.warn_if_legacy(DiagnosticCode::InvalidDirection, "'direction' in a subgraph", stmt.span);
+            root.children[index].add_property("direction", direction.to_uppercase());
+        } else if is_graph {
+            self.diagnostics.push(Diagnostic::hint(
+                DiagnosticCode::InvalidDirection,
+                "'direction' outside a subgraph is ignored in 'graph' diagrams; the header direction is used",
+                stmt.span,
+            ));
+        }
+    }
+
+    /// Sets the `declared` property on every node occurrence, so bare
+    /// references share the flag of the id's shaped declaration.
+    fn mark_declared(&self, node: &mut AstNode) {
+        if node.kind == NodeKind::Node {
+            if let Some(id) = node.get_property("id") {
+                let declared = self.declared_ids.contains(id);
+                node.add_property("declared", declared.to_string());
+            }
+        }
+        for child in &mut node.children {
+            self.mark_declared(child);
         }
     }
 
@@ -142,6 +270,11 @@ impl<'a> FlowchartParserImpl<'a> {
             return None;
         }
 
+        // Check for accessibility
+        if self.check(&FlowToken::AccTitle) || self.check(&FlowToken::AccDescr) {
+            return self.parse_accessibility();
+        }
+
         // Check for different statement types
         if self.check(&FlowToken::Subgraph) {
             return self.parse_subgraph();
@@ -175,46 +308,145 @@ impl<'a> FlowchartParserImpl<'a> {
             return self.parse_linkstyle();
         }
 
+        if self.check(&FlowToken::EdgeId) && self.check_next(&FlowToken::LBrace) && self.is_edge_id() {
+            return self.parse_edge_data();
+        }
+
+        if let Some(node) = self.parse_unknown_statement() {
+            return Some(node);
+        }
+
         // Otherwise, try to parse a node/link statement
         self.parse_node_or_link()
     }
 
+    /// Skips a statement starting with a keyword-like word, with a warning.
+    fn parse_unknown_statement(&mut self) -> Option<AstNode> {
+        let start = self.current_span().start;
+        let keyword = unknown::leading_keyword(&self.source[start..])?;
+        while !self.is_at_end() && !self.check(&FlowToken::Newline) && !self.check(&FlowToken::Semicolon) {
+            self.advance();
+        }
+        let span = Span::new(start, self.previous_span().end);
+        let (node, diagnostic) = unknown::skipped_statement(self.source, span, keyword, NEW_KEYWORDS);
+        self.diagnostics.push(diagnostic);
+        Some(node)
+    }
+
     fn parse_node_or_link(&mut self) -> Option<AstNode> {
         let start = self.current_span().start;
 
-        // Parse the first node
-        let first_node = self.parse_node()?;
+        // Parse the first node, or `&` list of nodes
+        let mut sources = self.parse_node_group()?;
 
         // Check if there's a link following
         if self.is_link_start() {
             let mut stmt = AstNode::new(NodeKind::Edge, Span::new(start, start));
-            stmt.add_child(first_node);
+            let mut source_count = sources.len();
+            stmt.children.append(&mut sources);
 
             // Parse chain of links
             while self.is_link_start() {
+                let edge_id = self.parse_edge_id();
+                let link_start = self.current_span().start;
+                let head = self.peek().and_then(|token| arrow_head(&token.text));
                 if let Some((link_type, label)) = self.parse_link() {
-                    // Parse the target node
-                    if let Some(target_node) = self.parse_node() {
+                    let link = Span::new(link_start, self.previous_span().end);
+                    if self.is_at_end() || self.check(&FlowToken::Newline) || self.check(&FlowToken::Semicolon) {
+                        // A label left open has taken the rest of the line
+                        // and is already reported
+                        let open_label = self.tokens[self.pos - 1].kind == FlowToken::PipeLabel
+                            && pipe_label_end(&self.tokens[self.pos - 1].text[1..]).is_none();
+                        if !open_label {
+                            self.diagnostics.push(dangling::missing_end(
+                                self.source,
+                                link,
+                                "Edge is missing its target",
+                                KEYWORDS,
+                            ));
+                        }
+                        break;
+                    }
+                    // Parse the target node, or `&` list of nodes
+                    if let Some(targets) = self.parse_node_group() {
                         let mut edge = AstNode::new(NodeKind::Edge, Span::new(start, self.previous_span().end));
                         edge.add_property("link_type", format!("{:?}", link_type));
+                        if let Some(head) = head {
+                            edge.add_property("arrow_head", head);
+                        }
                         if let Some(lbl) = label {
-                            edge.add_property("label", lbl);
+                            edge.add_property("label", lbl.text);
+                            edge.add_property("raw_label", lbl.raw);
+                        }
+                        // Links are numbered in source order, as `linkStyle`
+                        // counts them; `A & B --> C & D` is four links
+                        edge.add_property("index", self.edge_count.to_string());
+                        self.edge_count += source_count * targets.len();
+                        if let Some(id) = edge_id {
+                            self.edge_ids.insert(id.clone());
+                            edge.add_property("edge_id", id);
                         }
-                        edge.add_child(target_node);
+                        source_count = targets.len();
+                        edge.children.extend(targets);
                         stmt.add_child(edge);
                     }
+                } else {
+                    // A `--`, `-.` or `==` not closed on its line
+                    let opener = self.advance()?.clone();
+                    self.diagnostics.push(
+                        Diagnostic::error(
+                            DiagnosticCode::InvalidEdgeType,
+                            format!("Link '{}' is not closed", opener.text),
+                            opener.span,
+                        )
+                        .with_note("write the label inside the link, as in 'A -- label --> B', or after it, as in 'A -->|label| B'"),
+                    );
+                    break;
                 }
             }
 
             stmt.span = Span::new(start, self.previous_span().end);
             Some(stmt)
-        } else {
+        } else if sources.len() == 1 {
             // Just a node definition
-            Some(first_node)
+            sources.pop()
+        } else {
+            let mut stmt = AstNode::new(NodeKind::Statement, Span::new(start, self.previous_span().end));
+            stmt.add_property("type", "node_list");
+            stmt.children = sources;
+            Some(stmt)
         }
     }
 
+    /// Parses a node, or several joined by `&`.
+    fn parse_node_group(&mut self) -> Option<Vec<AstNode>> {
+        let mut nodes = vec![self.parse_node()?];
+        while self.check(&FlowToken::Ampersand) {
+            let ampersand = self.current_span();
+            self.advance();
+            if nodes.len() == 1 {
+                self.warn_if_legacy(DiagnosticCode::InvalidSyntax, "'&' between nodes", ampersand);
+            }
+            match self.parse_node() {
+                Some(node) => nodes.push(node),
+                None => {
+                    self.diagnostics.push(Diagnostic::error(
+                        DiagnosticCode::ExpectedToken,
+                        "Expected a node after '&'",
+                        self.current_span(),
+                    ));
+                    return None;
+                }
+            }
+        }
+        Some(nodes)
+    }
+
     fn parse_node(&mut self) -> Option<AstNode> {
+        if self.check(&FlowToken::EdgeId) && self.check_next(&FlowToken::LBrace) {
+            return self.parse_node_metadata();
+        }
+
         let start = self.current_span().start;
 
         // Parse node ID
@@ -225,7 +457,22 @@ impl<'a> FlowchartParserImpl<'a> {
         };
 
         // Check for shape/label
+        let shape_start = self.current_span();
         let (shape, label) = self.parse_node_shape_and_label();
+        if label.as_ref().is_some_and(Label::is_empty) {
+            let delimiters = Span::new(shape_start.start, self.previous_span().end);
+            self.diagnostics.push(
+                Diagnostic::error(DiagnosticCode::ParserError, "Empty node label is not allowed", shape_start)
+                    .with_fix(TextEdit::delete(delimiters)),
+            );
+        }
+
+        if label.is_some() {
+            self.declared_ids.insert(id.clone());
+        }
+
+        // Check for a `:::class` shorthand
+        let class = self.parse_class_shorthand();
 
         let end = self.previous_span().end;
         let mut node = AstNode::with_text(NodeKind::Node, Span::new(start, end), &id);
@@ -233,209 +480,362 @@ impl<'a> FlowchartParserImpl<'a> {
         node.add_property("shape", format!("{:?}", shape));
 
         if let Some(lbl) = label {
-            node.add_property("label", lbl);
+            node.add_property("label", lbl.text);
+            node.add_property("raw_label", lbl.raw);
+        }
+        if let Some(class) = class {
+            node.add_property("class", class);
         }
 
         Some(node)
     }
 
-    fn parse_node_shape_and_label(&mut self) -> (NodeShape, Option<String>) {
+    /// Parses `:::name` after a node, returning the class name.
+    fn parse_class_shorthand(&mut self) -> Option<String> {
+        if !self.check(&FlowToken::TripleColon) {
+            return None;
+        }
+        self.advance();
+        self.parse_class_name("':::'")
+    }
+
+    /// Parses the class name after `after`, reporting it if missing.
+    fn parse_class_name(&mut self, after: &str) -> Option<String> {
+        if self.check(&FlowToken::Identifier) {
+            return Some(self.class_name());
+        }
+        let span = self.current_span();
+        self.diagnostics.push(Diagnostic::error(
+            DiagnosticCode::ExpectedToken,
+            format!("Expected a class name after {}", after),
+            span,
+        ));
+        None
+    }
+
+    /// Parses `id@{ ... }`, a node declared with shape metadata.
+    ///
+    /// The body is YAML, as in Mermaid: a single line is read as a flow
+    /// mapping, several lines as a block mapping. `shape` must name a shape
+    /// from the catalog; it sets `shape_name`, and `shape` too when the shape
+    /// has a bracket equivalent. `label` sets the label, and other keys are
+    /// kept as `metadata.<key>` properties, so that keys such as `id` or
+    /// `class` don't overwrite the parser's own. The body as written is kept
+    /// in `metadata`.
+    fn parse_node_metadata(&mut self) -> Option<AstNode> {
+        let start = self.current_span().start;
+        let id = self.parse_edge_id()?;
+        let body_start = self.advance()?.span.end; // consume '{'
+        self.warn_if_legacy(
+            DiagnosticCode::InvalidNodeShape,
+            "'@{ ... }' node metadata",
+            Span::new(start, body_start),
+        );
+
+        let (body, end) = match patterns::closing_brace(&self.source[body_start..]) {
+            Some(close) => (&self.source[body_start..body_start + close], body_start + close + 1),
+            None => {
+                self.diagnostics.push(Diagnostic::error(
+                    DiagnosticCode::MissingElement,
+                    format!("Missing '}}' for the metadata of node '{}'", id),
+                    Span::new(start, body_start),
+                ));
+                (&self.source[body_start..], self.source.len())
+            }
+        };
+        while !self.is_at_end() && self.current_span().start < end {
+            self.advance();
+        }
+        self.declared_ids.insert(id.clone());
+
+        let span = Span::new(start, end);
+        let mut node = AstNode::with_text(NodeKind::Node, span, &id);
+        node.add_property("id", id);
+        node.add_property("metadata", body.trim());
+
+        let yaml = if body.contains('\n') {
+            body.to_string()
+        } else {
+            format!("{{{}}}", body)
+        };
+        let entries = match serde_yaml::from_str::<serde_yaml::Mapping>(&yaml) {
+            Ok(entries) => entries,
+            Err(e) => {
+                self.diagnostics.push(Diagnostic::error(
+                    DiagnosticCode::InvalidSyntax,
+                    format!("Invalid node metadata: {}", e),
+                    span,
+                ));
+                return Some(node);
+            }
+        };
+
+        for (key, value) in entries {
+            let (Some(key), Some(value)) = (key.as_str(), yaml_scalar(&value)) else {
+                continue;
+            };
+            match key {
+                "shape" => match shapes::lookup(&value) {
+                    Some(shape) => {
+                        node.add_property("shape_name", shape.name);
+                        if let Some(bracket) = shape.bracket {
+                            node.add_property("shape", format!("{:?}", bracket));
+                        }
+                    }
+                    None => {
+                        let value_span = metadata_value_span(body, key)
+                            .map_or(span, |value| Span::new(body_start + value.start, body_start + value.end));
+                        self.diagnostics.push(Diagnostic::error(
+                            DiagnosticCode::InvalidNodeShape,
+                            format!("Unknown shape '{}'", value),
+                            value_span,
+                        ));
+                    }
+                },
+                "label" => {
+                    node.add_property("label", value.clone());
+                    node.add_property("raw_label", value);
+                }
+                _ => node.add_property(format!("metadata.{}", key), value),
+            }
+        }
+
+        Some(node)
+    }
+
+    fn parse_node_shape_and_label(&mut self) -> (NodeShape, Option<Label>) {
         // Check for different shape delimiters
         if self.check(&FlowToken::LDoubleParen) {
-            let start_span = self.current_span();
             self.advance();
             if self.check(&FlowToken::LParen) {
                 // ((( ))) - double circle
                 self.advance();
                 let label = self.parse_label_content();
-                if label.is_empty() {
-                    self.diagnostics.push(Diagnostic::error(
-                        DiagnosticCode::ParserError,
-                        "Empty node label is not allowed",
-                        start_span,
-                    ));
-                }
                 self.expect(&FlowToken::RParen);
                 self.expect(&FlowToken::RDoubleParen);
                 return (NodeShape::DoubleCircle, Some(label));
             }
             let label = self.parse_label_content();
-            if label.is_empty() {
-                self.diagnostics.push(Diagnostic::error(
-                    DiagnosticCode::ParserError,
-                    "Empty node label is not allowed",
-                    start_span,
-                ));
-            }
             self.expect(&FlowToken::RDoubleParen);
             return (NodeShape::Circle, Some(label));
         }
 
         if self.check(&FlowToken::LDoubleBracket) {
-            let start_span = self.current_span();
             self.advance();
             let label = self.parse_label_content();
-            if label.is_empty() {
-                self.diagnostics.push(Diagnostic::error(
-                    DiagnosticCode::ParserError,
-                    "Empty node label is not allowed",
-                    start_span,
-                ));
-            }
             self.expect(&FlowToken::RDoubleBracket);
             return (NodeShape::Subroutine, Some(label));
         }
 
         if self.check(&FlowToken::LDoubleBrace) {
-            let start_span = self.current_span();
             self.advance();
             let label = self.parse_label_content();
-            if label.is_empty() {
-                self.diagnostics.push(Diagnostic::error(
-                    DiagnosticCode::ParserError,
-                    "Empty node label is not allowed",
-                    start_span,
-                ));
-            }
             self.expect(&FlowToken::RDoubleBrace);
             return (NodeShape::Hexagon, Some(label));
         }
 
         if self.check(&FlowToken::LParenBracket) {
-            let start_span = self.current_span();
             self.advance();
             let label = self.parse_label_content();
-            if label.is_empty() {
-                self.diagnostics.push(Diagnostic::error(
-                    DiagnosticCode::ParserError,
-                    "Empty node label is not allowed",
-                    start_span,
-                ));
-            }
             self.expect(&FlowToken::RBracketParen);
             return (NodeShape::Stadium, Some(label));
         }
 
         if self.check(&FlowToken::LBracketParen) {
-            let start_span = self.current_span();
             self.advance();
             let label = self.parse_label_content();
-            if label.is_empty() {
-                self.diagnostics.push(Diagnostic::error(
-                    DiagnosticCode::ParserError,
-                    "Empty node label is not allowed",
-                    start_span,
-                ));
-            }
             self.expect(&FlowToken::RParenBracket);
             return (NodeShape::Cylindrical, Some(label));
         }
 
+        if self.check(&FlowToken::LSlashBracket) || self.check(&FlowToken::LBackslashBracket) {
+            return self.parse_slanted_shape();
+        }
+
         if self.check(&FlowToken::LBracket) {
-            let start_span = self.current_span();
             self.advance();
             let label = self.parse_label_content();
-            if label.is_empty() {
-                self.diagnostics.push(Diagnostic::error(
-                    DiagnosticCode::ParserError,
-                    "Empty node label is not allowed",
-                    start_span,
-                ));
-            }
             self.expect(&FlowToken::RBracket);
             return (NodeShape::Rectangle, Some(label));
         }
 
         if self.check(&FlowToken::LParen) {
-            let start_span = self.current_span();
             self.advance();
             let label = self.parse_label_content();
-            if label.is_empty() {
-                self.diagnostics.push(Diagnostic::error(
-                    DiagnosticCode::ParserError,
-                    "Empty node label is not allowed",
-                    start_span,
-                ));
-            }
             self.expect(&FlowToken::RParen);
             return (NodeShape::RoundedRect, Some(label));
         }
 
         if self.check(&FlowToken::LBrace) {
-            let start_span = self.current_span();
             self.advance();
             let label = self.parse_label_content();
-            if label.is_empty() {
-                self.diagnostics.push(Diagnostic::error(
-                    DiagnosticCode::ParserError,
-                    "Empty node label is not allowed",
-                    start_span,
-                ));
-            }
             self.expect(&FlowToken::RBrace);
             return (NodeShape::Rhombus, Some(label));
         }
 
         if self.check(&FlowToken::GreaterThan) {
-            let start_span = self.current_span();
             self.advance();
-            let label = self.parse_label_content();
-            if label.is_empty() {
-                self.diagnostics.push(Diagnostic::error(
-                    DiagnosticCode::ParserError,
-                    "Empty node label is not allowed",
-                    start_span,
-                ));
+            // Only `]` closes the flag shape; `>`, `)` and `}` are label text
+            let mut label =
+                self.parse_label_until(|t| matches!(t.kind, FlowToken::RBracket | FlowToken::RParenBracket));
+            if self.check(&FlowToken::RParenBracket) {
+                // `text (note)]` lexes its end as `)]`
+                label.text.push(')');
+                label.raw.push(')');
+                self.advance();
+            } else {
+                self.expect(&FlowToken::RBracket);
             }
-            self.expect(&FlowToken::RBracket);
             return (NodeShape::Asymmetric, Some(label));
         }
 
         (NodeShape::Rectangle, None)
     }
 
-    fn parse_label_content(&mut self) -> String {
-        let mut label = String::new();
+    /// Parses the parallelogram and trapezoid shapes, `[/text/]`,
+    /// `[\text\]`, `[/text\]` and `[\text/]`. The slants of the opening and
+    /// closing delimiters pick the shape. A `/]` preceded by a backslash is
+    /// label text, so `\/` can be written inside the label.
+    fn parse_slanted_shape(&mut self) -> (NodeShape, Option<Label>) {
+        let leading_slash = self.check(&FlowToken::LSlashBracket);
+        self.advance();
+        let source = self.source;
+        let mut label = self.parse_label_until(|t| {
+            let escaped = t.kind == FlowToken::RSlashBracket && source[..t.span.start].ends_with('\\');
+            (matches!(t.kind, FlowToken::RSlashBracket | FlowToken::RBackslashBracket) && !escaped)
+                || is_label_closer(&t.kind)
+        });
+        label.text = label.text.replace("\\/", "/");
+
+        let trailing_slash = self.check(&FlowToken::RSlashBracket);
+        if trailing_slash || self.check(&FlowToken::RBackslashBracket) {
+            self.advance();
+        } else {
+            self.diagnostics.push(Diagnostic::error(
+                DiagnosticCode::ExpectedToken,
+                "Expected '/]' or '\\]' to close the shape",
+                self.current_span(),
+            ));
+            // A plain `]` still ends the node
+            if self.check(&FlowToken::RBracket) {
+                self.advance();
+            }
+        }
+        let shape = match (leading_slash, trailing_slash) {
+            (true, true) => NodeShape::Parallelogram,
+            (false, false) => NodeShape::ParallelogramAlt,
+            (true, false) => NodeShape::Trapezoid,
+            (false, true) => NodeShape::TrapezoidAlt,
+        };
+        (shape, Some(label))
+    }
+
+    fn parse_label_content(&mut self) -> Label {
+        self.parse_label_until(|t| is_label_closer(&t.kind))
+    }
+
+    /// Parses label text up to, but not including, the first token for
+    /// which `is_closer` holds.
+    fn parse_label_until(&mut self, is_closer: impl Fn(&Token) -> bool) -> Label {
+        let tokens = self.tokens;
+        let mut builder = LabelBuilder::default();
+        let start = self.previous_span().end;
+        let mut cursor = start;
 
         while !self.is_at_end() {
-            if self.check(&FlowToken::RBracket)
-                || self.check(&FlowToken::RParen)
-                || self.check(&FlowToken::RBrace)
-                || self.check(&FlowToken::RDoubleParen)
-                || self.check(&FlowToken::RDoubleBracket)
-                || self.check(&FlowToken::RDoubleBrace)
-                || self.check(&FlowToken::RBracketParen)
-                || self.check(&FlowToken::RParenBracket)
-            {
+            if is_closer(&tokens[self.pos]) {
                 break;
             }
 
-            if self.check(&FlowToken::DoubleQuotedString) || self.check(&FlowToken::SingleQuotedString) {
-                let quoted = self.advance().map(|t| &t.text).unwrap();
-                // Remove quotes
-                label.push_str(&quoted[1..quoted.len() - 1]);
-            } else if let Some(token) = self.advance() {
-                label.push_str(&token.text);
+            let token = &tokens[self.pos];
+            self.pos += 1;
+
+            // Keep the original text between tokens, including whitespace
+            // and characters the lexer skipped
+            builder.push_text(&self.source[cursor..token.span.start], cursor);
+            cursor = token.span.end;
+
+            if token.kind == FlowToken::DoubleQuotedString || token.kind == FlowToken::SingleQuotedString {
+                builder.push_quoted(&token.text, token.span.start);
+            } else {
+                builder.push_text(&token.text, token.span.start);
             }
         }
 
-        label.trim().to_string()
+        let end = self.current_span().start.max(cursor);
+        builder.push_text(&self.source[cursor..end], cursor);
+
+        let raw = self.source[start..end].trim();
+        Label {
+            text: builder.finish(raw, &mut self.diagnostics),
+            raw: raw.to_string(),
+        }
     }
 
     fn is_link_start(&self) -> bool {
-        self.check(&FlowToken::Arrow)
-            || self.check(&FlowToken::Line)
-            || self.check(&FlowToken::DottedLine)
-            || self.check(&FlowToken::DottedArrow)
-            || self.check(&FlowToken::ThickArrow)
-            || self.check(&FlowToken::ThickLine)
-            || self.check(&FlowToken::Invisible)
-            || self.check(&FlowToken::DoubleDash)
-            || self.check(&FlowToken::DashDot)
-            || self.check(&FlowToken::DoubleEqual)
-    }
-
-    fn parse_link(&mut self) -> Option<(LinkType, Option<String>)> {
+        let offset = usize::from(self.check(&FlowToken::EdgeId));
+        self.tokens.get(self.pos + offset).is_some_and(|t| {
+            matches!(
+                t.kind,
+                FlowToken::Arrow
+                    | FlowToken::Line
+                    | FlowToken::DottedLine
+                    | FlowToken::DottedArrow
+                    | FlowToken::ThickArrow
+                    | FlowToken::ThickLine
+                    | FlowToken::Invisible
+                    | FlowToken::DoubleDash
+                    | FlowToken::DashDot
+                    | FlowToken::DoubleEqual
+                    | FlowToken::LabeledLink
+            )
+        })
+    }
+
+    /// Parses the `id@` that names the following link, if present.
+    fn parse_edge_id(&mut self) -> Option<String> {
+        if !self.check(&FlowToken::EdgeId) {
+            return None;
+        }
+        let token = self.advance()?;
+        Some(token.text.trim_end_matches('@').to_string())
+    }
+
+    /// Returns true if the current `id@` token names a link parsed earlier.
+    fn is_edge_id(&self) -> bool {
+        self.peek()
+            .is_some_and(|t| self.edge_ids.contains(t.text.trim_end_matches('@')))
+    }
+
+    /// Parses `id@{ ... }` for a named link. The body is kept as written.
+    fn parse_edge_data(&mut self) -> Option<AstNode> {
+        let start = self.current_span().start;
+        let edge_id = self.parse_edge_id()?;
+        let body_start = self.advance()?.span.end; // consume '{'
+
+        let (body, end) = match self.source[body_start..].find('}') {
+            Some(close) => (&self.source[body_start..body_start + close], body_start + close + 1),
+            None => {
+                self.diagnostics.push(Diagnostic::error(
+                    DiagnosticCode::MissingElement,
+                    format!("Missing '}}' for the data of edge '{}'", edge_id),
+                    Span::new(start, body_start),
+                ));
+                (&self.source[body_start..], self.source.len())
+            }
+        };
+        while !self.is_at_end() && self.current_span().start < end {
+            self.advance();
+        }
+
+        let mut node = AstNode::new(NodeKind::Statement, Span::new(start, end));
+        node.add_property("type", "edge_data");
+        node.add_property("edge_id", edge_id);
+        node.add_property("data", body.trim());
+        Some(node)
+    }
+
+    fn parse_link(&mut self) -> Option<(LinkType, Option<Label>)> {
         let link_type = match self.peek()?.kind {
             FlowToken::Arrow => {
                 self.advance();
@@ -465,28 +865,30 @@ impl<'a> FlowchartParserImpl<'a> {
                 self.advance();
                 LinkType::Invisible
             }
-            FlowToken::DoubleDash => {
-                self.advance();
-                // Check for label: -- label -->
-                let label = self.parse_edge_label();
-                // Expect closing arrow
-                if self.check(&FlowToken::Arrow) {
-                    self.advance();
-                    return Some((LinkType::Arrow, label));
-                }
-                return Some((LinkType::Open, label));
+            FlowToken::LabeledLink => {
+                let token = self.advance()?.clone();
+                let text = token.text;
+                let (opener, rest) = text.split_at(2);
+                let closer = link_closer(opener, rest)?;
+                let arrow = rest.ends_with('>');
+                let link_type = match (opener, arrow) {
+                    ("-.", true) => LinkType::DottedArrow,
+                    ("-.", false) => LinkType::Dotted,
+                    ("==", true) => LinkType::ThickArrow,
+                    ("==", false) => LinkType::Thick,
+                    (_, true) => LinkType::Arrow,
+                    (_, false) => LinkType::Open,
+                };
+                let label = self.link_label(&rest[..closer.start], token.span.start + opener.len(), false);
+                return Some((link_type, Some(label)));
             }
             _ => return None,
         };
 
         // Check for pipe-delimited label: -->|label|
-        let label = if self.check(&FlowToken::Pipe) {
-            self.advance();
-            let lbl = self.parse_until_pipe();
-            if self.check(&FlowToken::Pipe) {
-                self.advance();
-            }
-            Some(lbl)
+        let label = if self.check(&FlowToken::PipeLabel) {
+            let token = self.advance()?.clone();
+            Some(self.pipe_label(&token))
         } else {
             None
         };
@@ -494,71 +896,117 @@ impl<'a> FlowchartParserImpl<'a> {
         Some((link_type, label))
     }
 
-    fn parse_edge_label(&mut self) -> Option<String> {
-        let mut label = String::new();
-
-        while !self.is_at_end()
-            && !self.check(&FlowToken::Arrow)
-            && !self.check(&FlowToken::Line)
-            && !self.check(&FlowToken::Newline)
-            && !self.check(&FlowToken::Identifier)
-        {
-            if let Some(token) = self.advance() {
-                if !label.is_empty() {
-                    label.push(' ');
-                }
-                label.push_str(&token.text);
+    /// Returns the label of a `|label|` token with its `\|` escapes removed,
+    /// reporting a label left open at the end of the line.
+    fn pipe_label(&mut self, token: &Token) -> Label {
+        let inner = &token.text[1..];
+        let inner = match pipe_label_end(inner) {
+            Some(end) => &inner[..end - 1],
+            None => {
+                self.diagnostics.push(
+                    Diagnostic::error(DiagnosticCode::UnterminatedString, "Unterminated edge label", token.span)
+                        .with_note("close the label with '|' on the same line; write '\\|' for a '|' in it"),
+                );
+                inner
             }
-        }
-
-        if label.is_empty() {
-            None
-        } else {
-            Some(label.trim().to_string())
-        }
+        };
+        self.link_label(inner, token.span.start + 1, true)
     }
 
-    fn parse_until_pipe(&mut self) -> String {
-        let mut content = String::new();
-
-        while !self.is_at_end() && !self.check(&FlowToken::Pipe) {
-            if let Some(token) = self.advance() {
-                content.push_str(&token.text);
+    /// Resolves link label `text`, which starts at `offset`, as node labels
+    /// are: a quoted label is unquoted and unescaped, and entity codes are
+    /// decoded. With `pipe`, `\|` is read as `|`.
+    fn link_label(&mut self, text: &str, offset: usize, pipe: bool) -> Label {
+        let raw = text.trim();
+        let offset = offset + text.len() - text.trim_start().len();
+        let mut builder = LabelBuilder::default();
+        if quotes::strip_matching_quotes(raw).is_some() {
+            builder.push_quoted(raw, offset);
+        } else {
+            let mut last = 0;
+            for (i, _) in raw.match_indices("\\|").filter(|_| pipe) {
+                builder.push_text(&raw[last..i], offset + last);
+                last = i + 1;
             }
+            builder.push_text(&raw[last..], offset + last);
+        }
+        Label {
+            text: builder.finish(raw, &mut self.diagnostics),
+            raw: raw.to_string(),
         }
-
-        content.trim().to_string()
     }
 
     fn parse_subgraph(&mut self) -> Option<AstNode> {
         let start = self.current_span().start;
         self.advance(); // consume 'subgraph'
 
-        // Parse subgraph ID/title
+        // Parse subgraph ID/title: `id`, `id [title]`, `[title]` or `"title"`
         let mut id = String::new();
         let mut label = None;
-
-        // Skip whitespace but not newlines
-        while self.check(&FlowToken::Text) || self.check(&FlowToken::Identifier) {
+        let mut end = start;
+
+        // The lexer drops a quote with no closing quote; the title then runs
+        // to the end of the line
+        let line_start = self.previous_span().end;
+        let line = &self.source[line_start..];
+        let line = line[..line.find('\n').unwrap_or(line.len())].trim_end();
+        let title_start = line_start + line.len() - line.trim_start().len();
+        let quoted = if self.check(&FlowToken::DoubleQuotedString) || self.check(&FlowToken::SingleQuotedString) {
             let token = self.advance()?;
-            if !id.is_empty() {
-                id.push(' ');
+            Some((token.text.clone(), token.span))
+        } else if line.trim_start().starts_with(['"', '\'']) {
+            let span = Span::new(title_start, line_start + line.len());
+            self.diagnostics.push(
+                Diagnostic::error(DiagnosticCode::UnterminatedString, "Unterminated subgraph title", span)
+                    .with_note("close the title with a matching quote"),
+            );
+            while !self.is_at_end() && !self.check(&FlowToken::Newline) {
+                self.advance();
+            }
+            Some((span.text(self.source).to_string(), span))
+        } else {
+            None
+        };
+
+        if let Some((raw, span)) = quoted {
+            end = span.end;
+            let mut builder = LabelBuilder::default();
+            builder.push_quoted(&raw, span.start);
+            let text = builder.finish(&raw, &mut self.diagnostics);
+            id = text.clone();
+            label = Some(Label { text, raw });
+        } else {
+            let id_start = self.current_span().start;
+            let mut id_end = None;
+            while self.check(&FlowToken::Text)
+                || self.check(&FlowToken::Identifier)
+                || self.check(&FlowToken::Number)
+                || self.check(&FlowToken::DirectionValue)
+            {
+                id_end = Some(self.advance()?.span.end);
+            }
+            if let Some(id_end) = id_end {
+                id = self.source[id_start..id_end].to_string();
             }
-            id.push_str(&token.text);
         }
 
         // Check for bracketed title
         if self.check(&FlowToken::LBracket) {
             self.advance();
-            label = Some(self.parse_label_content());
+            let title = self.parse_label_content();
             self.expect(&FlowToken::RBracket);
+            if id.is_empty() {
+                id = title.text.clone();
+            }
+            label = Some(title);
         }
 
-        let end = self.previous_span().end;
+        let end = self.previous_span().end.max(end);
         let mut node = AstNode::new(NodeKind::Subgraph, Span::new(start, end));
         node.add_property("id", id.trim().to_string());
         if let Some(lbl) = label {
-            node.add_property("label", lbl);
+            node.add_property("label", lbl.text);
+            node.add_property("raw_label", lbl.raw);
         }
 
         Some(node)
@@ -569,7 +1017,9 @@ impl<'a> FlowchartParserImpl<'a> {
         self.advance(); // consume 'end'
         let end = self.previous_span().end;
 
-        Some(AstNode::new(NodeKind::Statement, Span::new(start, end)))
+        let mut node = AstNode::new(NodeKind::Statement, Span::new(start, end));
+        node.add_property("type", "end");
+        Some(node)
     }
 
     fn parse_style(&mut self) -> Option<AstNode> {
@@ -577,21 +1027,48 @@ impl<'a> FlowchartParserImpl<'a> {
         self.advance(); // consume 'style'
 
         // Parse node ID
+        let id_span = self.current_span();
         let id = if self.check(&FlowToken::Identifier) {
             self.advance()?.text.clone()
         } else {
             return None;
         };
 
-        // Parse styles (rest of line)
+        // Mermaid accepts one id per style statement; read a list anyway so
+        // its ids don't end up in the styles
+        let mut ids = vec![id.clone()];
+        while self.check(&FlowToken::Comma) && self.check_next(&FlowToken::Identifier) {
+            self.advance();
+            ids.push(self.advance()?.text.clone());
+        }
+        let ids_span = Span::new(id_span.start, self.previous_span().end);
+
+        // Parse styles (rest of the statement)
+        let styles_start = self.current_span().start;
         let mut styles = Vec::new();
-        while !self.is_at_end() && !self.check(&FlowToken::Newline) {
+        while !self.is_at_end() && !self.check(&FlowToken::Newline) && !self.check(&FlowToken::Semicolon) {
             if let Some(token) = self.advance() {
                 styles.push(token.text.clone());
             }
         }
-
         let end = self.previous_span().end;
+
+        if ids.len() > 1 {
+            let written = self.source.get(styles_start..end.max(styles_start)).unwrap_or_default();
+            self.diagnostics.push(
+                Diagnostic::error(
+                    DiagnosticCode::InvalidSyntax,
+                    format!("'style' takes a single node id, not '{}'", ids.join(",")),
+                    ids_span,
+                )
+                .with_note(format!(
+                    "to style several nodes, define a class and apply it: 'classDef highlight {}' then 'class {} highlight'",
+                    written.trim(),
+                    ids.join(",")
+                )),
+            );
+        }
+
         let mut node = AstNode::new(NodeKind::Style, Span::new(start, end));
         node.add_property("node_id", id);
         node.add_property("styles", styles.join(" "));
@@ -605,14 +1082,14 @@ impl<'a> FlowchartParserImpl<'a> {
 
         // Parse class name
         let name = if self.check(&FlowToken::Identifier) {
-            self.advance()?.text.clone()
+            self.class_name()
         } else {
             return None;
         };
 
-        // Parse styles (rest of line)
+        // Parse styles (rest of the statement)
         let mut styles = Vec::new();
-        while !self.is_at_end() && !self.check(&FlowToken::Newline) {
+        while !self.is_at_end() && !self.check(&FlowToken::Newline) && !self.check(&FlowToken::Semicolon) {
             if let Some(token) = self.advance() {
                 styles.push(token.text.clone());
             }
@@ -626,24 +1103,46 @@ impl<'a> FlowchartParserImpl<'a> {
         Some(node)
     }
 
+    /// Consumes a class name starting with the current identifier. The name
+    /// may join words with single hyphens, as in `my-class`; a link right
+    /// after it, as in `A:::foo-->B`, is left alone.
+    fn class_name(&mut self) -> String {
+        let start = self.current_span().start;
+        let mut end = self.current_span().end;
+        while let Some(word) = self.source[end..].strip_prefix('-') {
+            let len = word.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(word.len());
+            if len == 0 {
+                break;
+            }
+            end += 1 + len;
+        }
+        while self.peek().is_some_and(|t| t.span.start < end) {
+            self.advance();
+        }
+        self.source[start..end].to_string()
+    }
+
     fn parse_class_assignment(&mut self) -> Option<AstNode> {
         let start = self.current_span().start;
         self.advance(); // consume 'class'
 
-        // Parse node IDs
+        // Parse node IDs: `a,b,c`
         let mut node_ids = Vec::new();
-        while self.check(&FlowToken::Identifier) {
+        while self.check(&FlowToken::Identifier) || self.check(&FlowToken::Number) {
             node_ids.push(self.advance()?.text.clone());
             if self.check(&FlowToken::Comma) {
                 self.advance();
+            } else {
+                break;
             }
         }
 
         // Parse class name
-        let class_name = if self.check(&FlowToken::Identifier) {
-            self.advance()?.text.clone()
-        } else {
+        let class_name = if node_ids.is_empty() {
+            self.expect(&FlowToken::Identifier);
             String::new()
+        } else {
+            self.parse_class_name("the node ids").unwrap_or_default()
         };
 
         let end = self.previous_span().end;
@@ -656,14 +1155,38 @@ impl<'a> FlowchartParserImpl<'a> {
     }
 
     fn parse_direction(&mut self) -> Option<AstNode> {
-        let start = self.current_span().start;
+        let keyword_span = self.current_span();
+        let start = keyword_span.start;
         self.advance(); // consume 'direction'
 
-        let direction = if self.check(&FlowToken::DirectionValue) {
-            self.advance()?.text.clone()
-        } else {
-            String::new()
-        };
+        // Take the rest of the line so an invalid value isn't read as a node
+        let value_start = self.current_span().start;
+        let mut value_end = None;
+        while !self.is_at_end()
+            && !self.check(&FlowToken::Newline)
+            && !self.check(&FlowToken::Semicolon)
+        {
+            value_end = Some(self.advance()?.span.end);
+        }
+        let direction = value_end
+            .map(|end| self.source[value_start..end].to_string())
+            .unwrap_or_default();
+
+        match value_end {
+            None => self.diagnostics.push(Diagnostic::error(
+                DiagnosticCode::InvalidDirection,
+                "Missing value for 'direction'; expected one of TB, BT, LR, RL",
+                keyword_span,
+            )),
+            Some(end) if !patterns::is_direction_value(&direction) => {
+                self.diagnostics.push(Diagnostic::error(
+                    DiagnosticCode::InvalidDirection,
+                    format!("Invalid direction '{}'; expected one of TB, BT, LR, RL", direction),
+                    Span::new(value_start, end),
+                ))
+            }
+            _ => {}
+        }
 
         let end = self.previous_span().end;
         let mut node = AstNode::new(NodeKind::Statement, Span::new(start, end));
@@ -685,18 +1208,25 @@ impl<'a> FlowchartParserImpl<'a> {
         };
 
         // Parse rest of click definition (URL, callback, tooltip)
-        let mut rest = Vec::new();
-        while !self.is_at_end() && !self.check(&FlowToken::Newline) {
-            if let Some(token) = self.advance() {
-                rest.push(token.text.clone());
-            }
+        let click_span = Span::new(start, self.previous_span().end);
+        let (action, definition) = click::parse_action(
+            self.source,
+            click_span,
+            self.current_span().start,
+            &mut self.diagnostics,
+        );
+        // A `;` ends the statement, as it does any other
+        while !self.is_at_end() && !self.check(&FlowToken::Newline) && self.current_span().start < definition.end {
+            self.advance();
         }
 
-        let end = self.previous_span().end;
-        let mut node = AstNode::new(NodeKind::Statement, Span::new(start, end));
+        let mut node = AstNode::new(NodeKind::Statement, Span::new(start, definition.end.max(click_span.end)));
         node.add_property("type", "click");
         node.add_property("node_id", node_id);
-        node.add_property("definition", rest.join(" "));
+        node.add_property("definition", definition.text(self.source));
+        if let Some(action) = action {
+            action.add_properties(&mut node);
+        }
 
         Some(node)
     }
@@ -731,13 +1261,23 @@ impl<'a> FlowchartParserImpl<'a> {
         Some(node)
     }
 
+    /// Parse accessibility statement.
+    fn parse_accessibility(&mut self) -> Option<AstNode> {
+        let start = self.current_span().start;
+        let (node, end) = accessibility::parse_statement(self.source, start, &mut self.diagnostics)?;
+        while !self.is_at_end() && self.current_span().start < end {
+            self.advance();
+        }
+        Some(node)
+    }
+
     // Helper methods
 
     fn is_at_end(&self) -> bool {
         self.pos >= self.tokens.len()
     }
 
-    fn peek(&self) -> Option<&PositionedToken> {
+    fn peek(&self) -> Option<&Token> {
         self.tokens.get(self.pos)
     }
 
@@ -745,7 +1285,11 @@ impl<'a> FlowchartParserImpl<'a> {
         self.peek().map(|t| &t.kind == kind).unwrap_or(false)
     }
 
-    fn advance(&mut self) -> Option<&PositionedToken> {
+    fn check_next(&self, kind: &FlowToken) -> bool {
+        self.tokens.get(self.pos + 1).is_some_and(|t| &t.kind == kind)
+    }
+
+    fn advance(&mut self) -> Option<&Token> {
         if !self.is_at_end() {
             self.pos += 1;
             self.tokens.get(self.pos - 1)
@@ -790,16 +1334,130 @@ impl<'a> FlowchartParserImpl<'a> {
         }
     }
 
-    fn skip_to_newline(&mut self) {
-        while !self.is_at_end() && !self.check(&FlowToken::Newline) {
+    /// Builds an `Error` node covering the tokens skipped since `start`.
+    ///
+    /// Carries the code of the first diagnostic reported for the statement,
+    /// or reports an `InvalidSyntax` error if the statement failed silently.
+    fn error_node(&mut self, start: usize, diagnostic_count: usize) -> AstNode {
+        let start_offset = self.tokens.get(start).map_or(self.source.len(), |t| t.span.start);
+        let end_offset = self.tokens[start..self.pos]
+            .iter()
+            .rev()
+            .find(|t| !matches!(t.kind, FlowToken::Newline | FlowToken::Semicolon))
+            .map_or(start_offset, |t| t.span.end);
+        let span = Span::new(start_offset, end_offset);
+
+        if self.diagnostics.len() == diagnostic_count {
+            self.diagnostics.push(Diagnostic::error(
+                DiagnosticCode::InvalidSyntax,
+                format!("Unable to parse statement '{}'", excerpt(span.text(self.source))),
+                span,
+            ));
+        }
+
+        let mut node = AstNode::with_text(NodeKind::Error, span, span.text(self.source));
+        node.add_property("code", self.diagnostics[diagnostic_count].code.as_str());
+        node
+    }
+
+    /// Skips past the next statement separator. Stopping at semicolons as
+    /// well as newlines keeps an error in a minified one-line diagram from
+    /// swallowing the rest of it.
+    fn skip_statement(&mut self) {
+        while !self.is_at_end() && !self.check(&FlowToken::Newline) && !self.check(&FlowToken::Semicolon) {
             self.advance();
         }
-        if self.check(&FlowToken::Newline) {
+        if self.check(&FlowToken::Newline) || self.check(&FlowToken::Semicolon) {
             self.advance();
         }
     }
 }
 
+impl StatementLoop for FlowchartParserImpl<'_> {
+    fn position(&self) -> usize {
+        self.pos
+    }
+
+    fn at_end(&self) -> bool {
+        self.is_at_end()
+    }
+
+    fn token_span(&self) -> Span {
+        self.current_span()
+    }
+
+    fn skip_token(&mut self) {
+        self.advance();
+    }
+
+    fn report(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+}
+
+/// Returns whether a token closes a node shape.
+fn is_label_closer(kind: &FlowToken) -> bool {
+    matches!(
+        kind,
+        FlowToken::RBracket
+            | FlowToken::RParen
+            | FlowToken::RBrace
+            | FlowToken::RDoubleParen
+            | FlowToken::RDoubleBracket
+            | FlowToken::RDoubleBrace
+            | FlowToken::RBracketParen
+            | FlowToken::RParenBracket
+    )
+}
+
+/// Returns the `arrow_head` property of a link written as `text`: `circle`
+/// for `--o`, `cross` for `--x`.
+fn arrow_head(text: &str) -> Option<&'static str> {
+    match text.chars().last()? {
+        'o' => Some("circle"),
+        'x' => Some("cross"),
+        _ => None,
+    }
+}
+
+/// Returns the span in `body`, a `@{ ... }` metadata body, of the value of
+/// its `key` entry, less quotes. Entries end at a `,` or a newline outside
+/// quotes.
+fn metadata_value_span(body: &str, key: &str) -> Option<Span> {
+    let mut cursor = Cursor::new(body);
+    while !cursor.is_eof() {
+        let entry_start = cursor.offset();
+        while !cursor.is_eof() && !matches!(cursor.peek_char(), Some(',' | '\n')) {
+            if !cursor.skip_quoted() {
+                cursor.bump();
+            }
+        }
+        let entry = &body[entry_start..cursor.offset()];
+        cursor.bump(); // the separator
+        let Some((name, value)) = entry.split_once(':') else {
+            continue;
+        };
+        if name.trim().trim_matches(['"', '\'']) != key {
+            continue;
+        }
+        // `value` runs to the end of the entry
+        let unquoted = value.trim_start().trim_start_matches(['"', '\'']);
+        let start = entry_start + entry.len() - unquoted.len();
+        return Some(Span::from_len(start, unquoted.trim_end().trim_end_matches(['"', '\'']).len()));
+    }
+    None
+}
+
+/// Returns a YAML scalar as text.
+fn yaml_scalar(value: &serde_yaml::Value) -> Option<String> {
+    match value {
+        serde_yaml::Value::String(s) => Some(s.clone()),
+        serde_yaml::Value::Number(n) => Some(n.to_string()),
+        serde_yaml::Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -857,6 +1515,74 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_node_metadata_shapes() {
+        let code = "flowchart TD\n    A@{ shape: cylinder, label: \"Orders\" } --> B@{ shape: diam }\n    C@{ shape: rounded, icon: \"fa:user\" }\n    D@{ shape: notch-rect }";
+        let ast = parse(code).unwrap();
+        let mut nodes = Vec::new();
+        ast.walk(|node, _| {
+            if node.kind == NodeKind::Node {
+                nodes.push(node.clone());
+            }
+        });
+        let shape = |i: usize| (nodes[i].get_property("shape_name"), nodes[i].get_property("shape"));
+        assert_eq!(shape(0), (Some("cyl"), Some("Cylindrical")));
+        assert_eq!(shape(1), (Some("diam"), Some("Rhombus")));
+        assert_eq!(shape(2), (Some("rounded"), Some("RoundedRect")));
+        assert_eq!(shape(3), (Some("notch-rect"), None));
+        assert_eq!(nodes[0].get_property("label"), Some("Orders"));
+        assert_eq!(nodes[2].get_property("metadata.icon"), Some("fa:user"));
+    }
+
+    #[test]
+    fn test_node_metadata_multi_line() {
+        let code = "flowchart TD\n    A@{\n      shape: hexagon\n      label: \"Prepare\"\n    }\n    A --> B";
+        let ast = parse(code).unwrap();
+        let node = &ast.root.children[1];
+        assert_eq!(node.get_property("shape"), Some("Hexagon"));
+        assert_eq!(node.get_property("label"), Some("Prepare"));
+    }
+
+    #[test]
+    fn test_node_metadata_unknown_shape() {
+        let code = "flowchart TD\n    A@{ shape: blob }";
+        let diagnostics = parse(code).unwrap_err();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, DiagnosticCode::InvalidNodeShape);
+        assert_eq!(diagnostics[0].span.text(code), "blob");
+
+        // The span is the shape's value, not an earlier match of its text
+        let code = "flowchart TD\n    A@{ label: \"blob\", shape: 'blob' }";
+        let diagnostics = parse(code).unwrap_err();
+        assert_eq!(diagnostics[0].span, Span::from_len(code.rfind("blob").unwrap(), 4));
+    }
+
+    #[test]
+    fn test_node_metadata_keeps_parser_properties() {
+        let code = "flowchart TD\n    A@{ shape: rect, id: other, class: hot, metadata: x, label: Orders }";
+        let ast = parse(code).unwrap();
+        let node = &ast.root.children[1];
+        assert_eq!(node.get_property("id"), Some("A"));
+        assert_eq!(node.get_property("class"), None);
+        assert_eq!(node.get_property("metadata.id"), Some("other"));
+        assert_eq!(node.get_property("metadata.class"), Some("hot"));
+        assert_eq!(node.get_property("metadata.metadata"), Some("x"));
+        assert_eq!(node.get_property("label"), Some("Orders"));
+    }
+
+    #[test]
+    fn test_style_with_several_ids() {
+        let code = "graph TD\n    style A,B fill:#f00\n    A --> B";
+        let diagnostics = parse(code).unwrap_err();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, DiagnosticCode::InvalidSyntax);
+        assert_eq!(diagnostics[0].span.text(code), "A,B");
+        assert_eq!(
+            diagnostics[0].notes[0],
+            "to style several nodes, define a class and apply it: 'classDef highlight fill:#f00' then 'class A,B highlight'"
+        );
+    }
+
     #[test]
     fn test_parse_classdef() {
         let code = r#"graph TD
@@ -867,6 +1593,35 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_parse_class_assignment() {
+        let ast = parse("graph TD\n    A --> B\n    class A, B important").unwrap();
+        let statement = ast.root.children.last().unwrap();
+        assert_eq!(statement.get_property("node_ids"), Some("A,B"));
+        assert_eq!(statement.get_property("class_name"), Some("important"));
+
+        let code = "graph TD\n    A --> B\n    class A";
+        let diagnostics = parse(code).unwrap_err();
+        assert_eq!(diagnostics[0].code, DiagnosticCode::ExpectedToken);
+        assert_eq!(diagnostics[0].message, "Expected a class name after the node ids");
+    }
+
+    #[test]
+    fn test_parse_class_shorthand() {
+        let ast = parse("graph TD\n    A:::important --> B[Label]:::done").unwrap();
+        let edge = &ast.root.children[1];
+        assert_eq!(edge.children[0].get_property("class"), Some("important"));
+        assert_eq!(edge.children[0].span.text(&ast.source), "A:::important");
+        let target = &edge.children[1].children[0];
+        assert_eq!(target.get_property("label"), Some("Label"));
+        assert_eq!(target.get_property("class"), Some("done"));
+
+        let code = "graph TD\n    A::: --> B";
+        let diagnostics = parse(code).unwrap_err();
+        assert_eq!(diagnostics[0].code, DiagnosticCode::ExpectedToken);
+        assert_eq!(diagnostics[0].message, "Expected a class name after ':::'");
+    }
+
     #[test]
     fn test_parse_different_shapes() {
         let shapes = vec![
@@ -878,6 +1633,11 @@ mod tests {
             "F[(Cylinder)]",
             "G([Stadium])",
             "H{{Hexagon}}",
+            "I[/Parallelogram/]",
+            "J[\\ParallelogramAlt\\]",
+            "K[/Trapezoid\\]",
+            "L[\\TrapezoidAlt/]",
+            "M>Asymmetric]",
         ];
 
         for shape in shapes {
@@ -887,6 +1647,133 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_slanted_and_asymmetric_shapes() {
+        let code = "graph TD\n    A[/in/] -->|x| B[\\alt\\] --> C[/trap\\] --> D[\\top/]\n    E[/a\\/b/] --> F>left (a > b)]";
+        let ast = parse(code).expect("parse failed");
+        let mut shapes = Vec::new();
+        ast.walk(|node, _| {
+            if node.kind == NodeKind::Node && node.get_property("label").is_some() {
+                shapes.push((node.get_property("shape").unwrap().to_string(), node.get_property("label").unwrap().to_string()));
+            }
+        });
+        let expected = [
+            ("Parallelogram", "in"),
+            ("ParallelogramAlt", "alt"),
+            ("Trapezoid", "trap"),
+            ("TrapezoidAlt", "top"),
+            ("Parallelogram", "a/b"),
+            ("Asymmetric", "left (a > b)"),
+        ];
+        assert_eq!(shapes, expected.map(|(s, l)| (s.to_string(), l.to_string())));
+
+        let diagnostics = parse("graph TD\n    A[/open] --> B").unwrap_err();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Expected '/]' or '\\]' to close the shape");
+    }
+
+    fn declared_flags(ast: &Ast) -> Vec<(String, String)> {
+        let mut flags = Vec::new();
+        ast.walk(|node, _| {
+            if node.kind == NodeKind::Node {
+                flags.push((
+                    node.get_property("id").unwrap().to_string(),
+                    node.get_property("declared").unwrap().to_string(),
+                ));
+            }
+        });
+        flags
+    }
+
+    #[test]
+    fn test_declared_flag_mixed() {
+        let ast = parse("graph TD\n    A --> B\n    B --> C\n    B[Shaped]").unwrap();
+        let flags = declared_flags(&ast);
+        assert_eq!(
+            flags,
+            vec![
+                ("A".to_string(), "false".to_string()),
+                ("B".to_string(), "true".to_string()),
+                ("B".to_string(), "true".to_string()),
+                ("C".to_string(), "false".to_string()),
+                ("B".to_string(), "true".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bad_statement_yields_error_node() {
+        let code = "graph TD\n    A --> B\n    --> oops\n    C --> D";
+        let (ast, diagnostics) =
+            FlowchartParser::new().parse_partial(code, &MermaidConfig::default());
+        let ast = ast.expect("partial AST");
+        let kinds: Vec<_> = ast.root.children.iter().map(|c| c.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                NodeKind::DiagramDeclaration,
+                NodeKind::Edge,
+                NodeKind::Error,
+                NodeKind::Edge
+            ]
+        );
+
+        let error = &ast.root.children[2];
+        assert_eq!(error.text.as_deref(), Some("--> oops"));
+        assert_eq!(error.get_property("code"), Some("E305"));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].span, error.span);
+    }
+
+    #[test]
+    fn test_label_keeps_whitespace_and_unquotes() {
+        let ast = parse("graph TD\n    A[Hello \"big\" World] --> B").unwrap();
+        let mut labels = Vec::new();
+        ast.walk(|node, _| {
+            if let Some(label) = node.get_property("label") {
+                labels.push(label.to_string());
+            }
+        });
+        assert_eq!(labels, vec!["Hello big World"]);
+    }
+
+    #[test]
+    fn test_quoted_subgraph_title_escapes() {
+        let ast = parse("graph TD\n    subgraph \"say \\\"hi\\\"\"\n    A\n    end").unwrap();
+        let subgraph = &ast.root.children[1];
+        assert_eq!(subgraph.get_property("label"), Some(r#"say "hi""#));
+        assert_eq!(subgraph.get_property("raw_label"), Some(r#""say \"hi\"""#));
+    }
+
+    #[test]
+    fn test_link_labels_decode_entities() {
+        let code = r#"flowchart LR
+    A -->|x #quot;y#quot;| B
+    C -- I #9829; you --> D
+    E -->|"say \"hi\""| F
+    G -->|a \| b| H"#;
+        let result = crate::parse(code, None);
+        assert!(result.ok, "{:?}", result.diagnostics);
+        let mut labels = Vec::new();
+        result.ast.unwrap().walk(|node, _| {
+            if let (Some(label), Some(raw)) = (node.get_property("label"), node.get_property("raw_label")) {
+                labels.push((label.to_string(), raw.to_string()));
+            }
+        });
+        let expected = [
+            ("x \"y\"", "x #quot;y#quot;"),
+            ("I \u{2665} you", "I #9829; you"),
+            ("say \"hi\"", r#""say \"hi\"""#),
+            ("a | b", r"a \| b"),
+        ];
+        assert_eq!(labels, expected.map(|(label, raw)| (label.to_string(), raw.to_string())));
+
+        let code = "flowchart LR\n    A -->|#bad;| B";
+        let result = crate::parse(code, None);
+        assert_eq!(result.diagnostics[0].code, DiagnosticCode::InvalidEscape);
+        assert_eq!(result.diagnostics[0].span.text(code), "#bad;");
+    }
+
     #[test]
     fn test_parse_invalid() {
         let code = "invalid diagram";
@@ -917,4 +1804,121 @@ mod tests {
         let result = parse(code);
         assert!(result.is_err(), "Expected error for empty braces");
     }
+
+    /// Returns the warnings for `body` under the `graph` and the
+    /// `flowchart` headers.
+    fn variant_warnings(body: &str) -> (Vec<Diagnostic>, Vec<Diagnostic>) {
+        let warnings = |diagram_type, header| {
+            let code = format!("{} TD\n{}", header, body);
+            let (ast, diagnostics) = FlowchartParser::for_type(diagram_type).parse_partial(&code, &MermaidConfig::default());
+            assert!(ast.is_some());
+            assert!(!diagnostics.iter().any(|d| d.severity.is_error()), "{:?}", diagnostics);
+            diagnostics
+        };
+        (
+            warnings(DiagramType::Flowchart, "graph"),
+            warnings(DiagramType::FlowchartV2, "flowchart"),
+        )
+    }
+
+    #[test]
+    fn test_subgraph_direction_needs_flowchart_keyword() {
+        let (graph, flowchart) = variant_warnings("    subgraph one\n    direction LR\n    A\n    end");
+        assert_eq!(graph.len(), 1);
+        assert_eq!(graph[0].code, DiagnosticCode::InvalidDirection);
+        assert_eq!(
+            graph[0].message,
+            "'direction' in a subgraph requires the 'flowchart' keyword or defaultRenderer: dagre-wrapper"
+        );
+        assert!(flowchart.is_empty());
+    }
+
+    #[test]
+    fn test_node_lists_need_flowchart_keyword() {
+        let (graph, flowchart) = variant_warnings("    A & B --> C\n    D & E");
+        assert_eq!(graph.len(), 2);
+        assert!(graph.iter().all(|d| d.message.starts_with("'&' between nodes requires")));
+        assert!(flowchart.is_empty());
+    }
+
+    #[test]
+    fn test_node_metadata_needs_flowchart_keyword() {
+        let (graph, flowchart) = variant_warnings("    A@{ shape: rounded } --> B");
+        assert_eq!(graph.len(), 1);
+        assert_eq!(graph[0].code, DiagnosticCode::InvalidNodeShape);
+        assert!(flowchart.is_empty());
+    }
+
+    #[test]
+    fn test_node_lists() {
+        let code = "flowchart TD\n    A & B --> C & D --> E\n    F & G";
+        let ast = parse(code).unwrap();
+        let edge = ast.root.find_child(&NodeKind::Edge).unwrap();
+        assert_eq!(edge.children_of_kind(&NodeKind::Node).len(), 2);
+        let links = edge.children_of_kind(&NodeKind::Edge);
+        assert_eq!(links[0].children_of_kind(&NodeKind::Node).len(), 2);
+        // Four links from the first arrow, so the next one is the fifth
+        assert_eq!(links[1].get_property("index"), Some("4"));
+        let list = &ast.root.children[2];
+        assert_eq!(list.get_property("type"), Some("node_list"));
+        assert_eq!(list.to_mermaid(), "F & G");
+        assert_eq!(edge.to_mermaid(), "A & B --> C & D --> E");
+
+        let diagnostics = parse("flowchart TD\n    A & --> B").unwrap_err();
+        assert_eq!(diagnostics[0].message, "Expected a node after '&'");
+    }
+
+    #[test]
+    fn test_invalid_direction_value() {
+        let code = "flowchart TD\n    subgraph one\n    direction NESW\n    A\n    end";
+        let diagnostics = parse(code).unwrap_err();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, DiagnosticCode::InvalidDirection);
+        assert_eq!(&code[diagnostics[0].span.start..diagnostics[0].span.end], "NESW");
+    }
+
+    #[test]
+    fn test_unterminated_subgraph_title() {
+        let code = "flowchart TD\n    subgraph \"My title\n    A\n    end";
+        let (ast, diagnostics) = FlowchartParser::new().parse_partial(code, &MermaidConfig::default());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, DiagnosticCode::UnterminatedString);
+        assert_eq!(diagnostics[0].span.text(code), "\"My title");
+        let subgraph = &ast.unwrap().root.children[1];
+        assert_eq!(subgraph.get_property("id"), Some("My title"));
+        assert_eq!(subgraph.span.text(code), "subgraph \"My title");
+
+        let code = "flowchart TD\n    subgraph \"\n    A\n    end";
+        let result = crate::parse(code, None);
+        assert_eq!(result.diagnostics[0].code, DiagnosticCode::UnterminatedString);
+        assert_eq!(result.diagnostics[0].span.text(code), "\"");
+    }
+
+    #[test]
+    fn test_subgraph_direction_recorded() {
+        let code = "flowchart TD\n    subgraph one\n    direction LR\n    A\n    end";
+        let ast = parse(code).unwrap();
+        let subgraph = ast
+            .root
+            .children
+            .iter()
+            .find(|n| n.kind == NodeKind::Subgraph)
+            .unwrap();
+        assert_eq!(subgraph.get_property("direction"), Some("LR"));
+    }
+
+    #[test]
+    fn test_top_level_direction_ignored_in_graph() {
+        let code = "graph TD\n    direction LR\n    A --> B";
+        let (ast, diagnostics) =
+            FlowchartParser::new().parse_partial(code, &MermaidConfig::default());
+        assert!(ast.is_some());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, crate::diagnostic::Severity::Hint);
+
+        let code = "flowchart TD\n    direction LR\n    A --> B";
+        let (_, diagnostics) =
+            FlowchartParser::new().parse_partial(code, &MermaidConfig::default());
+        assert!(diagnostics.is_empty());
+    }
 }
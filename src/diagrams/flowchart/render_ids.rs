@@ -0,0 +1,171 @@
+//! Element ids Mermaid generates when rendering a flowchart.
+//!
+//! Best effort: the schemes below follow Mermaid's flowchart database and
+//! may change between releases.
+//!
+//! - Nodes are `flowchart-<id>-<n>`, where `n` counts the vertex
+//!   references (node occurrences and `style` statements) before the
+//!   node's first one.
+//! - Links are `L-<from>-<to>-<n>` in v10, with `n` counting earlier links
+//!   between the same pair. In v11 they are `L_<from>_<to>_<n>`, with `n`
+//!   being 0 for the first link of a pair and one more than the number of
+//!   earlier links otherwise; a link named with `id@` uses that id.
+//!
+//! `tests/integration/render_id_tests.rs` checks the schemes against ids
+//! recorded from Mermaid's rendered SVG.
+
+use std::collections::HashMap;
+
+use crate::ast::{Ast, AstNode, NodeKind};
+use crate::config::MermaidVersion;
+
+/// Sets the `render_id` property on every node occurrence and link.
+pub fn assign(ast: &mut Ast, version: MermaidVersion) {
+    let mut ids = RenderIds {
+        version,
+        ..Default::default()
+    };
+    for stmt in &mut ast.root.children {
+        ids.statement(stmt);
+    }
+}
+
+#[derive(Default)]
+struct RenderIds {
+    version: MermaidVersion,
+    /// Number of vertex references so far.
+    vertex_counter: usize,
+    /// Element id of each node, fixed at its first reference.
+    vertices: HashMap<String, String>,
+    /// Number of links between each pair so far.
+    pairs: HashMap<(String, String), usize>,
+}
+
+impl RenderIds {
+    fn statement(&mut self, stmt: &mut AstNode) {
+        match stmt.kind {
+            NodeKind::Node => self.vertex(stmt),
+            NodeKind::Style => {
+                if let Some(id) = stmt.get_property("node_id").map(str::to_string) {
+                    self.vertex_id(&id);
+                }
+            }
+            NodeKind::Edge => {
+                // A link between `&` lists gets one element id per pair,
+                // separated by spaces
+                let mut from: Vec<String> = Vec::new();
+                for child in &mut stmt.children {
+                    if child.kind == NodeKind::Node {
+                        self.vertex(child);
+                        from.extend(child.get_property("id").map(str::to_string));
+                        continue;
+                    }
+                    let mut to = Vec::new();
+                    for target in child.children.iter_mut().filter(|c| c.kind == NodeKind::Node) {
+                        self.vertex(target);
+                        to.push(target.get_property("id").unwrap_or_default().to_string());
+                    }
+                    if to.is_empty() {
+                        continue;
+                    }
+                    let edge_id = child.get_property("edge_id").map(str::to_string);
+                    let link_ids: Vec<_> = from
+                        .iter()
+                        .flat_map(|f| to.iter().map(move |t| (f, t)))
+                        .map(|(f, t)| self.link_id(f, t, edge_id.as_deref()))
+                        .collect();
+                    child.add_property("render_id", link_ids.join(" "));
+                    from = to;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn vertex(&mut self, node: &mut AstNode) {
+        if let Some(id) = node.get_property("id").map(str::to_string) {
+            let render_id = self.vertex_id(&id);
+            node.add_property("render_id", render_id);
+        }
+    }
+
+    /// Records a reference to vertex `id` and returns its element id.
+    fn vertex_id(&mut self, id: &str) -> String {
+        let counter = self.vertex_counter;
+        self.vertex_counter += 1;
+        self.vertices
+            .entry(id.to_string())
+            .or_insert_with(|| format!("flowchart-{}-{}", id, counter))
+            .clone()
+    }
+
+    fn link_id(&mut self, from: &str, to: &str, edge_id: Option<&str>) -> String {
+        let earlier = self.pairs.entry((from.to_string(), to.to_string())).or_insert(0);
+        let count = *earlier;
+        *earlier += 1;
+        match self.version {
+            MermaidVersion::V10 => format!("L-{}-{}-{}", from, to, count),
+            MermaidVersion::V11 => match edge_id {
+                Some(edge_id) => edge_id.to_string(),
+                None if count == 0 => format!("L_{}_{}_0", from, to),
+                None => format!("L_{}_{}_{}", from, to, count + 1),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MermaidConfig;
+    use crate::diagrams::flowchart::FlowchartParser;
+    use crate::parser::traits::DiagramParser;
+
+    fn render_ids(code: &str, version: MermaidVersion) -> Vec<String> {
+        let mut ast = FlowchartParser::new()
+            .parse(code, &MermaidConfig::default())
+            .expect("parse failed");
+        assign(&mut ast, version);
+        let mut ids = Vec::new();
+        ast.walk(|node, _| {
+            if let Some(id) = node.get_property("render_id") {
+                ids.push(id.to_string());
+            }
+        });
+        ids
+    }
+
+    #[test]
+    fn test_node_ids_count_references() {
+        let code = "flowchart LR\n    A --> B\n    style C fill:#f00\n    B --> C\n    A";
+        let ids = render_ids(code, MermaidVersion::V11);
+        let nodes: Vec<_> = ids.iter().filter(|id| id.starts_with("flowchart-")).collect();
+        assert_eq!(
+            nodes,
+            vec!["flowchart-A-0", "flowchart-B-1", "flowchart-B-1", "flowchart-C-2", "flowchart-A-0"]
+        );
+    }
+
+    #[test]
+    fn test_link_ids_by_version() {
+        let code = "flowchart LR\n    A --> B\n    A --> B\n    A --> B\n    B e1@--> A";
+        let links = |version| {
+            render_ids(code, version)
+                .into_iter()
+                .filter(|id| !id.starts_with("flowchart-"))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(links(MermaidVersion::V10), vec!["L-A-B-0", "L-A-B-1", "L-A-B-2", "L-B-A-0"]);
+        assert_eq!(links(MermaidVersion::V11), vec!["L_A_B_0", "L_A_B_2", "L_A_B_3", "e1"]);
+    }
+
+    #[test]
+    fn test_node_list_link_ids() {
+        let code = "flowchart LR\n    A & B --> C";
+        let links: Vec<_> = render_ids(code, MermaidVersion::V11)
+            .into_iter()
+            .filter(|id| !id.starts_with("flowchart-"))
+            .collect();
+        assert_eq!(links, vec!["L_A_C_0 L_B_C_0"]);
+    }
+}
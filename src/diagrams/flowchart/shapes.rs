@@ -0,0 +1,87 @@
+//! The node shape catalog for `id@{ shape: ... }` metadata.
+//!
+//! Mermaid v11 names each shape with a short name and accepts several
+//! aliases. Shapes that also have a bracket syntax map to a [`NodeShape`].
+
+use super::NodeShape;
+
+/// A shape from the catalog.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Shape {
+    /// Mermaid's short name for the shape.
+    pub name: &'static str,
+    /// The bracket shape drawn the same way, if there is one.
+    pub bracket: Option<NodeShape>,
+}
+
+/// Short names, their aliases and bracket equivalents.
+const CATALOG: &[(&str, &[&str], Option<NodeShape>)] = &[
+    ("rect", &["proc", "process", "rectangle"], Some(NodeShape::Rectangle)),
+    ("rounded", &["event"], Some(NodeShape::RoundedRect)),
+    ("stadium", &["pill", "terminal"], Some(NodeShape::Stadium)),
+    ("fr-rect", &["framed-rectangle", "subproc", "subprocess", "subroutine"], Some(NodeShape::Subroutine)),
+    ("cyl", &["cylinder", "database", "db"], Some(NodeShape::Cylindrical)),
+    ("circle", &["circ"], Some(NodeShape::Circle)),
+    ("odd", &[], Some(NodeShape::Asymmetric)),
+    ("diam", &["decision", "diamond", "question"], Some(NodeShape::Rhombus)),
+    ("hex", &["hexagon", "prepare"], Some(NodeShape::Hexagon)),
+    ("lean-r", &["in-out", "lean-right"], Some(NodeShape::Parallelogram)),
+    ("lean-l", &["lean-left", "out-in"], Some(NodeShape::ParallelogramAlt)),
+    ("trap-b", &["priority", "trapezoid", "trapezoid-bottom"], Some(NodeShape::Trapezoid)),
+    ("trap-t", &["inv-trapezoid", "manual", "trapezoid-top"], Some(NodeShape::TrapezoidAlt)),
+    ("dbl-circ", &["double-circle"], Some(NodeShape::DoubleCircle)),
+    ("notch-rect", &["card", "notched-rectangle"], None),
+    ("hourglass", &["collate"], None),
+    ("bolt", &["com-link", "lightning-bolt"], None),
+    ("brace", &["brace-l", "comment"], None),
+    ("brace-r", &[], None),
+    ("braces", &[], None),
+    ("delay", &["half-rounded-rectangle"], None),
+    ("h-cyl", &["das", "horizontal-cylinder"], None),
+    ("lin-cyl", &["disk", "lined-cylinder"], None),
+    ("curv-trap", &["curved-trapezoid", "display"], None),
+    ("div-rect", &["div-proc", "divided-process", "divided-rectangle"], None),
+    ("doc", &["document"], None),
+    ("tri", &["extract", "triangle"], None),
+    ("fork", &["join"], None),
+    ("win-pane", &["internal-storage", "window-pane"], None),
+    ("f-circ", &["filled-circle", "junction"], None),
+    ("lin-doc", &["lined-document"], None),
+    ("lin-rect", &["lin-proc", "lined-process", "lined-rectangle", "shaded-process"], None),
+    ("notch-pent", &["loop-limit", "notched-pentagon"], None),
+    ("flip-tri", &["flipped-triangle", "manual-file"], None),
+    ("sl-rect", &["manual-input", "sloped-rectangle"], None),
+    ("docs", &["documents", "st-doc", "stacked-document"], None),
+    ("st-rect", &["processes", "procs", "stacked-rectangle"], None),
+    ("flag", &["paper-tape"], None),
+    ("sm-circ", &["small-circle", "start"], None),
+    ("fr-circ", &["framed-circle", "stop"], None),
+    ("bow-rect", &["bow-tie-rectangle", "stored-data"], None),
+    ("cross-circ", &["crossed-circle", "summary"], None),
+    ("tag-doc", &["tagged-document"], None),
+    ("tag-rect", &["tag-proc", "tagged-process", "tagged-rectangle"], None),
+    ("text", &[], None),
+];
+
+/// Looks up a shape by its short name or an alias. Names are
+/// case-sensitive, as in Mermaid.
+pub(super) fn lookup(name: &str) -> Option<Shape> {
+    CATALOG
+        .iter()
+        .find(|(short, aliases, _)| *short == name || aliases.contains(&name))
+        .map(|&(name, _, bracket)| Shape { name, bracket })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_by_alias() {
+        assert_eq!(lookup("database").map(|s| s.name), Some("cyl"));
+        assert_eq!(lookup("cyl").and_then(|s| s.bracket), Some(NodeShape::Cylindrical));
+        assert_eq!(lookup("card").and_then(|s| s.bracket), None);
+        assert!(lookup("card").is_some());
+        assert!(lookup("blob").is_none());
+    }
+}
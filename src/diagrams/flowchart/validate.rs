@@ -0,0 +1,430 @@
+//! Semantic checks for flowcharts.
+
+use std::collections::HashMap;
+
+use crate::ast::{flow_edges, flow_nodes, Ast, AstNode, NodeKind, Span};
+use crate::diagnostic::{Diagnostic, DiagnosticCode, OptInCheck, RelatedDiagnostic};
+use crate::parser::references::References;
+
+/// Validates a parsed flowchart AST.
+///
+/// - A link styled both by `linkStyle` index and by an `id@{ ... }` block
+///   gets an informational note at the `linkStyle`, since the two overlap.
+/// - A subgraph id already used by an earlier subgraph, or by a node
+///   declared with a shape or label, produces a warning at the subgraph.
+///   Bare references to a subgraph id are links to the subgraph and are
+///   fine.
+pub fn validate(ast: &Ast) -> Vec<Diagnostic> {
+    let mut diagnostics = subgraph_id_collisions(ast);
+    diagnostics.extend(link_styles_with_edge_data(ast));
+    diagnostics
+}
+
+/// Reports subgraph ids shared with another subgraph or a declared node.
+fn subgraph_id_collisions(ast: &Ast) -> Vec<Diagnostic> {
+    let nodes = flow_nodes(ast);
+    let mut subgraphs: HashMap<String, Span> = HashMap::new();
+    let mut diagnostics = Vec::new();
+    ast.walk(|node, _| {
+        if node.kind != NodeKind::Subgraph {
+            return;
+        }
+        let Some(id) = node.id() else {
+            return;
+        };
+        if let Some(&first) = subgraphs.get(id) {
+            diagnostics.push(
+                Diagnostic::warning(
+                    DiagnosticCode::DuplicateDefinition,
+                    format!("Subgraph id '{}' is already used by another subgraph", id),
+                    node.span,
+                )
+                .with_related(RelatedDiagnostic::new("first defined here", first)),
+            );
+            return;
+        }
+        subgraphs.insert(id.to_string(), node.span);
+        if let Some(declared) = nodes.iter().find(|n| n.id == id && n.shape.is_some()) {
+            diagnostics.push(
+                Diagnostic::warning(
+                    DiagnosticCode::DuplicateDefinition,
+                    format!("Subgraph id '{}' is also the id of a node", id),
+                    node.span,
+                )
+                .with_related(RelatedDiagnostic::new(format!("node '{}' defined here", id), declared.span)),
+            );
+        }
+    });
+    diagnostics
+}
+
+/// Notes links styled both by `linkStyle` index and by edge id.
+fn link_styles_with_edge_data(ast: &Ast) -> Vec<Diagnostic> {
+    let mut edge_data = HashMap::new();
+    let mut link_styles = Vec::new();
+    ast.walk(|node, _| {
+        if node.kind != NodeKind::Statement {
+            return;
+        }
+        match node.statement_type() {
+            Some("edge_data") => {
+                let edge_id = node.get_property("edge_id").unwrap_or_default().to_string();
+                edge_data.entry(edge_id).or_insert(node.span);
+            }
+            Some("linkStyle") => {
+                let indices = node.get_property("indices").unwrap_or_default().to_string();
+                link_styles.push((node.span, indices));
+            }
+            _ => {}
+        }
+    });
+    if edge_data.is_empty() {
+        return Vec::new();
+    }
+
+    let edges = flow_edges(ast);
+    let mut diagnostics = Vec::new();
+    for (span, indices) in &link_styles {
+        for index in indices.split(',').filter_map(|i| i.parse::<usize>().ok()) {
+            let edge_id = edges.iter().find(|e| e.index == index).and_then(|e| e.id.as_ref());
+            let Some((edge_id, data_span)) = edge_id.and_then(|id| Some((id, edge_data.get(id)?))) else {
+                continue;
+            };
+            diagnostics.push(
+                Diagnostic::info(
+                    DiagnosticCode::DuplicateDefinition,
+                    format!("Link {} is also styled by its id '{}'", index, edge_id),
+                    *span,
+                )
+                .with_related(RelatedDiagnostic::new("styled by id here", *data_span)),
+            );
+        }
+    }
+    diagnostics
+}
+
+/// Warns about edges that repeat an earlier edge exactly: same source,
+/// target, link type and label.
+///
+/// Parallel edges with different labels or link types are left alone.
+pub fn duplicate_edges(ast: &Ast) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut first_seen = HashMap::new();
+
+    for edge in flow_edges(ast) {
+        let key = (edge.from.clone(), edge.to.clone(), edge.edge_type, edge.label.clone());
+        match first_seen.get(&key) {
+            Some(&first_span) => diagnostics.push(
+                OptInCheck::DuplicateEdges
+                    .diagnostic(
+                        format!("Edge from '{}' to '{}' duplicates an earlier edge", edge.from, edge.to),
+                        edge.span,
+                    )
+                    .with_related(RelatedDiagnostic::new("first defined here", first_span)),
+            ),
+            None => {
+                first_seen.insert(key, edge.span);
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// Suggests a `classDef` when three or more `style` statements apply the
+/// same styles.
+///
+/// Styles are compared as [`normalize_styles`] writes them. One hint is
+/// reported per repeated style, at its first statement.
+pub fn repeated_styles(ast: &Ast) -> Vec<Diagnostic> {
+    let mut groups: Vec<(String, Vec<(Span, String)>)> = Vec::new();
+    ast.walk(|node, _| {
+        if node.kind != NodeKind::Style {
+            return;
+        }
+        let styles = normalize_styles(node.get_property("styles").unwrap_or_default());
+        let target = (node.span, node.get_property("node_id").unwrap_or_default().to_string());
+        match groups.iter_mut().find(|(key, _)| *key == styles) {
+            Some((_, targets)) => targets.push(target),
+            None => groups.push((styles, vec![target])),
+        }
+    });
+
+    let mut diagnostics = Vec::new();
+    for (styles, targets) in groups {
+        if targets.len() < 3 {
+            continue;
+        }
+        let ids: Vec<_> = targets.iter().map(|(_, id)| id.as_str()).collect();
+        let mut diagnostic = OptInCheck::RepeatedStyles
+            .diagnostic(
+                format!("{} 'style' statements apply the same styles '{}'", targets.len(), styles),
+                targets[0].0,
+            )
+            .with_note(format!(
+                "define them once with 'classDef name {}' and apply it with 'class {} name'",
+                styles,
+                ids.join(",")
+            ));
+        for (span, _) in &targets[1..] {
+            diagnostic = diagnostic.with_related(RelatedDiagnostic::new("same styles here", *span));
+        }
+        diagnostics.push(diagnostic);
+    }
+    diagnostics
+}
+
+/// Returns `styles` with whitespace runs collapsed to one space and removed
+/// around `:` and `,`, so `fill : #f00` matches `fill:#f00` while values
+/// such as `stroke-dasharray: 5 5` keep their spaces.
+fn normalize_styles(styles: &str) -> String {
+    let collapsed = styles.split_whitespace().collect::<Vec<_>>().join(" ");
+    collapsed.replace(" :", ":").replace(": ", ":").replace(" ,", ",").replace(", ", ",")
+}
+
+/// Warns about link endpoints that are neither a declared node nor a
+/// subgraph id, which are often typos: Mermaid silently adds a new node.
+///
+/// A node counts as declared if it is given a shape or label anywhere, or
+/// stands alone as a statement. One warning is reported per id, at its
+/// first use as an endpoint.
+pub fn undeclared_endpoints(ast: &Ast) -> Vec<Diagnostic> {
+    let mut ids = References::default();
+    collect_endpoints(&ast.root, &mut ids);
+
+    ids.undefined()
+        .into_iter()
+        .map(|(id, span)| {
+            OptInCheck::UndeclaredEndpoints
+                .diagnostic(format!("'{}' is not a declared node or subgraph", id), span)
+                .with_note(format!(
+                    "Mermaid adds a new node for it; check the spelling, or declare it as '{}[label]'",
+                    id
+                ))
+        })
+        .collect()
+}
+
+/// Collects subgraph ids, standalone node statements and declared link
+/// endpoints as definitions, and the other link endpoints as references.
+fn collect_endpoints<'a>(node: &'a AstNode, ids: &mut References<'a>) {
+    fn define<'a>(node: &'a AstNode, ids: &mut References<'a>) {
+        if let Some(id) = node.id() {
+            ids.define(id, node.span);
+        }
+    }
+    fn endpoint<'a>(node: &'a AstNode, ids: &mut References<'a>) {
+        match node.id() {
+            Some(id) if node.get_property("declared") == Some("true") => ids.define(id, node.span),
+            Some(id) => ids.reference(id, node.span),
+            None => {}
+        }
+    }
+    for child in &node.children {
+        match child.kind {
+            NodeKind::Node => define(child, ids),
+            NodeKind::Statement if child.statement_type() == Some("node_list") => {
+                for node in &child.children {
+                    define(node, ids);
+                }
+            }
+            // Source nodes, then one `Edge` per link holding its targets
+            NodeKind::Edge => {
+                for part in &child.children {
+                    match part.kind {
+                        NodeKind::Node => endpoint(part, ids),
+                        NodeKind::Edge => {
+                            for target in part.children.iter().filter(|n| n.kind == NodeKind::Node) {
+                                endpoint(target, ids);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            NodeKind::Subgraph => {
+                define(child, ids);
+                collect_endpoints(child, ids);
+            }
+            _ => collect_endpoints(child, ids),
+        }
+    }
+}
+
+/// Warns about `classDef` definitions that no `class` statement or `:::`
+/// shorthand applies (`unused-classdef`). The `default` class applies to
+/// every node without a class, so it is never reported.
+pub fn unused_class_defs(ast: &Ast) -> Vec<Diagnostic> {
+    let mut classes = References::default();
+    collect_classes(&ast.root, &mut classes);
+
+    classes
+        .unreferenced()
+        .into_iter()
+        .filter(|(name, _)| *name != "default")
+        .map(|(name, span)| {
+            OptInCheck::UnusedClassDef
+                .diagnostic(format!("Class '{}' is never applied", name), span)
+                .with_note(format!(
+                    "nothing references it; apply it with 'class <ids> {}' or ':::{}', or remove it",
+                    name, name
+                ))
+        })
+        .collect()
+}
+
+/// Collects `classDef` names as definitions, and the classes applied by
+/// `class` statements and `:::` shorthands as references.
+fn collect_classes<'a>(node: &'a AstNode, classes: &mut References<'a>) {
+    match (&node.kind, node.statement_type()) {
+        (NodeKind::ClassDef, _) => {
+            if let Some(name) = node.get_property("name") {
+                classes.define(name, node.span);
+            }
+        }
+        (NodeKind::Statement, Some("class_assignment")) => {
+            if let Some(name) = node.get_property("class_name") {
+                classes.reference(name, node.span);
+            }
+        }
+        _ => {
+            if let Some(name) = node.get_property("class") {
+                classes.reference(name, node.span);
+            }
+        }
+    }
+    for child in &node.children {
+        collect_classes(child, classes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MermaidConfig;
+    use crate::diagrams::flowchart::FlowchartParser;
+    use crate::parser::traits::DiagramParser;
+
+    fn check(code: &str) -> Vec<Diagnostic> {
+        let ast = FlowchartParser::new()
+            .parse(code, &MermaidConfig::default())
+            .expect("parse failed");
+        duplicate_edges(&ast)
+    }
+
+    #[test]
+    fn test_link_styled_by_index_and_id() {
+        let code = "graph TD\n    A --> B\n    B e1@--> C\n    e1@{ animate: true }\n    linkStyle 0,1 stroke:red";
+        let ast = FlowchartParser::new()
+            .parse(code, &MermaidConfig::default())
+            .expect("parse failed");
+        let diagnostics = validate(&ast);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, crate::diagnostic::Severity::Info);
+        assert_eq!(diagnostics[0].message, "Link 1 is also styled by its id 'e1'");
+        assert_eq!(diagnostics[0].related[0].span.text(code), "e1@{ animate: true }");
+    }
+
+    fn validate_code(code: &str) -> Vec<Diagnostic> {
+        let ast = FlowchartParser::new()
+            .parse(code, &MermaidConfig::default())
+            .expect("parse failed");
+        validate(&ast)
+    }
+
+    #[test]
+    fn test_subgraph_id_used_twice() {
+        let code = "flowchart TD\n    subgraph one [First]\n    A\n    end\n    subgraph one\n    B\n    end";
+        let diagnostics = validate_code(code);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, DiagnosticCode::DuplicateDefinition);
+        assert_eq!(diagnostics[0].message, "Subgraph id 'one' is already used by another subgraph");
+        assert_eq!(diagnostics[0].span.text(code), "subgraph one");
+        assert_eq!(diagnostics[0].related[0].span.text(code), "subgraph one [First]");
+    }
+
+    #[test]
+    fn test_subgraph_id_used_by_node() {
+        let code = "flowchart TD\n    sub[Box] --> B\n    subgraph sub\n    B\n    end\n    subgraph other\n    end\n    other --> B";
+        let diagnostics = validate_code(code);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Subgraph id 'sub' is also the id of a node");
+        assert_eq!(diagnostics[0].span.text(code), "subgraph sub");
+        assert_eq!(diagnostics[0].related[0].span.text(code), "sub[Box]");
+    }
+
+    #[test]
+    fn test_exact_duplicate_warns() {
+        let code = "graph TD\n    A --> B\n    A --> B";
+        let diagnostics = check(code);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].span.text(code), "A --> B");
+        assert_eq!(diagnostics[0].span.start, 25);
+        assert_eq!(diagnostics[0].related[0].span.start, 13);
+    }
+
+    #[test]
+    fn test_parallel_edges_allowed() {
+        assert!(check("graph TD\n    A -->|yes| B\n    A -->|no| B").is_empty());
+        assert!(check("graph TD\n    A --> B\n    A -.-> B").is_empty());
+        assert!(check("graph TD\n    A --> B\n    B --> A").is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_within_chain() {
+        let code = "graph TD\n    A --> B --> C\n    B --> C";
+        assert_eq!(check(code).len(), 1);
+    }
+
+    #[test]
+    fn test_repeated_styles_hint() {
+        let code = "graph TD\n    style A fill:#f00\n    style B fill: #f00\n    style C stroke:#333\n    style D fill:#f00";
+        let ast = FlowchartParser::new()
+            .parse(code, &MermaidConfig::default())
+            .expect("parse failed");
+        let diagnostics = repeated_styles(&ast);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, crate::diagnostic::Severity::Hint);
+        assert_eq!(diagnostics[0].span.text(code), "style A fill:#f00");
+        assert_eq!(diagnostics[0].related.len(), 2);
+        assert!(diagnostics[0].notes[0].contains("class A,B,D name"));
+    }
+
+    #[test]
+    fn test_repeated_styles_keep_spaces_in_values() {
+        let code = "graph TD\n    style A stroke-dasharray: 5 5, font-family:Open  Sans\n    style B stroke-dasharray:5 5,font-family : Open Sans\n    style C stroke-dasharray:5 5,font-family:Open Sans\n    style D stroke-dasharray:55,font-family:OpenSans";
+        let ast = FlowchartParser::new()
+            .parse(code, &MermaidConfig::default())
+            .expect("parse failed");
+        let diagnostics = repeated_styles(&ast);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "3 'style' statements apply the same styles 'stroke-dasharray:5 5,font-family:Open Sans'"
+        );
+        assert!(diagnostics[0].notes[0].contains("class A,B,C name"));
+    }
+
+    #[test]
+    fn test_two_repeated_styles_allowed() {
+        let code = "graph TD\n    style A fill:#f00\n    style B fill:#f00";
+        let ast = FlowchartParser::new()
+            .parse(code, &MermaidConfig::default())
+            .expect("parse failed");
+        assert!(repeated_styles(&ast).is_empty());
+    }
+
+    #[test]
+    fn test_undeclared_endpoints() {
+        let code = "flowchart TD\n    A[Start] --> realSub\n    A --> missingSub\n    subgraph realSub [Real]\n    B\n    end\n    B --> A & missingSub\n    B --> C((C))";
+        let ast = FlowchartParser::new()
+            .parse(code, &MermaidConfig::default())
+            .expect("parse failed");
+        let diagnostics = undeclared_endpoints(&ast);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, DiagnosticCode::UndefinedReference);
+        assert_eq!(diagnostics[0].message, "'missingSub' is not a declared node or subgraph");
+        assert_eq!(diagnostics[0].span.start, code.find("missingSub").unwrap());
+        assert_eq!(diagnostics[0].span.text(code), "missingSub");
+    }
+}
@@ -2,6 +2,8 @@
 
 use logos::Logos;
 
+use crate::parser::lexer;
+
 /// Tokens for Gantt chart lexing.
 #[derive(Logos, Debug, Clone, PartialEq)]
 #[logos(skip r"[ \t]+")]
@@ -56,6 +58,10 @@ pub enum GanttToken {
     #[token("milestone", ignore(case))]
     Milestone,
 
+    #[token("vert", ignore(case))]
+    #[token("vertical", ignore(case))]
+    Vert,
+
     #[token("after", ignore(case))]
     After,
 
@@ -126,30 +132,12 @@ pub enum GanttToken {
     Dash,
 }
 
-/// A token with its span information.
-#[derive(Debug, Clone)]
-pub struct Token {
-    pub kind: GanttToken,
-    pub text: String,
-    pub span: std::ops::Range<usize>,
-}
+/// A Gantt chart token with its span and text.
+pub type Token = lexer::Token<GanttToken>;
 
-/// Tokenize Gantt chart source.
+/// Tokenizes Gantt chart source, skipping text no token matches.
 pub fn tokenize(source: &str) -> Vec<Token> {
-    let mut tokens = Vec::new();
-    let mut lexer = GanttToken::lexer(source);
-
-    while let Some(result) = lexer.next() {
-        if let Ok(kind) = result {
-            tokens.push(Token {
-                kind,
-                text: lexer.slice().to_string(),
-                span: lexer.span(),
-            });
-        }
-    }
-
-    tokens
+    lexer::tokenize(source)
 }
 
 #[cfg(test)]
@@ -196,11 +184,12 @@ mod tests {
 
     #[test]
     fn test_tokenize_modifiers() {
-        let input = "done crit active milestone";
+        let input = "done crit active milestone vert vertical";
         let tokens = tokenize(input);
         assert!(tokens.iter().any(|t| t.kind == GanttToken::Done));
         assert!(tokens.iter().any(|t| t.kind == GanttToken::Crit));
         assert!(tokens.iter().any(|t| t.kind == GanttToken::Active));
         assert!(tokens.iter().any(|t| t.kind == GanttToken::Milestone));
+        assert_eq!(tokens.iter().filter(|t| t.kind == GanttToken::Vert).count(), 2);
     }
 }
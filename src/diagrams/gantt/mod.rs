@@ -15,6 +15,7 @@
 
 pub mod lexer;
 pub mod parser;
+pub mod validate;
 
 pub use parser::GanttParser;
 
@@ -33,6 +34,7 @@ pub enum TaskStatus {
 
 impl TaskStatus {
     /// Parse from string.
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
             "active" => Some(TaskStatus::Active),
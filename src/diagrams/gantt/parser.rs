@@ -1,31 +1,83 @@
 //! Parser for Gantt charts.
 
 use crate::ast::{Ast, AstNode, NodeKind, Span};
+use crate::config::MermaidConfig;
 use crate::diagnostic::{Diagnostic, DiagnosticCode, Severity};
+use crate::parser::traits::DiagramParser;
+use crate::parser::{accessibility, finish_parse, title, StatementLoop};
 
 use super::lexer::{tokenize, GanttToken, Token};
 
 /// Parser for Gantt charts.
-pub struct GanttParser<'a> {
+pub struct GanttParser;
+
+impl GanttParser {
+    /// Creates a new Gantt chart parser.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for GanttParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DiagramParser for GanttParser {
+    fn parse(&self, code: &str, config: &MermaidConfig) -> Result<Ast, Vec<Diagnostic>> {
+        GanttParserImpl::new(code).with_config(config).parse()
+    }
+
+    /// Also returns the warnings and hints of a successful parse. There is
+    /// no error recovery, so a failed parse has no AST.
+    fn parse_partial(&self, code: &str, config: &MermaidConfig) -> (Option<Ast>, Vec<Diagnostic>) {
+        GanttParserImpl::new(code).with_config(config).parse_partial()
+    }
+
+    fn name(&self) -> &'static str {
+        "gantt"
+    }
+}
+
+struct GanttParserImpl<'a> {
     tokens: Vec<Token>,
     pos: usize,
     source: &'a str,
     diagnostics: Vec<Diagnostic>,
+    /// `gantt.displayMode` from the config, recorded on the root.
+    display_mode: Option<String>,
 }
 
-impl<'a> GanttParser<'a> {
+impl<'a> GanttParserImpl<'a> {
     /// Create a new parser.
-    pub fn new(source: &'a str) -> Self {
+    fn new(source: &'a str) -> Self {
         Self {
             tokens: tokenize(source),
             pos: 0,
             source,
             diagnostics: Vec::new(),
+            display_mode: None,
+        }
+    }
+
+    /// Takes the settings that affect the chart from `config`.
+    fn with_config(mut self, config: &MermaidConfig) -> Self {
+        self.display_mode = config.gantt.display_mode.clone();
+        self
+    }
+
+    /// Parses the Gantt chart, also returning the warnings and hints that
+    /// [`Self::parse`] drops on success. A failed parse has no AST.
+    fn parse_partial(&mut self) -> (Option<Ast>, Vec<Diagnostic>) {
+        match self.parse() {
+            Ok(ast) => (Some(ast), std::mem::take(&mut self.diagnostics)),
+            Err(diagnostics) => (None, diagnostics),
         }
     }
 
     /// Parse the Gantt chart.
-    pub fn parse(&mut self) -> Result<Ast, Vec<Diagnostic>> {
+    fn parse(&mut self) -> Result<Ast, Vec<Diagnostic>> {
         let start_span = Span::new(0, self.source.len());
         let mut root = AstNode::new(NodeKind::Root, start_span);
 
@@ -42,11 +94,13 @@ impl<'a> GanttParser<'a> {
                 Severity::Error,
                 self.current_span(),
             ));
-            return Err(self.diagnostics.clone());
+            // Move the diagnostics out instead of cloning them
+            return Err(std::mem::take(&mut self.diagnostics));
         }
 
         // Parse statements
         while !self.is_at_end() {
+            let loop_start = self.pos;
             self.skip_newlines();
             if self.is_at_end() {
                 break;
@@ -58,13 +112,14 @@ impl<'a> GanttParser<'a> {
                 // Skip unknown token
                 self.advance();
             }
+            self.ensure_progress(loop_start);
         }
 
-        if self.diagnostics.iter().any(|d| d.severity == Severity::Error) {
-            Err(self.diagnostics.clone())
-        } else {
-            Ok(Ast::new(root, self.source.to_string()))
+        if let Some(mode) = &self.display_mode {
+            root.add_property("displayMode", mode.clone());
         }
+
+        finish_parse(root, self.source, &mut self.diagnostics)
     }
 
     /// Parse the gantt declaration.
@@ -147,15 +202,11 @@ impl<'a> GanttParser<'a> {
 
     /// Parse title statement.
     fn parse_title(&mut self) -> Option<AstNode> {
-        let start = self.current_span().start;
-        self.advance(); // consume 'title'
-
-        let title = self.consume_until_newline();
-        let end = self.previous_span().end;
-
-        let mut node = AstNode::new(NodeKind::Statement, Span::new(start, end));
-        node.add_property("type", "title");
-        node.add_property("value", title.trim().to_string());
+        let keyword_span = self.current_span();
+        let (node, end) = title::parse_statement(self.source, keyword_span, &mut self.diagnostics);
+        while !self.is_at_end() && self.current_span().start < end {
+            self.advance();
+        }
         Some(node)
     }
 
@@ -257,62 +308,30 @@ impl<'a> GanttParser<'a> {
         Some(node)
     }
 
-    /// Parse section statement.
+    /// Parse section statement. The name is the rest of the line as
+    /// written, colons included.
     fn parse_section(&mut self) -> Option<AstNode> {
         let start = self.current_span().start;
         self.advance(); // consume 'section'
 
-        let name = self.consume_until_newline();
+        let name_start = self.current_span().start;
+        self.consume_until_newline();
         let end = self.previous_span().end;
+        let name = self.source.get(name_start..end).unwrap_or_default().trim();
 
         let mut node = AstNode::new(NodeKind::Subgraph, Span::new(start, end));
         node.add_property("type", "section");
-        node.add_property("name", name.trim().to_string());
+        node.add_property("name", name);
         Some(node)
     }
 
     /// Parse accessibility statement.
     fn parse_accessibility(&mut self) -> Option<AstNode> {
         let start = self.current_span().start;
-        let acc_type = if self.check(&GanttToken::AccTitle) {
-            "accTitle"
-        } else {
-            "accDescr"
-        };
-        self.advance();
-
-        // Skip colon if present
-        if self.check(&GanttToken::Colon) {
+        let (node, end) = accessibility::parse_statement(self.source, start, &mut self.diagnostics)?;
+        while !self.is_at_end() && self.current_span().start < end {
             self.advance();
         }
-
-        // Check for multi-line description
-        if self.check(&GanttToken::OpenBrace) {
-            self.advance();
-            let mut content = String::new();
-            while !self.check(&GanttToken::CloseBrace) && !self.is_at_end() {
-                content.push_str(&self.current_text());
-                content.push(' ');
-                self.advance();
-            }
-            if self.check(&GanttToken::CloseBrace) {
-                self.advance();
-            }
-            let end = self.previous_span().end;
-
-            let mut node = AstNode::new(NodeKind::Statement, Span::new(start, end));
-            node.add_property("type", acc_type);
-            node.add_property("value", content.trim().to_string());
-            return Some(node);
-        }
-
-        // Single line
-        let value = self.consume_until_newline();
-        let end = self.previous_span().end;
-
-        let mut node = AstNode::new(NodeKind::Statement, Span::new(start, end));
-        node.add_property("type", acc_type);
-        node.add_property("value", value.trim().to_string());
         Some(node)
     }
 
@@ -320,28 +339,55 @@ impl<'a> GanttParser<'a> {
     fn parse_task(&mut self) -> Option<AstNode> {
         let start = self.current_span().start;
 
-        // Collect task name (everything before the colon)
-        let mut task_name = String::new();
-        while !self.check(&GanttToken::Colon) && !self.check(&GanttToken::Newline) && !self.is_at_end() {
-            if !task_name.is_empty() && !self.current_text().is_empty() {
-                task_name.push(' ');
-            }
-            task_name.push_str(&self.current_text());
-            self.advance();
+        if self.check(&GanttToken::Colon) {
+            self.consume_until_newline();
+            self.diagnostics.push(Diagnostic::error(
+                DiagnosticCode::MissingElement,
+                "Task is missing a name before ':'",
+                Span::new(start, self.previous_span().end),
+            ));
+            return None;
         }
 
-        if task_name.trim().is_empty() {
+        // Names may contain colons: the data follows the last colon whose
+        // right-hand side reads as task data
+        let line_end = (self.pos..self.tokens.len())
+            .find(|&i| self.tokens[i].kind == GanttToken::Newline)
+            .unwrap_or(self.tokens.len());
+        let colons: Vec<usize> = (self.pos..line_end)
+            .filter(|&i| self.tokens[i].kind == GanttToken::Colon)
+            .collect();
+        let data_colon = colons
+            .iter()
+            .rev()
+            .copied()
+            .find(|&i| is_task_data(&self.tokens[i + 1..line_end]));
+
+        let name_end = match data_colon {
+            Some(colon) => self.tokens[colon].span.start,
+            None => self.tokens[line_end - 1].span.end,
+        };
+        let task_name = self.source[start..name_end].trim();
+        if task_name.is_empty() {
             return None;
         }
 
         let mut node = AstNode::new(NodeKind::Node, Span::new(start, start));
         node.add_property("type", "task");
-        node.add_property("name", task_name.trim().to_string());
+        node.add_property("name", task_name);
 
-        // Parse task data after colon
-        if self.check(&GanttToken::Colon) {
-            self.advance();
-            self.parse_task_data(&mut node);
+        match data_colon {
+            Some(colon) => {
+                self.pos = colon + 1;
+                self.parse_task_data(&mut node);
+            }
+            None => {
+                self.pos = line_end;
+                // Kept for validation to report
+                if !colons.is_empty() {
+                    node.add_property("name_only", "true");
+                }
+            }
         }
 
         let end = self.previous_span().end;
@@ -352,12 +398,15 @@ impl<'a> GanttParser<'a> {
     /// Parse task data (modifiers, ID, dates, duration).
     fn parse_task_data(&mut self, node: &mut AstNode) {
         let mut modifiers = Vec::new();
-        let mut task_id = None;
         let mut start_date = None;
         let mut end_date = None;
         let mut duration = None;
-        let mut after_ref = None;
+        let mut after_refs = Vec::new();
         let mut until_ref = None;
+        // Bare words other than dates and durations; the last is the task id
+        let mut words = Vec::new();
+        // Numbers, times and separators: dates in a custom `dateFormat`
+        let mut other_fields = false;
 
         while !self.check(&GanttToken::Newline) && !self.is_at_end() {
             // Skip commas
@@ -387,12 +436,17 @@ impl<'a> GanttParser<'a> {
                 self.advance();
                 continue;
             }
+            if self.check(&GanttToken::Vert) {
+                modifiers.push("vert");
+                self.advance();
+                continue;
+            }
 
-            // Check for after dependency
+            // Check for after dependency (`after a1 a2` depends on both)
             if self.check(&GanttToken::After) {
                 self.advance();
-                if self.check(&GanttToken::Identifier) {
-                    after_ref = Some(self.current_text().trim().to_string());
+                while self.check(&GanttToken::Identifier) {
+                    after_refs.push(self.current_text().trim().to_string());
                     self.advance();
                 }
                 continue;
@@ -426,19 +480,33 @@ impl<'a> GanttParser<'a> {
                 continue;
             }
 
-            // Check for identifier (task ID)
+            // Check for identifier (task ID or unknown modifier)
             if self.check(&GanttToken::Identifier) {
-                if task_id.is_none() {
-                    task_id = Some(self.current_text());
-                }
+                words.push((self.current_text(), self.current_span()));
                 self.advance();
                 continue;
             }
 
             // Skip other tokens
+            other_fields = true;
             self.advance();
         }
 
+        let untimed = [&start_date, &end_date, &duration, &until_ref].iter().all(|field| field.is_none())
+            && after_refs.is_empty()
+            && !other_fields;
+
+        // Modifiers come before the id, so earlier bare words are modifiers
+        // this parser doesn't know; they are kept for validation to report
+        let task_id = words.pop().map(|(id, _)| id);
+        for (word, span) in words {
+            node.add_child(AstNode::with_text(
+                NodeKind::Other("UnknownModifier".to_string()),
+                span,
+                word,
+            ));
+        }
+
         // Add properties to node
         if !modifiers.is_empty() {
             node.add_property("modifiers", modifiers.join(","));
@@ -455,12 +523,16 @@ impl<'a> GanttParser<'a> {
         if let Some(dur) = duration {
             node.add_property("duration", dur);
         }
-        if let Some(after) = after_ref {
-            node.add_property("after", after);
+        if !after_refs.is_empty() {
+            node.add_property("after", after_refs.join(" "));
         }
         if let Some(until) = until_ref {
             node.add_property("until", until);
         }
+        // Kept for validation to report
+        if untimed {
+            node.add_property("untimed", "true");
+        }
     }
 
     /// Consume tokens until newline.
@@ -488,7 +560,7 @@ impl<'a> GanttParser<'a> {
 
     fn current_span(&self) -> Span {
         self.current()
-            .map(|t| Span::new(t.span.start, t.span.end))
+            .map(|t| t.span)
             .unwrap_or(Span::new(self.source.len(), self.source.len()))
     }
 
@@ -496,7 +568,7 @@ impl<'a> GanttParser<'a> {
         if self.pos > 0 {
             self.tokens
                 .get(self.pos - 1)
-                .map(|t| Span::new(t.span.start, t.span.end))
+                .map(|t| t.span)
                 .unwrap_or(Span::new(0, 0))
         } else {
             Span::new(0, 0)
@@ -524,6 +596,50 @@ impl<'a> GanttParser<'a> {
     }
 }
 
+impl StatementLoop for GanttParserImpl<'_> {
+    fn position(&self) -> usize {
+        self.pos
+    }
+
+    fn at_end(&self) -> bool {
+        self.is_at_end()
+    }
+
+    fn token_span(&self) -> Span {
+        self.current_span()
+    }
+
+    fn skip_token(&mut self) {
+        self.advance();
+    }
+
+    fn report(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+}
+
+/// Returns true if `tokens`, the rest of a task line after a colon, read
+/// as task data.
+///
+/// The data is comma-separated fields. A field fails if it contains a
+/// colon or a quoted string, or more than one bare word other than the ids
+/// after `after` and `until`; dates in any format pass.
+fn is_task_data(tokens: &[Token]) -> bool {
+    tokens.split(|t| t.kind == GanttToken::Comma).all(|field| {
+        let mut words = 0;
+        let mut in_dependency = false;
+        for token in field {
+            match token.kind {
+                GanttToken::Colon | GanttToken::QuotedString => return false,
+                GanttToken::After | GanttToken::Until => in_dependency = true,
+                GanttToken::Identifier | GanttToken::DayName if !in_dependency => words += 1,
+                _ => {}
+            }
+        }
+        words <= 1
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -536,7 +652,7 @@ mod tests {
     section Section
     A task :a1, 2024-01-01, 30d"#;
 
-        let mut parser = GanttParser::new(code);
+        let mut parser = GanttParserImpl::new(code);
         let result = parser.parse();
         assert!(result.is_ok(), "Failed: {:?}", result.err());
     }
@@ -550,7 +666,7 @@ mod tests {
     Task 1 :a1, 2024-01-01, 30d
     Task 2 :after a1, 20d"#;
 
-        let mut parser = GanttParser::new(code);
+        let mut parser = GanttParserImpl::new(code);
         let result = parser.parse();
         assert!(result.is_ok(), "Failed: {:?}", result.err());
     }
@@ -564,7 +680,7 @@ mod tests {
     Critical task :crit, a2, after a1, 20d
     Active task :active, a3, after a2, 15d"#;
 
-        let mut parser = GanttParser::new(code);
+        let mut parser = GanttParserImpl::new(code);
         let result = parser.parse();
         assert!(result.is_ok(), "Failed: {:?}", result.err());
     }
@@ -576,7 +692,7 @@ mod tests {
     section Milestones
     Release v1 :milestone, m1, 2024-02-01, 0d"#;
 
-        let mut parser = GanttParser::new(code);
+        let mut parser = GanttParserImpl::new(code);
         let result = parser.parse();
         assert!(result.is_ok(), "Failed: {:?}", result.err());
     }
@@ -589,7 +705,7 @@ mod tests {
     section Tasks
     Task 1 :a1, 2024-01-01, 30d"#;
 
-        let mut parser = GanttParser::new(code);
+        let mut parser = GanttParserImpl::new(code);
         let result = parser.parse();
         assert!(result.is_ok(), "Failed: {:?}", result.err());
     }
@@ -602,15 +718,30 @@ mod tests {
     section Tasks
     Task 1 :a1, 2024-01-01, 30d"#;
 
-        let mut parser = GanttParser::new(code);
+        let mut parser = GanttParserImpl::new(code);
         let result = parser.parse();
         assert!(result.is_ok(), "Failed: {:?}", result.err());
     }
 
+    #[test]
+    fn test_names_with_colons() {
+        let code = "gantt\n    section Phase 1: Discovery\n    Review: security :a1, 2024-01-01, 5d";
+        let ast = GanttParserImpl::new(code).parse().unwrap();
+        let section = &ast.root.children[1];
+        assert_eq!(section.get_property("name"), Some("Phase 1: Discovery"));
+
+        let task = &ast.root.children[2];
+        assert_eq!(task.get_property("name"), Some("Review: security"));
+        assert_eq!(task.get_property("id"), Some("a1"));
+        assert_eq!(task.get_property("startDate"), Some("2024-01-01"));
+        assert_eq!(task.get_property("duration"), Some("5d"));
+        assert!(task.children.is_empty());
+    }
+
     #[test]
     fn test_parse_invalid() {
         let code = "not a gantt chart";
-        let mut parser = GanttParser::new(code);
+        let mut parser = GanttParserImpl::new(code);
         let result = parser.parse();
         assert!(result.is_err());
     }
@@ -0,0 +1,292 @@
+//! Semantic validation for Gantt charts.
+
+use std::collections::HashMap;
+
+use crate::ast::{gantt_graph, Ast, AstNode, NodeKind, Span};
+use crate::diagnostic::{Diagnostic, DiagnosticCode, RelatedDiagnostic, TextEdit};
+use crate::parser::calendar;
+
+/// Validates a parsed Gantt AST.
+///
+/// - A task id declared more than once produces a warning pointing at the
+///   first declaration; `after` references resolve to the last one.
+/// - A task modifier the parser doesn't recognize produces a hint.
+/// - A task line with colons but no readable task data produces a warning;
+///   the whole line is the task name.
+/// - A task with data but no start date, `after` or `until` dependency or
+///   duration produces a warning, as Mermaid can't place it.
+/// - An `excludes` value other than `weekends`, an English day name or a
+///   date produces a warning, as Mermaid ignores it; a `weekday` other than
+///   an English day name is an error. A German, French or Spanish day name,
+///   or month name in an excluded date, gets a fix to the English name.
+pub fn validate(ast: &Ast) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    ast.walk(|node, _| {
+        if node.kind == NodeKind::Statement {
+            match node.statement_type() {
+                Some("excludes") => diagnostics.extend(validate_excludes(node, &ast.source)),
+                Some("weekday") => diagnostics.extend(validate_weekday(node, &ast.source)),
+                _ => {}
+            }
+        }
+        if node.get_property("name_only") == Some("true") {
+            diagnostics.push(
+                Diagnostic::warning(
+                    DiagnosticCode::InvalidSyntax,
+                    format!(
+                        "No task data found in '{}'; the whole line is the task name",
+                        node.get_property("name").unwrap_or_default()
+                    ),
+                    node.span,
+                )
+                .with_note("task data follows the last ':' as comma-separated fields, e.g. 'Task name :a1, 2024-01-01, 5d'"),
+            );
+        }
+        if node.get_property("untimed") == Some("true") {
+            diagnostics.push(
+                Diagnostic::warning(
+                    DiagnosticCode::MissingElement,
+                    format!(
+                        "Task '{}' has no start date, dependency or duration",
+                        node.get_property("name").unwrap_or_default()
+                    ),
+                    node.span,
+                )
+                .with_note("give the task a date, 'after <id>' or a duration, e.g. 'Task :a1, after a0, 5d'"),
+            );
+        }
+        if node.kind == NodeKind::Other("UnknownModifier".to_string()) {
+            diagnostics.push(
+                Diagnostic::hint(
+                    DiagnosticCode::InvalidValue,
+                    format!("Unknown task modifier '{}' is ignored", node.text.as_deref().unwrap_or_default()),
+                    node.span,
+                )
+                .with_note("known modifiers are done, active, crit, milestone and vert"),
+            );
+        }
+    });
+
+    let graph = gantt_graph(ast);
+    let mut first_declared = HashMap::new();
+
+    for task in &graph.tasks {
+        let Some(id) = &task.id else {
+            continue;
+        };
+        match first_declared.get(id.as_str()) {
+            Some(&first_span) => diagnostics.push(
+                Diagnostic::warning(
+                    DiagnosticCode::DuplicateDefinition,
+                    format!("Task id '{}' is already defined", id),
+                    task.span,
+                )
+                .with_related(RelatedDiagnostic::new("first defined here", first_span)),
+            ),
+            None => {
+                first_declared.insert(id.as_str(), task.span);
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// Checks the values of an `excludes` statement.
+fn validate_excludes(node: &AstNode, source: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for (word, span) in value_words(node, source) {
+        let lower = word.to_lowercase();
+        if lower == "weekends" || calendar::DAYS.contains(&lower.as_str()) {
+            continue;
+        }
+        // A date, which may spell out its month as in `25-December-2024`
+        if word.contains(|c: char| c.is_ascii_digit()) {
+            diagnostics.extend(translated_months(word, span));
+            continue;
+        }
+        diagnostics.push(match calendar::translated_day(word) {
+            Some(translation) => Diagnostic::warning(DiagnosticCode::InvalidValue, translation.message(word), span)
+                .with_fix(TextEdit::new(span, translation.english)),
+            None => Diagnostic::warning(
+                DiagnosticCode::InvalidValue,
+                format!("Unknown excludes value '{}' is ignored", word),
+                span,
+            )
+            .with_note("excludes takes 'weekends', English day names and dates in the dateFormat"),
+        });
+    }
+    diagnostics
+}
+
+/// Checks the day of a `weekday` statement.
+fn validate_weekday(node: &AstNode, source: &str) -> Vec<Diagnostic> {
+    value_words(node, source)
+        .into_iter()
+        .filter(|(word, _)| !calendar::DAYS.contains(&word.to_lowercase().as_str()))
+        .map(|(word, span)| match calendar::translated_day(word) {
+            Some(translation) => Diagnostic::error(DiagnosticCode::InvalidValue, translation.message(word), span)
+                .with_fix(TextEdit::new(span, translation.english)),
+            None => Diagnostic::error(DiagnosticCode::InvalidValue, format!("Unknown weekday '{}'", word), span)
+                .with_note("weekday takes an English day name, e.g. 'weekday monday'"),
+        })
+        .collect()
+}
+
+/// Reports the month names in `date`, which starts at `span.start`, that
+/// are written in another language.
+fn translated_months(date: &str, span: Span) -> Vec<Diagnostic> {
+    split_words(date, span.start, |c| !c.is_alphabetic())
+        .into_iter()
+        .filter_map(|(word, span)| {
+            let translation = calendar::translated_month(word)?;
+            Some(
+                Diagnostic::warning(DiagnosticCode::InvalidValue, translation.message(word), span)
+                    .with_fix(TextEdit::new(span, translation.english)),
+            )
+        })
+        .collect()
+}
+
+/// Returns the words of a statement's value, after its keyword, split on
+/// whitespace and commas as Mermaid splits them.
+fn value_words<'a>(node: &AstNode, source: &'a str) -> Vec<(&'a str, Span)> {
+    let text = node.span.text(source);
+    let keyword_len = text.find(char::is_whitespace).unwrap_or(text.len());
+    split_words(&text[keyword_len..], node.span.start + keyword_len, |c| c.is_whitespace() || c == ',')
+}
+
+/// Splits `text`, which starts at `start`, into the non-empty runs between
+/// separators, with their spans.
+fn split_words(text: &str, start: usize, is_separator: impl Fn(char) -> bool) -> Vec<(&str, Span)> {
+    let mut words = Vec::new();
+    let mut word_start = None;
+    for (i, c) in text.char_indices().chain(std::iter::once((text.len(), ','))) {
+        match (is_separator(c) || i == text.len(), word_start) {
+            (false, None) => word_start = Some(i),
+            (true, Some(s)) => {
+                words.push((&text[s..i], Span::new(start + s, start + i)));
+                word_start = None;
+            }
+            _ => {}
+        }
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MermaidConfig;
+    use crate::diagrams::gantt::GanttParser;
+    use crate::parser::traits::DiagramParser;
+
+    fn validate_code(code: &str) -> Vec<Diagnostic> {
+        let ast = GanttParser::new()
+            .parse(code, &MermaidConfig::default())
+            .expect("parse failed");
+        validate(&ast)
+    }
+
+    #[test]
+    fn test_unique_ids() {
+        let code = "gantt\n    section A\n    One :a1, 2024-01-01, 1d\n    Two :a2, after a1, 1d";
+        assert!(validate_code(code).is_empty());
+    }
+
+    #[test]
+    fn test_unknown_modifier_hint() {
+        let code = "gantt\n    section A\n    One :ready, crit, a1, 2024-01-01, 1d";
+        let diagnostics = validate_code(code);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, crate::diagnostic::Severity::Hint);
+        assert_eq!(diagnostics[0].span.text(code), "ready");
+    }
+
+    #[test]
+    fn test_duplicate_id_warns() {
+        let code = "gantt\n    section A\n    One :a1, 2024-01-01, 1d\n    section B\n    Two :a1, 2024-01-02, 1d";
+        let diagnostics = validate_code(code);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, DiagnosticCode::DuplicateDefinition);
+        assert_eq!(diagnostics[0].span.text(code), "Two :a1, 2024-01-02, 1d");
+        assert_eq!(diagnostics[0].related[0].span.text(code), "One :a1, 2024-01-01, 1d");
+    }
+
+    #[test]
+    fn test_task_timing_forms() {
+        let code = "gantt\n    section A\n    One :a1, 2024-01-01\n    Two :a2, after a1, 5d\n    Three :crit, a3, 2024-01-10, 2d\n    Four :3d\n    Five :a5, 2024-01-01, 2024-01-09\n    Six :until a5";
+        assert!(validate_code(code).is_empty(), "{:?}", validate_code(code));
+
+        let code = "gantt\n    dateFormat DD/MM/YYYY\n    section A\n    One :a1, 01/02/2024, 2d";
+        assert!(validate_code(code).is_empty());
+    }
+
+    #[test]
+    fn test_untimed_task_warns() {
+        let code = "gantt\n    section A\n    One :a1, 2024-01-01, 1d\n    Two :crit, a2";
+        let diagnostics = validate_code(code);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, DiagnosticCode::MissingElement);
+        assert_eq!(diagnostics[0].severity, crate::diagnostic::Severity::Warning);
+        assert_eq!(diagnostics[0].message, "Task 'Two' has no start date, dependency or duration");
+        assert_eq!(diagnostics[0].span.text(code), "Two :crit, a2");
+    }
+
+    #[test]
+    fn test_localized_day_names() {
+        let code = "gantt\n    dateFormat DD-MMMM-YYYY\n    excludes weekends, Montag 25-Dezember-2024\n    weekday lundi";
+        let diagnostics = validate_code(code);
+        let found: Vec<_> = diagnostics
+            .iter()
+            .map(|d| {
+                let fix = d.fixes.first().map(|f| f.new_text.as_str());
+                (d.severity, d.message.as_str(), d.span.text(code), fix)
+            })
+            .collect();
+        use crate::diagnostic::Severity::{Error, Warning};
+        assert_eq!(
+            found,
+            [
+                (
+                    Warning,
+                    "Mermaid only accepts English day names; 'Montag' looks like German for 'monday'",
+                    "Montag",
+                    Some("monday")
+                ),
+                (
+                    Warning,
+                    "Mermaid only accepts English month names; 'Dezember' looks like German for 'December'",
+                    "Dezember",
+                    Some("December")
+                ),
+                (
+                    Error,
+                    "Mermaid only accepts English day names; 'lundi' looks like French for 'monday'",
+                    "lundi",
+                    Some("monday")
+                ),
+            ]
+        );
+        assert!(validate_code("gantt\n    excludes Saturday sunday\n    weekday Friday").is_empty());
+    }
+
+    #[test]
+    fn test_unknown_day_names_get_the_generic_message() {
+        let code = "gantt\n    excludes holidays\n    weekday someday";
+        let diagnostics = validate_code(code);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].message, "Unknown excludes value 'holidays' is ignored");
+        assert_eq!(diagnostics[1].message, "Unknown weekday 'someday'");
+        assert!(diagnostics.iter().all(|d| d.code == DiagnosticCode::InvalidValue && d.fixes.is_empty()));
+    }
+
+    #[test]
+    fn test_name_only_task_warns() {
+        let code = "gantt\n    section A\n    Design: phase two";
+        let diagnostics = validate_code(code);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, DiagnosticCode::InvalidSyntax);
+        assert_eq!(diagnostics[0].span.text(code), "Design: phase two");
+    }
+}
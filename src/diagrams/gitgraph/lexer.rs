@@ -2,6 +2,8 @@
 
 use logos::Logos;
 
+use crate::parser::{lexer, quotes};
+
 /// Tokens for GitGraph lexing.
 #[derive(Logos, Debug, Clone, PartialEq)]
 #[logos(skip r"[ \t]+")]
@@ -77,7 +79,8 @@ pub enum GitGraphToken {
     CloseBrace,
 
     // Quoted strings
-    #[regex(r#""[^"]*""#)]
+    #[token("\"", quotes::lex_quoted)]
+    #[token("“", quotes::lex_quoted)]
     QuotedString,
 
     // Identifiers (branch names, commit IDs, etc.)
@@ -93,30 +96,12 @@ pub enum GitGraphToken {
     Newline,
 }
 
-/// A token with its span information.
-#[derive(Debug, Clone)]
-pub struct Token {
-    pub kind: GitGraphToken,
-    pub text: String,
-    pub span: std::ops::Range<usize>,
-}
+/// A GitGraph diagram token with its span and text.
+pub type Token = lexer::Token<GitGraphToken>;
 
-/// Tokenize GitGraph source.
+/// Tokenizes GitGraph diagram source, skipping text no token matches.
 pub fn tokenize(source: &str) -> Vec<Token> {
-    let mut tokens = Vec::new();
-    let mut lexer = GitGraphToken::lexer(source);
-
-    while let Some(result) = lexer.next() {
-        if let Ok(kind) = result {
-            tokens.push(Token {
-                kind,
-                text: lexer.slice().to_string(),
-                span: lexer.span(),
-            });
-        }
-    }
-
-    tokens
+    lexer::tokenize(source)
 }
 
 #[cfg(test)]
@@ -16,5 +16,6 @@
 
 pub mod lexer;
 pub mod parser;
+pub mod validate;
 
 pub use parser::GitGraphParser;
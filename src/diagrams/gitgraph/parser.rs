@@ -1,21 +1,55 @@
 //! Parser for GitGraph diagrams.
 
 use crate::ast::{Ast, AstNode, NodeKind, Span};
+use crate::config::MermaidConfig;
 use crate::diagnostic::{Diagnostic, DiagnosticCode, Severity};
+use crate::parser::traits::DiagramParser;
+use crate::parser::{accessibility, finish_parse, quotes, StatementLoop};
 
 use super::lexer::{tokenize, GitGraphToken, Token};
 
 /// Parser for GitGraph diagrams.
-pub struct GitGraphParser<'a> {
+pub struct GitGraphParser;
+
+impl GitGraphParser {
+    /// Creates a new GitGraph diagram parser.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for GitGraphParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DiagramParser for GitGraphParser {
+    fn parse(&self, code: &str, _config: &MermaidConfig) -> Result<Ast, Vec<Diagnostic>> {
+        GitGraphParserImpl::new(code).parse()
+    }
+
+    /// Also returns the warnings and hints of a successful parse. There is
+    /// no error recovery, so a failed parse has no AST.
+    fn parse_partial(&self, code: &str, _config: &MermaidConfig) -> (Option<Ast>, Vec<Diagnostic>) {
+        GitGraphParserImpl::new(code).parse_partial()
+    }
+
+    fn name(&self) -> &'static str {
+        "gitgraph"
+    }
+}
+
+struct GitGraphParserImpl<'a> {
     tokens: Vec<Token>,
     pos: usize,
     source: &'a str,
     diagnostics: Vec<Diagnostic>,
 }
 
-impl<'a> GitGraphParser<'a> {
+impl<'a> GitGraphParserImpl<'a> {
     /// Create a new parser.
-    pub fn new(source: &'a str) -> Self {
+    fn new(source: &'a str) -> Self {
         Self {
             tokens: tokenize(source),
             pos: 0,
@@ -24,8 +58,17 @@ impl<'a> GitGraphParser<'a> {
         }
     }
 
+    /// Parses the GitGraph diagram, also returning the warnings and hints that
+    /// [`Self::parse`] drops on success. A failed parse has no AST.
+    fn parse_partial(&mut self) -> (Option<Ast>, Vec<Diagnostic>) {
+        match self.parse() {
+            Ok(ast) => (Some(ast), std::mem::take(&mut self.diagnostics)),
+            Err(diagnostics) => (None, diagnostics),
+        }
+    }
+
     /// Parse the GitGraph diagram.
-    pub fn parse(&mut self) -> Result<Ast, Vec<Diagnostic>> {
+    fn parse(&mut self) -> Result<Ast, Vec<Diagnostic>> {
         let start_span = Span::new(0, self.source.len());
         let mut root = AstNode::new(NodeKind::Root, start_span);
 
@@ -42,11 +85,13 @@ impl<'a> GitGraphParser<'a> {
                 Severity::Error,
                 self.current_span(),
             ));
-            return Err(self.diagnostics.clone());
+            // Move the diagnostics out instead of cloning them
+            return Err(std::mem::take(&mut self.diagnostics));
         }
 
         // Parse statements
         while !self.is_at_end() {
+            let loop_start = self.pos;
             self.skip_newlines();
             if self.is_at_end() {
                 break;
@@ -57,13 +102,10 @@ impl<'a> GitGraphParser<'a> {
             } else {
                 self.advance();
             }
+            self.ensure_progress(loop_start);
         }
 
-        if self.diagnostics.iter().any(|d| d.severity == Severity::Error) {
-            Err(self.diagnostics.clone())
-        } else {
-            Ok(Ast::new(root, self.source.to_string()))
-        }
+        finish_parse(root, self.source, &mut self.diagnostics)
     }
 
     /// Parse the gitGraph declaration.
@@ -154,9 +196,9 @@ impl<'a> GitGraphParser<'a> {
                     self.advance();
                 }
                 if self.check(&GitGraphToken::QuotedString) {
-                    let id = self.current_text();
-                    node.add_property("id", id[1..id.len() - 1].to_string());
-                    self.advance();
+                    if let Some(id) = self.quoted_text() {
+                        node.add_property("id", id);
+                    }
                 } else if self.check(&GitGraphToken::Identifier) {
                     node.add_property("id", self.current_text());
                     self.advance();
@@ -167,9 +209,9 @@ impl<'a> GitGraphParser<'a> {
                     self.advance();
                 }
                 if self.check(&GitGraphToken::QuotedString) {
-                    let msg = self.current_text();
-                    node.add_property("message", msg[1..msg.len() - 1].to_string());
-                    self.advance();
+                    if let Some(msg) = self.quoted_text() {
+                        node.add_property("message", msg);
+                    }
                 }
             } else if self.check(&GitGraphToken::Tag) {
                 self.advance();
@@ -177,9 +219,9 @@ impl<'a> GitGraphParser<'a> {
                     self.advance();
                 }
                 if self.check(&GitGraphToken::QuotedString) {
-                    let tag = self.current_text();
-                    node.add_property("tag", tag[1..tag.len() - 1].to_string());
-                    self.advance();
+                    if let Some(tag) = self.quoted_text() {
+                        node.add_property("tag", tag);
+                    }
                 }
             } else if self.check(&GitGraphToken::Type) {
                 self.advance();
@@ -272,9 +314,9 @@ impl<'a> GitGraphParser<'a> {
                     self.advance();
                 }
                 if self.check(&GitGraphToken::QuotedString) {
-                    let id = self.current_text();
-                    node.add_property("id", id[1..id.len() - 1].to_string());
-                    self.advance();
+                    if let Some(id) = self.quoted_text() {
+                        node.add_property("id", id);
+                    }
                 }
             } else if self.check(&GitGraphToken::Tag) {
                 self.advance();
@@ -282,9 +324,9 @@ impl<'a> GitGraphParser<'a> {
                     self.advance();
                 }
                 if self.check(&GitGraphToken::QuotedString) {
-                    let tag = self.current_text();
-                    node.add_property("tag", tag[1..tag.len() - 1].to_string());
-                    self.advance();
+                    if let Some(tag) = self.quoted_text() {
+                        node.add_property("tag", tag);
+                    }
                 }
             } else if self.check(&GitGraphToken::Type) {
                 self.advance();
@@ -320,9 +362,9 @@ impl<'a> GitGraphParser<'a> {
                 self.advance();
             }
             if self.check(&GitGraphToken::QuotedString) {
-                let id = self.current_text();
-                node.add_property("id", id[1..id.len() - 1].to_string());
-                self.advance();
+                if let Some(id) = self.quoted_text() {
+                    node.add_property("id", id);
+                }
             }
         }
 
@@ -334,69 +376,33 @@ impl<'a> GitGraphParser<'a> {
     /// Parse accessibility statement.
     fn parse_accessibility(&mut self) -> Option<AstNode> {
         let start = self.current_span().start;
-        let acc_type = if self.check(&GitGraphToken::AccTitle) {
-            "accTitle"
-        } else {
-            "accDescr"
-        };
-        self.advance();
-
-        if self.check(&GitGraphToken::Colon) {
+        let (node, end) = accessibility::parse_statement(self.source, start, &mut self.diagnostics)?;
+        while !self.is_at_end() && self.current_span().start < end {
             self.advance();
         }
-
-        if self.check(&GitGraphToken::OpenBrace) {
-            self.advance();
-            let mut content = String::new();
-            while !self.check(&GitGraphToken::CloseBrace) && !self.is_at_end() {
-                content.push_str(&self.current_text());
-                content.push(' ');
-                self.advance();
-            }
-            if self.check(&GitGraphToken::CloseBrace) {
-                self.advance();
-            }
-            let end = self.previous_span().end;
-
-            let mut node = AstNode::new(NodeKind::Statement, Span::new(start, end));
-            node.add_property("type", acc_type);
-            node.add_property("value", content.trim().to_string());
-            return Some(node);
-        }
-
-        let value = self.consume_until_newline();
-        let end = self.previous_span().end;
-
-        let mut node = AstNode::new(NodeKind::Statement, Span::new(start, end));
-        node.add_property("type", acc_type);
-        node.add_property("value", value.trim().to_string());
         Some(node)
     }
 
-    fn consume_until_newline(&mut self) -> String {
-        let mut text = String::new();
-        while !self.check(&GitGraphToken::Newline) && !self.is_at_end() {
-            if !text.is_empty() {
-                text.push(' ');
-            }
-            text.push_str(&self.current_text());
-            self.advance();
-        }
-        text
-    }
-
     // Helper methods
     fn current(&self) -> Option<&Token> {
         self.tokens.get(self.pos)
     }
 
+    /// Consumes a quoted string token, returning the text between the
+    /// quotes; see [`quotes::unquote`].
+    fn quoted_text(&mut self) -> Option<String> {
+        let (text, span) = (self.current_text(), self.current_span());
+        self.advance();
+        quotes::unquote(&text, span, &mut self.diagnostics)
+    }
+
     fn current_text(&self) -> String {
         self.current().map(|t| t.text.clone()).unwrap_or_default()
     }
 
     fn current_span(&self) -> Span {
         self.current()
-            .map(|t| Span::new(t.span.start, t.span.end))
+            .map(|t| t.span)
             .unwrap_or(Span::new(self.source.len(), self.source.len()))
     }
 
@@ -404,7 +410,7 @@ impl<'a> GitGraphParser<'a> {
         if self.pos > 0 {
             self.tokens
                 .get(self.pos - 1)
-                .map(|t| Span::new(t.span.start, t.span.end))
+                .map(|t| t.span)
                 .unwrap_or(Span::new(0, 0))
         } else {
             Span::new(0, 0)
@@ -432,6 +438,28 @@ impl<'a> GitGraphParser<'a> {
     }
 }
 
+impl StatementLoop for GitGraphParserImpl<'_> {
+    fn position(&self) -> usize {
+        self.pos
+    }
+
+    fn at_end(&self) -> bool {
+        self.is_at_end()
+    }
+
+    fn token_span(&self) -> Span {
+        self.current_span()
+    }
+
+    fn skip_token(&mut self) {
+        self.advance();
+    }
+
+    fn report(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -445,7 +473,7 @@ mod tests {
     checkout develop
     commit"#;
 
-        let mut parser = GitGraphParser::new(code);
+        let mut parser = GitGraphParserImpl::new(code);
         let result = parser.parse();
         assert!(result.is_ok(), "Failed: {:?}", result.err());
     }
@@ -460,7 +488,7 @@ mod tests {
     checkout main
     merge feature"#;
 
-        let mut parser = GitGraphParser::new(code);
+        let mut parser = GitGraphParserImpl::new(code);
         let result = parser.parse();
         assert!(result.is_ok(), "Failed: {:?}", result.err());
     }
@@ -471,7 +499,7 @@ mod tests {
     commit id: "1" msg: "Initial commit" tag: "v1.0"
     commit id: "2" type: HIGHLIGHT"#;
 
-        let mut parser = GitGraphParser::new(code);
+        let mut parser = GitGraphParserImpl::new(code);
         let result = parser.parse();
         assert!(result.is_ok(), "Failed: {:?}", result.err());
     }
@@ -479,8 +507,19 @@ mod tests {
     #[test]
     fn test_parse_invalid() {
         let code = "not a git graph";
-        let mut parser = GitGraphParser::new(code);
+        let mut parser = GitGraphParserImpl::new(code);
         let result = parser.parse();
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_curly_and_unterminated_quotes() {
+        let code = "gitGraph\n    commit id: “a1” tag: \"v1\"\n    commit id: \"b2";
+        let result = crate::parse(code, None);
+        assert!(!result.ok);
+        assert_eq!(result.diagnostics.len(), 2);
+        assert_eq!(result.diagnostics[0].span.text(code), "“a1”");
+        assert_eq!(result.diagnostics[1].code, DiagnosticCode::UnterminatedString);
+        assert_eq!(result.diagnostics[1].span.text(code), "\"b2");
+    }
 }
@@ -0,0 +1,149 @@
+//! Semantic validation for GitGraph diagrams.
+//!
+//! Replays the statements in order to track branches and commit ids, so
+//! operations that refer to history which doesn't exist yet can be reported.
+
+use std::collections::HashSet;
+
+use crate::ast::{Ast, NodeKind};
+use crate::diagnostic::{Diagnostic, DiagnosticCode};
+
+/// The branch every git graph starts on.
+const DEFAULT_BRANCH: &str = "main";
+
+/// Validates a parsed GitGraph AST.
+///
+/// - `checkout`, `merge` and `cherry-pick` before the implicit `main` branch
+///   has any commit produce a warning.
+/// - `cherry-pick` of a commit id that was never committed is an error.
+pub fn validate(ast: &Ast) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut commit_ids: HashSet<&str> = HashSet::new();
+    let mut main_has_commit = false;
+    let mut current_branch = DEFAULT_BRANCH;
+
+    for node in &ast.root.children {
+        let Some(stmt_type) = node.statement_type() else {
+            continue;
+        };
+
+        match (&node.kind, stmt_type) {
+            (NodeKind::Node, "commit") => {
+                if current_branch == DEFAULT_BRANCH {
+                    main_has_commit = true;
+                }
+                if let Some(id) = node.id() {
+                    commit_ids.insert(id);
+                }
+            }
+            (NodeKind::Statement, "branch") => {
+                if let Some(name) = node.get_property("name") {
+                    current_branch = name;
+                }
+            }
+            (NodeKind::Statement, op @ ("checkout" | "merge" | "cherry-pick")) => {
+                if !main_has_commit {
+                    diagnostics.push(Diagnostic::warning(
+                        DiagnosticCode::SemanticError,
+                        format!(
+                            "'{}' before the '{}' branch has any commit",
+                            op, DEFAULT_BRANCH
+                        ),
+                        node.span,
+                    ));
+                }
+
+                match op {
+                    "checkout" => {
+                        if let Some(branch) = node.get_property("branch") {
+                            current_branch = branch;
+                        }
+                    }
+                    "merge" => {
+                        if current_branch == DEFAULT_BRANCH {
+                            main_has_commit = true;
+                        }
+                        if let Some(id) = node.id() {
+                            commit_ids.insert(id);
+                        }
+                    }
+                    _ => {
+                        if let Some(id) = node.id() {
+                            if !commit_ids.contains(id) {
+                                diagnostics.push(Diagnostic::error(
+                                    DiagnosticCode::UndefinedReference,
+                                    format!("cherry-pick references unknown commit id '{}'", id),
+                                    node.span,
+                                ));
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostic::Severity;
+    use crate::config::MermaidConfig;
+    use crate::diagrams::gitgraph::GitGraphParser;
+    use crate::parser::traits::DiagramParser;
+
+    fn validate_code(code: &str) -> Vec<Diagnostic> {
+        let ast = GitGraphParser::new()
+            .parse(code, &MermaidConfig::default())
+            .expect("parse failed");
+        validate(&ast)
+    }
+
+    #[test]
+    fn test_valid_history() {
+        let code = r#"gitGraph
+    commit id: "a"
+    branch develop
+    commit
+    checkout main
+    merge develop
+    cherry-pick id: "a""#;
+        assert!(validate_code(code).is_empty());
+    }
+
+    #[test]
+    fn test_cherry_pick_unknown_id() {
+        let code = r#"gitGraph
+    commit id: "a"
+    cherry-pick id: "x""#;
+        let diagnostics = validate_code(code);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, DiagnosticCode::UndefinedReference);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_checkout_before_first_commit_warns() {
+        let code = r#"gitGraph
+    branch develop
+    checkout main
+    commit"#;
+        let diagnostics = validate_code(code);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_commit_on_other_branch_does_not_count_for_main() {
+        let code = r#"gitGraph
+    branch develop
+    commit
+    merge main"#;
+        let diagnostics = validate_code(code);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+    }
+}
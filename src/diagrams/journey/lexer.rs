@@ -2,6 +2,8 @@
 
 use logos::Logos;
 
+use crate::parser::lexer;
+
 /// Tokens for Journey diagram lexing.
 #[derive(Logos, Debug, Clone, PartialEq)]
 #[logos(skip r"[ \t]+")]
@@ -52,30 +54,12 @@ pub enum JourneyToken {
     Newline,
 }
 
-/// A token with its span information.
-#[derive(Debug, Clone)]
-pub struct Token {
-    pub kind: JourneyToken,
-    pub text: String,
-    pub span: std::ops::Range<usize>,
-}
+/// A User Journey diagram token with its span and text.
+pub type Token = lexer::Token<JourneyToken>;
 
-/// Tokenize Journey diagram source.
+/// Tokenizes User Journey diagram source, skipping text no token matches.
 pub fn tokenize(source: &str) -> Vec<Token> {
-    let mut tokens = Vec::new();
-    let mut lexer = JourneyToken::lexer(source);
-
-    while let Some(result) = lexer.next() {
-        if let Ok(kind) = result {
-            tokens.push(Token {
-                kind,
-                text: lexer.slice().to_string(),
-                span: lexer.span(),
-            });
-        }
-    }
-
-    tokens
+    lexer::tokenize(source)
 }
 
 #[cfg(test)]
@@ -21,6 +21,8 @@ pub use parser::JourneyParser;
 #[cfg(test)]
 mod tests {
     use super::parser::JourneyParser;
+    use crate::config::MermaidConfig;
+    use crate::parser::traits::DiagramParser;
 
     #[test]
     fn test_parse_simple_journey() {
@@ -29,8 +31,7 @@ mod tests {
     section Section 1
     Task 1: 5: Actor"#;
 
-        let mut parser = JourneyParser::new(code);
-        let result = parser.parse();
+        let result = JourneyParser::new().parse(code, &MermaidConfig::default());
         assert!(result.is_ok(), "Failed: {:?}", result.err());
     }
 }
@@ -1,21 +1,55 @@
 //! Parser for User Journey diagrams.
 
 use crate::ast::{Ast, AstNode, NodeKind, Span};
+use crate::config::MermaidConfig;
 use crate::diagnostic::{Diagnostic, DiagnosticCode, Severity};
+use crate::parser::traits::DiagramParser;
+use crate::parser::{accessibility, finish_parse, title, StatementLoop};
 
 use super::lexer::{tokenize, JourneyToken, Token};
 
 /// Parser for User Journey diagrams.
-pub struct JourneyParser<'a> {
+pub struct JourneyParser;
+
+impl JourneyParser {
+    /// Creates a new User Journey diagram parser.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for JourneyParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DiagramParser for JourneyParser {
+    fn parse(&self, code: &str, _config: &MermaidConfig) -> Result<Ast, Vec<Diagnostic>> {
+        JourneyParserImpl::new(code).parse()
+    }
+
+    /// Also returns the warnings and hints of a successful parse. There is
+    /// no error recovery, so a failed parse has no AST.
+    fn parse_partial(&self, code: &str, _config: &MermaidConfig) -> (Option<Ast>, Vec<Diagnostic>) {
+        JourneyParserImpl::new(code).parse_partial()
+    }
+
+    fn name(&self) -> &'static str {
+        "journey"
+    }
+}
+
+struct JourneyParserImpl<'a> {
     tokens: Vec<Token>,
     pos: usize,
     source: &'a str,
     diagnostics: Vec<Diagnostic>,
 }
 
-impl<'a> JourneyParser<'a> {
+impl<'a> JourneyParserImpl<'a> {
     /// Create a new parser.
-    pub fn new(source: &'a str) -> Self {
+    fn new(source: &'a str) -> Self {
         Self {
             tokens: tokenize(source),
             pos: 0,
@@ -24,8 +58,17 @@ impl<'a> JourneyParser<'a> {
         }
     }
 
+    /// Parses the Journey diagram, also returning the warnings and hints that
+    /// [`Self::parse`] drops on success. A failed parse has no AST.
+    fn parse_partial(&mut self) -> (Option<Ast>, Vec<Diagnostic>) {
+        match self.parse() {
+            Ok(ast) => (Some(ast), std::mem::take(&mut self.diagnostics)),
+            Err(diagnostics) => (None, diagnostics),
+        }
+    }
+
     /// Parse the Journey diagram.
-    pub fn parse(&mut self) -> Result<Ast, Vec<Diagnostic>> {
+    fn parse(&mut self) -> Result<Ast, Vec<Diagnostic>> {
         let start_span = Span::new(0, self.source.len());
         let mut root = AstNode::new(NodeKind::Root, start_span);
 
@@ -42,11 +85,13 @@ impl<'a> JourneyParser<'a> {
                 Severity::Error,
                 self.current_span(),
             ));
-            return Err(self.diagnostics.clone());
+            // Move the diagnostics out instead of cloning them
+            return Err(std::mem::take(&mut self.diagnostics));
         }
 
         // Parse statements
         while !self.is_at_end() {
+            let loop_start = self.pos;
             self.skip_newlines();
             if self.is_at_end() {
                 break;
@@ -58,13 +103,10 @@ impl<'a> JourneyParser<'a> {
                 // Skip unknown token
                 self.advance();
             }
+            self.ensure_progress(loop_start);
         }
 
-        if self.diagnostics.iter().any(|d| d.severity == Severity::Error) {
-            Err(self.diagnostics.clone())
-        } else {
-            Ok(Ast::new(root, self.source.to_string()))
-        }
+        finish_parse(root, self.source, &mut self.diagnostics)
     }
 
     /// Parse the journey declaration.
@@ -112,15 +154,11 @@ impl<'a> JourneyParser<'a> {
 
     /// Parse title statement.
     fn parse_title(&mut self) -> Option<AstNode> {
-        let start = self.current_span().start;
-        self.advance(); // consume 'title'
-
-        let title = self.consume_until_newline();
-        let end = self.previous_span().end;
-
-        let mut node = AstNode::new(NodeKind::Statement, Span::new(start, end));
-        node.add_property("type", "title");
-        node.add_property("value", title.trim().to_string());
+        let keyword_span = self.current_span();
+        let (node, end) = title::parse_statement(self.source, keyword_span, &mut self.diagnostics);
+        while !self.is_at_end() && self.current_span().start < end {
+            self.advance();
+        }
         Some(node)
     }
 
@@ -141,45 +179,10 @@ impl<'a> JourneyParser<'a> {
     /// Parse accessibility statement.
     fn parse_accessibility(&mut self) -> Option<AstNode> {
         let start = self.current_span().start;
-        let acc_type = if self.check(&JourneyToken::AccTitle) {
-            "accTitle"
-        } else {
-            "accDescr"
-        };
-        self.advance();
-
-        // Skip colon if present
-        if self.check(&JourneyToken::Colon) {
-            self.advance();
-        }
-
-        // Check for multi-line description
-        if self.check(&JourneyToken::OpenBrace) {
+        let (node, end) = accessibility::parse_statement(self.source, start, &mut self.diagnostics)?;
+        while !self.is_at_end() && self.current_span().start < end {
             self.advance();
-            let mut content = String::new();
-            while !self.check(&JourneyToken::CloseBrace) && !self.is_at_end() {
-                content.push_str(&self.current_text());
-                content.push(' ');
-                self.advance();
-            }
-            if self.check(&JourneyToken::CloseBrace) {
-                self.advance();
-            }
-            let end = self.previous_span().end;
-
-            let mut node = AstNode::new(NodeKind::Statement, Span::new(start, end));
-            node.add_property("type", acc_type);
-            node.add_property("value", content.trim().to_string());
-            return Some(node);
         }
-
-        // Single line
-        let value = self.consume_until_newline();
-        let end = self.previous_span().end;
-
-        let mut node = AstNode::new(NodeKind::Statement, Span::new(start, end));
-        node.add_property("type", acc_type);
-        node.add_property("value", value.trim().to_string());
         Some(node)
     }
 
@@ -225,12 +228,8 @@ impl<'a> JourneyParser<'a> {
                 while !self.check(&JourneyToken::Newline) && !self.is_at_end() {
                     if self.check(&JourneyToken::Identifier) {
                         actors.push(self.current_text());
-                        self.advance();
-                    } else if self.check(&JourneyToken::Comma) {
-                        self.advance();
-                    } else {
-                        self.advance();
                     }
+                    self.advance();
                 }
 
                 if !actors.is_empty() {
@@ -269,7 +268,7 @@ impl<'a> JourneyParser<'a> {
 
     fn current_span(&self) -> Span {
         self.current()
-            .map(|t| Span::new(t.span.start, t.span.end))
+            .map(|t| t.span)
             .unwrap_or(Span::new(self.source.len(), self.source.len()))
     }
 
@@ -277,7 +276,7 @@ impl<'a> JourneyParser<'a> {
         if self.pos > 0 {
             self.tokens
                 .get(self.pos - 1)
-                .map(|t| Span::new(t.span.start, t.span.end))
+                .map(|t| t.span)
                 .unwrap_or(Span::new(0, 0))
         } else {
             Span::new(0, 0)
@@ -305,6 +304,28 @@ impl<'a> JourneyParser<'a> {
     }
 }
 
+impl StatementLoop for JourneyParserImpl<'_> {
+    fn position(&self) -> usize {
+        self.pos
+    }
+
+    fn at_end(&self) -> bool {
+        self.is_at_end()
+    }
+
+    fn token_span(&self) -> Span {
+        self.current_span()
+    }
+
+    fn skip_token(&mut self) {
+        self.advance();
+    }
+
+    fn report(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -317,7 +338,7 @@ mod tests {
     Make tea: 5: Me
     Go upstairs: 3: Me"#;
 
-        let mut parser = JourneyParser::new(code);
+        let mut parser = JourneyParserImpl::new(code);
         let result = parser.parse();
         assert!(result.is_ok(), "Failed: {:?}", result.err());
     }
@@ -330,7 +351,7 @@ mod tests {
     Wake up: 5: Me
     Do work: 3: Me, Cat"#;
 
-        let mut parser = JourneyParser::new(code);
+        let mut parser = JourneyParserImpl::new(code);
         let result = parser.parse();
         assert!(result.is_ok(), "Failed: {:?}", result.err());
     }
@@ -346,7 +367,7 @@ mod tests {
     section Purchase
     Buy product: 5: Customer"#;
 
-        let mut parser = JourneyParser::new(code);
+        let mut parser = JourneyParserImpl::new(code);
         let result = parser.parse();
         assert!(result.is_ok(), "Failed: {:?}", result.err());
     }
@@ -356,15 +377,25 @@ mod tests {
         let code = r#"journey
     title My User Journey"#;
 
-        let mut parser = JourneyParser::new(code);
+        let mut parser = JourneyParserImpl::new(code);
         let result = parser.parse();
         assert!(result.is_ok(), "Failed: {:?}", result.err());
     }
 
+    #[test]
+    fn test_blank_title() {
+        let code = "journey\n    title   \n    section A\n    Task: 5: Me";
+        let result = crate::parse(code, None);
+        assert!(result.ok);
+        assert_eq!(result.diagnostics.len(), 1);
+        assert_eq!(result.diagnostics[0].message, "'title' has no text; Mermaid renders an empty title");
+        assert_eq!(result.diagnostics[0].span.text(code), "title");
+    }
+
     #[test]
     fn test_parse_invalid() {
         let code = "not a journey diagram";
-        let mut parser = JourneyParser::new(code);
+        let mut parser = JourneyParserImpl::new(code);
         let result = parser.parse();
         assert!(result.is_err());
     }
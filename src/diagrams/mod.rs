@@ -2,6 +2,7 @@
 //!
 //! Each diagram type has its own submodule with lexer, parser, and AST definitions.
 
+pub mod c4;
 pub mod class;
 pub mod er;
 pub mod flowchart;
@@ -2,6 +2,8 @@
 
 use logos::Logos;
 
+use crate::parser::{lexer, quotes};
+
 /// Tokens for Pie chart lexing.
 #[derive(Logos, Debug, Clone, PartialEq)]
 #[logos(skip r"[ \t]+")]
@@ -37,7 +39,8 @@ pub enum PieToken {
     Number,
 
     // Quoted strings (for slice labels)
-    #[regex(r#""[^"]*""#)]
+    #[token("\"", quotes::lex_quoted)]
+    #[token("“", quotes::lex_quoted)]
     QuotedString,
 
     // Identifiers
@@ -49,30 +52,12 @@ pub enum PieToken {
     Newline,
 }
 
-/// A token with its span information.
-#[derive(Debug, Clone)]
-pub struct Token {
-    pub kind: PieToken,
-    pub text: String,
-    pub span: std::ops::Range<usize>,
-}
+/// A Pie chart token with its span and text.
+pub type Token = lexer::Token<PieToken>;
 
-/// Tokenize Pie chart source.
+/// Tokenizes Pie chart source, skipping text no token matches.
 pub fn tokenize(source: &str) -> Vec<Token> {
-    let mut tokens = Vec::new();
-    let mut lexer = PieToken::lexer(source);
-
-    while let Some(result) = lexer.next() {
-        if let Ok(kind) = result {
-            tokens.push(Token {
-                kind,
-                text: lexer.slice().to_string(),
-                span: lexer.span(),
-            });
-        }
-    }
-
-    tokens
+    lexer::tokenize(source)
 }
 
 #[cfg(test)]
@@ -1,31 +1,80 @@
 //! Parser for Pie charts.
 
 use crate::ast::{Ast, AstNode, NodeKind, Span};
-use crate::diagnostic::{Diagnostic, DiagnosticCode, Severity};
+use crate::config::MermaidConfig;
+use crate::diagnostic::{Diagnostic, DiagnosticCode, RelatedDiagnostic, Severity};
+use crate::parser::traits::DiagramParser;
+use crate::parser::{accessibility, finish_parse, quotes, title, StatementLoop};
 
 use super::lexer::{tokenize, PieToken, Token};
 
 /// Parser for Pie charts.
-pub struct PieParser<'a> {
+pub struct PieParser;
+
+impl PieParser {
+    /// Creates a new Pie chart parser.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for PieParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DiagramParser for PieParser {
+    fn parse(&self, code: &str, _config: &MermaidConfig) -> Result<Ast, Vec<Diagnostic>> {
+        PieParserImpl::new(code).parse()
+    }
+
+    /// Also returns the warnings and hints of a successful parse. There is
+    /// no error recovery, so a failed parse has no AST.
+    fn parse_partial(&self, code: &str, _config: &MermaidConfig) -> (Option<Ast>, Vec<Diagnostic>) {
+        PieParserImpl::new(code).parse_partial()
+    }
+
+    fn name(&self) -> &'static str {
+        "pie"
+    }
+}
+
+struct PieParserImpl<'a> {
     tokens: Vec<Token>,
     pos: usize,
     source: &'a str,
     diagnostics: Vec<Diagnostic>,
+    /// The span of the first `title` statement.
+    title_span: Option<Span>,
+    /// Whether a slice has been parsed.
+    seen_slice: bool,
 }
 
-impl<'a> PieParser<'a> {
+impl<'a> PieParserImpl<'a> {
     /// Create a new parser.
-    pub fn new(source: &'a str) -> Self {
+    fn new(source: &'a str) -> Self {
         Self {
             tokens: tokenize(source),
             pos: 0,
             source,
             diagnostics: Vec::new(),
+            title_span: None,
+            seen_slice: false,
+        }
+    }
+
+    /// Parses the Pie chart, also returning the warnings and hints that
+    /// [`Self::parse`] drops on success. A failed parse has no AST.
+    fn parse_partial(&mut self) -> (Option<Ast>, Vec<Diagnostic>) {
+        match self.parse() {
+            Ok(ast) => (Some(ast), std::mem::take(&mut self.diagnostics)),
+            Err(diagnostics) => (None, diagnostics),
         }
     }
 
     /// Parse the Pie chart.
-    pub fn parse(&mut self) -> Result<Ast, Vec<Diagnostic>> {
+    fn parse(&mut self) -> Result<Ast, Vec<Diagnostic>> {
         let start_span = Span::new(0, self.source.len());
         let mut root = AstNode::new(NodeKind::Root, start_span);
 
@@ -42,11 +91,13 @@ impl<'a> PieParser<'a> {
                 Severity::Error,
                 self.current_span(),
             ));
-            return Err(self.diagnostics.clone());
+            // Move the diagnostics out instead of cloning them
+            return Err(std::mem::take(&mut self.diagnostics));
         }
 
         // Parse statements
         while !self.is_at_end() {
+            let loop_start = self.pos;
             self.skip_newlines();
             if self.is_at_end() {
                 break;
@@ -58,13 +109,10 @@ impl<'a> PieParser<'a> {
                 // Skip unknown token
                 self.advance();
             }
+            self.ensure_progress(loop_start);
         }
 
-        if self.diagnostics.iter().any(|d| d.severity == Severity::Error) {
-            Err(self.diagnostics.clone())
-        } else {
-            Ok(Ast::new(root, self.source.to_string()))
-        }
+        finish_parse(root, self.source, &mut self.diagnostics)
     }
 
     /// Parse the pie declaration.
@@ -92,6 +140,9 @@ impl<'a> PieParser<'a> {
     }
 
     /// Parse a statement.
+    ///
+    /// A single `title` may come before the slices; `showData` is only valid
+    /// on the `pie` line.
     fn parse_statement(&mut self) -> Option<AstNode> {
         self.skip_newlines();
 
@@ -101,7 +152,34 @@ impl<'a> PieParser<'a> {
 
         // Check for title
         if self.check(&PieToken::Title) {
-            return self.parse_title();
+            let node = self.parse_title()?;
+            if let Some(first) = self.title_span {
+                self.diagnostics.push(
+                    Diagnostic::error(DiagnosticCode::UnexpectedToken, "Unexpected second 'title'", node.span)
+                        .with_related(RelatedDiagnostic::new("title set here", first)),
+                );
+            } else if self.seen_slice {
+                self.diagnostics.push(Diagnostic::error(
+                    DiagnosticCode::UnexpectedToken,
+                    "'title' must come before the slices",
+                    node.span,
+                ));
+            }
+            self.title_span.get_or_insert(node.span);
+            return Some(node);
+        }
+
+        // The statement loop skips the token
+        if self.check(&PieToken::ShowData) {
+            self.diagnostics.push(
+                Diagnostic::error(
+                    DiagnosticCode::UnexpectedToken,
+                    "Unexpected 'showData'",
+                    self.current_span(),
+                )
+                .with_note("'showData' goes on the header line: 'pie showData'"),
+            );
+            return None;
         }
 
         // Check for accessibility
@@ -110,65 +188,28 @@ impl<'a> PieParser<'a> {
         }
 
         // Otherwise, try to parse a slice
-        self.parse_slice()
+        let slice = self.parse_slice()?;
+        self.seen_slice = true;
+        Some(slice)
     }
 
     /// Parse title statement.
     fn parse_title(&mut self) -> Option<AstNode> {
-        let start = self.current_span().start;
-        self.advance(); // consume 'title'
-
-        let title = self.consume_until_newline();
-        let end = self.previous_span().end;
-
-        let mut node = AstNode::new(NodeKind::Statement, Span::new(start, end));
-        node.add_property("type", "title");
-        node.add_property("value", title.trim().to_string());
+        let keyword_span = self.current_span();
+        let (node, end) = title::parse_statement(self.source, keyword_span, &mut self.diagnostics);
+        while !self.is_at_end() && self.current_span().start < end {
+            self.advance();
+        }
         Some(node)
     }
 
     /// Parse accessibility statement.
     fn parse_accessibility(&mut self) -> Option<AstNode> {
         let start = self.current_span().start;
-        let acc_type = if self.check(&PieToken::AccTitle) {
-            "accTitle"
-        } else {
-            "accDescr"
-        };
-        self.advance();
-
-        // Skip colon if present
-        if self.check(&PieToken::Colon) {
+        let (node, end) = accessibility::parse_statement(self.source, start, &mut self.diagnostics)?;
+        while !self.is_at_end() && self.current_span().start < end {
             self.advance();
         }
-
-        // Check for multi-line description
-        if self.check(&PieToken::OpenBrace) {
-            self.advance();
-            let mut content = String::new();
-            while !self.check(&PieToken::CloseBrace) && !self.is_at_end() {
-                content.push_str(&self.current_text());
-                content.push(' ');
-                self.advance();
-            }
-            if self.check(&PieToken::CloseBrace) {
-                self.advance();
-            }
-            let end = self.previous_span().end;
-
-            let mut node = AstNode::new(NodeKind::Statement, Span::new(start, end));
-            node.add_property("type", acc_type);
-            node.add_property("value", content.trim().to_string());
-            return Some(node);
-        }
-
-        // Single line
-        let value = self.consume_until_newline();
-        let end = self.previous_span().end;
-
-        let mut node = AstNode::new(NodeKind::Statement, Span::new(start, end));
-        node.add_property("type", acc_type);
-        node.add_property("value", value.trim().to_string());
         Some(node)
     }
 
@@ -179,10 +220,7 @@ impl<'a> PieParser<'a> {
 
         // Get slice label
         let label = if self.check(&PieToken::QuotedString) {
-            let text = self.current_text();
-            self.advance();
-            // Remove quotes
-            text[1..text.len() - 1].to_string()
+            self.quoted_text()?
         } else if self.check(&PieToken::Identifier) {
             let text = self.current_text();
             self.advance();
@@ -215,32 +253,27 @@ impl<'a> PieParser<'a> {
         Some(node)
     }
 
-    /// Consume tokens until newline.
-    fn consume_until_newline(&mut self) -> String {
-        let mut text = String::new();
-        while !self.check(&PieToken::Newline) && !self.is_at_end() {
-            if !text.is_empty() {
-                text.push(' ');
-            }
-            text.push_str(&self.current_text());
-            self.advance();
-        }
-        text
-    }
-
     // Helper methods
 
     fn current(&self) -> Option<&Token> {
         self.tokens.get(self.pos)
     }
 
+    /// Consumes a quoted string token, returning the text between the
+    /// quotes; see [`quotes::unquote`].
+    fn quoted_text(&mut self) -> Option<String> {
+        let (text, span) = (self.current_text(), self.current_span());
+        self.advance();
+        quotes::unquote(&text, span, &mut self.diagnostics)
+    }
+
     fn current_text(&self) -> String {
         self.current().map(|t| t.text.clone()).unwrap_or_default()
     }
 
     fn current_span(&self) -> Span {
         self.current()
-            .map(|t| Span::new(t.span.start, t.span.end))
+            .map(|t| t.span)
             .unwrap_or(Span::new(self.source.len(), self.source.len()))
     }
 
@@ -248,7 +281,7 @@ impl<'a> PieParser<'a> {
         if self.pos > 0 {
             self.tokens
                 .get(self.pos - 1)
-                .map(|t| Span::new(t.span.start, t.span.end))
+                .map(|t| t.span)
                 .unwrap_or(Span::new(0, 0))
         } else {
             Span::new(0, 0)
@@ -276,6 +309,28 @@ impl<'a> PieParser<'a> {
     }
 }
 
+impl StatementLoop for PieParserImpl<'_> {
+    fn position(&self) -> usize {
+        self.pos
+    }
+
+    fn at_end(&self) -> bool {
+        self.is_at_end()
+    }
+
+    fn token_span(&self) -> Span {
+        self.current_span()
+    }
+
+    fn skip_token(&mut self) {
+        self.advance();
+    }
+
+    fn report(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -287,7 +342,7 @@ mod tests {
     "Calcium" : 42.96
     "Potassium" : 50.05"#;
 
-        let mut parser = PieParser::new(code);
+        let mut parser = PieParserImpl::new(code);
         let result = parser.parse();
         assert!(result.is_ok(), "Failed: {:?}", result.err());
     }
@@ -299,7 +354,7 @@ mod tests {
     "A" : 30
     "B" : 70"#;
 
-        let mut parser = PieParser::new(code);
+        let mut parser = PieParserImpl::new(code);
         let result = parser.parse();
         assert!(result.is_ok(), "Failed: {:?}", result.err());
     }
@@ -311,16 +366,58 @@ mod tests {
     "Cats" : 85
     "Rats" : 15"#;
 
-        let mut parser = PieParser::new(code);
+        let mut parser = PieParserImpl::new(code);
         let result = parser.parse();
         assert!(result.is_ok(), "Failed: {:?}", result.err());
     }
 
+    #[test]
+    fn test_duplicate_title() {
+        let code = "pie\n    title Pets\n    title Animals\n    \"Dogs\" : 386";
+        let diagnostics = PieParserImpl::new(code).parse().unwrap_err();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, DiagnosticCode::UnexpectedToken);
+        assert_eq!(diagnostics[0].span.text(code), "title Animals");
+        assert_eq!(diagnostics[0].related[0].span.text(code), "title Pets");
+    }
+
+    #[test]
+    fn test_empty_title() {
+        let code = "pie title\n    \"Dogs\" : 386";
+        let result = crate::parse(code, None);
+        assert!(result.ok);
+        assert_eq!(result.diagnostics.len(), 1);
+        assert_eq!(result.diagnostics[0].code, DiagnosticCode::MissingElement);
+        assert_eq!(result.diagnostics[0].span.text(code), "title");
+    }
+
+    #[test]
+    fn test_misplaced_title_and_show_data() {
+        let code = "pie title Pets\n    \"Dogs\" : 386\n    showData";
+        let diagnostics = PieParserImpl::new(code).parse().unwrap_err();
+        assert_eq!(diagnostics[0].message, "Unexpected 'showData'");
+
+        let code = "pie\n    \"Dogs\" : 386\n    title Pets";
+        let diagnostics = PieParserImpl::new(code).parse().unwrap_err();
+        assert_eq!(diagnostics[0].message, "'title' must come before the slices");
+    }
+
     #[test]
     fn test_parse_invalid() {
         let code = "not a pie chart";
-        let mut parser = PieParser::new(code);
+        let mut parser = PieParserImpl::new(code);
         let result = parser.parse();
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_curly_and_unterminated_quotes() {
+        let result = crate::parse("pie\n    “Dogs” : 3\n    \"Cats\" : 4", None);
+        assert!(result.ok);
+        assert_eq!(result.diagnostics.len(), 1);
+        assert_eq!(result.diagnostics[0].fixes[0].new_text, "\"Dogs\"");
+
+        let result = crate::parse("pie\n    \"Cats : 4\n    \"Rats\" : 2", None);
+        assert_eq!(result.diagnostics[0].code, DiagnosticCode::UnterminatedString);
+    }
 }
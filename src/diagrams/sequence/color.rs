@@ -0,0 +1,235 @@
+//! Color specifiers for sequence `box` and `rect` statements.
+//!
+//! A box header is `box [color] [label]`. Like Mermaid, the first word is
+//! only taken as the color when it actually is one; otherwise the whole
+//! header is the label. A rect header is just `rect <color>`.
+//!
+//! A color is a named color, a `#` hex color, or an `rgb()`, `rgba()`,
+//! `hsl()` or `hsla()` function.
+
+use crate::ast::Span;
+use crate::diagnostic::{Diagnostic, DiagnosticCode};
+
+/// CSS named colors, lowercase.
+const NAMED_COLORS: &[&str] = &[
+    "aliceblue", "antiquewhite", "aqua", "aquamarine", "azure", "beige", "bisque", "black",
+    "blanchedalmond", "blue", "blueviolet", "brown", "burlywood", "cadetblue", "chartreuse",
+    "chocolate", "coral", "cornflowerblue", "cornsilk", "crimson", "cyan", "darkblue",
+    "darkcyan", "darkgoldenrod", "darkgray", "darkgreen", "darkgrey", "darkkhaki",
+    "darkmagenta", "darkolivegreen", "darkorange", "darkorchid", "darkred", "darksalmon",
+    "darkseagreen", "darkslateblue", "darkslategray", "darkslategrey", "darkturquoise",
+    "darkviolet", "deeppink", "deepskyblue", "dimgray", "dimgrey", "dodgerblue", "firebrick",
+    "floralwhite", "forestgreen", "fuchsia", "gainsboro", "ghostwhite", "gold", "goldenrod",
+    "gray", "green", "greenyellow", "grey", "honeydew", "hotpink", "indianred", "indigo",
+    "ivory", "khaki", "lavender", "lavenderblush", "lawngreen", "lemonchiffon", "lightblue",
+    "lightcoral", "lightcyan", "lightgoldenrodyellow", "lightgray", "lightgreen", "lightgrey",
+    "lightpink", "lightsalmon", "lightseagreen", "lightskyblue", "lightslategray",
+    "lightslategrey", "lightsteelblue", "lightyellow", "lime", "limegreen", "linen", "magenta",
+    "maroon", "mediumaquamarine", "mediumblue", "mediumorchid", "mediumpurple",
+    "mediumseagreen", "mediumslateblue", "mediumspringgreen", "mediumturquoise",
+    "mediumvioletred", "midnightblue", "mintcream", "mistyrose", "moccasin", "navajowhite",
+    "navy", "oldlace", "olive", "olivedrab", "orange", "orangered", "orchid", "palegoldenrod",
+    "palegreen", "paleturquoise", "palevioletred", "papayawhip", "peachpuff", "peru", "pink",
+    "plum", "powderblue", "purple", "rebeccapurple", "red", "rosybrown", "royalblue",
+    "saddlebrown", "salmon", "sandybrown", "seagreen", "seashell", "sienna", "silver",
+    "skyblue", "slateblue", "slategray", "slategrey", "snow", "springgreen", "steelblue", "tan",
+    "teal", "thistle", "tomato", "turquoise", "violet", "wheat", "white", "whitesmoke",
+    "yellow", "yellowgreen",
+];
+
+/// Returns true if `word` is a CSS named color or `transparent`.
+fn is_color_keyword(word: &str) -> bool {
+    let word = word.to_ascii_lowercase();
+    word == "transparent" || NAMED_COLORS.contains(&word.as_str())
+}
+
+/// Returns true if `word` is a `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`
+/// hex color.
+fn is_hex_color(word: &str) -> bool {
+    word.strip_prefix('#')
+        .is_some_and(|hex| matches!(hex.len(), 3 | 4 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Returns true if `header` starts with `rgb(`, `rgba(`, `hsl(` or `hsla(`.
+fn starts_with_color_function(header: &str) -> bool {
+    let lower = header.to_ascii_lowercase();
+    ["rgb(", "rgba(", "hsl(", "hsla("].iter().any(|function| lower.starts_with(function))
+}
+
+/// Returns the color function's length if `header` starts with one.
+fn color_function_len(header: &str) -> Option<usize> {
+    if !starts_with_color_function(header) {
+        return None;
+    }
+    header.find(')').map(|close| close + 1)
+}
+
+/// Splits a box header into its optional color and its label.
+pub(super) fn split_box_color(header: &str) -> (Option<&str>, &str) {
+    let header = header.trim();
+
+    if let Some(len) = color_function_len(header) {
+        return (Some(&header[..len]), header[len..].trim());
+    }
+
+    let word_len = header.find(char::is_whitespace).unwrap_or(header.len());
+    if is_color_keyword(&header[..word_len]) || is_hex_color(&header[..word_len]) {
+        (Some(&header[..word_len]), header[word_len..].trim())
+    } else {
+        (None, header)
+    }
+}
+
+/// Checks the arguments of an `rgb()`, `rgba()`, `hsl()` or `hsla()` color.
+///
+/// A wrong argument count or a non-numeric argument is an error; a value
+/// outside its range is a warning, since browsers clamp it. An `hsl()` hue
+/// is an angle, in degrees or with a `deg` unit, and has no range.
+pub(super) fn validate_color(color: &str, span: Span) -> Option<Diagnostic> {
+    let (function, args) = color.strip_suffix(')')?.split_once('(')?;
+    let function = function.to_ascii_lowercase();
+    let expected = match function.as_str() {
+        "rgb" | "hsl" => 3,
+        "rgba" | "hsla" => 4,
+        _ => return None,
+    };
+    let is_hsl = function.starts_with("hsl");
+
+    let args: Vec<&str> = args.split(',').map(str::trim).collect();
+    if args.len() != expected {
+        return Some(Diagnostic::error(
+            DiagnosticCode::InvalidValue,
+            format!("{}() expects {} arguments, found {}", function, expected, args.len()),
+            span,
+        ));
+    }
+
+    for (index, arg) in args.iter().enumerate() {
+        let is_alpha = index == 3;
+        let (number, max) = match arg.strip_suffix('%') {
+            _ if is_hsl && index == 0 => (arg.strip_suffix("deg").unwrap_or(arg), f64::INFINITY),
+            Some(percent) => (percent, 100.0),
+            None if is_alpha => (*arg, 1.0),
+            None if is_hsl => (*arg, 100.0),
+            None => (*arg, 255.0),
+        };
+        let Ok(value) = number.parse::<f64>() else {
+            return Some(Diagnostic::error(
+                DiagnosticCode::InvalidValue,
+                format!("{}() argument '{}' is not a number", function, arg),
+                span,
+            ));
+        };
+        if max.is_finite() && !(0.0..=max).contains(&value) {
+            return Some(Diagnostic::warning(
+                DiagnosticCode::InvalidValue,
+                format!("{}() argument '{}' is out of range 0-{}", function, arg, max),
+                span,
+            ));
+        }
+    }
+
+    None
+}
+
+/// Checks the color of a `rect` header, which is the whole header.
+///
+/// A color function is checked like a box color; an unclosed one is an
+/// error. Anything else that isn't a named or hex color is a warning.
+pub(super) fn validate_rect_color(color: &str, span: Span) -> Option<Diagnostic> {
+    if starts_with_color_function(color) {
+        if !color.ends_with(')') {
+            return Some(Diagnostic::error(
+                DiagnosticCode::MissingElement,
+                format!("Missing ')' in '{}'", color),
+                span,
+            ));
+        }
+        return validate_color(color, span);
+    }
+
+    if color.is_empty() || is_color_keyword(color) || is_hex_color(color) {
+        None
+    } else {
+        Some(
+            Diagnostic::warning(
+                DiagnosticCode::InvalidValue,
+                format!("'{}' is not a color", color),
+                span,
+            )
+            .with_note("rect takes a color such as 'rgb(200, 150, 255)', 'rgba(0, 0, 255, 0.1)', '#ffcc00' or 'aqua'"),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostic::Severity;
+
+    #[test]
+    fn test_split_named_color() {
+        assert_eq!(split_box_color("Purple Team"), (Some("Purple"), "Team"));
+        assert_eq!(split_box_color("transparent"), (Some("transparent"), ""));
+        assert_eq!(split_box_color("Backend Team"), (None, "Backend Team"));
+    }
+
+    #[test]
+    fn test_split_color_function() {
+        assert_eq!(
+            split_box_color("rgb(33,66,99) Group name"),
+            (Some("rgb(33,66,99)"), "Group name")
+        );
+        assert_eq!(
+            split_box_color("rgba(33, 66, 99, 0.5)"),
+            (Some("rgba(33, 66, 99, 0.5)"), "")
+        );
+    }
+
+    #[test]
+    fn test_validate_color() {
+        let span = Span::new(0, 0);
+        assert!(validate_color("rgb(33,66,99)", span).is_none());
+        assert!(validate_color("rgba(33,66,99,50%)", span).is_none());
+
+        let count = validate_color("rgb(33,66)", span).unwrap();
+        assert_eq!(count.severity, Severity::Error);
+
+        let range = validate_color("rgb(300,66,99)", span).unwrap();
+        assert_eq!(range.severity, Severity::Warning);
+
+        let alpha = validate_color("rgba(33,66,99,2)", span).unwrap();
+        assert_eq!(alpha.severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_validate_rect_color() {
+        let span = Span::new(0, 0);
+        assert!(validate_rect_color("rgb(200, 150, 255)", span).is_none());
+        assert!(validate_rect_color("Aqua", span).is_none());
+
+        let unclosed = validate_rect_color("rgb(200, 150, 255", span).unwrap();
+        assert_eq!(unclosed.code, DiagnosticCode::MissingElement);
+
+        let count = validate_rect_color("rgba(0, 0, 255)", span).unwrap();
+        assert_eq!(count.severity, Severity::Error);
+
+        let text = validate_rect_color("Highlighted", span).unwrap();
+        assert_eq!(text.severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_hex_and_hsl_colors() {
+        let span = Span::new(0, 0);
+        for color in ["#ffcc00", "#FC0", "#ffcc0080", "hsl(120, 50%, 50%)", "hsla(120deg, 50%, 50%, 0.3)"] {
+            assert!(validate_rect_color(color, span).is_none(), "{}", color);
+        }
+        assert!(validate_rect_color("#ffcc0", span).is_some());
+        assert!(validate_rect_color("#ggcc00", span).is_some());
+        assert_eq!(validate_rect_color("hsl(120, 150%, 50%)", span).unwrap().severity, Severity::Warning);
+        assert_eq!(validate_rect_color("hsla(120, 50%, 50%)", span).unwrap().severity, Severity::Error);
+
+        assert_eq!(split_box_color("#ffcc00 Team"), (Some("#ffcc00"), "Team"));
+        assert_eq!(split_box_color("hsl(120, 50%, 50%) Team"), (Some("hsl(120, 50%, 50%)"), "Team"));
+    }
+}
@@ -2,7 +2,7 @@
 
 use logos::Logos;
 
-use crate::ast::Span;
+use crate::parser::{lexer, quotes};
 
 /// Token types for sequence diagram parsing.
 #[derive(Logos, Debug, Clone, PartialEq, Eq)]
@@ -93,6 +93,12 @@ pub enum SeqToken {
     #[token("link", ignore(case))]
     Link,
 
+    #[token("accTitle", ignore(case))]
+    AccTitle,
+
+    #[token("accDescr", ignore(case))]
+    AccDescr,
+
     // Arrow types (order matters - longer patterns first)
     #[token("->>")]
     SolidArrow,
@@ -140,11 +146,16 @@ pub enum SeqToken {
     #[token("\n")]
     Newline,
 
+    #[token(";")]
+    Semicolon,
+
     // Quoted strings
-    #[regex(r#""([^"\\]|\\.)*""#)]
+    #[token("\"", quotes::lex_quoted)]
+    #[token("“", quotes::lex_quoted)]
     DoubleQuotedString,
 
-    #[regex(r#"'([^'\\]|\\.)*'"#)]
+    #[token("'", quotes::lex_quoted)]
+    #[token("‘", quotes::lex_quoted)]
     SingleQuotedString,
 
     // Identifiers
@@ -157,36 +168,16 @@ pub enum SeqToken {
 
     // Text (for messages, notes) - lower priority so other patterns match first
     // Note: Excludes spaces so identifiers can be matched separately
-    #[regex(r#"[^\n:,\-+"' \t]+"#, priority = 1)]
+    #[regex(r#"[^\n:,\-+"' \t“‘;][^\n:,\-+"' \t]*"#, priority = 1)]
     Text,
 }
 
-/// A positioned token.
-#[derive(Debug, Clone)]
-pub struct PositionedToken {
-    pub kind: SeqToken,
-    pub span: Span,
-    pub text: String,
-}
-
-/// Tokenize sequence diagram source code.
-pub fn tokenize(source: &str) -> Vec<PositionedToken> {
-    let mut tokens = Vec::new();
-    let mut lexer = SeqToken::lexer(source);
-
-    while let Some(result) = lexer.next() {
-        if let Ok(kind) = result {
-            let span = lexer.span();
-            let text = lexer.slice().to_string();
-            tokens.push(PositionedToken {
-                kind,
-                span: Span::new(span.start, span.end),
-                text,
-            });
-        }
-    }
+/// A sequence diagram token with its span and text.
+pub type Token = lexer::Token<SeqToken>;
 
-    tokens
+/// Tokenizes sequence diagram source, skipping text no token matches.
+pub fn tokenize(source: &str) -> Vec<Token> {
+    lexer::tokenize(source)
 }
 
 #[cfg(test)]
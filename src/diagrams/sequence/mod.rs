@@ -12,8 +12,11 @@
 //!     Bob-->>Alice: Hi Alice
 //! ```
 
+mod color;
 mod lexer;
 mod parser;
+pub mod render_ids;
+pub mod validate;
 
 pub use parser::SequenceParser;
 
@@ -42,6 +45,7 @@ pub enum ArrowType {
 }
 
 impl ArrowType {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s {
             "->>" => Some(ArrowType::Solid),
@@ -2,12 +2,37 @@
 
 use crate::ast::{Ast, AstNode, NodeKind, Span};
 use crate::config::MermaidConfig;
-use crate::diagnostic::{Diagnostic, DiagnosticCode};
+use crate::diagnostic::{excerpt, Diagnostic, DiagnosticCode, TextEdit};
+use crate::parser::{accessibility, dangling, quotes, title, unknown, StatementLoop};
+use crate::parser::lexer::patterns;
 use crate::parser::traits::DiagramParser;
 
-use super::lexer::{tokenize, PositionedToken, SeqToken};
+use super::color::{split_box_color, validate_color, validate_rect_color};
+use super::lexer::{tokenize, Token, SeqToken};
 use super::ArrowType;
 
+/// The participant types a `"type"` metadata entry can name.
+const PARTICIPANT_TYPES: &[&str] = &[
+    "participant",
+    "actor",
+    "boundary",
+    "control",
+    "entity",
+    "database",
+    "collections",
+    "queue",
+];
+
+/// Statement keywords: a line starting with one doesn't continue a message
+/// split across lines.
+const KEYWORDS: &[&str] = &[
+    "participant", "actor", "note", "activate", "deactivate", "loop", "end", "alt", "else", "opt", "par", "and",
+    "critical", "option", "break", "rect", "autonumber", "title", "box", "create", "destroy", "links", "link",
+];
+
+/// Mermaid statement keywords this parser skips rather than checks.
+const NEW_KEYWORDS: &[&str] = &["links", "link", "properties", "details"];
+
 /// Sequence diagram parser.
 pub struct SequenceParser;
 
@@ -25,12 +50,18 @@ impl Default for SequenceParser {
 }
 
 impl DiagramParser for SequenceParser {
-    fn parse(&self, code: &str, _config: &MermaidConfig) -> Result<Ast, Vec<Diagnostic>> {
+    fn parse(&self, code: &str, config: &MermaidConfig) -> Result<Ast, Vec<Diagnostic>> {
         let tokens = tokenize(code);
-        let mut parser = SequenceParserImpl::new(&tokens, code);
+        let mut parser = SequenceParserImpl::new(&tokens, code, config);
         parser.parse()
     }
 
+    fn parse_partial(&self, code: &str, config: &MermaidConfig) -> (Option<Ast>, Vec<Diagnostic>) {
+        let tokens = tokenize(code);
+        let mut parser = SequenceParserImpl::new(&tokens, code, config);
+        parser.parse_partial()
+    }
+
     fn name(&self) -> &'static str {
         "sequence"
     }
@@ -38,23 +69,35 @@ impl DiagramParser for SequenceParser {
 
 /// Internal parser implementation.
 struct SequenceParserImpl<'a> {
-    tokens: &'a [PositionedToken],
+    tokens: &'a [Token],
     pos: usize,
     source: &'a str,
     diagnostics: Vec<Diagnostic>,
+    /// Whether messages wrap unless they say otherwise, from `config.wrap`.
+    wrap: bool,
 }
 
 impl<'a> SequenceParserImpl<'a> {
-    fn new(tokens: &'a [PositionedToken], source: &'a str) -> Self {
+    fn new(tokens: &'a [Token], source: &'a str, config: &MermaidConfig) -> Self {
         Self {
             tokens,
             pos: 0,
             source,
             diagnostics: Vec::new(),
+            wrap: config.wrap,
         }
     }
 
     fn parse(&mut self) -> Result<Ast, Vec<Diagnostic>> {
+        match self.parse_partial() {
+            (Some(ast), diagnostics) if !diagnostics.iter().any(|d| d.severity.is_error()) => Ok(ast),
+            (_, diagnostics) => Err(diagnostics),
+        }
+    }
+
+    /// Parses the diagram, returning the AST built so far alongside all
+    /// diagnostics. Unparseable statements are kept as `Error` nodes.
+    fn parse_partial(&mut self) -> (Option<Ast>, Vec<Diagnostic>) {
         let start_span = Span::new(0, self.source.len());
         let mut root = AstNode::new(NodeKind::Root, start_span);
 
@@ -68,7 +111,7 @@ impl<'a> SequenceParserImpl<'a> {
                 "Expected 'sequenceDiagram' declaration",
                 Span::new(0, 0),
             ));
-            return Err(std::mem::take(&mut self.diagnostics));
+            return (None, std::mem::take(&mut self.diagnostics));
         }
 
         let decl_span = self.current_span();
@@ -82,25 +125,29 @@ impl<'a> SequenceParserImpl<'a> {
 
         // Parse statements
         while !self.is_at_end() {
+            let loop_start = self.pos;
             self.skip_newlines();
 
             if self.is_at_end() {
                 break;
             }
 
+            let stmt_start = self.pos;
+            let diagnostic_count = self.diagnostics.len();
             if let Some(stmt) = self.parse_statement() {
                 root.add_child(stmt);
             } else {
                 // Skip to next line on error
                 self.skip_to_newline();
+                root.add_child(self.error_node(stmt_start, diagnostic_count));
             }
+            self.ensure_progress(loop_start);
         }
 
-        if self.diagnostics.iter().any(|d| d.severity.is_error()) {
-            Err(std::mem::take(&mut self.diagnostics))
-        } else {
-            Ok(Ast::new(root, self.source.to_string()))
-        }
+        (
+            Some(Ast::new(root, self.source.to_string())),
+            std::mem::take(&mut self.diagnostics),
+        )
     }
 
     fn parse_statement(&mut self) -> Option<AstNode> {
@@ -110,6 +157,11 @@ impl<'a> SequenceParserImpl<'a> {
             return None;
         }
 
+        // Check for accessibility
+        if self.check(&SeqToken::AccTitle) || self.check(&SeqToken::AccDescr) {
+            return self.parse_accessibility();
+        }
+
         // Check for different statement types
         if self.check(&SeqToken::Participant) {
             return self.parse_participant();
@@ -164,7 +216,15 @@ impl<'a> SequenceParserImpl<'a> {
         }
 
         if self.check(&SeqToken::Else) {
-            return self.parse_else();
+            return self.parse_branch("else");
+        }
+
+        if self.check(&SeqToken::And) {
+            return self.parse_branch("and");
+        }
+
+        if self.check(&SeqToken::Option) {
+            return self.parse_branch("option");
         }
 
         if self.check(&SeqToken::Autonumber) {
@@ -187,16 +247,58 @@ impl<'a> SequenceParserImpl<'a> {
             return self.parse_destroy();
         }
 
+        if let Some(node) = self.parse_unknown_statement() {
+            return Some(node);
+        }
+
         // Otherwise, try to parse a message
         self.parse_message()
     }
 
-    fn parse_participant(&mut self) -> Option<AstNode> {
+    /// Skips a statement starting with a keyword-like word, with a warning,
+    /// unless the line holds a message arrow.
+    fn parse_unknown_statement(&mut self) -> Option<AstNode> {
         let start = self.current_span().start;
-        self.advance(); // consume 'participant'
+        let keyword = unknown::leading_keyword(&self.source[start..])?;
+        let has_arrow = self.tokens[self.pos..]
+            .iter()
+            .take_while(|t| !matches!(t.kind, SeqToken::Newline | SeqToken::Colon))
+            .any(|t| arrow_type(&t.kind).is_some());
+        if has_arrow {
+            return None;
+        }
+        while !self.is_at_end() && !self.check(&SeqToken::Newline) {
+            self.advance();
+        }
+        let span = Span::new(start, self.previous_span().end);
+        let (node, diagnostic) = unknown::skipped_statement(self.source, span, keyword, NEW_KEYWORDS);
+        self.diagnostics.push(diagnostic);
+        Some(node)
+    }
 
-        // Parse participant ID
-        let id = self.expect_identifier()?;
+    fn parse_participant(&mut self) -> Option<AstNode> {
+        self.parse_participant_declaration("participant")
+    }
+
+    fn parse_actor(&mut self) -> Option<AstNode> {
+        self.parse_participant_declaration("actor")
+    }
+
+    /// Parses `participant` or `actor` (the keyword, given as `keyword`),
+    /// then the id, optional `@{ ... }` metadata and optional alias.
+    fn parse_participant_declaration(&mut self, keyword: &str) -> Option<AstNode> {
+        let start = self.current_span().start;
+        self.advance(); // consume the keyword
+
+        // Parse participant ID; the lexer reads `A@{` as one text token
+        let (id, metadata) = match self.peek().and_then(|t| Some((t.span.start, t.text.find("@{")?))) {
+            Some((token_start, at)) if at > 0 => {
+                let id = self.advance()?.text[..at].to_string();
+                let metadata = self.parse_participant_metadata(&id, token_start + at);
+                (id, metadata)
+            }
+            _ => (self.expect_identifier()?, None),
+        };
 
         // Check for alias
         let alias = if self.check(&SeqToken::As) {
@@ -209,36 +311,88 @@ impl<'a> SequenceParserImpl<'a> {
         let end = self.previous_span().end;
         let mut node = AstNode::with_text(NodeKind::Participant, Span::new(start, end), &id);
         node.add_property("id", id);
-        node.add_property("type", "participant");
+        node.add_property("type", keyword);
         if let Some(a) = alias {
             node.add_property("alias", a);
         }
+        if let Some((body, entries)) = metadata {
+            node.add_property("keyword", keyword);
+            node.add_property("metadata", body);
+            for (key, value) in entries {
+                match key.as_str() {
+                    "alias" if node.get_property("alias").is_some() => {}
+                    "id" | "keyword" | "metadata" => {}
+                    _ => node.add_property(key, value),
+                }
+            }
+        }
 
         Some(node)
     }
 
-    fn parse_actor(&mut self) -> Option<AstNode> {
-        let start = self.current_span().start;
-        self.advance(); // consume 'actor'
-
-        let id = self.expect_identifier()?;
-
-        let alias = if self.check(&SeqToken::As) {
+    /// Parses the `@{ ... }` metadata after a participant id; `id_end` is the
+    /// offset of the `@`.
+    ///
+    /// The body is a JSON object, as in `A@{ "type": "database" }`. Returns
+    /// the body as written and its scalar entries, with `type` checked
+    /// against the participant types Mermaid draws. Invalid JSON is reported
+    /// as a `DirectiveJsonError` and yields no entries.
+    fn parse_participant_metadata(&mut self, id: &str, id_end: usize) -> Option<(String, Vec<(String, String)>)> {
+        let body_start = id_end + 2;
+        let (body, end) = match patterns::closing_brace(&self.source[body_start..]) {
+            Some(close) => (&self.source[body_start..body_start + close], body_start + close + 1),
+            None => {
+                self.diagnostics.push(Diagnostic::error(
+                    DiagnosticCode::MissingElement,
+                    format!("Missing '}}' for the metadata of participant '{}'", id),
+                    Span::new(id_end, body_start),
+                ));
+                let line_end = self.source[body_start..].find('\n').map_or(self.source.len(), |i| body_start + i);
+                (&self.source[body_start..line_end], line_end)
+            }
+        };
+        while !self.is_at_end() && self.current_span().start < end {
             self.advance();
-            Some(self.parse_text_until_newline())
-        } else {
-            None
+        }
+        let span = Span::new(id_end, end);
+
+        let object = match serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(&format!("{{{}}}", body)) {
+            Ok(object) => object,
+            Err(e) => {
+                self.diagnostics.push(Diagnostic::error(
+                    DiagnosticCode::DirectiveJsonError,
+                    format!("Invalid metadata for participant '{}': {}", id, e),
+                    span,
+                ));
+                return Some((body.trim().to_string(), Vec::new()));
+            }
         };
 
-        let end = self.previous_span().end;
-        let mut node = AstNode::with_text(NodeKind::Participant, Span::new(start, end), &id);
-        node.add_property("id", id);
-        node.add_property("type", "actor");
-        if let Some(a) = alias {
-            node.add_property("alias", a);
+        let mut entries = Vec::new();
+        for (key, value) in object {
+            let value = match value {
+                serde_json::Value::String(s) => s,
+                serde_json::Value::Number(n) => n.to_string(),
+                serde_json::Value::Bool(b) => b.to_string(),
+                _ => continue,
+            };
+            if key == "type" && !PARTICIPANT_TYPES.contains(&value.as_str()) {
+                let value_span = body
+                    .find(&format!("\"{}\"", value))
+                    .map_or(span, |i| Span::from_len(body_start + i, value.len() + 2));
+                self.diagnostics.push(
+                    Diagnostic::error(
+                        DiagnosticCode::InvalidValue,
+                        format!("Unknown participant type '{}'", value),
+                        value_span,
+                    )
+                    .with_note(format!("expected one of: {}", PARTICIPANT_TYPES.join(", "))),
+                );
+                continue;
+            }
+            entries.push((key, value));
         }
-
-        Some(node)
+        Some((body.trim().to_string(), entries))
     }
 
     fn parse_message(&mut self) -> Option<AstNode> {
@@ -248,6 +402,7 @@ impl<'a> SequenceParserImpl<'a> {
         let from = self.expect_identifier()?;
 
         // Parse arrow type
+        let arrow_start = self.current_span().start;
         let arrow_type = self.parse_arrow_type()?;
 
         // Check for activation marker
@@ -257,6 +412,17 @@ impl<'a> SequenceParserImpl<'a> {
             self.advance();
         }
 
+        if self.is_at_end() || self.check(&SeqToken::Newline) {
+            let arrow = Span::new(arrow_start, self.previous_span().end);
+            self.diagnostics.push(dangling::missing_end(
+                self.source,
+                arrow,
+                "Message is missing its receiver",
+                KEYWORDS,
+            ));
+            return None;
+        }
+
         // Parse receiver
         let to = self.expect_identifier()?;
 
@@ -267,6 +433,7 @@ impl<'a> SequenceParserImpl<'a> {
         } else {
             String::new()
         };
+        let (text, wrap) = split_wrap_prefix(&text);
 
         let end = self.previous_span().end;
         let mut node = AstNode::new(NodeKind::Message, Span::new(start, end));
@@ -274,6 +441,10 @@ impl<'a> SequenceParserImpl<'a> {
         node.add_property("to", to);
         node.add_property("arrow_type", format!("{:?}", arrow_type));
         node.add_property("text", text);
+        // An explicit prefix wins over `config.wrap`
+        if let Some(wrap) = wrap.or(self.wrap.then_some(true)) {
+            node.add_property("wrap", wrap.to_string());
+        }
 
         if has_activation {
             node.add_property("activation", "activate");
@@ -285,18 +456,7 @@ impl<'a> SequenceParserImpl<'a> {
     }
 
     fn parse_arrow_type(&mut self) -> Option<ArrowType> {
-        let arrow = match self.peek()?.kind {
-            SeqToken::SolidArrow => ArrowType::Solid,
-            SeqToken::DottedArrow => ArrowType::Dotted,
-            SeqToken::SolidLine => ArrowType::SolidLine,
-            SeqToken::DottedLine => ArrowType::DottedLine,
-            SeqToken::SolidCross | SeqToken::SolidCrossUpper => ArrowType::SolidCross,
-            SeqToken::DottedCross | SeqToken::DottedCrossUpper => ArrowType::DottedCross,
-            SeqToken::SolidAsync => ArrowType::SolidAsync,
-            SeqToken::DottedAsync => ArrowType::DottedAsync,
-            _ => return None,
-        };
-
+        let arrow = arrow_type(&self.peek()?.kind)?;
         self.advance();
         Some(arrow)
     }
@@ -439,17 +599,26 @@ impl<'a> SequenceParserImpl<'a> {
         Some(node)
     }
 
+    /// Parses `rect <color>` and the statements up to its `end`, which
+    /// become its children.
     fn parse_rect(&mut self) -> Option<AstNode> {
-        let start = self.current_span().start;
-        self.advance();
+        let keyword_span = self.current_span();
+        let start = keyword_span.start;
+        self.advance(); // consume 'rect'
 
-        let label = self.parse_text_until_newline();
+        let color_start = self.current_span().start;
+        let mut color_end = color_start;
+        while !self.is_at_end() && !self.check(&SeqToken::Newline) {
+            color_end = self.advance()?.span.end;
+        }
+        let color = &self.source[color_start..color_end];
+        self.diagnostics.extend(validate_rect_color(color, Span::new(color_start, color_end)));
 
-        let end = self.previous_span().end;
-        let mut node = AstNode::new(NodeKind::Statement, Span::new(start, end));
+        let mut node = AstNode::new(NodeKind::Statement, Span::new(start, self.previous_span().end));
         node.add_property("type", "rect");
-        node.add_property("label", label);
+        node.add_property("color", color);
 
+        self.parse_block_body(&mut node, "rect", keyword_span);
         Some(node)
     }
 
@@ -464,15 +633,17 @@ impl<'a> SequenceParserImpl<'a> {
         Some(node)
     }
 
-    fn parse_else(&mut self) -> Option<AstNode> {
+    /// Parses a branch of a block: `else` in `alt`, `and` in `par` or
+    /// `option` in `critical`, given as `keyword`, and its label.
+    fn parse_branch(&mut self, keyword: &str) -> Option<AstNode> {
         let start = self.current_span().start;
-        self.advance(); // consume 'else'
+        self.advance(); // consume the keyword
 
         let label = self.parse_text_until_newline();
 
         let end = self.previous_span().end;
         let mut node = AstNode::new(NodeKind::Statement, Span::new(start, end));
-        node.add_property("type", "else");
+        node.add_property("type", keyword);
         node.add_property("label", label);
 
         Some(node)
@@ -493,43 +664,124 @@ impl<'a> SequenceParserImpl<'a> {
     }
 
     fn parse_title(&mut self) -> Option<AstNode> {
-        let start = self.current_span().start;
-        self.advance();
-
-        let title = self.parse_text_until_newline();
-
-        let end = self.previous_span().end;
-        let mut node = AstNode::new(NodeKind::Statement, Span::new(start, end));
-        node.add_property("type", "title");
-        node.add_property("value", title);
-
+        let keyword_span = self.current_span();
+        let (node, end) = title::parse_statement(self.source, keyword_span, &mut self.diagnostics);
+        while !self.is_at_end() && self.current_span().start < end {
+            self.advance();
+        }
         Some(node)
     }
 
     fn parse_box(&mut self) -> Option<AstNode> {
-        let start = self.current_span().start;
-        self.advance();
+        let keyword_span = self.current_span();
+        let start = keyword_span.start;
+        self.advance(); // consume 'box'
 
-        let label = self.parse_text_until_newline();
+        // Split the color off the raw header so `rgb(...)` keeps its spelling
+        let header_start = self.current_span().start;
+        let mut header_end = header_start;
+        while !self.is_at_end() && !self.check(&SeqToken::Newline) {
+            header_end = self.advance()?.span.end;
+        }
+        let (color, label) = split_box_color(&self.source[header_start..header_end]);
 
-        let end = self.previous_span().end;
-        let mut node = AstNode::new(NodeKind::Statement, Span::new(start, end));
+        let mut node = AstNode::new(NodeKind::Statement, Span::new(start, self.previous_span().end));
         node.add_property("type", "box");
         node.add_property("label", label);
+        if let Some(color) = color {
+            let color_span = Span::new(header_start, header_start + color.len());
+            self.diagnostics.extend(validate_color(color, color_span));
+            node.add_property("color", color);
+        }
 
+        // Participants declared inside the box are its children
+        self.parse_block_body(&mut node, "box", keyword_span);
         Some(node)
     }
 
+    /// Parses the statements of a `box` or `rect` block into `node`, up to
+    /// and including its `end`, and extends the node's span over them.
+    ///
+    /// Blocks that stay flat (`loop`, `alt`, `opt`, `par`, `critical` and
+    /// `break`) are counted, so their `end` is kept as a statement instead of
+    /// closing this block.
+    fn parse_block_body(&mut self, node: &mut AstNode, keyword: &str, keyword_span: Span) {
+        let mut open_blocks = 0;
+        loop {
+            self.skip_newlines();
+
+            if self.is_at_end() {
+                self.diagnostics.push(Diagnostic::error(
+                    DiagnosticCode::MissingElement,
+                    format!("Missing 'end' for '{}'", keyword),
+                    keyword_span,
+                ));
+                break;
+            }
+
+            if self.check(&SeqToken::End) {
+                if open_blocks == 0 {
+                    self.advance();
+                    break;
+                }
+                open_blocks -= 1;
+            }
+
+            let stmt_start = self.pos;
+            let diagnostic_count = self.diagnostics.len();
+            if let Some(stmt) = self.parse_statement() {
+                if is_flat_block(&stmt) {
+                    open_blocks += 1;
+                }
+                node.add_child(stmt);
+            } else {
+                self.skip_to_newline();
+                node.add_child(self.error_node(stmt_start, diagnostic_count));
+            }
+        }
+
+        node.span = Span::new(node.span.start, self.previous_span().end);
+    }
+
+    /// Parses `create participant X` as a `Participant` node marked
+    /// `created`, spanning the `create` keyword. Mermaid needs the
+    /// `participant` or `actor` keyword, so `create X` is kept as a `create`
+    /// statement with a warning.
     fn parse_create(&mut self) -> Option<AstNode> {
-        let start = self.current_span().start;
+        let keyword = self.current_span();
+        let start = keyword.start;
         self.advance();
 
-        // Next should be participant
+        if self.is_at_end() || self.check(&SeqToken::Newline) {
+            self.diagnostics.push(
+                Diagnostic::error(
+                    DiagnosticCode::MissingElement,
+                    "'create' must be followed by the participant or actor it creates",
+                    keyword,
+                )
+                .with_note("write 'create participant Name' or 'create actor Name'"),
+            );
+            return None;
+        }
+
         if self.check(&SeqToken::Participant) || self.check(&SeqToken::Actor) {
-            return self.parse_statement();
+            let mut node = self.parse_statement()?;
+            node.span = Span::new(start, node.span.end);
+            node.add_property("created", "true");
+            return Some(node);
         }
 
+        let id_start = self.current_span().start;
         let id = self.expect_identifier()?;
+        self.diagnostics.push(
+            Diagnostic::warning(
+                DiagnosticCode::InvalidSyntax,
+                format!("'create {}' doesn't declare the participant it creates", id),
+                Span::new(start, self.previous_span().end),
+            )
+            .with_note(format!("write 'create participant {}' or 'create actor {}'", id, id))
+            .with_fix(TextEdit::new(Span::new(id_start, id_start), "participant ")),
+        );
 
         let end = self.previous_span().end;
         let mut node = AstNode::new(NodeKind::Statement, Span::new(start, end));
@@ -553,13 +805,23 @@ impl<'a> SequenceParserImpl<'a> {
         Some(node)
     }
 
+    /// Parse accessibility statement.
+    fn parse_accessibility(&mut self) -> Option<AstNode> {
+        let start = self.current_span().start;
+        let (node, end) = accessibility::parse_statement(self.source, start, &mut self.diagnostics)?;
+        while !self.is_at_end() && self.current_span().start < end {
+            self.advance();
+        }
+        Some(node)
+    }
+
     // Helper methods
 
     fn is_at_end(&self) -> bool {
         self.pos >= self.tokens.len()
     }
 
-    fn peek(&self) -> Option<&PositionedToken> {
+    fn peek(&self) -> Option<&Token> {
         self.tokens.get(self.pos)
     }
 
@@ -567,7 +829,7 @@ impl<'a> SequenceParserImpl<'a> {
         self.peek().map(|t| &t.kind == kind).unwrap_or(false)
     }
 
-    fn advance(&mut self) -> Option<&PositionedToken> {
+    fn advance(&mut self) -> Option<&Token> {
         if !self.is_at_end() {
             self.pos += 1;
             self.tokens.get(self.pos - 1)
@@ -576,12 +838,18 @@ impl<'a> SequenceParserImpl<'a> {
         }
     }
 
+    /// Consumes a quoted string token, returning the text between the
+    /// quotes; see [`quotes::unquote`].
+    fn quoted_text(&mut self) -> Option<String> {
+        let token = self.advance()?.clone();
+        quotes::unquote(&token.text, token.span, &mut self.diagnostics)
+    }
+
     fn expect_identifier(&mut self) -> Option<String> {
         if self.check(&SeqToken::Identifier) {
             Some(self.advance()?.text.clone())
         } else if self.check(&SeqToken::DoubleQuotedString) || self.check(&SeqToken::SingleQuotedString) {
-            let quoted = self.advance()?.text.clone();
-            Some(quoted[1..quoted.len() - 1].to_string())
+            self.quoted_text()
         } else {
             // Try text token as identifier
             if self.check(&SeqToken::Text) {
@@ -601,19 +869,19 @@ impl<'a> SequenceParserImpl<'a> {
         }
     }
 
+    /// Consumes the rest of the line and returns its source text, trimmed of
+    /// outer whitespace. Inner whitespace and markup such as `<br/>` are kept
+    /// as written.
     fn parse_text_until_newline(&mut self) -> String {
-        let mut text = String::new();
-
+        let start = self.current_span().start;
+        let mut end = start;
         while !self.is_at_end() && !self.check(&SeqToken::Newline) {
             if let Some(token) = self.advance() {
-                if !text.is_empty() && !matches!(token.kind, SeqToken::Colon | SeqToken::Comma) {
-                    text.push(' ');
-                }
-                text.push_str(&token.text);
+                end = token.span.end;
             }
         }
 
-        text.trim().to_string()
+        self.source[start..end].trim().to_string()
     }
 
     fn current_span(&self) -> Span {
@@ -631,11 +899,38 @@ impl<'a> SequenceParserImpl<'a> {
     }
 
     fn skip_newlines(&mut self) {
-        while self.check(&SeqToken::Newline) {
+        // Semicolons separate statements like newlines
+        while self.check(&SeqToken::Newline) || self.check(&SeqToken::Semicolon) {
             self.advance();
         }
     }
 
+    /// Builds an `Error` node covering the tokens skipped since `start`.
+    ///
+    /// Carries the code of the first diagnostic reported for the statement,
+    /// or reports an `InvalidSyntax` error if the statement failed silently.
+    fn error_node(&mut self, start: usize, diagnostic_count: usize) -> AstNode {
+        let start_offset = self.tokens.get(start).map_or(self.source.len(), |t| t.span.start);
+        let end_offset = self.tokens[start..self.pos]
+            .iter()
+            .rev()
+            .find(|t| t.kind != SeqToken::Newline)
+            .map_or(start_offset, |t| t.span.end);
+        let span = Span::new(start_offset, end_offset);
+
+        if self.diagnostics.len() == diagnostic_count {
+            self.diagnostics.push(Diagnostic::error(
+                DiagnosticCode::InvalidSyntax,
+                format!("Unable to parse statement '{}'", excerpt(span.text(self.source))),
+                span,
+            ));
+        }
+
+        let mut node = AstNode::with_text(NodeKind::Error, span, span.text(self.source));
+        node.add_property("code", self.diagnostics[diagnostic_count].code.as_str());
+        node
+    }
+
     fn skip_to_newline(&mut self) {
         while !self.is_at_end() && !self.check(&SeqToken::Newline) {
             self.advance();
@@ -646,6 +941,70 @@ impl<'a> SequenceParserImpl<'a> {
     }
 }
 
+impl StatementLoop for SequenceParserImpl<'_> {
+    fn position(&self) -> usize {
+        self.pos
+    }
+
+    fn at_end(&self) -> bool {
+        self.is_at_end()
+    }
+
+    fn token_span(&self) -> Span {
+        self.current_span()
+    }
+
+    fn skip_token(&mut self) {
+        self.advance();
+    }
+
+    fn report(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+}
+
+/// Returns the message arrow a token is, if any.
+fn arrow_type(kind: &SeqToken) -> Option<ArrowType> {
+    let arrow = match kind {
+        SeqToken::SolidArrow => ArrowType::Solid,
+        SeqToken::DottedArrow => ArrowType::Dotted,
+        SeqToken::SolidLine => ArrowType::SolidLine,
+        SeqToken::DottedLine => ArrowType::DottedLine,
+        SeqToken::SolidCross | SeqToken::SolidCrossUpper => ArrowType::SolidCross,
+        SeqToken::DottedCross | SeqToken::DottedCrossUpper => ArrowType::DottedCross,
+        SeqToken::SolidAsync => ArrowType::SolidAsync,
+        SeqToken::DottedAsync => ArrowType::DottedAsync,
+        _ => return None,
+    };
+    Some(arrow)
+}
+
+/// Splits Mermaid's `wrap:` or `nowrap:` prefix, which may follow a second
+/// `:`, off message text, returning the rest and whether it wraps.
+fn split_wrap_prefix(text: &str) -> (&str, Option<bool>) {
+    let rest = text.strip_prefix(':').unwrap_or(text);
+    if let Some(rest) = rest.strip_prefix("wrap:") {
+        (rest.trim(), Some(true))
+    } else if let Some(rest) = rest.strip_prefix("nowrap:") {
+        (rest.trim(), Some(false))
+    } else {
+        (text, None)
+    }
+}
+
+/// Returns true if `stmt` opens a block whose body stays flat, closed by a
+/// later `end` statement.
+fn is_flat_block(stmt: &AstNode) -> bool {
+    match stmt.kind {
+        NodeKind::Loop | NodeKind::Alt => true,
+        NodeKind::Statement => matches!(
+            stmt.get_property("type"),
+            Some("opt" | "par" | "critical" | "break")
+        ),
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -694,6 +1053,15 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_note_text_kept_as_written() {
+        let code = "sequenceDiagram\n    Note over A,B: multi  word<br/>second line, too  \n    A->>B: a  b";
+        let ast = parse(code).unwrap();
+        let note = &ast.root.children[1];
+        assert_eq!(note.get_property("text"), Some("multi  word<br/>second line, too"));
+        assert_eq!(ast.root.children[2].get_property("text"), Some("a  b"));
+    }
+
     #[test]
     fn test_parse_with_loop() {
         let code = r#"sequenceDiagram
@@ -729,10 +1097,137 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_bad_statement_yields_error_node() {
+        let code = "sequenceDiagram\n    Alice->>Bob: Hi\n    ->> Bob: oops\n    Bob->>Alice: Hello";
+        let (ast, diagnostics) =
+            SequenceParser::new().parse_partial(code, &MermaidConfig::default());
+        let ast = ast.expect("partial AST");
+        let kinds: Vec<_> = ast.root.children.iter().map(|c| c.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                NodeKind::DiagramDeclaration,
+                NodeKind::Message,
+                NodeKind::Error,
+                NodeKind::Message
+            ]
+        );
+
+        let error = &ast.root.children[2];
+        assert_eq!(error.text.as_deref(), Some("->> Bob: oops"));
+        assert!(!diagnostics.is_empty());
+        assert_eq!(error.get_property("code"), Some(diagnostics[0].code.as_str()));
+    }
+
+    #[test]
+    fn test_semicolon_lines_are_skipped() {
+        let code = "sequenceDiagram\n    ;\n    Alice->>Bob: Hi\n    ;;\n    loop Daily\n    ;\n    end";
+        let result = crate::parse(code, None);
+        assert!(result.ok, "{:?}", result.diagnostics);
+        assert!(result.diagnostics.is_empty());
+        let ast = result.ast.unwrap();
+        let kinds: Vec<_> = ast.root.children.iter().map(|c| c.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            vec![NodeKind::DiagramDeclaration, NodeKind::Message, NodeKind::Loop, NodeKind::Statement]
+        );
+    }
+
+    #[test]
+    fn test_box_contains_participants() {
+        let code = "sequenceDiagram\n    box rgb(33,66,99) Group name\n    participant A\n    actor B\n    end\n    A->>B: Hi";
+        let ast = parse(code).unwrap();
+        let group = &ast.root.children[1];
+        assert_eq!(group.get_property("type"), Some("box"));
+        assert_eq!(group.get_property("color"), Some("rgb(33,66,99)"));
+        assert_eq!(group.get_property("label"), Some("Group name"));
+        assert_eq!(group.children.len(), 2);
+        assert!(group.children.iter().all(|c| c.kind == NodeKind::Participant));
+        assert_eq!(ast.root.children[2].kind, NodeKind::Message);
+    }
+
+    #[test]
+    fn test_box_without_end() {
+        let code = "sequenceDiagram\n    box Team\n    participant A";
+        let diagnostics = parse(code).unwrap_err();
+        assert_eq!(diagnostics[0].code, DiagnosticCode::MissingElement);
+    }
+
+    #[test]
+    fn test_rect_nests_inside_blocks() {
+        let code = "sequenceDiagram\n    loop Daily\n    rect rgb(191, 223, 255)\n    alt ok\n    A->>B: Hi\n    end\n    rect aqua\n    B->>A: Yes\n    end\n    end\n    end";
+        let ast = parse(code).unwrap();
+        let rect = &ast.root.children[2];
+        assert_eq!(rect.get_property("type"), Some("rect"));
+        assert_eq!(rect.get_property("color"), Some("rgb(191, 223, 255)"));
+        assert_eq!(rect.children.len(), 4);
+        assert_eq!(rect.children[2].get_property("type"), Some("end"));
+        assert_eq!(rect.children[3].get_property("color"), Some("aqua"));
+        assert_eq!(rect.children[3].children.len(), 1);
+        // The loop's `end` follows the rect
+        assert_eq!(ast.root.children[3].get_property("type"), Some("end"));
+    }
+
+    #[test]
+    fn test_rect_without_end() {
+        let code = "sequenceDiagram\n    rect rgb(0, 0, 0)\n    A->>B: Hi";
+        let diagnostics = parse(code).unwrap_err();
+        assert_eq!(diagnostics[0].message, "Missing 'end' for 'rect'");
+        assert_eq!(diagnostics[0].span.text(code), "rect");
+    }
+
+    #[test]
+    fn test_rect_color_span() {
+        let code = "sequenceDiagram\n    rect rgb(200, 150)\n    A->>B: Hi\n    end";
+        let diagnostics = parse(code).unwrap_err();
+        assert_eq!(diagnostics[0].code, DiagnosticCode::InvalidValue);
+        assert_eq!(diagnostics[0].span.text(code), "rgb(200, 150)");
+    }
+
+    #[test]
+    fn test_participant_metadata_sets_type() {
+        let code = "sequenceDiagram\n    participant A@{ \"type\": \"actor\" }\n    participant D@{ \"type\": \"database\" } as Store\n    A->>D: Save";
+        let ast = parse(code).unwrap();
+        let participants: Vec<_> = ast.root.children.iter().filter(|c| c.kind == NodeKind::Participant).collect();
+        assert_eq!(participants[0].get_property("id"), Some("A"));
+        assert_eq!(participants[0].get_property("type"), Some("actor"));
+        assert_eq!(participants[0].span.text(code), "participant A@{ \"type\": \"actor\" }");
+        assert_eq!(participants[1].get_property("type"), Some("database"));
+        assert_eq!(participants[1].get_property("alias"), Some("Store"));
+        assert_eq!(participants[1].to_mermaid(), "participant D@{ \"type\": \"database\" } as Store");
+    }
+
+    #[test]
+    fn test_participant_metadata_errors() {
+        let code = "sequenceDiagram\n    participant A@{ type: actor }\n    actor B@{ \"type\": \"robot\" }";
+        let diagnostics = parse(code).unwrap_err();
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].code, DiagnosticCode::DirectiveJsonError);
+        assert_eq!(diagnostics[0].span.text(code), "@{ type: actor }");
+        assert_eq!(diagnostics[1].message, "Unknown participant type 'robot'");
+        assert_eq!(diagnostics[1].span.text(code), "\"robot\"");
+    }
+
     #[test]
     fn test_parse_invalid() {
         let code = "invalid diagram";
         let result = parse(code);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_curly_and_unterminated_quotes() {
+        let code = "sequenceDiagram\n    participant “Alice”\n    participant ‘Bob’\n    Alice->>Bob: it’s “fine”";
+        let result = crate::parse(code, None);
+        assert!(result.ok);
+        assert_eq!(result.diagnostics.len(), 2);
+        assert_eq!(result.diagnostics[1].fixes[0].new_text, "'Bob'");
+        let message = &result.ast.unwrap().root.children[3];
+        assert_eq!(message.get_property("text"), Some("it’s “fine”"));
+
+        let result = crate::parse("sequenceDiagram\n    participant \"Carl\n    A->>B: hi", None);
+        assert_eq!(result.diagnostics.len(), 1);
+        assert_eq!(result.diagnostics[0].code, DiagnosticCode::UnterminatedString);
+    }
 }
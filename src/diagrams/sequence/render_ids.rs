@@ -0,0 +1,66 @@
+//! Element ids Mermaid generates when rendering a sequence diagram.
+//!
+//! Best effort: actors are numbered in the order Mermaid first sees them,
+//! whether declared with `participant`/`actor` or implied by a message, and
+//! rendered as `actor<n>`. v10 and v11 use the same scheme.
+//!
+//! `tests/integration/render_id_tests.rs` checks the scheme against ids
+//! recorded from Mermaid's rendered SVG.
+
+use std::collections::HashMap;
+
+use crate::ast::{Ast, AstNode, NodeKind};
+use crate::config::MermaidVersion;
+
+/// Sets the `render_id` property on every participant declaration.
+pub fn assign(ast: &mut Ast, _version: MermaidVersion) {
+    let mut actors = HashMap::new();
+    number_actors(&mut ast.root.children, &mut actors);
+}
+
+fn number_actors(nodes: &mut [AstNode], actors: &mut HashMap<String, usize>) {
+    for node in nodes {
+        match node.kind {
+            NodeKind::Participant => {
+                let id = node.get_property("id").unwrap_or_default().to_string();
+                let next = actors.len();
+                let index = *actors.entry(id).or_insert(next);
+                node.add_property("render_id", format!("actor{}", index));
+            }
+            NodeKind::Message => {
+                for key in ["from", "to"] {
+                    if let Some(id) = node.get_property(key) {
+                        let next = actors.len();
+                        actors.entry(id.to_string()).or_insert(next);
+                    }
+                }
+            }
+            _ => number_actors(&mut node.children, actors),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MermaidConfig;
+    use crate::diagrams::sequence::SequenceParser;
+    use crate::parser::traits::DiagramParser;
+
+    #[test]
+    fn test_actors_numbered_in_order_of_appearance() {
+        let code = "sequenceDiagram\n    participant A\n    A->>B: implied\n    box Group\n    actor C\n    end\n    participant B";
+        let mut ast = SequenceParser::new()
+            .parse(code, &MermaidConfig::default())
+            .expect("parse failed");
+        assign(&mut ast, MermaidVersion::V11);
+
+        let mut ids = Vec::new();
+        ast.walk(|node, _| {
+            if let Some(id) = node.get_property("render_id") {
+                ids.push(id.to_string());
+            }
+        });
+        assert_eq!(ids, vec!["actor0", "actor2", "actor1"]);
+    }
+}
@@ -0,0 +1,251 @@
+//! Semantic validation for sequence diagrams.
+
+use std::collections::HashMap;
+
+use crate::ast::{Ast, AstNode, NodeKind, Span};
+use crate::diagnostic::{Diagnostic, DiagnosticCode, OptInCheck, RelatedDiagnostic};
+use crate::parser::references::References;
+
+/// Validates a parsed sequence diagram AST.
+///
+/// - A message endpoint that matches a participant's alias but no id produces
+///   a warning: Mermaid resolves endpoints by id only, so it silently adds a
+///   new participant named after the alias.
+/// - Two participants declaring the same alias produce a warning pointing at
+///   the first one.
+/// - A participant declared, messaged or activated before its `create`
+///   statement produces a warning at that first use.
+pub fn validate(ast: &Ast) -> Vec<Diagnostic> {
+    let mut participants = Vec::new();
+    let mut messages = Vec::new();
+    collect(&ast.root, &mut participants, &mut messages);
+
+    let mut diagnostics = Vec::new();
+    let mut by_alias: HashMap<&str, &AstNode> = HashMap::new();
+    for participant in &participants {
+        let Some(alias) = participant.get_property("alias") else {
+            continue;
+        };
+        match by_alias.get(alias) {
+            Some(first) => diagnostics.push(
+                Diagnostic::warning(
+                    DiagnosticCode::DuplicateDefinition,
+                    format!(
+                        "Alias '{}' is already used by participant '{}'",
+                        alias,
+                        first.id().unwrap_or_default()
+                    ),
+                    participant.span,
+                )
+                .with_related(RelatedDiagnostic::new("first used here", first.span)),
+            ),
+            None => {
+                by_alias.insert(alias, participant);
+            }
+        }
+    }
+
+    let is_id = |name: &str| participants.iter().any(|p| p.id() == Some(name));
+    for message in messages {
+        for (endpoint, name) in [("from", message.from()), ("to", message.to())] {
+            let name = name.unwrap_or_default();
+            let Some(participant) = by_alias.get(name).filter(|_| !is_id(name)) else {
+                continue;
+            };
+            let id = participant.id().unwrap_or_default();
+            diagnostics.push(
+                Diagnostic::warning(
+                    DiagnosticCode::UndefinedReference,
+                    format!(
+                        "'{}' is the alias of participant '{}', not an id; Mermaid adds a new participant named '{}'",
+                        name, id, name
+                    ),
+                    endpoint_span(ast, message, endpoint, name),
+                )
+                .with_note(format!("use the id '{}' to refer to this participant", id))
+                .with_related(RelatedDiagnostic::new(format!("'{}' is declared here", id), participant.span)),
+            );
+        }
+    }
+    diagnostics.extend(used_before_created(ast));
+    diagnostics
+}
+
+/// Returns a warning for each created participant that appears before its
+/// `create` statement.
+fn used_before_created(ast: &Ast) -> Vec<Diagnostic> {
+    fn collect<'a>(ast: &Ast, node: &'a AstNode, creations: &mut Vec<(&'a str, Span)>, uses: &mut Vec<(&'a str, Span)>) {
+        match (&node.kind, node.statement_type()) {
+            (NodeKind::Participant, _) if node.get_property("created").is_some() => {
+                creations.extend(node.id().map(|id| (id, node.span)));
+            }
+            (NodeKind::Statement, Some("create")) => {
+                creations.extend(node.get_property("participant").map(|id| (id, node.span)));
+            }
+            (NodeKind::Participant, _) => uses.extend(node.id().map(|id| (id, node.span))),
+            (NodeKind::Activation, _) => uses.extend(node.get_property("participant").map(|id| (id, node.span))),
+            (NodeKind::Message, _) => {
+                for (endpoint, name) in [("from", node.from()), ("to", node.to())] {
+                    if let Some(name) = name {
+                        uses.push((name, endpoint_span(ast, node, endpoint, name)));
+                    }
+                }
+            }
+            _ => {}
+        }
+        for child in &node.children {
+            collect(ast, child, creations, uses);
+        }
+    }
+    // Creations and other appearances of participants, in source order
+    let mut creations = Vec::new();
+    let mut uses = Vec::new();
+    collect(ast, &ast.root, &mut creations, &mut uses);
+
+    creations
+        .into_iter()
+        .filter_map(|(id, created)| {
+            let (_, used) = uses.iter().find(|(name, span)| *name == id && span.start < created.start)?;
+            Some(
+                Diagnostic::warning(
+                    DiagnosticCode::UndefinedReference,
+                    format!("Participant '{}' is used before it is created", id),
+                    *used,
+                )
+                .with_note(format!("move the 'create' statement before the first use of '{}'", id))
+                .with_related(RelatedDiagnostic::new(format!("'{}' is created here", id), created)),
+            )
+        })
+        .collect()
+}
+
+/// Warns about declared participants that no message, note or activation
+/// refers to (`unused-participant`).
+pub fn unused_participants(ast: &Ast) -> Vec<Diagnostic> {
+    fn collect<'a>(ast: &Ast, node: &'a AstNode, participants: &mut References<'a>) {
+        match node.kind {
+            NodeKind::Participant => {
+                if let Some(id) = node.id() {
+                    participants.define(id, node.span);
+                }
+            }
+            NodeKind::Message => {
+                for (endpoint, name) in [("from", node.from()), ("to", node.to())] {
+                    if let Some(name) = name {
+                        participants.reference(name, endpoint_span(ast, node, endpoint, name));
+                    }
+                }
+            }
+            NodeKind::Note => {
+                let position = node.get_property("position").unwrap_or_default();
+                let names = ["left of ", "right of ", "over "]
+                    .iter()
+                    .find_map(|keyword| position.strip_prefix(keyword))
+                    .unwrap_or_default();
+                for name in names.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+                    participants.reference(name, node.span);
+                }
+            }
+            NodeKind::Activation => {
+                if let Some(name) = node.get_property("participant") {
+                    participants.reference(name, node.span);
+                }
+            }
+            _ => {}
+        }
+        for child in &node.children {
+            collect(ast, child, participants);
+        }
+    }
+    let mut participants = References::default();
+    collect(ast, &ast.root, &mut participants);
+
+    participants
+        .unreferenced()
+        .into_iter()
+        .map(|(id, span)| {
+            OptInCheck::UnusedParticipant
+                .diagnostic(format!("Participant '{}' never sends or receives a message", id), span)
+                .with_note(format!("nothing references '{}'; remove it if it is no longer needed", id))
+        })
+        .collect()
+}
+
+/// Collects participants and messages, including those inside blocks.
+fn collect<'a>(node: &'a AstNode, participants: &mut Vec<&'a AstNode>, messages: &mut Vec<&'a AstNode>) {
+    for child in &node.children {
+        match child.kind {
+            NodeKind::Participant => participants.push(child),
+            NodeKind::Message => messages.push(child),
+            _ => collect(child, participants, messages),
+        }
+    }
+}
+
+/// Returns the span of a message's sender (`from`) or receiver (`to`),
+/// falling back to the whole message.
+fn endpoint_span(ast: &Ast, message: &AstNode, endpoint: &str, name: &str) -> Span {
+    let text = ast.text_for_span(&message.span);
+    let head = text.split(':').next().unwrap_or_default();
+    let offset = if endpoint == "from" { head.find(name) } else { head.rfind(name) };
+    offset.map_or(message.span, |offset| Span::from_len(message.span.start + offset, name.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MermaidConfig;
+    use crate::diagrams::sequence::SequenceParser;
+    use crate::parser::traits::DiagramParser;
+
+    fn validate_code(code: &str) -> Vec<Diagnostic> {
+        let ast = SequenceParser::new()
+            .parse(code, &MermaidConfig::default())
+            .expect("parse failed");
+        validate(&ast)
+    }
+
+    #[test]
+    fn test_alias_used_as_id() {
+        let code = "sequenceDiagram\n    participant A as Alice\n    participant B\n    A->>B: hi\n    B-->>+Alice: back";
+        let diagnostics = validate_code(code);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, DiagnosticCode::UndefinedReference);
+        assert_eq!(diagnostics[0].span.text(code), "Alice");
+        assert_eq!(diagnostics[0].span.start, code.rfind("Alice").unwrap());
+        assert!(diagnostics[0].notes[0].contains("'A'"));
+        assert_eq!(diagnostics[0].related[0].span.text(code), "participant A as Alice");
+    }
+
+    #[test]
+    fn test_alias_that_is_also_an_id() {
+        let code = "sequenceDiagram\n    participant A as Alice\n    participant Alice\n    Alice->>A: hi";
+        assert!(validate_code(code).is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_alias() {
+        let code = "sequenceDiagram\n    box Team\n    participant A as Alice\n    end\n    actor B as Alice";
+        let diagnostics = validate_code(code);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, DiagnosticCode::DuplicateDefinition);
+        assert_eq!(diagnostics[0].message, "Alias 'Alice' is already used by participant 'A'");
+        assert_eq!(diagnostics[0].span.text(code), "actor B as Alice");
+        assert_eq!(diagnostics[0].related[0].span.text(code), "participant A as Alice");
+    }
+
+    #[test]
+    fn test_used_before_created() {
+        let code = "sequenceDiagram\n    loop Retry\n    A->>B: hi\n    end\n    create participant B\n    A->>B: hi\n    create actor C\n    B->>C: hey";
+        let diagnostics = validate_code(code);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Participant 'B' is used before it is created");
+        assert_eq!(diagnostics[0].span.start, code.find("B:").unwrap());
+        assert_eq!(diagnostics[0].related[0].span.text(code), "create participant B");
+
+        let code = "sequenceDiagram\n    activate C\n    create C\n    A->>C: hi";
+        let diagnostics = validate_code(code);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].span.text(code), "activate C");
+    }
+}
@@ -2,7 +2,7 @@
 
 use logos::Logos;
 
-use crate::ast::Span;
+use crate::parser::{lexer, quotes};
 
 /// Token types for state diagram parsing.
 #[derive(Logos, Debug, Clone, PartialEq, Eq)]
@@ -33,6 +33,12 @@ pub enum StateToken {
     #[token("direction", ignore(case))]
     Direction,
 
+    #[token("accTitle", ignore(case))]
+    AccTitle,
+
+    #[token("accDescr", ignore(case))]
+    AccDescr,
+
     // Special states
     #[token("[*]")]
     StartEnd,
@@ -67,7 +73,8 @@ pub enum StateToken {
     Newline,
 
     // Quoted strings
-    #[regex(r#""([^"\\]|\\.)*""#)]
+    #[token("\"", quotes::lex_quoted)]
+    #[token("“", quotes::lex_quoted)]
     DoubleQuotedString,
 
     // Stereotypes
@@ -84,36 +91,16 @@ pub enum StateToken {
 
     // Text - lower priority so other patterns match first
     // Note: Excludes spaces so identifiers can be matched separately
-    #[regex(r#"[^\n{}:\[\]"<> \t]+"#, priority = 1)]
+    #[regex(r#"[^\n{}:\[\]"<> \t“][^\n{}:\[\]"<> \t]*"#, priority = 1)]
     Text,
 }
 
-/// A positioned token.
-#[derive(Debug, Clone)]
-pub struct PositionedToken {
-    pub kind: StateToken,
-    pub span: Span,
-    pub text: String,
-}
-
-/// Tokenize state diagram source code.
-pub fn tokenize(source: &str) -> Vec<PositionedToken> {
-    let mut tokens = Vec::new();
-    let mut lexer = StateToken::lexer(source);
-
-    while let Some(result) = lexer.next() {
-        if let Ok(kind) = result {
-            let span = lexer.span();
-            let text = lexer.slice().to_string();
-            tokens.push(PositionedToken {
-                kind,
-                span: Span::new(span.start, span.end),
-                text,
-            });
-        }
-    }
+/// A state diagram token with its span and text.
+pub type Token = lexer::Token<StateToken>;
 
-    tokens
+/// Tokenizes state diagram source, skipping text no token matches.
+pub fn tokenize(source: &str) -> Vec<Token> {
+    lexer::tokenize(source)
 }
 
 #[cfg(test)]
@@ -16,6 +16,7 @@
 
 mod lexer;
 mod parser;
+pub mod validate;
 
 pub use parser::StateParser;
 
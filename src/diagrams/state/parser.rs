@@ -3,11 +3,16 @@
 use crate::ast::{Ast, AstNode, NodeKind, Span};
 use crate::config::MermaidConfig;
 use crate::diagnostic::{Diagnostic, DiagnosticCode};
+use crate::parser::{accessibility, finish_parse, quotes, unknown, StatementLoop};
+use crate::parser::lexer::patterns;
 use crate::parser::traits::DiagramParser;
 
-use super::lexer::{tokenize, PositionedToken, StateToken};
+use super::lexer::{tokenize, Token, StateToken};
 use super::StateType;
 
+/// Mermaid statement keywords this parser skips rather than checks.
+const NEW_KEYWORDS: &[&str] = &["classDef", "class", "style", "hide"];
+
 /// State diagram parser.
 pub struct StateParser;
 
@@ -30,20 +35,31 @@ impl DiagramParser for StateParser {
         parser.parse()
     }
 
+    /// Also returns the warnings and hints of a successful parse. There is
+    /// no error recovery, so a failed parse has no AST.
+    fn parse_partial(&self, code: &str, _config: &MermaidConfig) -> (Option<Ast>, Vec<Diagnostic>) {
+        let tokens = tokenize(code);
+        let mut parser = StateParserImpl::new(&tokens, code);
+        match parser.parse() {
+            Ok(ast) => (Some(ast), std::mem::take(&mut parser.diagnostics)),
+            Err(diagnostics) => (None, diagnostics),
+        }
+    }
+
     fn name(&self) -> &'static str {
         "state"
     }
 }
 
 struct StateParserImpl<'a> {
-    tokens: &'a [PositionedToken],
+    tokens: &'a [Token],
     pos: usize,
     source: &'a str,
     diagnostics: Vec<Diagnostic>,
 }
 
 impl<'a> StateParserImpl<'a> {
-    fn new(tokens: &'a [PositionedToken], source: &'a str) -> Self {
+    fn new(tokens: &'a [Token], source: &'a str) -> Self {
         Self {
             tokens,
             pos: 0,
@@ -76,6 +92,7 @@ impl<'a> StateParserImpl<'a> {
 
         // Parse statements
         while !self.is_at_end() {
+            let loop_start = self.pos;
             self.skip_newlines();
 
             if self.is_at_end() {
@@ -83,17 +100,15 @@ impl<'a> StateParserImpl<'a> {
             }
 
             if let Some(stmt) = self.parse_statement() {
+                self.apply_direction(&mut root, &stmt);
                 root.add_child(stmt);
             } else {
                 self.skip_to_newline();
             }
+            self.ensure_progress(loop_start);
         }
 
-        if self.diagnostics.iter().any(|d| d.severity.is_error()) {
-            Err(std::mem::take(&mut self.diagnostics))
-        } else {
-            Ok(Ast::new(root, self.source.to_string()))
-        }
+        finish_parse(root, self.source, &mut self.diagnostics)
     }
 
     fn parse_statement(&mut self) -> Option<AstNode> {
@@ -103,6 +118,11 @@ impl<'a> StateParserImpl<'a> {
             return None;
         }
 
+        // Check for accessibility
+        if self.check(&StateToken::AccTitle) || self.check(&StateToken::AccDescr) {
+            return self.parse_accessibility();
+        }
+
         // Check for state definition
         if self.check(&StateToken::State) {
             return self.parse_state_definition();
@@ -118,18 +138,34 @@ impl<'a> StateParserImpl<'a> {
             return self.parse_direction();
         }
 
+        if let Some(node) = self.parse_unknown_statement() {
+            return Some(node);
+        }
+
         // Try to parse a transition
         self.parse_transition()
     }
 
+    /// Skips a statement starting with a keyword-like word, with a warning.
+    fn parse_unknown_statement(&mut self) -> Option<AstNode> {
+        let start = self.current_span().start;
+        let keyword = unknown::leading_keyword(&self.source[start..])?;
+        while !self.is_at_end() && !self.check(&StateToken::Newline) {
+            self.advance();
+        }
+        let span = Span::new(start, self.previous_span().end);
+        let (node, diagnostic) = unknown::skipped_statement(self.source, span, keyword, NEW_KEYWORDS);
+        self.diagnostics.push(diagnostic);
+        Some(node)
+    }
+
     fn parse_state_definition(&mut self) -> Option<AstNode> {
         let start = self.current_span().start;
         self.advance(); // consume 'state'
 
         // Parse state name or quoted description
         let (id, label) = if self.check(&StateToken::DoubleQuotedString) {
-            let quoted = self.advance()?.text.clone();
-            let label = quoted[1..quoted.len() - 1].to_string();
+            let label = self.quoted_text()?;
 
             // Check for "as" identifier
             if self.check(&StateToken::Identifier) && self.peek()?.text.to_lowercase() == "as" {
@@ -182,6 +218,7 @@ impl<'a> StateParserImpl<'a> {
             self.skip_newlines();
 
             while !self.is_at_end() && !self.check(&StateToken::RBrace) {
+                let loop_start = self.pos;
                 self.skip_newlines();
 
                 if self.check(&StateToken::RBrace) {
@@ -189,10 +226,13 @@ impl<'a> StateParserImpl<'a> {
                 }
 
                 if let Some(stmt) = self.parse_statement() {
+                    // A direction inside the body applies to this composite state
+                    self.apply_direction(&mut node, &stmt);
                     node.add_child(stmt);
                 } else {
                     self.skip_to_newline();
                 }
+                self.ensure_progress(loop_start);
             }
 
             if self.check(&StateToken::RBrace) {
@@ -203,7 +243,7 @@ impl<'a> StateParserImpl<'a> {
         // Check for colon (state description)
         if self.check(&StateToken::Colon) {
             self.advance();
-            let description = self.parse_text_until_newline();
+            let (description, _) = self.parse_text_until_newline();
             node.add_property("description", description);
         }
 
@@ -238,12 +278,34 @@ impl<'a> StateParserImpl<'a> {
             None
         };
 
-        let end = self.previous_span().end;
+        // A bracket ending the label is not a token, so the label may end
+        // past the last one
+        let end = label.as_ref().map_or(0, |(_, span)| span.end).max(self.previous_span().end);
         let mut node = AstNode::new(NodeKind::Transition, Span::new(start, end));
         node.add_property("from", from);
         node.add_property("to", to);
 
-        if let Some(lbl) = label {
+        if let Some((lbl, label_span)) = label {
+            if !brackets_balanced(&lbl) {
+                self.diagnostics.push(
+                    Diagnostic::warning(
+                        DiagnosticCode::InvalidSyntax,
+                        "Unbalanced brackets in the transition guard",
+                        label_span,
+                    )
+                    .with_note("a guard is written as 'event [condition] / action'"),
+                );
+            } else if let Some(parts) = TransitionLabel::split(&lbl) {
+                if !parts.event.is_empty() {
+                    node.add_property("event", parts.event);
+                }
+                if let Some(guard) = parts.guard {
+                    node.add_property("guard", guard);
+                }
+                if let Some(action) = parts.action {
+                    node.add_property("action", action);
+                }
+            }
             node.add_property("label", lbl);
         }
 
@@ -294,7 +356,7 @@ impl<'a> StateParserImpl<'a> {
         if self.check(&StateToken::Colon) {
             // Single line note
             self.advance();
-            content = self.parse_text_until_newline();
+            content = self.parse_text_until_newline().0;
         } else {
             // Multi-line note (until "end note")
             self.skip_newlines();
@@ -327,10 +389,25 @@ impl<'a> StateParserImpl<'a> {
     }
 
     fn parse_direction(&mut self) -> Option<AstNode> {
-        let start = self.current_span().start;
+        let keyword_span = self.current_span();
+        let start = keyword_span.start;
         self.advance(); // consume 'direction'
 
-        let direction = self.parse_text_until_newline();
+        let (direction, value_span) = self.parse_text_until_newline();
+
+        if direction.is_empty() {
+            self.diagnostics.push(Diagnostic::error(
+                DiagnosticCode::InvalidDirection,
+                "Missing value for 'direction'; expected one of TB, BT, LR, RL",
+                keyword_span,
+            ));
+        } else if !patterns::is_direction_value(&direction) {
+            self.diagnostics.push(Diagnostic::error(
+                DiagnosticCode::InvalidDirection,
+                format!("Invalid direction '{}'; expected one of TB, BT, LR, RL", direction),
+                value_span,
+            ));
+        }
 
         let end = self.previous_span().end;
         let mut node = AstNode::new(NodeKind::Statement, Span::new(start, end));
@@ -340,13 +417,35 @@ impl<'a> StateParserImpl<'a> {
         Some(node)
     }
 
+    /// Applies a `direction` statement to the scope it appears in: the root
+    /// or the enclosing composite state. The last direction in a scope wins.
+    fn apply_direction(&self, scope: &mut AstNode, stmt: &AstNode) {
+        if stmt.get_property("type") != Some("direction") {
+            return;
+        }
+        let direction = stmt.get_property("direction").unwrap_or_default();
+        if patterns::is_direction_value(direction) {
+            scope.add_property("direction", direction.to_uppercase());
+        }
+    }
+
+    /// Parse accessibility statement.
+    fn parse_accessibility(&mut self) -> Option<AstNode> {
+        let start = self.current_span().start;
+        let (node, end) = accessibility::parse_statement(self.source, start, &mut self.diagnostics)?;
+        while !self.is_at_end() && self.current_span().start < end {
+            self.advance();
+        }
+        Some(node)
+    }
+
     // Helper methods
 
     fn is_at_end(&self) -> bool {
         self.pos >= self.tokens.len()
     }
 
-    fn peek(&self) -> Option<&PositionedToken> {
+    fn peek(&self) -> Option<&Token> {
         self.tokens.get(self.pos)
     }
 
@@ -354,7 +453,7 @@ impl<'a> StateParserImpl<'a> {
         self.peek().map(|t| &t.kind == kind).unwrap_or(false)
     }
 
-    fn advance(&mut self) -> Option<&PositionedToken> {
+    fn advance(&mut self) -> Option<&Token> {
         if !self.is_at_end() {
             self.pos += 1;
             self.tokens.get(self.pos - 1)
@@ -363,12 +462,18 @@ impl<'a> StateParserImpl<'a> {
         }
     }
 
+    /// Consumes a quoted string token, returning the text between the
+    /// quotes; see [`quotes::unquote`].
+    fn quoted_text(&mut self) -> Option<String> {
+        let token = self.advance()?.clone();
+        quotes::unquote(&token.text, token.span, &mut self.diagnostics)
+    }
+
     fn expect_identifier(&mut self) -> Option<String> {
         if self.check(&StateToken::Identifier) {
             Some(self.advance()?.text.clone())
         } else if self.check(&StateToken::DoubleQuotedString) {
-            let quoted = self.advance()?.text.clone();
-            Some(quoted[1..quoted.len() - 1].to_string())
+            self.quoted_text()
         } else if self.check(&StateToken::Text) {
             let text = self.advance()?.text.trim().to_string();
             if !text.is_empty() {
@@ -386,19 +491,21 @@ impl<'a> StateParserImpl<'a> {
         }
     }
 
-    fn parse_text_until_newline(&mut self) -> String {
-        let mut text = String::new();
-
+    /// Returns the source text from the end of the previous token to the
+    /// end of the line, trimmed, and its span. The text is taken as written,
+    /// so arrows, brackets and other characters the lexer splits off or
+    /// skips stay in it.
+    fn parse_text_until_newline(&mut self) -> (String, Span) {
+        let start = self.previous_span().end;
         while !self.is_at_end() && !self.check(&StateToken::Newline) {
-            if let Some(token) = self.advance() {
-                if !text.is_empty() {
-                    text.push(' ');
-                }
-                text.push_str(&token.text);
-            }
+            self.advance();
         }
-
-        text.trim().to_string()
+        let end = self.current_span().start.max(start);
+        let text = &self.source[start..end];
+        let trimmed = text.trim_start();
+        let text_start = start + text.len() - trimmed.len();
+        let trimmed = trimmed.trim_end();
+        (trimmed.to_string(), Span::from_len(text_start, trimmed.len()))
     }
 
     fn current_span(&self) -> Span {
@@ -431,6 +538,78 @@ impl<'a> StateParserImpl<'a> {
     }
 }
 
+impl StatementLoop for StateParserImpl<'_> {
+    fn position(&self) -> usize {
+        self.pos
+    }
+
+    fn at_end(&self) -> bool {
+        self.is_at_end()
+    }
+
+    fn token_span(&self) -> Span {
+        self.current_span()
+    }
+
+    fn skip_token(&mut self) {
+        self.advance();
+    }
+
+    fn report(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+}
+
+/// The parts of a UML-style `event [guard] / action` transition label.
+#[derive(Debug, PartialEq, Eq)]
+struct TransitionLabel<'a> {
+    event: &'a str,
+    guard: Option<&'a str>,
+    action: Option<&'a str>,
+}
+
+impl<'a> TransitionLabel<'a> {
+    /// Splits a label with a guard or an action, or both; the event may be
+    /// empty. Returns `None` for labels without either, or that don't
+    /// follow the pattern.
+    fn split(label: &'a str) -> Option<Self> {
+        let (event, guard, rest) = match label.find('[') {
+            Some(open) => {
+                let close = open + label[open..].find(']')?;
+                (&label[..open], Some(label[open + 1..close].trim()), label[close + 1..].trim())
+            }
+            None => {
+                let slash = label.find('/')?;
+                (&label[..slash], None, &label[slash..])
+            }
+        };
+        let action = match rest.strip_prefix('/') {
+            Some(action) if !action.trim().is_empty() => Some(action.trim()),
+            None if rest.is_empty() => None,
+            _ => return None,
+        };
+        let event = event.trim();
+        if event.contains(['/', ']']) {
+            return None;
+        }
+        Some(Self { event, guard, action })
+    }
+}
+
+/// Returns whether every `[` in `text` is closed by a later `]`.
+fn brackets_balanced(text: &str) -> bool {
+    let mut depth: usize = 0;
+    for c in text.chars() {
+        match c {
+            '[' => depth += 1,
+            ']' if depth == 0 => return false,
+            ']' => depth -= 1,
+            _ => {}
+        }
+    }
+    depth == 0
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -529,4 +708,108 @@ mod tests {
         let result = parse(code);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_invalid_direction_value() {
+        let code = "stateDiagram-v2\n    direction NESW\n    [*] --> A";
+        let diagnostics = parse(code).unwrap_err();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, DiagnosticCode::InvalidDirection);
+        assert_eq!(&code[diagnostics[0].span.start..diagnostics[0].span.end], "NESW");
+    }
+
+    #[test]
+    fn test_composite_direction_recorded() {
+        let code = r#"stateDiagram-v2
+    state Composite {
+        direction lr
+        [*] --> Inner
+    }
+"#;
+        let ast = parse(code).unwrap();
+        let composite = &ast.root.children[1];
+        assert_eq!(composite.get_property("direction"), Some("LR"));
+        assert_eq!(ast.root.get_property("direction"), None);
+    }
+
+    #[test]
+    fn test_top_level_direction_sets_root() {
+        let ast = parse("stateDiagram-v2\n    direction RL\n    [*] --> A").unwrap();
+        assert_eq!(ast.root.get_property("direction"), Some("RL"));
+    }
+
+    #[test]
+    fn test_curly_and_unterminated_quotes() {
+        let code = "stateDiagram-v2\n    state “Idle state” as Idle\n    [*] --> Idle";
+        let result = crate::parse(code, None);
+        assert!(result.ok);
+        assert_eq!(result.diagnostics[0].span.text(code), "“Idle state”");
+
+        let code = "stateDiagram-v2\n    state \"Busy as B\n    [*] --> B";
+        let result = crate::parse(code, None);
+        assert_eq!(result.diagnostics.len(), 1);
+        assert_eq!(result.diagnostics[0].code, DiagnosticCode::UnterminatedString);
+        assert_eq!(result.diagnostics[0].span.text(code), "\"Busy as B");
+    }
+
+    #[test]
+    fn test_transition_label_kept_as_written() {
+        let code = "stateDiagram-v2\n    A --> B : goes to --> next\n    B --> C : x[i] >= 0";
+        let ast = parse(code).unwrap();
+        assert_eq!(ast.root.children[1].get_property("label"), Some("goes to --> next"));
+        assert_eq!(ast.root.children[1].to(), Some("B"));
+        assert_eq!(ast.root.children[2].get_property("label"), Some("x[i] >= 0"));
+        assert_eq!(ast.root.children[2].get_property("guard"), None);
+    }
+
+    #[test]
+    fn test_transition_guard_and_action() {
+        let code = "stateDiagram-v2\n    Still --> Moving : push [speed > 0] / startEngine()\n    Moving --> Still : stop\n    Moving --> Crash : / alarm()";
+        let ast = parse(code).unwrap();
+        let push = &ast.root.children[1];
+        assert_eq!(push.get_property("label"), Some("push [speed > 0] / startEngine()"));
+        assert_eq!(push.get_property("event"), Some("push"));
+        assert_eq!(push.get_property("guard"), Some("speed > 0"));
+        assert_eq!(push.get_property("action"), Some("startEngine()"));
+        assert_eq!(ast.root.children[2].get_property("event"), None);
+        assert_eq!(ast.root.children[3].get_property("event"), None);
+        assert_eq!(ast.root.children[3].get_property("action"), Some("alarm()"));
+
+        assert_eq!(TransitionLabel::split("a [b] c"), None);
+        assert_eq!(TransitionLabel::split("a/b [c]"), None);
+        assert_eq!(
+            TransitionLabel::split("[ready]"),
+            Some(TransitionLabel { event: "", guard: Some("ready"), action: None })
+        );
+    }
+
+    #[test]
+    fn test_unbalanced_guard_brackets() {
+        let code = "stateDiagram-v2\n    A --> B : go [ready / run()\n    B --> C : done] now";
+        let result = crate::parse(code, None);
+        assert!(result.ok);
+        assert_eq!(result.diagnostics.len(), 2);
+        assert_eq!(result.diagnostics[0].message, "Unbalanced brackets in the transition guard");
+        assert_eq!(result.diagnostics[0].span.text(code), "go [ready / run()");
+        assert_eq!(result.diagnostics[1].span.text(code), "done] now");
+        let ast = result.ast.unwrap();
+        assert_eq!(ast.root.children[1].get_property("guard"), None);
+    }
+
+    #[test]
+    fn test_transition_label_with_brackets_at_its_edges() {
+        let code = "stateDiagram-v2\n    C --> D : ev [g]\n    A --> D : [ready]\n    G --> H : a]";
+        let result = crate::parse(code, None);
+        assert_eq!(result.diagnostics.len(), 1);
+        assert_eq!(result.diagnostics[0].span.text(code), "a]");
+        let ast = result.ast.unwrap();
+        let ev = &ast.root.children[1];
+        assert_eq!(ev.get_property("label"), Some("ev [g]"));
+        assert_eq!(ev.get_property("event"), Some("ev"));
+        assert_eq!(ev.get_property("guard"), Some("g"));
+        assert_eq!(ast.root.children[2].get_property("label"), Some("[ready]"));
+        assert_eq!(ast.root.children[2].get_property("guard"), Some("ready"));
+        assert_eq!(ast.root.children[3].get_property("label"), Some("a]"));
+        assert_eq!(ast.root.children[3].span.text(code), "G --> H : a]");
+    }
 }
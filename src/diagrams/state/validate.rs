@@ -0,0 +1,70 @@
+//! Semantic validation for state diagrams.
+
+use crate::ast::{Ast, AstNode};
+use crate::diagnostic::{Diagnostic, DiagnosticCode, RelatedDiagnostic};
+
+/// Validates a parsed state diagram AST.
+///
+/// - A `direction` statement repeated in the same scope (the diagram or one
+///   composite state) produces a warning pointing at the previous one; the
+///   last direction is used.
+pub fn validate(ast: &Ast) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    check_scope(&ast.root, &mut diagnostics);
+    diagnostics
+}
+
+/// Checks the direction statements directly inside `scope`, then those of
+/// nested composite states.
+fn check_scope(scope: &AstNode, diagnostics: &mut Vec<Diagnostic>) {
+    let mut previous: Option<&AstNode> = None;
+    for child in &scope.children {
+        if child.statement_type() == Some("direction") {
+            if let Some(previous) = previous {
+                diagnostics.push(
+                    Diagnostic::warning(
+                        DiagnosticCode::DuplicateDefinition,
+                        "'direction' is already set in this scope; the last one is used",
+                        child.span,
+                    )
+                    .with_related(RelatedDiagnostic::new("previously set here", previous.span)),
+                );
+            }
+            previous = Some(child);
+        }
+        if child.get_property("is_composite") == Some("true") {
+            check_scope(child, diagnostics);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MermaidConfig;
+    use crate::diagrams::state::StateParser;
+    use crate::parser::traits::DiagramParser;
+
+    fn validate_code(code: &str) -> Vec<Diagnostic> {
+        let ast = StateParser::new()
+            .parse(code, &MermaidConfig::default())
+            .expect("parse failed");
+        validate(&ast)
+    }
+
+    #[test]
+    fn test_one_direction_per_scope() {
+        let code = "stateDiagram-v2\n    direction LR\n    state C {\n        direction TB\n        [*] --> A\n    }";
+        assert!(validate_code(code).is_empty());
+    }
+
+    #[test]
+    fn test_repeated_direction_warns() {
+        let code = "stateDiagram-v2\n    state C {\n        direction LR\n        direction TB\n    }";
+        let diagnostics = validate_code(code);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, DiagnosticCode::DuplicateDefinition);
+        assert_eq!(diagnostics[0].span.text(code), "direction TB");
+        assert_eq!(diagnostics[0].related[0].span.text(code), "direction LR");
+    }
+}
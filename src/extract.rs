@@ -0,0 +1,201 @@
+//! Extracting Mermaid diagrams embedded in documentation markup.
+//!
+//! Each extractor returns the span of every diagram's content in the
+//! document, together with the diagram code to lint. Diagnostics on that code
+//! are relative to the extracted text, not to the document.
+
+use crate::ast::Span;
+
+/// Extracts the diagrams of `.. mermaid::` directives in a reStructuredText
+/// document.
+///
+/// A directive's content is the indented block that follows it; the block
+/// ends at the first non-blank line indented no deeper than the directive.
+/// Option lines (`:caption: ...`) at the start of the block are skipped and
+/// the content is dedented. Directives without content, such as those naming
+/// an external file, are skipped.
+pub fn extract_mermaid_rst(text: &str) -> Vec<(Span, String)> {
+    let lines = lines_with_offsets(text);
+    let mut diagrams = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let (_, line) = lines[i];
+        let indent = indentation(line);
+        i += 1;
+        if !is_rst_directive(line.trim()) {
+            continue;
+        }
+
+        let mut body = Vec::new();
+        while i < lines.len() {
+            let (_, line) = lines[i];
+            if !line.trim().is_empty() && indentation(line) <= indent {
+                break;
+            }
+            body.push(lines[i]);
+            i += 1;
+        }
+
+        // Options come first, right after the directive
+        let content_start = body
+            .iter()
+            .position(|(_, line)| !is_rst_option(line.trim()))
+            .unwrap_or(body.len());
+        if let Some(diagram) = join_lines(&body[content_start..], true) {
+            diagrams.push(diagram);
+        }
+    }
+    diagrams
+}
+
+/// Extracts the diagrams of `[mermaid]` blocks in an AsciiDoc document.
+///
+/// The block attribute line may carry further attributes, as in
+/// `[mermaid, flow, svg]`. The content is the following `....` or `----`
+/// delimited block, which runs to the end of the document if it is never
+/// closed, or else the paragraph up to the next blank line.
+pub fn extract_mermaid_adoc(text: &str) -> Vec<(Span, String)> {
+    let lines = lines_with_offsets(text);
+    let mut diagrams = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let (_, line) = lines[i];
+        i += 1;
+        if !is_adoc_mermaid_block(line.trim()) || i == lines.len() {
+            continue;
+        }
+
+        let delimiter = lines[i].1.trim_end();
+        let content = if is_adoc_delimiter(delimiter) {
+            i += 1;
+            let start = i;
+            while i < lines.len() && lines[i].1.trim_end() != delimiter {
+                i += 1;
+            }
+            let content = &lines[start..i];
+            i += 1;
+            content
+        } else {
+            let start = i;
+            while i < lines.len() && !lines[i].1.trim().is_empty() {
+                i += 1;
+            }
+            &lines[start..i]
+        };
+        if let Some(diagram) = join_lines(content, false) {
+            diagrams.push(diagram);
+        }
+    }
+    diagrams
+}
+
+/// Splits `text` into lines, without their line endings, paired with the
+/// offset each line starts at.
+fn lines_with_offsets(text: &str) -> Vec<(usize, &str)> {
+    let mut offset = 0;
+    text.split_inclusive('\n')
+        .map(|line| {
+            let start = offset;
+            offset += line.len();
+            (start, line.trim_end_matches('\n').trim_end_matches('\r'))
+        })
+        .collect()
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Matches `.. mermaid::`, with or without an argument.
+fn is_rst_directive(line: &str) -> bool {
+    line.strip_prefix("..")
+        .map(str::trim_start)
+        .is_some_and(|rest| rest.starts_with("mermaid::"))
+}
+
+/// Matches a directive option such as `:caption: Overview`.
+fn is_rst_option(line: &str) -> bool {
+    line.len() > 1 && line.starts_with(':') && line[1..].contains(':')
+}
+
+/// Matches `[mermaid]` and `[mermaid, ...]`.
+fn is_adoc_mermaid_block(line: &str) -> bool {
+    let Some(attributes) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) else {
+        return false;
+    };
+    attributes.split(',').next().is_some_and(|style| style.trim() == "mermaid")
+}
+
+/// Matches a literal (`....`) or listing (`----`) block delimiter.
+fn is_adoc_delimiter(line: &str) -> bool {
+    line.len() >= 4 && (line.bytes().all(|b| b == b'.') || line.bytes().all(|b| b == b'-'))
+}
+
+fn trim_blank_lines<'a, 'b>(lines: &'a [(usize, &'b str)]) -> &'a [(usize, &'b str)] {
+    let start = lines.iter().position(|(_, l)| !l.trim().is_empty()).unwrap_or(lines.len());
+    let end = lines.iter().rposition(|(_, l)| !l.trim().is_empty()).map_or(start, |i| i + 1);
+    &lines[start..end]
+}
+
+/// Joins content lines into a diagram, removing their common indentation if
+/// `dedent` is set. Returns `None` if there is no content.
+fn join_lines(lines: &[(usize, &str)], dedent: bool) -> Option<(Span, String)> {
+    let lines = trim_blank_lines(lines);
+    let (&(start, _), &(last_start, last)) = (lines.first()?, lines.last()?);
+    let margin = if dedent {
+        lines
+            .iter()
+            .filter(|(_, l)| !l.trim().is_empty())
+            .map(|(_, l)| indentation(l))
+            .min()
+            .unwrap_or(0)
+    } else {
+        0
+    };
+    let code = lines
+        .iter()
+        .map(|(_, l)| l.get(margin..).unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some((Span::new(start, last_start + last.len()), code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rst_indented_block() {
+        let text = "Intro\n\n.. mermaid::\n   :caption: Flow\n\n   graph TD\n\n     A --> B\n\nAfter\n";
+        let diagrams = extract_mermaid_rst(text);
+        assert_eq!(diagrams.len(), 1);
+        let (span, code) = &diagrams[0];
+        assert_eq!(code, "graph TD\n\n  A --> B");
+        assert_eq!(span.text(text), "   graph TD\n\n     A --> B");
+    }
+
+    #[test]
+    fn test_rst_nested_directive_ends_at_outdent() {
+        let text = "- item\n\n  .. mermaid::\n\n     pie\n       \"A\" : 1\n  Back in the item\n.. mermaid:: flow.mmd\n";
+        let diagrams = extract_mermaid_rst(text);
+        assert_eq!(diagrams.len(), 1);
+        assert_eq!(diagrams[0].1, "pie\n  \"A\" : 1");
+    }
+
+    #[test]
+    fn test_adoc_delimited_and_paragraph_blocks() {
+        let text = "= Doc\n\n[mermaid, flow, svg]\n....\ngraph LR\n----\n....\n\n[mermaid]\nsequenceDiagram\nA->>B: Hi\n\n[source, mermaid]\n----\ngraph TD\n----\n";
+        let diagrams = extract_mermaid_adoc(text);
+        assert_eq!(diagrams.len(), 2);
+        assert_eq!(diagrams[0].1, "graph LR\n----");
+        assert_eq!(diagrams[0].0.text(text), "graph LR\n----");
+        assert_eq!(diagrams[1].1, "sequenceDiagram\nA->>B: Hi");
+    }
+
+    #[test]
+    fn test_adoc_unclosed_block_runs_to_end() {
+        let text = "[mermaid]\r\n----\r\ngraph TD\r\n    A --> B\r\n";
+        let diagrams = extract_mermaid_adoc(text);
+        assert_eq!(diagrams, vec![(Span::new(17, 38), "graph TD\n    A --> B".to_string())]);
+    }
+}
@@ -0,0 +1,158 @@
+//! Applying the fixes attached to diagnostics.
+//!
+//! [`parse`] maps the edits of fixes back to the source, past the
+//! frontmatter, directives and comment lines that preprocessing removed. An
+//! edit is only applied if the text it replaces reached the parser
+//! unchanged; edits of text preprocessing rewrote, such as double-quoted
+//! HTML attributes or a CRLF line ending, are skipped and counted in
+//! [`FixResult::skipped`].
+
+use thiserror::Error;
+
+use crate::ast::Span;
+use crate::diagnostic::TextEdit;
+use crate::preprocess::SourceMap;
+use crate::{parse, ParseOptions};
+
+/// Errors from applying edits.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum FixError {
+    /// Two edits change overlapping text, so the result would be ambiguous.
+    #[error("edits at {0:?} and {1:?} overlap")]
+    Overlap(Span, Span),
+    /// An edit extends past the end of the source or splits a character.
+    #[error("edit at {0:?} is outside the source")]
+    OutOfBounds(Span),
+}
+
+/// A fix applied by [`fix`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppliedFix {
+    /// The message of the diagnostic the fix resolves.
+    pub message: String,
+    /// The edit, in source offsets.
+    pub edit: TextEdit,
+}
+
+/// The result of [`fix`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixResult {
+    /// The fixed source.
+    pub code: String,
+    /// The fixes applied, in source order.
+    pub applied: Vec<AppliedFix>,
+    /// The number of fixes not applied because preprocessing changed the
+    /// text they edit.
+    pub skipped: usize,
+}
+
+/// Parses `code` and applies every unambiguous fix: diagnostics that carry
+/// exactly one edit.
+///
+/// Returns an error, and changes nothing, if two fixes overlap.
+pub fn fix(code: &str, options: Option<ParseOptions>) -> Result<FixResult, FixError> {
+    let options = ParseOptions {
+        include_preprocessed: true,
+        ..options.unwrap_or_default()
+    };
+    let result = parse(code, Some(options));
+    let preprocessed = result.preprocessed_code.as_deref().unwrap_or(code);
+
+    let mut applied = Vec::new();
+    let mut skipped = 0;
+    for diagnostic in &result.diagnostics {
+        let [edit] = diagnostic.fixes.as_slice() else {
+            continue;
+        };
+        if edits_parsed_text(edit, code, preprocessed, &result.source_map) {
+            applied.push(AppliedFix {
+                message: diagnostic.message.clone(),
+                edit: edit.clone(),
+            });
+        } else {
+            skipped += 1;
+        }
+    }
+    applied.sort_by_key(|fix| fix.edit.span.start);
+
+    let edits: Vec<_> = applied.iter().map(|fix| fix.edit.clone()).collect();
+    Ok(FixResult {
+        code: apply_edits(code, &edits)?,
+        applied,
+        skipped,
+    })
+}
+
+/// Returns whether the text `edit` replaces in the source is the text the
+/// parser saw, and doesn't start inside a CRLF line ending.
+fn edits_parsed_text(edit: &TextEdit, code: &str, preprocessed: &str, map: &SourceMap) -> bool {
+    let parsed = Span::new(map.to_preprocessed(edit.span.start), map.to_preprocessed(edit.span.end));
+    let source = code.get(edit.span.start..edit.span.end);
+    let splits_crlf = code.get(..edit.span.start).is_some_and(|s| s.ends_with('\r'))
+        && source.is_some_and(|s| s.starts_with('\n'));
+    source.is_some() && source == preprocessed.get(parsed.start..parsed.end) && !splits_crlf
+}
+
+/// Applies non-overlapping edits to `source`, from the last to the first so
+/// earlier offsets stay valid.
+pub fn apply_edits(source: &str, edits: &[TextEdit]) -> Result<String, FixError> {
+    let mut edits: Vec<&TextEdit> = edits.iter().collect();
+    edits.sort_by_key(|edit| (edit.span.start, edit.span.end));
+    for pair in edits.windows(2) {
+        if pair[1].span.start < pair[0].span.end {
+            return Err(FixError::Overlap(pair[0].span, pair[1].span));
+        }
+    }
+
+    let mut fixed = source.to_string();
+    for edit in edits.iter().rev() {
+        let span = edit.span;
+        if span.start > span.end || source.get(span.start..span.end).is_none() {
+            return Err(FixError::OutOfBounds(span));
+        }
+        fixed.replace_range(span.start..span.end, &edit.new_text);
+    }
+    Ok(fixed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_edits_end_to_start() {
+        let edits = [
+            TextEdit::new(Span::new(0, 5), "graph"),
+            TextEdit::delete(Span::new(11, 13)),
+        ];
+        assert_eq!(apply_edits("chart TD\n A[] --> B", &edits).unwrap(), "graph TD\n A --> B");
+    }
+
+    #[test]
+    fn test_apply_edits_rejects_overlap() {
+        let edits = [TextEdit::delete(Span::new(0, 4)), TextEdit::delete(Span::new(2, 6))];
+        assert_eq!(
+            apply_edits("abcdefgh", &edits),
+            Err(FixError::Overlap(Span::new(0, 4), Span::new(2, 6)))
+        );
+    }
+
+    #[test]
+    fn test_fix_maps_past_frontmatter_and_comments() {
+        let code = "---\ntitle: T\n---\n%% A[] --> B\ngraph TD\r\n    A[] --> B\n    C[\"\"]\n";
+        let result = fix(code, None).unwrap();
+        assert_eq!(result.applied.len(), 2);
+        assert_eq!(result.code, "---\ntitle: T\n---\n%% A[] --> B\ngraph TD\r\n    A --> B\n    C\n");
+        assert_eq!(result.skipped, 0);
+    }
+
+    #[test]
+    fn test_fix_counts_edits_of_rewritten_text() {
+        // Joining the lines would replace a CRLF the parser saw as "\n"
+        let code = "graph TD\r\n    A -->\r\n    B[]\r\n";
+        let result = fix(code, None).unwrap();
+        assert_eq!(result.skipped, 1);
+        assert_eq!(result.applied.len(), 1);
+        assert_eq!(result.code, "graph TD\r\n    A -->\r\n    B\r\n");
+    }
+}
@@ -29,21 +29,40 @@
 //! assert_eq!(result.diagram_type, Some(mermaid_linter::DiagramType::Flowchart));
 //! ```
 
+/// Enters a `tracing` span for a parse phase, held by `$guard` until the end
+/// of the enclosing scope. Expands to nothing without the `tracing` feature.
+macro_rules! phase_span {
+    ($guard:ident, $phase:expr) => {
+        #[cfg(feature = "tracing")]
+        let $guard = tracing::debug_span!("mermaid_linter", phase = $phase).entered();
+    };
+}
+
 pub mod ast;
 pub mod config;
+#[cfg(feature = "test-corpus")]
+pub mod corpus;
 pub mod detector;
 pub mod diagnostic;
 pub mod diagrams;
+pub mod extract;
+pub mod fix;
+pub mod linter;
 pub mod parser;
 pub mod preprocess;
+pub mod rename;
+pub mod schema;
 
 // Re-export main types for convenience
-pub use ast::{Ast, AstNode, Span};
-pub use config::{MermaidConfig, ParseOptions};
+pub use ast::{Ast, AstNode, BudgetExcess, ComplexityBudgets, DiagramMetrics, LineIndex, PropertyKey, Span};
+pub use config::{ConfigChange, ConfigSource, MermaidConfig, MermaidVersion, ParseOptions, ParseOptionsBuilder};
 pub use detector::DiagramType;
-pub use diagnostic::{Diagnostic, DiagnosticCode, Severity};
+pub use diagnostic::{Diagnostic, DiagnosticBuilder, DiagnosticCode, OptInCheck, ParseSeverityError, Severity, TextEdit};
+pub use linter::{CacheStats, Linter};
+pub use schema::{output_schema, OUTPUT_SCHEMA_VERSION};
 
-use preprocess::preprocessor::Preprocessor;
+use preprocess::preprocessor::{PreprocessResult, Preprocessor};
+use preprocess::SourceMap;
 
 /// The result of parsing a Mermaid diagram.
 #[derive(Debug, Clone)]
@@ -55,11 +74,33 @@ pub struct ParseResult {
     /// The merged configuration from base config, frontmatter, and directives.
     pub config: MermaidConfig,
     /// The AST, if parsing was successful.
+    ///
+    /// In recovery mode ([`ParseOptions::recover`]) this also holds the
+    /// partial AST of a failed parse, with `NodeKind::Error` nodes marking
+    /// the statements that could not be parsed. The semantic validation
+    /// warnings for that AST are included in `diagnostics`.
     pub ast: Option<Ast>,
     /// Diagnostics (errors and warnings) from parsing.
     pub diagnostics: Vec<Diagnostic>,
     /// The title extracted from frontmatter, if any.
     pub title: Option<String>,
+    /// The diagram's `accTitle`, if any. The last one wins when set more than once.
+    pub acc_title: Option<String>,
+    /// The diagram's `accDescr`, if any. The last one wins when set more than once.
+    pub acc_descr: Option<String>,
+    /// The source after preprocessing, exactly as seen by the detector and parser.
+    ///
+    /// Only populated when [`ParseOptions::include_preprocessed`] is set.
+    pub preprocessed_code: Option<String>,
+    /// Maps offsets in the preprocessed code, which AST spans index into,
+    /// back to the source. Diagnostic spans are already mapped.
+    pub source_map: SourceMap,
+    /// Every configuration field the base config, frontmatter and
+    /// directives set, in merge order, so the last entry for a key holds
+    /// its final value.
+    ///
+    /// Only populated when [`ParseOptions::trace_config`] is set.
+    pub config_trace: Vec<ConfigChange>,
 }
 
 impl ParseResult {
@@ -72,6 +113,11 @@ impl ParseResult {
             ast: Some(ast),
             diagnostics: Vec::new(),
             title: None,
+            acc_title: None,
+            acc_descr: None,
+            preprocessed_code: None,
+            source_map: SourceMap::default(),
+            config_trace: Vec::new(),
         }
     }
 
@@ -84,6 +130,11 @@ impl ParseResult {
             ast: None,
             diagnostics,
             title: None,
+            acc_title: None,
+            acc_descr: None,
+            preprocessed_code: None,
+            source_map: SourceMap::default(),
+            config_trace: Vec::new(),
         }
     }
 
@@ -103,6 +154,54 @@ impl ParseResult {
         self.title = title;
         self
     }
+
+    /// Sets the preprocessed source.
+    pub fn with_preprocessed_code(mut self, code: Option<String>) -> Self {
+        self.preprocessed_code = code;
+        self
+    }
+
+    /// Returns size and complexity counts for the parsed diagram.
+    ///
+    /// All counts are zero when there is no AST.
+    pub fn metrics(&self) -> DiagramMetrics {
+        self.ast
+            .as_ref()
+            .map(DiagramMetrics::from_ast)
+            .unwrap_or_default()
+    }
+
+    /// Returns the result as the JSON object `mermaid-lint --format json`
+    /// prints for a diagram, less its `file` field, with codes linked to
+    /// this crate's rules page. `source` is the code that was parsed.
+    ///
+    /// ```
+    /// let code = "graph TD\n    A --> B";
+    /// let json = mermaid_linter::parse(code, None).to_json_value(code);
+    /// assert_eq!(json["ok"], true);
+    /// assert_eq!(json["diagram_type"], "flowchart");
+    /// ```
+    pub fn to_json_value(&self, source: &str) -> serde_json::Value {
+        self.to_json_value_with_docs_base(source, diagnostic::DEFAULT_DOCS_BASE_URL)
+    }
+
+    /// Like [`ParseResult::to_json_value`], with codes linked to pages
+    /// under `docs_base`; see [`ParseOptions::docs_base_url`].
+    pub fn to_json_value_with_docs_base(&self, source: &str, docs_base: &str) -> serde_json::Value {
+        let diagnostics: Vec<_> = self
+            .diagnostics
+            .iter()
+            .map(|d| d.to_json_value(self.diagram_type, source, docs_base))
+            .collect();
+        serde_json::json!({
+            "ok": self.ok,
+            "diagram_type": self.diagram_type.map(|t| t.as_str()),
+            "title": self.title,
+            "acc_title": self.acc_title,
+            "acc_descr": self.acc_descr,
+            "diagnostics": diagnostics,
+        })
+    }
 }
 
 /// Parse a Mermaid diagram string.
@@ -121,12 +220,59 @@ impl ParseResult {
 /// # Returns
 ///
 /// A `ParseResult` containing the parse status, AST (if successful), and any diagnostics.
+/// Diagnostic spans, and the edits of their fixes, are offsets into `code`,
+/// and each diagnostic's `range` holds its span as lines and columns of it.
+/// The diagnostics are sorted by where their spans start; at the same place
+/// errors come first, then warnings, info and hints, and otherwise they keep
+/// the order they were found in.
 pub fn parse(code: &str, options: Option<ParseOptions>) -> ParseResult {
     let options = options.unwrap_or_default();
+    let include_preprocessed = options.include_preprocessed;
+    let config_trace = if options.trace_config { config_trace(code, &options) } else { Vec::new() };
+    let mut result = parse_diagram(
+        code,
+        ParseOptions {
+            include_preprocessed: true,
+            ..options
+        },
+    );
+    let index = LineIndex::new(code);
+    for diagnostic in &mut result.diagnostics {
+        diagnostic.map_spans(|span| result.source_map.span_to_source(span));
+        diagnostic.resolve_range_with_index(code, &index);
+    }
+    // Severities are declared from most to least severe
+    result.diagnostics.sort_by_key(|d| (d.span.start, d.severity as u8));
+    if !include_preprocessed {
+        result.preprocessed_code = None;
+    }
+    result.config_trace = config_trace;
+    result
+}
 
+/// Returns the fields the base config, frontmatter and directives of `code`
+/// set, in the order [`parse`] merges them.
+fn config_trace(code: &str, options: &ParseOptions) -> Vec<ConfigChange> {
+    let mut trace = options
+        .base_config
+        .as_ref()
+        .map(|config| ConfigChange::from_config(config, ConfigSource::Base))
+        .unwrap_or_default();
+    if let Ok(preprocessed) = Preprocessor::new().preprocess(code) {
+        trace.extend(preprocessed.config_trace);
+    }
+    trace
+}
+
+/// Runs the parse pipeline; see [`parse`].
+fn parse_diagram(code: &str, options: ParseOptions) -> ParseResult {
     // Step 1: Preprocess the text
     let preprocessor = Preprocessor::new();
-    let preprocess_result = match preprocessor.preprocess(code) {
+    let preprocessed = {
+        phase_span!(_phase, "preprocess");
+        preprocessor.preprocess(code)
+    };
+    let mut preprocess_result = match preprocessed {
         Ok(result) => result,
         Err(e) => {
             return ParseResult::failure_single(Diagnostic::new(
@@ -137,35 +283,76 @@ pub fn parse(code: &str, options: Option<ParseOptions>) -> ParseResult {
             ));
         }
     };
+    let mut result = parse_preprocessed(&mut preprocess_result, options);
+    result.source_map = preprocess_result.source_map;
+    result
+}
 
+/// Runs steps 2 to 4 of the parse pipeline on preprocessed code.
+fn parse_preprocessed(preprocess_result: &mut PreprocessResult, options: ParseOptions) -> ParseResult {
     // Merge config: base_config <- frontmatter config <- directive config
-    let mut config = options.base_config.unwrap_or_default();
+    let mut config = options.base_config.clone().unwrap_or_default();
     config.merge(&preprocess_result.config);
 
+    let mut preprocessed_code = options
+        .include_preprocessed
+        .then(|| preprocess_result.code.clone());
+
+    // Comments and frontmatter are gone, so this also catches input with
+    // nothing but those
+    if preprocess_result.code.trim().is_empty() {
+        return ParseResult::failure_single(
+            Diagnostic::new(
+                DiagnosticCode::EmptyInput,
+                "Input is empty — provide a Mermaid diagram starting with a header like 'flowchart TD'"
+                    .to_string(),
+                Severity::Error,
+                Span::default(),
+            )
+            .with_note("common headers are flowchart TD, sequenceDiagram, classDiagram, stateDiagram-v2 and erDiagram"),
+        )
+        .with_title(preprocess_result.title.take())
+        .with_preprocessed_code(preprocessed_code);
+    }
+
     // Step 2: Detect diagram type
-    let diagram_type = match detector::detect_type(&preprocess_result.code, &config) {
-        Some(dt) => dt,
+    let header = {
+        phase_span!(_phase, "detect");
+        detector::detect_header(&preprocess_result.code, &config)
+    };
+    let diagram_type = match header.or_else(|| config.diagram_type_hint()) {
+        Some(dt) => {
+            log::debug!("detected diagram type: {}", dt);
+            dt
+        }
         None => {
+            log::debug!("no diagram type matched");
             return ParseResult::failure_single(Diagnostic::new(
                 DiagnosticCode::UnknownDiagram,
                 "Could not detect diagram type".to_string(),
                 Severity::Error,
                 Span::default(),
             ))
-            .with_title(preprocess_result.title);
+            .with_title(preprocess_result.title.take())
+            .with_preprocessed_code(preprocessed_code);
         }
     };
 
     // Handle special diagram types
     match diagram_type {
         DiagramType::Error => {
-            return ParseResult::failure_single(Diagnostic::new(
-                DiagnosticCode::ParserError,
-                "Error diagram type".to_string(),
-                Severity::Error,
-                Span::default(),
-            ))
-            .with_title(preprocess_result.title);
+            return ParseResult::failure_single(
+                Diagnostic::new(
+                    DiagnosticCode::ParserError,
+                    "'error' is Mermaid's internal directive for rendering its error diagram and is not a valid diagram"
+                        .to_string(),
+                    Severity::Error,
+                    Span::default(),
+                )
+                .with_note("Mermaid renders 'error' when a diagram fails to parse; the linter treats it as invalid input"),
+            )
+            .with_title(preprocess_result.title.take())
+            .with_preprocessed_code(preprocessed_code);
         }
         DiagramType::BadFrontmatter => {
             return ParseResult::failure_single(Diagnostic::new(
@@ -174,40 +361,169 @@ pub fn parse(code: &str, options: Option<ParseOptions>) -> ParseResult {
                 Severity::Error,
                 Span::default(),
             ))
-            .with_title(preprocess_result.title);
+            .with_title(preprocess_result.title.take())
+            .with_preprocessed_code(preprocessed_code);
         }
         _ => {}
     }
 
-    // Step 3: Encode entities for flowchart-related diagrams
-    let code_to_parse = if diagram_type.needs_entity_encoding() {
-        preprocess::encode_entities(&preprocess_result.code)
-    } else {
-        preprocess_result.code.clone()
+    // Text typed only by diagramType gets the header it names, so the parser
+    // sees a whole diagram; spans past it map back through the source map
+    if header.is_none() {
+        let header = format!("{}\n", diagram_type.descriptor().header_keywords[0]);
+        let len = preprocess_result.code.len();
+        preprocess_result.source_map.push_step(vec![(header.len(), 0, len)]);
+        preprocess_result.code.insert_str(0, &header);
+        if let Some(code) = preprocessed_code.as_mut() {
+            *code = preprocess_result.code.clone();
+        }
+    }
+
+    // Step 3: Parse with diagram-specific parser. Flowchart entity codes are
+    // decoded by the label parser, so spans stay aligned with the source.
+    let (ast, mut diagnostics) = {
+        phase_span!(_phase, "parse");
+        parser::parse_diagram_partial(diagram_type, &preprocess_result.code, &config)
     };
+    diagnostics.splice(0..0, preprocess_result.config.validate());
+    if !diagram_type.is_fully_parsed() {
+        diagnostics.push(
+            Diagnostic::info(
+                DiagnosticCode::UnvalidatedSyntax,
+                format!("syntax not yet validated for {}", diagram_type),
+                Span::default(),
+            )
+            .with_note("the linter has no parser for this diagram type yet, so only the header was checked"),
+        );
+    }
+    if let Some(hint) = config.diagram_type_hint().filter(|hint| hint.canonical() != diagram_type.canonical()) {
+        diagnostics.insert(
+            0,
+            Diagnostic::warning(
+                DiagnosticCode::InvalidValue,
+                format!(
+                    "diagramType '{}' conflicts with the diagram header, which starts a {} diagram",
+                    hint, diagram_type
+                ),
+                Span::default(),
+            )
+            .with_note("the header wins; remove diagramType or change the header"),
+        );
+    }
+    apply_severity_overrides(&mut diagnostics, &options);
 
-    // Step 4: Parse with diagram-specific parser
-    let parse_result = parser::parse_diagram(diagram_type, &code_to_parse, &config);
+    // The parser was chosen by the detected type; only the reported type is normalized
+    let reported_type = if options.normalize_diagram_types {
+        diagram_type.canonical()
+    } else {
+        diagram_type
+    };
 
-    match parse_result {
-        Ok(ast) => {
-            let mut result = ParseResult::success(diagram_type, config, ast);
-            result.title = preprocess_result.title;
+    match ast {
+        Some(mut ast) if !diagnostics.iter().any(|d| d.severity.is_error()) => {
+            // Step 4: Semantic validation
+            if !options.syntax_only {
+                diagnostics.extend(run_validation(diagram_type, &ast, &config, &options));
+            }
+            if options.compute_render_ids {
+                parser::assign_render_ids(diagram_type, &mut ast, options.mermaid_version);
+            }
+            let (acc_title, acc_descr) = parser::accessibility::values(&ast);
+            let mut result = ParseResult::success(reported_type, config, ast);
+            result.ok = !diagnostics.iter().any(|d| d.severity.is_error());
+            result.diagnostics = diagnostics;
+            result.title = preprocess_result.title.take();
+            result.acc_title = acc_title;
+            result.acc_descr = acc_descr;
+            result.preprocessed_code = preprocessed_code;
             result
         }
-        Err(diagnostics) => {
+        partial_ast => {
+            let partial_ast = partial_ast.filter(|_| options.recover);
+            if let Some(ast) = partial_ast.as_ref().filter(|_| !options.syntax_only) {
+                // Errors on a partial AST may only reflect the statements that
+                // failed to parse, so only the warnings are kept
+                let validation = run_validation(diagram_type, ast, &config, &options);
+                diagnostics.extend(validation.into_iter().filter(|d| !d.severity.is_error()));
+            }
             let mut result = ParseResult::failure(diagnostics);
-            result.diagram_type = Some(diagram_type);
+            result.ast = partial_ast;
+            result.diagram_type = Some(reported_type);
             result.config = config;
-            result.title = preprocess_result.title;
+            result.title = preprocess_result.title.take();
+            result.preprocessed_code = preprocessed_code;
             result
         }
     }
 }
 
+/// Runs the semantic validation passes, including the opt-in ones enabled in
+/// `options`, and applies the severity overrides to their diagnostics.
+fn run_validation(diagram_type: DiagramType, ast: &Ast, config: &MermaidConfig, options: &ParseOptions) -> Vec<Diagnostic> {
+    phase_span!(_phase, "validate");
+    // wasm32 has no clock to time the passes with; `Instant::now` panics there
+    #[cfg(not(target_arch = "wasm32"))]
+    let started = std::time::Instant::now();
+    let mut validation = parser::validate_diagram(diagram_type, ast, config);
+    if matches!(
+        diagram_type,
+        DiagramType::Flowchart | DiagramType::FlowchartV2 | DiagramType::FlowchartElk
+    ) {
+        if options.warn_duplicate_edges {
+            validation.extend(diagrams::flowchart::validate::duplicate_edges(ast));
+        }
+        if options.hint_repeated_styles {
+            validation.extend(diagrams::flowchart::validate::repeated_styles(ast));
+        }
+        if options.warn_undeclared_endpoints {
+            validation.extend(diagrams::flowchart::validate::undeclared_endpoints(ast));
+        }
+        if options.warn_unused_class_defs {
+            validation.extend(diagrams::flowchart::validate::unused_class_defs(ast));
+        }
+    }
+    if diagram_type == DiagramType::Sequence && options.warn_unused_participants {
+        validation.extend(diagrams::sequence::validate::unused_participants(ast));
+    }
+    if options.warn_confusable_identifiers {
+        validation.extend(parser::confusables::validate(diagram_type, ast));
+    }
+    if matches!(diagram_type, DiagramType::Class | DiagramType::ClassDiagram) && options.prefer_click_syntax {
+        validation.extend(diagrams::class::validate::prefer_click_syntax(ast));
+    }
+    if let Some(types) = options.er_attribute_types.as_deref().filter(|_| diagram_type == DiagramType::Er) {
+        validation.extend(diagrams::er::validate::attribute_types(ast, types));
+    }
+    validation.extend(options.budgets.validate(ast));
+    apply_severity_overrides(&mut validation, options);
+    #[cfg(not(target_arch = "wasm32"))]
+    log::debug!(
+        "validation of {} produced {} diagnostic(s) in {:?}",
+        diagram_type,
+        validation.len(),
+        started.elapsed()
+    );
+    #[cfg(target_arch = "wasm32")]
+    log::debug!("validation of {} produced {} diagnostic(s)", diagram_type, validation.len());
+    validation
+}
+
+/// Drops diagnostics whose code is disabled and replaces the severity of
+/// those whose code has an override.
+fn apply_severity_overrides(diagnostics: &mut Vec<Diagnostic>, options: &ParseOptions) {
+    diagnostics.retain(|diagnostic| !options.disabled_codes.contains(&diagnostic.code));
+    for diagnostic in diagnostics {
+        if let Some(&severity) = options.severity_overrides.get(&diagnostic.code) {
+            diagnostic.severity = severity;
+        }
+    }
+}
+
 /// Validate a Mermaid diagram string without producing an AST.
 ///
 /// This is a convenience function that only checks if the diagram is valid.
+/// Pass options with [`ParseOptions::syntax_only`] set to skip the semantic
+/// validation passes when only the syntax matters.
 ///
 /// # Arguments
 ///
@@ -233,7 +549,7 @@ pub fn validate(code: &str, options: Option<ParseOptions>) -> bool {
 pub fn detect_type(code: &str) -> Option<DiagramType> {
     let preprocessor = Preprocessor::new();
     let preprocess_result = preprocessor.preprocess(code).ok()?;
-    detector::detect_type(&preprocess_result.code, &MermaidConfig::default())
+    detector::detect_type(&preprocess_result.code, &preprocess_result.config)
 }
 
 #[cfg(test)]
@@ -277,6 +593,47 @@ sequenceDiagram
         );
     }
 
+    #[test]
+    fn test_preprocessed_code_strips_trailing_comment() {
+        let code = "graph TD\n    A --> B\n%% trailing comment\n";
+        let options = ParseOptions::default().with_preprocessed();
+        let result = parse(code, Some(options));
+        assert!(result.ok);
+        let preprocessed = result.preprocessed_code.expect("preprocessed code");
+        assert!(!preprocessed.contains("trailing comment"));
+        assert!(preprocessed.contains("A --> B"));
+    }
+
+    #[test]
+    fn test_preprocessed_code_not_included_by_default() {
+        let result = parse("graph TD\n    A --> B", None);
+        assert!(result.preprocessed_code.is_none());
+    }
+
+    #[test]
+    fn test_severity_override_downgrades_error() {
+        let code = "graph TD\n    A --> B\n    --> oops";
+        assert!(!parse(code, None).ok);
+
+        let options = ParseOptions::default()
+            .with_severity_override(DiagnosticCode::InvalidSyntax, Severity::Warning);
+        let result = parse(code, Some(options));
+        assert!(result.ok);
+        assert_eq!(result.diagnostics[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_severity_override_promotes_warning() {
+        let code = "gantt\n    section A\n    One :a1, 2024-01-01, 1d\n    Two :a1, 2024-01-02, 1d";
+        assert!(parse(code, None).ok);
+
+        let options = ParseOptions::default()
+            .with_severity_override(DiagnosticCode::DuplicateDefinition, Severity::Error);
+        let result = parse(code, Some(options));
+        assert!(!result.ok);
+        assert_eq!(result.diagnostics[0].code, DiagnosticCode::DuplicateDefinition);
+    }
+
     #[test]
     fn test_invalid_diagram() {
         let result = parse("this is not a valid diagram", None);
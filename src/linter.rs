@@ -0,0 +1,181 @@
+//! A reusable linter with an optional cache of parse results.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+
+use crate::{parse, ParseOptions, ParseResult};
+
+/// Parses diagrams with one set of options, optionally caching the results.
+///
+/// With a cache, parsing code that is already cached returns a clone of the
+/// stored result instead of parsing it again. The cache holds at most its
+/// capacity of diagrams and drops the least recently used one when full.
+/// The options are fixed when the linter is created, so entries are keyed
+/// by a hash of the code alone.
+///
+/// ```rust
+/// use mermaid_linter::{Linter, ParseOptions};
+///
+/// let mut linter = Linter::new(ParseOptions::default()).with_cache(64);
+/// let first = linter.parse("graph TD\n    A --> B");
+/// let second = linter.parse("graph TD\n    A --> B");
+/// assert_eq!(first.ok, second.ok);
+/// assert_eq!(linter.cache_stats().hits, 1);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Linter {
+    options: ParseOptions,
+    cache: Option<ParseCache>,
+    stats: CacheStats,
+}
+
+/// How often a [`Linter`]'s cache answered a parse.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Parses answered from the cache.
+    pub hits: usize,
+    /// Parses that ran the parser, including every parse without a cache.
+    pub misses: usize,
+}
+
+impl Linter {
+    /// Creates a linter that parses with `options` and caches nothing.
+    pub fn new(options: ParseOptions) -> Self {
+        Self {
+            options,
+            cache: None,
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// Caches the results of up to `capacity` diagrams. A capacity of zero
+    /// turns the cache off.
+    pub fn with_cache(mut self, capacity: usize) -> Self {
+        self.cache = (capacity > 0).then(|| ParseCache::new(capacity));
+        self
+    }
+
+    /// Returns the options the linter parses with.
+    pub fn options(&self) -> &ParseOptions {
+        &self.options
+    }
+
+    /// Parses `code` like [`parse`], or returns the cached result for it.
+    pub fn parse(&mut self, code: &str) -> ParseResult {
+        let key = hash(code);
+        if let Some(result) = self.cache.as_mut().and_then(|cache| cache.get(key, code)) {
+            self.stats.hits += 1;
+            return result;
+        }
+        self.stats.misses += 1;
+        let result = parse(code, Some(self.options.clone()));
+        if let Some(cache) = &mut self.cache {
+            cache.insert(key, code, result.clone());
+        }
+        result
+    }
+
+    /// Returns the cache's hit and miss counts so far.
+    pub fn cache_stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Empties the cache, keeping its capacity and statistics.
+    pub fn clear_cache(&mut self) {
+        if let Some(cache) = &mut self.cache {
+            cache.entries.clear();
+            cache.order.clear();
+        }
+    }
+}
+
+/// Parse results by code hash, with the code kept to rule out collisions.
+#[derive(Debug, Clone)]
+struct ParseCache {
+    capacity: usize,
+    entries: HashMap<u64, (String, ParseResult)>,
+    /// Keys from least to most recently used.
+    order: VecDeque<u64>,
+}
+
+impl ParseCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    fn get(&mut self, key: u64, code: &str) -> Option<ParseResult> {
+        let result = self.entries.get(&key).filter(|(cached, _)| cached == code)?.1.clone();
+        self.touch(key);
+        Some(result)
+    }
+
+    fn insert(&mut self, key: u64, code: &str, result: ParseResult) {
+        if self.entries.insert(key, (code.to_string(), result)).is_some() {
+            self.touch(key);
+            return;
+        }
+        self.order.push_back(key);
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    /// Marks `key` as the most recently used.
+    fn touch(&mut self, key: u64) {
+        if let Some(at) = self.order.iter().position(|k| *k == key) {
+            self.order.remove(at);
+        }
+        self.order.push_back(key);
+    }
+}
+
+fn hash(code: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    code.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cached_parse_is_equal_and_skips_parsing() {
+        let code = "graph TD\n    A --> B\n    B --> A";
+        let mut linter = Linter::new(ParseOptions::default().with_duplicate_edge_warnings()).with_cache(2);
+        let first = linter.parse(code);
+        let second = linter.parse(code);
+        assert_eq!(first.to_json_value(code), second.to_json_value(code));
+        assert_eq!(linter.cache_stats(), CacheStats { hits: 1, misses: 1 });
+
+        linter.clear_cache();
+        linter.parse(code);
+        assert_eq!(linter.cache_stats(), CacheStats { hits: 1, misses: 2 });
+    }
+
+    #[test]
+    fn test_cache_drops_least_recently_used() {
+        let mut linter = Linter::new(ParseOptions::default()).with_cache(2);
+        let (a, b, c) = ("graph TD\n    A", "graph TD\n    B", "graph TD\n    C");
+        linter.parse(a);
+        linter.parse(b);
+        linter.parse(a);
+        linter.parse(c);
+        assert_eq!(linter.cache_stats(), CacheStats { hits: 1, misses: 3 });
+        linter.parse(a);
+        linter.parse(b);
+        assert_eq!(linter.cache_stats(), CacheStats { hits: 2, misses: 4 });
+
+        let mut uncached = Linter::new(ParseOptions::default());
+        uncached.parse(a);
+        uncached.parse(a);
+        assert_eq!(uncached.cache_stats(), CacheStats { hits: 0, misses: 2 });
+    }
+}
@@ -0,0 +1,160 @@
+//! Accessibility statements shared by all diagram parsers.
+//!
+//! Every diagram accepts `accTitle: text`, `accDescr: text` and the
+//! multi-line `accDescr { ... }`. Lexers only need to recognise the keyword;
+//! the value is read straight from the source, so it is independent of how
+//! each lexer splits the text that follows.
+
+use crate::ast::{Ast, AstNode, NodeKind, Span};
+use crate::diagnostic::{Diagnostic, DiagnosticCode, RelatedDiagnostic};
+
+/// Parses the accessibility statement whose keyword starts at `start`.
+///
+/// Returns the `Statement` node (with `type` and `value` properties) and the
+/// offset where the statement ends, or `None` if there is no `accTitle` or
+/// `accDescr` keyword at `start`. The newline ending a single-line statement
+/// is not consumed.
+pub(crate) fn parse_statement(
+    source: &str,
+    start: usize,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<(AstNode, usize)> {
+    let rest = &source[start..];
+    let keyword_len = rest.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(rest.len());
+    let acc_type = match rest[..keyword_len].to_ascii_lowercase().as_str() {
+        "acctitle" => "accTitle",
+        "accdescr" => "accDescr",
+        _ => return None,
+    };
+    let keyword_span = Span::new(start, start + keyword_len);
+
+    let after_keyword = &rest[keyword_len..];
+    let padding = after_keyword.len() - after_keyword.trim_start_matches([' ', '\t']).len();
+    let value_start = keyword_span.end + padding;
+    let (value, end) = match source[value_start..].chars().next() {
+        Some('{') => {
+            let body_start = value_start + 1;
+            let (body, end) = match source[body_start..].find('}') {
+                Some(close) => (&source[body_start..body_start + close], body_start + close + 1),
+                None => {
+                    diagnostics.push(Diagnostic::error(
+                        DiagnosticCode::MissingElement,
+                        format!("Missing '}}' for '{}'", acc_type),
+                        keyword_span,
+                    ));
+                    (&source[body_start..], source.len())
+                }
+            };
+            // Like Mermaid, drop the indentation of continuation lines
+            let value = body.trim().lines().map(str::trim_start).collect::<Vec<_>>().join("\n");
+            (value, end)
+        }
+        first => {
+            let line_end = source[value_start..].find('\n').map_or(source.len(), |i| value_start + i);
+            let line = &source[value_start..line_end];
+            let value = match first {
+                Some(':') => &line[1..],
+                _ => {
+                    diagnostics.push(Diagnostic::error(
+                        DiagnosticCode::ExpectedToken,
+                        format!("Expected ':' or '{{' after '{}'", acc_type),
+                        keyword_span,
+                    ));
+                    line
+                }
+            };
+            (value.trim().to_string(), line_end)
+        }
+    };
+
+    let mut node = AstNode::new(NodeKind::Statement, Span::new(start, end));
+    node.add_property("type", acc_type);
+    node.add_property("value", value);
+    Some((node, end))
+}
+
+/// Returns the span and value of each statement setting `acc_type`.
+fn statements(ast: &Ast, acc_type: &str) -> Vec<(Span, String)> {
+    let mut found = Vec::new();
+    ast.walk(|node, _| {
+        if node.kind == NodeKind::Statement && node.statement_type() == Some(acc_type) {
+            found.push((node.span, node.get_property("value").unwrap_or_default().to_string()));
+        }
+    });
+    found
+}
+
+/// Returns the `accTitle` and `accDescr` values of a diagram. When either is
+/// set more than once, the last value wins.
+pub(crate) fn values(ast: &Ast) -> (Option<String>, Option<String>) {
+    let last = |acc_type| statements(ast, acc_type).pop().map(|(_, value)| value);
+    (last("accTitle"), last("accDescr"))
+}
+
+/// Warns about `accTitle` and `accDescr` statements that override an
+/// earlier one.
+pub(crate) fn validate(ast: &Ast) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for acc_type in ["accTitle", "accDescr"] {
+        for pair in statements(ast, acc_type).windows(2) {
+            diagnostics.push(
+                Diagnostic::warning(
+                    DiagnosticCode::DuplicateDefinition,
+                    format!("'{}' is set more than once; the last value is used", acc_type),
+                    pair[1].0,
+                )
+                .with_related(RelatedDiagnostic::new("previously set here", pair[0].0)),
+            );
+        }
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> (AstNode, usize, Vec<Diagnostic>) {
+        let mut diagnostics = Vec::new();
+        let (node, end) = parse_statement(source, 0, &mut diagnostics).unwrap();
+        (node, end, diagnostics)
+    }
+
+    #[test]
+    fn test_single_line() {
+        let (node, end, diagnostics) = parse("accTitle: My title \nnext");
+        assert_eq!(node.get_property("type"), Some("accTitle"));
+        assert_eq!(node.get_property("value"), Some("My title"));
+        assert_eq!(end, 19);
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_multi_line() {
+        let source = "accDescr {\n    First line\n    second line\n}\nnext";
+        let (node, end, diagnostics) = parse(source);
+        assert_eq!(node.get_property("value"), Some("First line\nsecond line"));
+        assert_eq!(&source[end..], "\nnext");
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_unclosed_multi_line() {
+        let (node, end, diagnostics) = parse("accDescr { never closed");
+        assert_eq!(node.get_property("value"), Some("never closed"));
+        assert_eq!(end, 23);
+        assert_eq!(diagnostics[0].code, DiagnosticCode::MissingElement);
+    }
+
+    #[test]
+    fn test_missing_colon() {
+        let (_, _, diagnostics) = parse("accTitle My title");
+        assert_eq!(diagnostics[0].code, DiagnosticCode::ExpectedToken);
+        assert_eq!(diagnostics[0].span, Span::new(0, 8));
+    }
+
+    #[test]
+    fn test_not_a_keyword() {
+        assert!(parse_statement("accTitles: x", 0, &mut Vec::new()).is_none());
+    }
+}
@@ -0,0 +1,156 @@
+//! English day and month names, and the German, French and Spanish names
+//! pasted in their place.
+//!
+//! Mermaid reads day and month names in English only. A word found in the
+//! translation tables here is most likely one of them written in another
+//! language, so a diagnostic can name the English word to use instead.
+
+/// The English day names, Monday first, as Mermaid spells them.
+pub(crate) const DAYS: [&str; 7] = ["monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"];
+
+/// The English month names, as date formats spell them.
+pub(crate) const MONTHS: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June", "July", "August", "September", "October", "November",
+    "December",
+];
+
+/// Day names in other languages, lowercase: the name, its language and the
+/// index of the English name in [`DAYS`].
+const DAY_TRANSLATIONS: &[(&str, &str, usize)] = &[
+    ("montag", "German", 0),
+    ("dienstag", "German", 1),
+    ("mittwoch", "German", 2),
+    ("donnerstag", "German", 3),
+    ("freitag", "German", 4),
+    ("samstag", "German", 5),
+    ("sonnabend", "German", 5),
+    ("sonntag", "German", 6),
+    ("lundi", "French", 0),
+    ("mardi", "French", 1),
+    ("mercredi", "French", 2),
+    ("jeudi", "French", 3),
+    ("vendredi", "French", 4),
+    ("samedi", "French", 5),
+    ("dimanche", "French", 6),
+    ("lunes", "Spanish", 0),
+    ("martes", "Spanish", 1),
+    ("miércoles", "Spanish", 2),
+    ("miercoles", "Spanish", 2),
+    ("jueves", "Spanish", 3),
+    ("viernes", "Spanish", 4),
+    ("sábado", "Spanish", 5),
+    ("sabado", "Spanish", 5),
+    ("domingo", "Spanish", 6),
+];
+
+/// Month names in other languages, lowercase, as for [`DAY_TRANSLATIONS`].
+/// Names spelled as in English, such as German `April`, are left out.
+const MONTH_TRANSLATIONS: &[(&str, &str, usize)] = &[
+    ("januar", "German", 0),
+    ("februar", "German", 1),
+    ("märz", "German", 2),
+    ("maerz", "German", 2),
+    ("mai", "German", 4),
+    ("juni", "German", 5),
+    ("juli", "German", 6),
+    ("oktober", "German", 9),
+    ("dezember", "German", 11),
+    ("janvier", "French", 0),
+    ("février", "French", 1),
+    ("fevrier", "French", 1),
+    ("mars", "French", 2),
+    ("avril", "French", 3),
+    ("mai", "French", 4),
+    ("juin", "French", 5),
+    ("juillet", "French", 6),
+    ("août", "French", 7),
+    ("aout", "French", 7),
+    ("septembre", "French", 8),
+    ("octobre", "French", 9),
+    ("novembre", "French", 10),
+    ("décembre", "French", 11),
+    ("decembre", "French", 11),
+    ("enero", "Spanish", 0),
+    ("febrero", "Spanish", 1),
+    ("marzo", "Spanish", 2),
+    ("abril", "Spanish", 3),
+    ("mayo", "Spanish", 4),
+    ("junio", "Spanish", 5),
+    ("julio", "Spanish", 6),
+    ("agosto", "Spanish", 7),
+    ("septiembre", "Spanish", 8),
+    ("setiembre", "Spanish", 8),
+    ("octubre", "Spanish", 9),
+    ("noviembre", "Spanish", 10),
+    ("diciembre", "Spanish", 11),
+];
+
+/// A word read as another language's name for an English day or month.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Translation {
+    /// `"day"` or `"month"`.
+    pub(crate) kind: &'static str,
+    /// The English name, as Mermaid spells it.
+    pub(crate) english: &'static str,
+    /// The languages the word is a name in, e.g. German and French `mai`.
+    pub(crate) languages: Vec<&'static str>,
+}
+
+impl Translation {
+    /// Returns the message explaining that `word` must be written in
+    /// English, e.g. "Mermaid only accepts English day names; 'Montag' looks
+    /// like German for 'monday'".
+    pub(crate) fn message(&self, word: &str) -> String {
+        format!(
+            "Mermaid only accepts English {} names; '{}' looks like {} for '{}'",
+            self.kind,
+            word,
+            self.languages.join(" or "),
+            self.english
+        )
+    }
+}
+
+/// Returns the English day `word` names in German, French or Spanish,
+/// ignoring case.
+pub(crate) fn translated_day(word: &str) -> Option<Translation> {
+    translate(word, "day", DAY_TRANSLATIONS, &DAYS)
+}
+
+/// Returns the English month `word` names in German, French or Spanish,
+/// ignoring case.
+pub(crate) fn translated_month(word: &str) -> Option<Translation> {
+    translate(word, "month", MONTH_TRANSLATIONS, &MONTHS)
+}
+
+fn translate(
+    word: &str,
+    kind: &'static str,
+    table: &[(&str, &'static str, usize)],
+    english: &[&'static str],
+) -> Option<Translation> {
+    let word = word.to_lowercase();
+    let matches: Vec<_> = table.iter().filter(|(name, _, _)| *name == word).collect();
+    let index = matches.first()?.2;
+    Some(Translation {
+        kind,
+        english: english[index],
+        languages: matches.iter().map(|(_, language, _)| *language).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translations() {
+        let montag = translated_day("Montag").unwrap();
+        assert_eq!(montag.message("Montag"), "Mermaid only accepts English day names; 'Montag' looks like German for 'monday'");
+        assert_eq!(translated_day("SÁBADO").unwrap().english, "saturday");
+        assert_eq!(translated_month("Mai").unwrap().languages, ["German", "French"]);
+        assert_eq!(translated_month("décembre").unwrap().english, "December");
+        assert_eq!(translated_day("monday"), None);
+        assert_eq!(translated_day("Mai"), None);
+    }
+}
@@ -0,0 +1,672 @@
+//! `click` statements shared by the flowchart and class diagram parsers.
+//!
+//! After `click <id>` comes one of:
+//!
+//! - `href "url" ["tooltip"] [target]`, or the same without `href`
+//! - `call name[(args)] ["tooltip"]`, or the same without `call`
+//!
+//! where `target` is `_self`, `_blank`, `_parent` or `_top`. Like the
+//! accessibility statements, the definition is read straight from the
+//! source, so quoting and call arguments don't depend on each lexer.
+//!
+//! Class diagrams also accept the older `link <id> "url" ["tooltip"]
+//! [target]` and `callback <id> "name" ["tooltip"]` statements, which
+//! [`parse_legacy_action`] reads into the same [`ClickAction`].
+
+use crate::ast::{AstNode, Span};
+use crate::diagnostic::{Diagnostic, DiagnosticCode};
+use crate::parser::lexer::Cursor;
+
+/// The link targets accepted after a URL.
+const LINK_TARGETS: &[&str] = &["_self", "_blank", "_parent", "_top"];
+
+/// The action of a `click` statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ClickAction {
+    /// Opens a URL.
+    Link {
+        url: String,
+        tooltip: Option<String>,
+        target: Option<String>,
+    },
+    /// Calls a JavaScript function, with arguments when they're given.
+    Callback {
+        name: String,
+        args: Option<Vec<String>>,
+        tooltip: Option<String>,
+    },
+}
+
+impl ClickAction {
+    /// Stores the action on a `click` statement node.
+    ///
+    /// Sets `action` (`link` or `callback`) and `url`, `callback`, `args`,
+    /// `tooltip` and `link_target` as present. `args` is a JSON array of strings,
+    /// since arguments may contain commas.
+    pub(crate) fn add_properties(&self, node: &mut AstNode) {
+        match self {
+            ClickAction::Link { url, tooltip, target } => {
+                node.add_property("action", "link");
+                node.add_property("url", url);
+                if let Some(target) = target {
+                    node.add_property("link_target", target);
+                }
+                if let Some(tooltip) = tooltip {
+                    node.add_property("tooltip", tooltip);
+                }
+            }
+            ClickAction::Callback { name, args, tooltip } => {
+                node.add_property("action", "callback");
+                node.add_property("callback", name);
+                if let Some(args) = args {
+                    node.add_property("args", serde_json::to_string(args).unwrap_or_default());
+                }
+                if let Some(tooltip) = tooltip {
+                    node.add_property("tooltip", tooltip);
+                }
+            }
+        }
+    }
+
+    /// Reads back the action stored by [`add_properties`](Self::add_properties).
+    pub(crate) fn from_properties(node: &AstNode) -> Option<ClickAction> {
+        let tooltip = node.get_property("tooltip").map(str::to_string);
+        match node.get_property("action")? {
+            "link" => Some(ClickAction::Link {
+                url: node.get_property("url")?.to_string(),
+                tooltip,
+                target: node.get_property("link_target").map(str::to_string),
+            }),
+            "callback" => Some(ClickAction::Callback {
+                name: node.get_property("callback")?.to_string(),
+                args: node.get_property("args").and_then(|args| serde_json::from_str(args).ok()),
+                tooltip,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the `click` statement for `id` that performs this action.
+    pub(crate) fn to_click_statement(&self, id: &str) -> String {
+        let (mut statement, tooltip) = match self {
+            ClickAction::Link { url, tooltip, .. } => (format!("click {} href \"{}\"", id, url), tooltip),
+            ClickAction::Callback { name, args, tooltip } => {
+                let args: Vec<String> = args
+                    .iter()
+                    .flatten()
+                    .map(|arg| {
+                        if arg.is_empty() || arg.contains([',', '(', ')', ' ']) {
+                            format!("\"{}\"", arg)
+                        } else {
+                            arg.clone()
+                        }
+                    })
+                    .collect();
+                (format!("click {} call {}({})", id, name, args.join(", ")), tooltip)
+            }
+        };
+        if let Some(tooltip) = tooltip {
+            statement.push_str(&format!(" \"{}\"", tooltip));
+        }
+        if let ClickAction::Link { target: Some(target), .. } = self {
+            statement.push_str(&format!(" {}", target));
+        }
+        statement
+    }
+}
+
+/// A piece of a click definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Item<'a> {
+    /// A bare word, such as `href`, a callback name or a link target.
+    Word(&'a str),
+    /// A double-quoted string, without its quotes.
+    Str(&'a str),
+    /// Parenthesized call arguments, split on top-level commas.
+    Args(Vec<String>),
+}
+
+/// Parses the click definition starting at `start` and running to the end
+/// of the line or to a `;` outside quotes.
+///
+/// Returns the action, if one could be read, and the span of the
+/// definition. Misordered or malformed parts are reported to
+/// `diagnostics`; `click_span` covers `click <id>` and is used when the
+/// definition is missing.
+pub(crate) fn parse_action(
+    source: &str,
+    click_span: Span,
+    start: usize,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> (Option<ClickAction>, Span) {
+    let definition = definition_span(source, start);
+
+    let items = scan(source, definition, diagnostics);
+    let Some(((first, first_span), mut rest)) = items.split_first().map(|(f, r)| (f, r.iter())) else {
+        diagnostics.push(
+            Diagnostic::error(DiagnosticCode::MissingElement, "Missing action after 'click'", click_span)
+                .with_note("write 'click <id> href \"url\"' or 'click <id> call callback()'"),
+        );
+        return (None, definition);
+    };
+
+    let action = match first {
+        Item::Word(keyword) if keyword.eq_ignore_ascii_case("href") => {
+            parse_link(*first_span, &mut rest, None, diagnostics)
+        }
+        Item::Word(keyword) if keyword.eq_ignore_ascii_case("call") => {
+            parse_callback(*first_span, &mut rest, None, diagnostics)
+        }
+        Item::Str(text) => match rest.clone().next() {
+            // `"tooltip" href "url"`: the tooltip came first
+            Some((Item::Word(keyword), keyword_span))
+                if keyword.eq_ignore_ascii_case("href") || keyword.eq_ignore_ascii_case("call") =>
+            {
+                let is_link = keyword.eq_ignore_ascii_case("href");
+                diagnostics.push(
+                    Diagnostic::error(
+                        DiagnosticCode::InvalidSyntax,
+                        format!(
+                            "Tooltip must come after the {}",
+                            if is_link { "URL" } else { "callback" }
+                        ),
+                        *first_span,
+                    )
+                    .with_note(if is_link {
+                        "the order is 'click <id> href \"url\" \"tooltip\" _blank'"
+                    } else {
+                        "the order is 'click <id> call callback() \"tooltip\"'"
+                    }),
+                );
+                rest.next();
+                let tooltip = Some(text.to_string());
+                if is_link {
+                    parse_link(*keyword_span, &mut rest, tooltip, diagnostics)
+                } else {
+                    parse_callback(*keyword_span, &mut rest, tooltip, diagnostics)
+                }
+            }
+            _ => link_tail(text, &mut rest, None, diagnostics),
+        },
+        Item::Word(word) if LINK_TARGETS.contains(word) => {
+            diagnostics.push(target_without_url(word, *first_span));
+            None
+        }
+        Item::Word(name) => callback_tail(name, &mut rest, None),
+        Item::Args(_) => {
+            diagnostics.push(Diagnostic::error(
+                DiagnosticCode::MissingElement,
+                "Missing callback name before the arguments",
+                *first_span,
+            ));
+            None
+        }
+    };
+
+    for (item, span) in rest {
+        diagnostics.push(match (item, &action) {
+            (Item::Word(word), Some(ClickAction::Callback { .. })) if LINK_TARGETS.contains(word) => {
+                target_without_url(word, *span)
+            }
+            _ => Diagnostic::error(
+                DiagnosticCode::UnexpectedToken,
+                format!("Unexpected '{}' in click statement", span.text(source)),
+                *span,
+            ),
+        });
+    }
+
+    (action, definition)
+}
+
+/// Parses the definition of a legacy `link` or `callback` statement,
+/// starting at `start` and running to the end of the line.
+///
+/// `keyword` is `link` or `callback`; `statement_span` covers the keyword
+/// and the class name and is used when the definition is missing.
+pub(crate) fn parse_legacy_action(
+    source: &str,
+    keyword: &str,
+    statement_span: Span,
+    start: usize,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> (Option<ClickAction>, Span) {
+    let definition = definition_span(source, start);
+
+    let items = scan(source, definition, diagnostics);
+    let mut rest = items.iter();
+    let is_link = keyword.eq_ignore_ascii_case("link");
+    let action = match rest.next() {
+        Some((Item::Str(url), _)) if is_link => link_tail(url, &mut rest, None, diagnostics),
+        Some((Item::Str(name), _)) => {
+            let tooltip = match rest.clone().next() {
+                Some((Item::Str(text), _)) => {
+                    rest.next();
+                    Some(text.to_string())
+                }
+                _ => None,
+            };
+            Some(ClickAction::Callback {
+                name: name.to_string(),
+                args: None,
+                tooltip,
+            })
+        }
+        first => {
+            diagnostics.push(Diagnostic::error(
+                DiagnosticCode::MissingElement,
+                format!(
+                    "Missing quoted {} after '{}'",
+                    if is_link { "URL" } else { "callback name" },
+                    keyword
+                ),
+                first.map_or(statement_span, |(_, span)| *span),
+            ));
+            return (None, definition);
+        }
+    };
+
+    for (_, span) in rest {
+        diagnostics.push(Diagnostic::error(
+            DiagnosticCode::UnexpectedToken,
+            format!("Unexpected '{}' in {} statement", span.text(source), keyword),
+            *span,
+        ));
+    }
+
+    (action, definition)
+}
+
+type Items<'s, 'a> = std::slice::Iter<'s, (Item<'a>, Span)>;
+
+/// Parses `"url" ["tooltip"] [target]` after `href`.
+fn parse_link(
+    href_span: Span,
+    rest: &mut Items,
+    tooltip: Option<String>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<ClickAction> {
+    match rest.clone().next() {
+        Some((Item::Str(url), _)) => {
+            rest.next();
+            link_tail(url, rest, tooltip, diagnostics)
+        }
+        Some((Item::Word(word), span)) if LINK_TARGETS.contains(word) => {
+            diagnostics.push(Diagnostic::error(
+                DiagnosticCode::InvalidSyntax,
+                format!("Link target '{}' must come after the URL", word),
+                *span,
+            ));
+            rest.next();
+            match rest.clone().next() {
+                Some((Item::Str(url), _)) => {
+                    rest.next();
+                    link_tail(url, rest, tooltip, diagnostics)
+                }
+                _ => None,
+            }
+        }
+        _ => {
+            diagnostics.push(Diagnostic::error(
+                DiagnosticCode::MissingElement,
+                "Missing quoted URL after 'href'",
+                href_span,
+            ));
+            None
+        }
+    }
+}
+
+/// Parses the optional `"tooltip"` and link target after a URL.
+fn link_tail(
+    url: &str,
+    rest: &mut Items,
+    mut tooltip: Option<String>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<ClickAction> {
+    if let Some((Item::Str(text), _)) = rest.clone().next() {
+        rest.next();
+        tooltip = Some(text.to_string());
+    }
+
+    let mut target = None;
+    if let Some((Item::Word(word), span)) = rest.clone().next() {
+        rest.next();
+        if LINK_TARGETS.contains(word) {
+            target = Some(word.to_string());
+        } else {
+            diagnostics.push(
+                Diagnostic::error(DiagnosticCode::InvalidValue, format!("Unknown link target '{}'", word), *span)
+                    .with_note("link targets are _self, _blank, _parent and _top"),
+            );
+        }
+
+        if let Some((Item::Str(_), span)) = rest.clone().next() {
+            rest.next();
+            diagnostics.push(Diagnostic::error(
+                DiagnosticCode::InvalidSyntax,
+                format!("Tooltip must come before the link target '{}'", word),
+                *span,
+            ));
+        }
+    }
+
+    Some(ClickAction::Link {
+        url: url.to_string(),
+        tooltip,
+        target,
+    })
+}
+
+/// Parses `name[(args)] ["tooltip"]` after `call`.
+fn parse_callback(
+    call_span: Span,
+    rest: &mut Items,
+    tooltip: Option<String>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<ClickAction> {
+    match rest.clone().next() {
+        Some((Item::Word(name), _)) => {
+            rest.next();
+            callback_tail(name, rest, tooltip)
+        }
+        _ => {
+            diagnostics.push(Diagnostic::error(
+                DiagnosticCode::MissingElement,
+                "Missing callback name after 'call'",
+                call_span,
+            ));
+            None
+        }
+    }
+}
+
+/// Parses the optional arguments and `"tooltip"` after a callback name.
+fn callback_tail(
+    name: &str,
+    rest: &mut Items,
+    mut tooltip: Option<String>,
+) -> Option<ClickAction> {
+    let mut args = None;
+    if let Some((Item::Args(list), _)) = rest.clone().next() {
+        rest.next();
+        args = Some(list.clone());
+    }
+    if tooltip.is_none() {
+        if let Some((Item::Str(text), _)) = rest.clone().next() {
+            rest.next();
+            tooltip = Some(text.to_string());
+        }
+    }
+
+    Some(ClickAction::Callback {
+        name: name.to_string(),
+        args,
+        tooltip,
+    })
+}
+
+fn target_without_url(target: &str, span: Span) -> Diagnostic {
+    Diagnostic::error(
+        DiagnosticCode::InvalidSyntax,
+        format!("Link target '{}' is only valid after a URL", target),
+        span,
+    )
+    .with_note("targets apply to links: 'click <id> href \"url\" \"tooltip\" _blank'")
+}
+
+/// Splits a definition into words, quoted strings and call arguments.
+/// Returns the span of the definition starting at `start`: the rest of the
+/// line, up to a `;` outside quotes, less trailing whitespace.
+fn definition_span(source: &str, start: usize) -> Span {
+    let line_end = source[start..].find('\n').map_or(source.len(), |i| start + i);
+    let mut cursor = Cursor::with_range(source, start, line_end);
+    while !cursor.is_eof() && cursor.peek_char() != Some(';') {
+        if !cursor.skip_quoted() {
+            cursor.bump();
+        }
+    }
+    Span::new(start, start + source[start..cursor.offset()].trim_end().len())
+}
+
+fn scan<'a>(source: &'a str, definition: Span, diagnostics: &mut Vec<Diagnostic>) -> Vec<(Item<'a>, Span)> {
+    let mut items = Vec::new();
+    let mut cursor = Cursor::with_range(source, definition.start, definition.end);
+    loop {
+        cursor.skip_whitespace();
+        let start = cursor.offset();
+        let item = match cursor.peek_char() {
+            None => break,
+            Some('"') => match cursor.scan_string('"').unwrap_or(Err("")) {
+                Ok(text) => Item::Str(text),
+                Err(text) => {
+                    diagnostics.push(Diagnostic::error(
+                        DiagnosticCode::UnterminatedString,
+                        "Missing closing '\"'",
+                        Span::new(start, definition.end),
+                    ));
+                    Item::Str(text)
+                }
+            },
+            Some('(') => match scan_args(&mut cursor) {
+                Ok(args) => Item::Args(args),
+                Err(args) => {
+                    diagnostics.push(
+                        Diagnostic::error(
+                            DiagnosticCode::MissingElement,
+                            "Missing ')' after the call arguments",
+                            Span::new(start, definition.end),
+                        )
+                        .with_note("quoted arguments may contain parentheses, but unquoted ones must balance"),
+                    );
+                    Item::Args(args)
+                }
+            },
+            Some(')') => {
+                cursor.bump();
+                diagnostics.push(Diagnostic::error(
+                    DiagnosticCode::UnexpectedToken,
+                    "Unbalanced ')' in click statement",
+                    Span::new(start, cursor.offset()),
+                ));
+                continue;
+            }
+            Some(_) => Item::Word(cursor.eat_while(|c| !c.is_whitespace() && !matches!(c, '"' | '(' | ')'))),
+        };
+        items.push((item, Span::new(start, cursor.offset())));
+    }
+    items
+}
+
+/// Scans parenthesized call arguments, splitting them on commas outside
+/// quotes and nested parentheses. Like Mermaid, each argument is
+/// [unquoted](unquote).
+///
+/// Returns the arguments read so far as the error if the `(` isn't closed.
+fn scan_args(cursor: &mut Cursor) -> Result<Vec<String>, Vec<String>> {
+    cursor.bump(); // consume '('
+    let mut args = Vec::new();
+    let mut arg_start = cursor.offset();
+    let mut depth = 0;
+
+    loop {
+        if cursor.skip_quoted() {
+            continue;
+        }
+        let offset = cursor.offset();
+        match cursor.bump() {
+            None => {
+                args.push(unquote(&cursor.source()[arg_start..offset]));
+                return Err(args);
+            }
+            Some('(') => depth += 1,
+            Some(')') if depth > 0 => depth -= 1,
+            Some(')') => {
+                let arg = &cursor.source()[arg_start..offset];
+                // `()` has no arguments rather than one empty one
+                if !(args.is_empty() && arg.trim().is_empty()) {
+                    args.push(unquote(arg));
+                }
+                return Ok(args);
+            }
+            Some(',') if depth == 0 => {
+                args.push(unquote(&cursor.source()[arg_start..offset]));
+                arg_start = cursor.offset();
+            }
+            Some(_) => {}
+        }
+    }
+}
+
+/// Trims a call argument and removes its surrounding double quotes.
+fn unquote(arg: &str) -> String {
+    let arg = arg.trim();
+    arg.strip_prefix('"')
+        .and_then(|a| a.strip_suffix('"'))
+        .unwrap_or(arg)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(definition: &str) -> (Option<ClickAction>, Vec<Diagnostic>) {
+        let source = format!("click A {}", definition);
+        let mut diagnostics = Vec::new();
+        let (action, _) = parse_action(&source, Span::new(0, 7), 8, &mut diagnostics);
+        (action, diagnostics)
+    }
+
+    fn link(url: &str, tooltip: Option<&str>, target: Option<&str>) -> Option<ClickAction> {
+        Some(ClickAction::Link {
+            url: url.to_string(),
+            tooltip: tooltip.map(str::to_string),
+            target: target.map(str::to_string),
+        })
+    }
+
+    fn callback(name: &str, args: Option<&[&str]>, tooltip: Option<&str>) -> Option<ClickAction> {
+        Some(ClickAction::Callback {
+            name: name.to_string(),
+            args: args.map(|args| args.iter().map(|a| a.to_string()).collect()),
+            tooltip: tooltip.map(str::to_string),
+        })
+    }
+
+    #[test]
+    fn test_valid_forms() {
+        let cases = [
+            ("callback", callback("callback", None, None)),
+            ("callback \"Tooltip\"", callback("callback", None, Some("Tooltip"))),
+            (
+                "call callback(\"arg1, with comma\", 3) \"Tooltip\"",
+                callback("callback", Some(&["arg1, with comma", "3"]), Some("Tooltip")),
+            ),
+            ("call callback()", callback("callback", Some(&[]), None)),
+            (
+                "href \"https://www.github.com\" \"Tooltip\" _blank",
+                link("https://www.github.com", Some("Tooltip"), Some("_blank")),
+            ),
+            ("\"https://www.github.com\" _top", link("https://www.github.com", None, Some("_top"))),
+        ];
+        for (definition, expected) in cases {
+            let (action, diagnostics) = parse(definition);
+            assert_eq!(action, expected, "{}", definition);
+            assert!(diagnostics.is_empty(), "{}: {:?}", definition, diagnostics);
+        }
+    }
+
+    #[test]
+    fn test_semicolon_ends_definition() {
+        let (action, diagnostics) = parse("href \"https://x.com\" _blank; B --> C");
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        assert_eq!(action, link("https://x.com", None, Some("_blank")));
+        let (action, _) = parse("href \"a;b\" \"c;d\";");
+        assert_eq!(action, link("a;b", Some("c;d"), None));
+        assert_eq!(definition_span("x href \"u\" ; y", 2), Span::new(2, 10));
+    }
+
+    #[test]
+    fn test_quote_aware_args() {
+        let (action, diagnostics) = parse("call show(\"a (b)\", 'c, d', f(1, 2))");
+        assert!(diagnostics.is_empty());
+        assert_eq!(action, callback("show", Some(&["a (b)", "'c, d'", "f(1, 2)"]), None));
+    }
+
+    #[test]
+    fn test_tooltip_before_url() {
+        let (action, diagnostics) = parse("\"Tooltip\" href \"https://example.com\"");
+        assert_eq!(diagnostics[0].message, "Tooltip must come after the URL");
+        assert_eq!(diagnostics[0].span, Span::new(8, 17));
+        assert_eq!(action, link("https://example.com", Some("Tooltip"), None));
+    }
+
+    #[test]
+    fn test_target_without_href() {
+        let (_, diagnostics) = parse("call callback() _blank");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Link target '_blank' is only valid after a URL");
+        assert_eq!(diagnostics[0].span, Span::new(24, 30));
+    }
+
+    #[test]
+    fn test_unbalanced_call_arguments() {
+        let (action, diagnostics) = parse("call callback(\"a)\", 3");
+        assert_eq!(diagnostics[0].code, DiagnosticCode::MissingElement);
+        assert_eq!(diagnostics[0].message, "Missing ')' after the call arguments");
+        assert_eq!(action, callback("callback", Some(&["a)", "3"]), None));
+    }
+
+    #[test]
+    fn test_tooltip_after_target() {
+        let (action, diagnostics) = parse("href \"https://example.com\" _blank \"Tooltip\"");
+        assert_eq!(diagnostics[0].message, "Tooltip must come before the link target '_blank'");
+        assert_eq!(action, link("https://example.com", None, Some("_blank")));
+    }
+
+    #[test]
+    fn test_missing_action() {
+        let (action, diagnostics) = parse("");
+        assert!(action.is_none());
+        assert_eq!(diagnostics[0].span, Span::new(0, 7));
+    }
+
+    #[test]
+    fn test_legacy_forms() {
+        let legacy = |keyword: &str, definition: &str| {
+            let source = format!("{} A {}", keyword, definition);
+            let start = keyword.len() + 3;
+            let mut diagnostics = Vec::new();
+            let (action, _) =
+                parse_legacy_action(&source, keyword, Span::new(0, start - 1), start, &mut diagnostics);
+            (action, diagnostics)
+        };
+
+        let (action, diagnostics) = legacy("link", "\"https://example.com\" \"Tooltip\" _blank");
+        assert!(diagnostics.is_empty());
+        assert_eq!(action, link("https://example.com", Some("Tooltip"), Some("_blank")));
+        let (action, _) = legacy("callback", "\"callback\" \"Tooltip\"");
+        assert_eq!(action, callback("callback", None, Some("Tooltip")));
+
+        let (action, diagnostics) = legacy("callback", "callback");
+        assert!(action.is_none());
+        assert_eq!(diagnostics[0].message, "Missing quoted callback name after 'callback'");
+        assert_eq!(diagnostics[0].span, Span::new(11, 19));
+        let (_, diagnostics) = legacy("callback", "\"callback\" \"Tooltip\" _blank");
+        assert_eq!(diagnostics[0].message, "Unexpected '_blank' in callback statement");
+    }
+
+    #[test]
+    fn test_to_click_statement() {
+        let action = link("https://example.com", Some("Tooltip"), Some("_blank")).unwrap();
+        assert_eq!(action.to_click_statement("A"), "click A href \"https://example.com\" \"Tooltip\" _blank");
+        let action = callback("show", Some(&["a, b", "3"]), None).unwrap();
+        assert_eq!(action.to_click_statement("A"), "click A call show(\"a, b\", 3)");
+        assert_eq!(callback("show", None, None).unwrap().to_click_statement("A"), "click A call show()");
+
+        let mut node = AstNode::new(crate::ast::NodeKind::Statement, Span::default());
+        action.add_properties(&mut node);
+        assert_eq!(ClickAction::from_properties(&node), Some(action));
+    }
+}
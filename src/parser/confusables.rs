@@ -0,0 +1,171 @@
+//! The opt-in `confusable-identifiers` lint.
+//!
+//! Ids that differ only by case or by invisible characters look like one
+//! element to the reader, but Mermaid renders them as separate ones. Ids are
+//! compared folded: invisible characters removed, fullwidth ASCII forms
+//! replaced by ASCII, and the rest lowercased. There are no Unicode
+//! normalization tables in the crate, so other compatibility forms are not
+//! folded.
+
+use std::collections::HashMap;
+
+use crate::ast::{Ast, AstNode, NodeKind, Span};
+use crate::detector::DiagramType;
+use crate::diagnostic::{Diagnostic, OptInCheck, RelatedDiagnostic};
+use crate::preprocess::is_invisible;
+
+/// Warns once per group of ids that fold to the same form but are spelled
+/// differently. The warning is at the first use of the second spelling, with
+/// the first use of each other spelling as related information.
+///
+/// Applies to flowchart node ids, state names, class names and sequence
+/// participants; other diagrams produce nothing.
+pub(crate) fn validate(diagram_type: DiagramType, ast: &Ast) -> Vec<Diagnostic> {
+    let applies = matches!(
+        diagram_type,
+        DiagramType::Flowchart
+            | DiagramType::FlowchartV2
+            | DiagramType::FlowchartElk
+            | DiagramType::State
+            | DiagramType::StateDiagram
+            | DiagramType::Class
+            | DiagramType::ClassDiagram
+            | DiagramType::Sequence
+    );
+    if !applies {
+        return Vec::new();
+    }
+
+    // Spellings in order of first use, per folded form
+    let mut groups: Vec<Vec<(&str, Span)>> = Vec::new();
+    let mut by_folded: HashMap<String, usize> = HashMap::new();
+    for (name, span) in identifiers(ast) {
+        let index = *by_folded.entry(fold(name)).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        if !groups[index].iter().any(|(spelling, _)| *spelling == name) {
+            groups[index].push((name, span));
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter(|spellings| spellings.len() > 1)
+        .map(|spellings| {
+            let listed: Vec<String> = spellings.iter().map(|(s, _)| format!("'{}'", s.escape_debug())).collect();
+            let mut diagnostic = OptInCheck::ConfusableIdentifiers
+                .diagnostic(
+                    format!(
+                        "Identifiers {} differ only by case or invisible characters; Mermaid treats them as separate elements",
+                        listed.join(", ")
+                    ),
+                    spellings[1].1,
+                )
+                .with_note("use one spelling if they are the same element, or rename one so they are clearly different");
+            for (i, (spelling, span)) in spellings.iter().enumerate() {
+                if i != 1 {
+                    diagnostic = diagnostic.with_related(RelatedDiagnostic::new(
+                        format!("'{}' is used here", spelling.escape_debug()),
+                        *span,
+                    ));
+                }
+            }
+            diagnostic
+        })
+        .collect()
+}
+
+/// Returns every declared or referenced id with the span of its use, in
+/// source order.
+fn identifiers(ast: &Ast) -> Vec<(&str, Span)> {
+    fn collect<'a>(ast: &Ast, node: &'a AstNode, ids: &mut Vec<(&'a str, Span)>) {
+        let names = match node.kind {
+            NodeKind::Node | NodeKind::State | NodeKind::Participant => vec![node.id()],
+            NodeKind::Class => vec![node.get_property("name")],
+            NodeKind::Relationship | NodeKind::Transition | NodeKind::Message => vec![node.from(), node.to()],
+            _ => Vec::new(),
+        };
+        for (i, name) in names.into_iter().enumerate() {
+            if let Some(name) = name.filter(|n| !n.is_empty()) {
+                ids.push((name, name_span(ast, node, name, i > 0)));
+            }
+        }
+        for child in &node.children {
+            collect(ast, child, ids);
+        }
+    }
+    let mut ids = Vec::new();
+    collect(ast, &ast.root, &mut ids);
+    ids
+}
+
+/// Returns the span of `name` in a node's text, searching from the end for
+/// the `to` side of a link, falling back to the whole node.
+fn name_span(ast: &Ast, node: &AstNode, name: &str, from_end: bool) -> Span {
+    let text = ast.text_for_span(&node.span);
+    let offset = if from_end { text.rfind(name) } else { text.find(name) };
+    offset.map_or(node.span, |offset| Span::from_len(node.span.start + offset, name.len()))
+}
+
+/// Folds an id for comparison.
+fn fold(name: &str) -> String {
+    name.chars()
+        .filter(|&c| !is_invisible(c))
+        .map(|c| match c {
+            '\u{ff01}'..='\u{ff5e}' => char::from_u32(c as u32 - 0xfee0).unwrap_or(c),
+            _ => c,
+        })
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ParseOptions;
+
+    fn lint(code: &str) -> Vec<Diagnostic> {
+        let options = ParseOptions::default().with_confusable_identifier_warnings();
+        crate::parse(code, Some(options))
+            .diagnostics
+            .into_iter()
+            .filter(|d| d.message.starts_with("Identifiers"))
+            .collect()
+    }
+
+    #[test]
+    fn test_ids_differing_by_case() {
+        let code = "flowchart TD\n    UserService --> Db\n    Api --> Userservice\n    Api --> USERSERVICE";
+        let diagnostics = lint(code);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "Identifiers 'UserService', 'Userservice', 'USERSERVICE' differ only by case or invisible characters; \
+             Mermaid treats them as separate elements"
+        );
+        assert_eq!(diagnostics[0].span.text(code), "Userservice");
+        assert_eq!(diagnostics[0].related.len(), 2);
+        assert_eq!(diagnostics[0].related[0].span.text(code), "UserService");
+    }
+
+    #[test]
+    fn test_ids_differing_by_invisible_characters() {
+        let code = "classDiagram\n    class Api\n    class A\u{200b}pi\n    Api <|-- \u{ff21}pi";
+        let diagnostics = lint(code);
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.contains("'A\\u{200b}pi'"));
+        assert!(diagnostics[0].message.contains("'\u{ff21}pi'"));
+        assert_eq!(diagnostics[0].span.text(code), "A\u{200b}pi");
+
+        let code = "sequenceDiagram\n    participant Api\n    A\u{2060}pi->>Api: hi";
+        assert_eq!(lint(code).len(), 1);
+    }
+
+    #[test]
+    fn test_distinct_ids_do_not_group() {
+        assert!(lint("stateDiagram-v2\n    A --> B\n    B --> A\n    state A").is_empty());
+        assert!(lint("flowchart TD\n    A --> B").is_empty());
+        assert!(crate::parse("flowchart TD\n    Api --> API", None).diagnostics.is_empty());
+    }
+}
@@ -0,0 +1,63 @@
+//! Links and messages split across two lines.
+//!
+//! Breaking `A --> B` or `Alice->>Bob: hi` after the operator leaves the
+//! first line without its other end, and the second line is then read as a
+//! statement of its own. The diagnostic points at the operator and, when the
+//! next line starts with an id that would complete it, offers to join them.
+
+use crate::ast::Span;
+use crate::diagnostic::{Diagnostic, DiagnosticCode, TextEdit};
+
+/// Returns the error for `operator`, which ends its line with nothing after
+/// it. `message` says what is missing, e.g. "Edge is missing its target".
+///
+/// A next line starting with a word other than one of `keywords` gets a
+/// note and a fix that joins it onto the operator's line.
+pub(crate) fn missing_end(source: &str, operator: Span, message: &str, keywords: &[&str]) -> Diagnostic {
+    let diagnostic = Diagnostic::error(
+        DiagnosticCode::UnexpectedEof,
+        format!("{}; did you mean to continue on the same line?", message),
+        operator,
+    );
+    match continuation(source, operator.end, keywords) {
+        Some((gap, word)) => diagnostic
+            .with_note(format!("the next line starts with '{}'; join the lines to complete the statement", word))
+            .with_fix(TextEdit::new(gap, " ")),
+        None => diagnostic,
+    }
+}
+
+/// Returns the line break and indentation between `end` and the next line,
+/// and the word that line starts with, if the rest of the line after `end`
+/// is blank and the next line starts with an id.
+fn continuation<'a>(source: &'a str, end: usize, keywords: &[&str]) -> Option<(Span, &'a str)> {
+    let rest = &source[end..];
+    let line_end = rest.find('\n')?;
+    if !rest[..line_end].trim().is_empty() {
+        return None;
+    }
+    let next = &rest[line_end + 1..];
+    let text = next.trim_start_matches([' ', '\t']);
+    let len = text.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(text.len());
+    let word = &text[..len];
+    if word.is_empty() || keywords.iter().any(|k| k.eq_ignore_ascii_case(word)) {
+        return None;
+    }
+    let start = end + line_end + 1 + (next.len() - text.len());
+    Some((Span::new(end, start), word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_continuation() {
+        let source = "A -->  \n    B --> C";
+        assert_eq!(continuation(source, 5, &[]), Some((Span::new(5, 12), "B")));
+        assert_eq!(continuation(source, 5, &["b"]), None);
+        assert_eq!(continuation("A -->\n\n    B", 5, &[]), None);
+        assert_eq!(continuation("A --> x\nB", 5, &[]), None);
+        assert_eq!(continuation("A -->", 5, &[]), None);
+    }
+}
@@ -1,6 +1,10 @@
 //! Common lexer utilities.
 //!
 //! This module provides common utilities for building diagram-specific lexers.
+//! Each diagram's tokens are a `logos` enum, run by [`tokenize`] into
+//! [`Token`]s; quoted strings are scanned by [`crate::parser::quotes`].
+
+use logos::Logos;
 
 use crate::ast::Span;
 
@@ -34,6 +38,26 @@ impl<T> Token<T> {
     }
 }
 
+/// Runs the `logos` lexer for the token kind `K` over `source`.
+///
+/// Text no token matches is skipped; parsers see the gap between the spans
+/// of the tokens around it.
+pub fn tokenize<'s, K>(source: &'s str) -> Vec<Token<K>>
+where
+    K: Logos<'s, Source = str>,
+    K::Extras: Default,
+{
+    let mut lexer = K::lexer(source);
+    let mut tokens = Vec::new();
+    while let Some(result) = lexer.next() {
+        if let Ok(kind) = result {
+            let span = lexer.span();
+            tokens.push(Token::new(kind, Span::new(span.start, span.end), lexer.slice()));
+        }
+    }
+    tokens
+}
+
 /// A position in the source text.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub struct Position {
@@ -181,6 +205,118 @@ impl<'a> BaseLexer<'a> {
     }
 }
 
+/// A cursor over part of a source text, for hand-written scanners.
+///
+/// Unlike [`BaseLexer`], it doesn't track lines and can start anywhere in
+/// the source, so parsers can scan the text of a single statement in place
+/// and keep offsets into the whole source.
+#[derive(Debug, Clone)]
+pub struct Cursor<'a> {
+    source: &'a str,
+    pos: usize,
+    end: usize,
+}
+
+impl<'a> Cursor<'a> {
+    /// Creates a cursor over the whole source.
+    pub fn new(source: &'a str) -> Self {
+        Self::with_range(source, 0, source.len())
+    }
+
+    /// Creates a cursor over `source[start..end]`.
+    pub fn with_range(source: &'a str, start: usize, end: usize) -> Self {
+        let end = end.min(source.len());
+        Self {
+            source,
+            pos: start.min(end),
+            end,
+        }
+    }
+
+    /// Returns the whole source text.
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
+
+    /// Returns the offset of the cursor in the source.
+    pub fn offset(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns true if the cursor is at the end of its range.
+    pub fn is_eof(&self) -> bool {
+        self.pos >= self.end
+    }
+
+    /// Returns the text left in the range.
+    pub fn rest(&self) -> &'a str {
+        &self.source[self.pos..self.end]
+    }
+
+    /// Returns the next character without consuming it.
+    pub fn peek_char(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    /// Consumes and returns the next character.
+    pub fn bump(&mut self) -> Option<char> {
+        let c = self.peek_char()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    /// Consumes characters while the predicate is true and returns them.
+    pub fn eat_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let start = self.pos;
+        while self.peek_char().is_some_and(&predicate) {
+            self.bump();
+        }
+        &self.source[start..self.pos]
+    }
+
+    /// Skips whitespace, including newlines.
+    pub fn skip_whitespace(&mut self) {
+        self.eat_while(char::is_whitespace);
+    }
+
+    /// Scans a string delimited by `quote`, which must be the next
+    /// character, and returns its contents without the quotes.
+    ///
+    /// Strings don't span lines: an unterminated string stops before the
+    /// newline or at the end of the range, and its contents are returned as
+    /// the error. Returns `None` if the next character isn't `quote`.
+    pub fn scan_string(&mut self, quote: char) -> Option<Result<&'a str, &'a str>> {
+        if self.peek_char() != Some(quote) {
+            return None;
+        }
+        self.bump();
+        let contents = self.eat_while(|c| c != quote && c != '\n');
+        if self.peek_char() == Some(quote) {
+            self.bump();
+            Some(Ok(contents))
+        } else {
+            Some(Err(contents))
+        }
+    }
+
+    /// Scans an identifier: a letter or `_`, then letters, digits and `_`.
+    pub fn scan_identifier(&mut self) -> Option<&'a str> {
+        if !self.peek_char().is_some_and(patterns::is_ident_start) {
+            return None;
+        }
+        Some(self.eat_while(patterns::is_ident_continue))
+    }
+
+    /// Skips a string if one starts here, quoted with `"` or `'`. Returns
+    /// true if it did.
+    pub fn skip_quoted(&mut self) -> bool {
+        match self.peek_char() {
+            Some(quote @ ('"' | '\'')) => self.scan_string(quote).is_some(),
+            _ => false,
+        }
+    }
+}
+
 /// Utilities for common lexing patterns.
 pub mod patterns {
     use super::*;
@@ -205,6 +341,21 @@ pub mod patterns {
         ch.is_ascii_hexdigit()
     }
 
+    /// Returns the offset of the `}` closing a metadata block (`id@{ ... }`),
+    /// skipping braces inside quoted strings.
+    pub fn closing_brace(text: &str) -> Option<usize> {
+        let mut cursor = Cursor::new(text);
+        loop {
+            if cursor.skip_quoted() {
+                continue;
+            }
+            let offset = cursor.offset();
+            if cursor.bump()? == '}' {
+                return Some(offset);
+            }
+        }
+    }
+
     /// Checks if a string is a valid identifier.
     pub fn is_identifier(s: &str) -> bool {
         let mut chars = s.chars();
@@ -215,6 +366,11 @@ pub mod patterns {
             && chars.all(is_ident_continue)
     }
 
+    /// Checks if a string is a valid value for a scoped `direction` statement.
+    pub fn is_direction_value(s: &str) -> bool {
+        matches!(s.to_ascii_uppercase().as_str(), "TB" | "BT" | "LR" | "RL")
+    }
+
     /// Reads an identifier from the lexer.
     pub fn read_identifier<'a>(lexer: &mut BaseLexer<'a>) -> Option<&'a str> {
         if !lexer.peek().map(is_ident_start).unwrap_or(false) {
@@ -279,6 +435,33 @@ pub mod patterns {
 mod tests {
     use super::*;
 
+    #[derive(Logos, Debug, Clone, PartialEq)]
+    #[logos(skip r"[ \t]+")]
+    enum TestToken {
+        #[token("graph")]
+        Graph,
+        #[regex(r"[a-z]+")]
+        Word,
+        #[token("\"", crate::parser::quotes::lex_quoted)]
+        Quoted,
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let source = "graph ab ? \"x y\" \"open";
+        let tokens = tokenize::<TestToken>(source);
+        let kinds: Vec<_> = tokens.iter().map(|t| t.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            [TestToken::Graph, TestToken::Word, TestToken::Quoted, TestToken::Quoted]
+        );
+        // `?` matches no token and is skipped
+        assert_eq!(tokens[2].span, Span::new(11, 16));
+        assert_eq!(tokens[2].text, "\"x y\"");
+        assert_eq!(tokens[3].text, "\"open");
+        assert!(tokens[1].is(&TestToken::Word));
+    }
+
     #[test]
     fn test_base_lexer_advance() {
         let mut lexer = BaseLexer::new("hello");
@@ -335,6 +518,37 @@ mod tests {
         assert_eq!(s, Ok("\"hello world\""));
     }
 
+    #[test]
+    fn test_is_direction_value() {
+        assert!(patterns::is_direction_value("LR"));
+        assert!(patterns::is_direction_value("bt"));
+        assert!(!patterns::is_direction_value("TD"));
+        assert!(!patterns::is_direction_value("NESW"));
+    }
+
+    #[test]
+    fn test_cursor_scan_string() {
+        let mut cursor = Cursor::with_range("x = \"a b\" 'c", 4, 13);
+        assert_eq!(cursor.scan_string('"'), Some(Ok("a b")));
+        cursor.skip_whitespace();
+        assert_eq!(cursor.scan_string('"'), None);
+        assert_eq!(cursor.scan_string('\''), Some(Err("c")));
+        assert!(cursor.is_eof());
+
+        // A lone quote is an empty unterminated string
+        assert_eq!(Cursor::new("\"").scan_string('"'), Some(Err("")));
+        assert_eq!(Cursor::new("\"a\nb\"").scan_string('"'), Some(Err("a")));
+    }
+
+    #[test]
+    fn test_cursor_scan_identifier() {
+        let mut cursor = Cursor::new("_id2(x)");
+        assert_eq!(cursor.scan_identifier(), Some("_id2"));
+        assert_eq!(cursor.scan_identifier(), None);
+        assert_eq!(cursor.bump(), Some('('));
+        assert_eq!(cursor.offset(), 5);
+    }
+
     #[test]
     fn test_advance_while() {
         let mut lexer = BaseLexer::new("aaabbb");
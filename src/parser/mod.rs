@@ -3,14 +3,23 @@
 //! This module provides the common infrastructure for parsing Mermaid diagrams,
 //! as well as the specific parsers for each diagram type.
 
+pub(crate) mod accessibility;
+pub(crate) mod calendar;
+pub(crate) mod click;
+pub(crate) mod confusables;
+pub(crate) mod dangling;
+pub(crate) mod quotes;
+pub(crate) mod references;
+pub(crate) mod title;
+pub(crate) mod unknown;
 pub mod error;
 pub mod lexer;
 pub mod traits;
 
-use crate::ast::Ast;
-use crate::config::MermaidConfig;
+use crate::ast::{Ast, AstNode, NodeKind, Span};
+use crate::config::{MermaidConfig, MermaidVersion};
 use crate::detector::DiagramType;
-use crate::diagnostic::Diagnostic;
+use crate::diagnostic::{Diagnostic, DiagnosticCode};
 
 pub use error::ParseError;
 pub use traits::DiagramParser;
@@ -24,58 +33,198 @@ pub fn parse_diagram(
     code: &str,
     config: &MermaidConfig,
 ) -> Result<Ast, Vec<Diagnostic>> {
-    match diagram_type {
-        // Special cases that always fail
-        DiagramType::Error | DiagramType::BadFrontmatter => {
-            // These should be handled before calling parse_diagram
-            unreachable!("Error and BadFrontmatter should be handled earlier");
-        }
+    match parse_diagram_partial(diagram_type, code, config) {
+        (Some(ast), diagnostics) if !diagnostics.iter().any(|d| d.severity.is_error()) => Ok(ast),
+        (_, diagnostics) => Err(diagnostics),
+    }
+}
+
+/// Parses a diagram of the given type, keeping the partial AST on failure.
+///
+/// Parsers that support recovery return the AST built so far, with
+/// unparseable statements marked by `NodeKind::Error` nodes. The others only
+/// return an AST when parsing succeeded.
+pub fn parse_diagram_partial(
+    diagram_type: DiagramType,
+    code: &str,
+    config: &MermaidConfig,
+) -> (Option<Ast>, Vec<Diagnostic>) {
+    let (ast, diagnostics) = dispatch(diagram_type, code, config);
+    if let Some(ast) = &ast {
+        log::debug!(
+            "{} parser produced {} top-level statement(s) and {} diagnostic(s)",
+            diagram_type,
+            ast.root.children.len(),
+            diagnostics.len()
+        );
+    } else {
+        log::debug!(
+            "{} parser failed with {} diagnostic(s)",
+            diagram_type,
+            diagnostics.len()
+        );
+    }
+    (ast, diagnostics)
+}
+
+/// Runs the parser registered for the given diagram type.
+fn dispatch(
+    diagram_type: DiagramType,
+    code: &str,
+    config: &MermaidConfig,
+) -> (Option<Ast>, Vec<Diagnostic>) {
+    let Some(parser) = parser_for(diagram_type) else {
+        // These should be handled before calling parse_diagram
+        unreachable!("Error and BadFrontmatter should be handled earlier");
+    };
+    parser.parse_partial(code, config)
+}
+
+/// Returns the parser for a diagram type, or `None` for the `Error` and
+/// `BadFrontmatter` types, which have nothing to parse.
+///
+/// Types without a parser yet get one that returns a stub AST.
+pub fn parser_for(diagram_type: DiagramType) -> Option<Box<dyn DiagramParser>> {
+    use crate::diagrams::*;
+
+    Some(match diagram_type {
+        DiagramType::Error | DiagramType::BadFrontmatter => return None,
 
         // Phase 1 diagrams
         DiagramType::Flowchart | DiagramType::FlowchartV2 | DiagramType::FlowchartElk => {
-            crate::diagrams::flowchart::FlowchartParser::new().parse(code, config)
-        }
-        DiagramType::Sequence => {
-            crate::diagrams::sequence::SequenceParser::new().parse(code, config)
-        }
-        DiagramType::Class | DiagramType::ClassDiagram => {
-            crate::diagrams::class::ClassParser::new().parse(code, config)
-        }
-        DiagramType::State | DiagramType::StateDiagram => {
-            crate::diagrams::state::StateParser::new().parse(code, config)
+            Box::new(flowchart::FlowchartParser::for_type(diagram_type))
         }
+        DiagramType::Sequence => Box::new(sequence::SequenceParser::new()),
+        DiagramType::Class | DiagramType::ClassDiagram => Box::new(class::ClassParser::new()),
+        DiagramType::State | DiagramType::StateDiagram => Box::new(state::StateParser::new()),
 
         // Phase 3 diagrams
-        DiagramType::Er => {
-            crate::diagrams::er::ErParser::new(code).parse()
-        }
-        DiagramType::Gantt => {
-            crate::diagrams::gantt::GanttParser::new(code).parse()
-        }
-        DiagramType::Journey => {
-            crate::diagrams::journey::JourneyParser::new(code).parse()
-        }
-        DiagramType::Pie => {
-            crate::diagrams::pie::PieParser::new(code).parse()
-        }
-        DiagramType::GitGraph => {
-            crate::diagrams::gitgraph::GitGraphParser::new(code).parse()
-        }
+        DiagramType::Er => Box::new(er::ErParser::new()),
+        DiagramType::Gantt => Box::new(gantt::GanttParser::new()),
+        DiagramType::Journey => Box::new(journey::JourneyParser::new()),
+        DiagramType::Pie => Box::new(pie::PieParser::new()),
+        DiagramType::GitGraph => Box::new(gitgraph::GitGraphParser::new()),
+
+        // Scanned until a full C4 parser exists
+        DiagramType::C4 => Box::new(c4::C4Parser::new()),
 
         // Phase 3+ diagrams - stub implementations for now
-        _ => {
-            // Return a minimal AST for unsupported diagram types
-            use crate::ast::{AstNode, NodeKind, Span};
+        _ => Box::new(StubParser(diagram_type)),
+    })
+}
+
+/// The parser for diagram types without one: returns a minimal AST whose
+/// root is marked with `status: stub`.
+struct StubParser(DiagramType);
 
-            let mut root = AstNode::new(NodeKind::Root, Span::new(0, code.len()));
-            root.add_property("diagram_type", diagram_type.as_str());
-            root.add_property("status", "stub");
+impl DiagramParser for StubParser {
+    fn parse(&self, code: &str, _config: &MermaidConfig) -> Result<Ast, Vec<Diagnostic>> {
+        let mut root = AstNode::new(NodeKind::Root, Span::new(0, code.len()));
+        root.add_property("diagram_type", self.0.as_str());
+        root.add_property("status", "stub");
+
+        Ok(Ast::new(root, code.to_string()))
+    }
+
+    fn name(&self) -> &'static str {
+        "stub"
+    }
+}
+
+/// Finishes a parse, handing the collected diagnostics back to the caller.
+///
+/// The diagnostics are moved out of the parser with `std::mem::take` rather
+/// than cloned; the parser is not used again once this has been called.
+pub(crate) fn finish_parse(
+    root: AstNode,
+    source: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Ast, Vec<Diagnostic>> {
+    if diagnostics.iter().any(|d| d.severity.is_error()) {
+        Err(std::mem::take(diagnostics))
+    } else {
+        Ok(Ast::new(root, source.to_string()))
+    }
+}
+
+/// A parser with statement loops over a token list.
+///
+/// Every statement loop calls [`StatementLoop::ensure_progress`] after each
+/// iteration, so a parser bug cannot hang on malformed input.
+pub(crate) trait StatementLoop {
+    /// Returns the index of the current token.
+    fn position(&self) -> usize;
 
-            Ok(Ast::new(root, code.to_string()))
+    /// Returns whether every token has been consumed.
+    fn at_end(&self) -> bool;
+
+    /// Returns the span of the current token.
+    fn token_span(&self) -> Span;
+
+    /// Consumes the current token.
+    fn skip_token(&mut self);
+
+    /// Records a diagnostic.
+    fn report(&mut self, diagnostic: Diagnostic);
+
+    /// Reports and skips the current token when the iteration that started
+    /// at `loop_start` consumed no tokens.
+    fn ensure_progress(&mut self, loop_start: usize) {
+        if self.position() == loop_start && !self.at_end() {
+            self.report(no_progress(self.token_span()));
+            self.skip_token();
         }
     }
 }
 
+/// Reports a statement loop iteration that consumed no tokens.
+fn no_progress(span: Span) -> Diagnostic {
+    Diagnostic::error(
+        DiagnosticCode::ParserError,
+        format!("Parser made no progress at offset {}; skipping one token", span.start),
+        span,
+    )
+}
+
+/// Sets `render_id` properties for diagram types with a known element id
+/// scheme; other diagrams are left unchanged.
+pub fn assign_render_ids(diagram_type: DiagramType, ast: &mut Ast, version: MermaidVersion) {
+    match diagram_type {
+        DiagramType::Flowchart | DiagramType::FlowchartV2 | DiagramType::FlowchartElk => {
+            crate::diagrams::flowchart::render_ids::assign(ast, version)
+        }
+        DiagramType::Sequence => crate::diagrams::sequence::render_ids::assign(ast, version),
+        _ => {}
+    }
+}
+
+/// Runs semantic validation on a successfully parsed diagram.
+///
+/// Returns the diagnostics found; an empty vector means the diagram passed
+/// (or the diagram type has no semantic checks yet). Checks shared by all
+/// diagram types, such as repeated accessibility statements, run first.
+pub fn validate_diagram(
+    diagram_type: DiagramType,
+    ast: &Ast,
+    _config: &MermaidConfig,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = accessibility::validate(ast);
+    diagnostics.extend(match diagram_type {
+        DiagramType::Class | DiagramType::ClassDiagram => crate::diagrams::class::validate::validate(ast),
+        DiagramType::Flowchart | DiagramType::FlowchartV2 | DiagramType::FlowchartElk => {
+            crate::diagrams::flowchart::validate::validate(ast)
+        }
+        DiagramType::Gantt => crate::diagrams::gantt::validate::validate(ast),
+        DiagramType::GitGraph => crate::diagrams::gitgraph::validate::validate(ast),
+        DiagramType::Sequence => crate::diagrams::sequence::validate::validate(ast),
+        DiagramType::State | DiagramType::StateDiagram => {
+            crate::diagrams::state::validate::validate(ast)
+        }
+        _ => Vec::new(),
+    });
+    diagnostics
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -93,4 +242,60 @@ mod tests {
         let result = parse_diagram(DiagramType::Sequence, code, &MermaidConfig::default());
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_parser_for() {
+        assert_eq!(parser_for(DiagramType::Er).map(|p| p.name()), Some("er"));
+        assert_eq!(parser_for(DiagramType::FlowchartElk).map(|p| p.name()), Some("flowchart"));
+        assert_eq!(parser_for(DiagramType::Mindmap).map(|p| p.name()), Some("stub"));
+        assert!(parser_for(DiagramType::Error).is_none());
+
+        let ast = parser_for(DiagramType::Pie)
+            .unwrap()
+            .parse("pie\n    \"A\" : 1", &MermaidConfig::default())
+            .unwrap();
+        assert_eq!(ast.root.children.len(), 2);
+    }
+
+    struct Cursor {
+        pos: usize,
+        len: usize,
+        diagnostics: Vec<Diagnostic>,
+    }
+
+    impl StatementLoop for Cursor {
+        fn position(&self) -> usize {
+            self.pos
+        }
+
+        fn at_end(&self) -> bool {
+            self.pos >= self.len
+        }
+
+        fn token_span(&self) -> Span {
+            Span::new(self.pos, self.pos + 1)
+        }
+
+        fn skip_token(&mut self) {
+            self.pos += 1;
+        }
+
+        fn report(&mut self, diagnostic: Diagnostic) {
+            self.diagnostics.push(diagnostic);
+        }
+    }
+
+    #[test]
+    fn test_ensure_progress() {
+        let mut cursor = Cursor { pos: 1, len: 2, diagnostics: Vec::new() };
+        cursor.ensure_progress(0);
+        assert_eq!((cursor.pos, cursor.diagnostics.len()), (1, 0));
+
+        cursor.ensure_progress(1);
+        assert_eq!(cursor.pos, 2);
+        assert_eq!(cursor.diagnostics[0].span, Span::new(1, 2));
+
+        cursor.ensure_progress(2);
+        assert_eq!((cursor.pos, cursor.diagnostics.len()), (2, 1));
+    }
 }
@@ -0,0 +1,90 @@
+//! Quoted text in diagram statements.
+//!
+//! Lexers read straight quotes and, for text pasted from word processors,
+//! typographic ones. A quote left open runs to the end of the line, so the
+//! parser can report it instead of the lexer dropping it.
+
+use logos::{Lexer, Logos};
+
+use crate::ast::Span;
+use crate::diagnostic::{Diagnostic, DiagnosticCode, TextEdit};
+
+/// Quote pairs, opening then closing: straight ones first, then curly ones.
+const QUOTE_PAIRS: &[(char, char)] = &[('"', '"'), ('\'', '\''), ('\u{201c}', '\u{201d}'), ('\u{2018}', '\u{2019}')];
+
+/// Returns the text between a quote at the start of `text` and the matching
+/// quote at its end, or `None` if `text` is not quoted, or the closing quote
+/// is missing or of another kind.
+pub(crate) fn strip_matching_quotes(text: &str) -> Option<&str> {
+    let open = text.chars().next()?;
+    let &(_, close) = QUOTE_PAIRS.iter().find(|(o, _)| *o == open)?;
+    text[open.len_utf8()..].strip_suffix(close)
+}
+
+/// Logos callback for a token that starts at an opening quote: extends it
+/// through the matching closing quote, which may be on a later line, or to
+/// the end of the line if there is none. A backslash escapes the character
+/// after it inside straight quotes.
+pub(crate) fn lex_quoted<'s, T>(lex: &mut Lexer<'s, T>)
+where
+    T: Logos<'s, Source = str>,
+{
+    let open = lex.slice().chars().next().unwrap_or('"');
+    let close = QUOTE_PAIRS.iter().find(|(o, _)| *o == open).map_or(open, |&(_, c)| c);
+    let rest = lex.remainder();
+    let mut escaped = false;
+    let mut len = None;
+    for (i, c) in rest.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' && open.is_ascii() {
+            escaped = true;
+        } else if c == close {
+            len = Some(i + c.len_utf8());
+            break;
+        }
+    }
+    lex.bump(len.unwrap_or_else(|| rest.find('\n').unwrap_or(rest.len())));
+}
+
+/// Returns the text of a quoted token at `span`, reporting an unterminated
+/// string as an error and curly quotes as a hint with the straight-quoted
+/// text as the fix.
+pub(crate) fn unquote(text: &str, span: Span, diagnostics: &mut Vec<Diagnostic>) -> Option<String> {
+    let Some(inner) = strip_matching_quotes(text) else {
+        diagnostics.push(
+            Diagnostic::error(DiagnosticCode::UnterminatedString, "Unterminated string", span)
+                .with_note("close the string with a matching quote"),
+        );
+        return None;
+    };
+    let open = text.chars().next().unwrap_or_default();
+    if !open.is_ascii() {
+        let straight = if open == '\u{201c}' { '"' } else { '\'' };
+        diagnostics.push(
+            Diagnostic::hint(DiagnosticCode::InvalidSyntax, "Curly quotes are read as straight quotes", span)
+                .with_note("Mermaid only recognizes straight quotes; replace them")
+                .with_fix(TextEdit::new(span, format!("{}{}{}", straight, inner, straight))),
+        );
+    }
+    Some(inner.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_matching_quotes() {
+        assert_eq!(strip_matching_quotes("\"a b\""), Some("a b"));
+        assert_eq!(strip_matching_quotes("'a'"), Some("a"));
+        assert_eq!(strip_matching_quotes("\u{201c}Caf\u{e9}\u{201d}"), Some("Caf\u{e9}"));
+        assert_eq!(strip_matching_quotes("\u{2018}\u{2019}"), Some(""));
+        assert_eq!(strip_matching_quotes("\""), None);
+        assert_eq!(strip_matching_quotes("\u{201c}"), None);
+        assert_eq!(strip_matching_quotes("\"a\u{201d}"), None);
+        assert_eq!(strip_matching_quotes("\u{201c}a\""), None);
+        assert_eq!(strip_matching_quotes("a"), None);
+        assert_eq!(strip_matching_quotes(""), None);
+    }
+}
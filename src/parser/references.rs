@@ -0,0 +1,70 @@
+//! Names a diagram defines and the places it refers to them.
+//!
+//! The checks for undefined references and for unused definitions ask the
+//! same question from opposite ends, so both read the [`References`] that
+//! one walk of the AST collects.
+
+use std::collections::HashSet;
+
+use crate::ast::Span;
+
+/// The definitions of names and the references to them, in the order they
+/// were recorded.
+#[derive(Debug, Default)]
+pub(crate) struct References<'a> {
+    definitions: Vec<(&'a str, Span)>,
+    references: Vec<(&'a str, Span)>,
+}
+
+impl<'a> References<'a> {
+    /// Records a definition of `name` at `span`.
+    pub(crate) fn define(&mut self, name: &'a str, span: Span) {
+        self.definitions.push((name, span));
+    }
+
+    /// Records a reference to `name` at `span`.
+    pub(crate) fn reference(&mut self, name: &'a str, span: Span) {
+        self.references.push((name, span));
+    }
+
+    /// Returns the names referenced but never defined, each at its first
+    /// reference.
+    pub(crate) fn undefined(&self) -> Vec<(&'a str, Span)> {
+        first_missing(&self.references, &self.definitions)
+    }
+
+    /// Returns the names defined but never referenced, each at its first
+    /// definition.
+    pub(crate) fn unreferenced(&self) -> Vec<(&'a str, Span)> {
+        first_missing(&self.definitions, &self.references)
+    }
+}
+
+/// Returns the first entry of `names` for each name that `other` lacks.
+fn first_missing<'a>(names: &[(&'a str, Span)], other: &[(&'a str, Span)]) -> Vec<(&'a str, Span)> {
+    let present: HashSet<&str> = other.iter().map(|(name, _)| *name).collect();
+    let mut seen = HashSet::new();
+    names
+        .iter()
+        .filter(|(name, _)| !present.contains(name) && seen.insert(*name))
+        .copied()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undefined_and_unreferenced() {
+        let mut references = References::default();
+        references.define("a", Span::new(0, 1));
+        references.define("b", Span::new(2, 3));
+        references.define("b", Span::new(4, 5));
+        references.reference("a", Span::new(6, 7));
+        references.reference("c", Span::new(8, 9));
+        references.reference("c", Span::new(10, 11));
+        assert_eq!(references.undefined(), vec![("c", Span::new(8, 9))]);
+        assert_eq!(references.unreferenced(), vec![("b", Span::new(2, 3))]);
+    }
+}
@@ -0,0 +1,65 @@
+//! `title` statements shared by the diagram parsers that accept them.
+//!
+//! Like the accessibility statements, the title is read straight from the
+//! source: it is the rest of the line after the keyword, trimmed, so its
+//! spacing doesn't depend on how each lexer splits the text.
+
+use crate::ast::{AstNode, NodeKind, Span};
+use crate::diagnostic::{Diagnostic, DiagnosticCode, TextEdit};
+
+/// Parses the `title` statement whose keyword is at `keyword_span`.
+///
+/// Returns the `Statement` node (with `type` and `value` properties), which
+/// ends with the title text, and the offset where the line ends; the newline
+/// is not consumed. A title with no text produces a warning at the keyword.
+pub(crate) fn parse_statement(
+    source: &str,
+    keyword_span: Span,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> (AstNode, usize) {
+    let rest = &source[keyword_span.end..];
+    let line_end = keyword_span.end + rest.find('\n').unwrap_or(rest.len());
+    let line = &source[keyword_span.end..line_end];
+    let value = line.trim();
+    let end = if value.is_empty() {
+        keyword_span.end
+    } else {
+        line_end - (line.len() - line.trim_end().len())
+    };
+
+    let mut node = AstNode::new(NodeKind::Statement, Span::new(keyword_span.start, end));
+    node.add_property("type", "title");
+    node.add_property("value", value);
+    if value.is_empty() {
+        diagnostics.push(
+            Diagnostic::warning(
+                DiagnosticCode::MissingElement,
+                "'title' has no text; Mermaid renders an empty title",
+                keyword_span,
+            )
+            .with_note("add the title text, or remove the 'title' line")
+            .with_fix(TextEdit::delete(node.span)),
+        );
+    }
+    (node, line_end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_statement() {
+        let source = "pie title  Pets   \n";
+        let mut diagnostics = Vec::new();
+        let (node, end) = parse_statement(source, Span::new(4, 9), &mut diagnostics);
+        assert_eq!(node.get_property("value"), Some("Pets"));
+        assert_eq!(node.span.text(source), "title  Pets");
+        assert_eq!(end, source.len() - 1);
+        assert!(diagnostics.is_empty());
+
+        let (node, _) = parse_statement("title   ", Span::new(0, 5), &mut diagnostics);
+        assert_eq!(node.get_property("value"), Some(""));
+        assert_eq!(diagnostics[0].span, Span::new(0, 5));
+    }
+}
@@ -11,6 +11,18 @@ pub trait DiagramParser {
     /// Parses the given code and returns an AST or diagnostics.
     fn parse(&self, code: &str, config: &MermaidConfig) -> Result<Ast, Vec<Diagnostic>>;
 
+    /// Parses the given code, keeping whatever AST could be built even when
+    /// errors were reported.
+    ///
+    /// Parsers that support recovery override this; the default only returns
+    /// an AST when parsing succeeded.
+    fn parse_partial(&self, code: &str, config: &MermaidConfig) -> (Option<Ast>, Vec<Diagnostic>) {
+        match self.parse(code, config) {
+            Ok(ast) => (Some(ast), Vec::new()),
+            Err(diagnostics) => (None, diagnostics),
+        }
+    }
+
     /// Returns the name of this parser.
     fn name(&self) -> &'static str;
 
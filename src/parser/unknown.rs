@@ -0,0 +1,84 @@
+//! Statements starting with a keyword the parser does not know.
+//!
+//! Mermaid adds statement keywords from release to release. A parser without
+//! a rule for one would report every token of the statement, or read its
+//! words as ids. Instead the statement is skipped with a single warning and
+//! the rest of the diagram is parsed as usual.
+
+use crate::ast::{AstNode, NodeKind, Span};
+use crate::diagnostic::{Diagnostic, DiagnosticCode};
+
+/// Returns the word starting `text` if it reads like a statement keyword:
+/// ASCII letters, the first lowercase, then whitespace and another word on
+/// the same line.
+pub(crate) fn leading_keyword(text: &str) -> Option<&str> {
+    let len = text.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(text.len());
+    let (word, rest) = text.split_at(len);
+    let argument = rest.trim_start_matches([' ', '\t']);
+    let keyword_like = word.starts_with(|c: char| c.is_ascii_lowercase())
+        && argument.len() < rest.len()
+        && argument.starts_with(|c: char| c.is_alphanumeric() || c == '_');
+    keyword_like.then_some(word)
+}
+
+/// Returns the node kept for the skipped statement `span`, which starts
+/// with `keyword`, and the warning reporting it.
+///
+/// `new_keywords` lists the Mermaid keywords a parser knows of but cannot
+/// check yet.
+pub(crate) fn skipped_statement(
+    source: &str,
+    span: Span,
+    keyword: &str,
+    new_keywords: &[&str],
+) -> (AstNode, Diagnostic) {
+    let keyword_span = Span::from_len(span.start, keyword.len());
+    let diagnostic = if new_keywords.contains(&keyword) {
+        Diagnostic::warning(
+            DiagnosticCode::InvalidSyntax,
+            format!(
+                "'{}' statements are not supported by this version of mermaid-linter; the statement is skipped",
+                keyword
+            ),
+            keyword_span,
+        )
+        .with_note(format!(
+            "'{}' is valid Mermaid, but mermaid-linter {} cannot check it yet",
+            keyword,
+            env!("CARGO_PKG_VERSION")
+        ))
+    } else {
+        Diagnostic::warning(
+            DiagnosticCode::InvalidSyntax,
+            format!(
+                "Unknown statement '{}' is not supported by this version of mermaid-linter; the statement is skipped",
+                keyword
+            ),
+            keyword_span,
+        )
+        .with_note("check the spelling; if this is newer Mermaid syntax, the rest of the diagram is still checked")
+    };
+
+    let mut node = AstNode::with_text(NodeKind::Statement, span, span.text(source));
+    node.add_property("type", "unknown");
+    node.add_property("keyword", keyword);
+    (node, diagnostic)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leading_keyword() {
+        assert_eq!(leading_keyword("hide empty description"), Some("hide"));
+        assert_eq!(leading_keyword("classDef hot fill:#f00"), Some("classDef"));
+        assert_eq!(leading_keyword("frobnicate\tA"), Some("frobnicate"));
+        assert_eq!(leading_keyword("Alice B"), None);
+        assert_eq!(leading_keyword("node1 node2"), None);
+        assert_eq!(leading_keyword("a --> b"), None);
+        assert_eq!(leading_keyword("a : label"), None);
+        assert_eq!(leading_keyword("a\nb"), None);
+        assert_eq!(leading_keyword("frobnicate"), None);
+    }
+}
@@ -1,5 +1,7 @@
 //! Comment removal from Mermaid diagrams.
 
+use super::source_map::Segment;
+
 /// Removes comment lines from text.
 ///
 /// Comments in Mermaid are lines starting with `%%` (but not `%%{` which are directives).
@@ -21,32 +23,46 @@
 /// assert!(result.contains("A --> B"));
 /// ```
 pub fn remove_comments(text: &str) -> String {
-    let mut result = String::new();
-    let mut first_non_comment = true;
+    remove_comment_lines(text).0
+}
 
-    for line in text.lines() {
+/// Like [`remove_comments`], also returning the runs of `text` the result
+/// copies, for a [`SourceMap`](super::SourceMap).
+pub(crate) fn remove_comment_lines(text: &str) -> (String, Vec<Segment>) {
+    let mut result = String::new();
+    let mut segments = Vec::new();
+    let mut removed = 0;
+    let mut offset = 0;
+
+    for raw in text.split_inclusive('\n') {
+        let line_start = offset;
+        offset += raw.len();
+        let line = raw.strip_suffix('\n').map_or(raw, |line| line.strip_suffix('\r').unwrap_or(line));
         let trimmed = line.trim_start();
 
         // Check if line is a comment (starts with %% but not %%{)
         let is_comment = trimmed.starts_with("%%") && !trimmed.starts_with("%%{");
 
-        if !is_comment {
-            if first_non_comment {
-                result.push_str(line);
-                first_non_comment = false;
-            } else {
+        if is_comment {
+            removed += 1;
+        } else {
+            if !segments.is_empty() {
                 result.push('\n');
-                result.push_str(line);
             }
+            // The line and the newline written after it
+            segments.push((result.len(), line_start, (line.len() + 1).min(text.len() - line_start)));
+            result.push_str(line);
         }
     }
 
+    log::debug!("removed {} comment line(s)", removed);
+
     // Handle trailing newline from original text
     if text.ends_with('\n') && !result.is_empty() {
         result.push('\n');
     }
 
-    result
+    (result, segments)
 }
 
 #[cfg(test)]
@@ -4,7 +4,8 @@ use once_cell::sync::Lazy;
 use regex::Regex;
 use serde_json::Value as JsonValue;
 
-use crate::config::MermaidConfig;
+use crate::ast::Span;
+use crate::config::{ConfigChange, ConfigSource, MermaidConfig};
 
 /// Regex for matching directive content (type: value or just type).
 static DIRECTIVE_CONTENT_REGEX: Lazy<Regex> = Lazy::new(|| {
@@ -18,16 +19,20 @@ pub enum DirectiveType {
     Init,
     /// Wrap directive to enable text wrapping.
     Wrap,
+    /// Nowrap directive to disable text wrapping.
+    NoWrap,
     /// Unknown directive type.
     Unknown(String),
 }
 
 impl DirectiveType {
     /// Parses a directive type from a string.
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Self {
         match s.to_lowercase().as_str() {
             "init" | "initialize" => DirectiveType::Init,
             "wrap" => DirectiveType::Wrap,
+            "nowrap" => DirectiveType::NoWrap,
             _ => DirectiveType::Unknown(s.to_string()),
         }
     }
@@ -43,24 +48,19 @@ pub struct Directive {
 }
 
 /// Result of parsing all directives.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct DirectiveResult {
     /// Text with directives removed.
     pub text: String,
     /// Configuration extracted from init directives.
     pub config: MermaidConfig,
-    /// Whether wrap was enabled.
-    pub wrap: bool,
-}
-
-impl Default for DirectiveResult {
-    fn default() -> Self {
-        Self {
-            text: String::new(),
-            config: MermaidConfig::default(),
-            wrap: false,
-        }
-    }
+    /// The setting of the last `wrap` or `nowrap` directive, if any.
+    pub wrap: Option<bool>,
+    /// The fields each directive set, in order, with spans in `text`
+    /// before the directives were removed.
+    pub trace: Vec<ConfigChange>,
+    /// The spans of the removed directives, in order.
+    pub removed: Vec<(usize, usize)>,
 }
 
 /// Find all directive spans in text (start, end positions).
@@ -145,7 +145,7 @@ fn parse_directive_content(content: &str) -> Option<Directive> {
 /// "#;
 ///
 /// let result = extract_directives(text);
-/// assert!(result.wrap);
+/// assert_eq!(result.wrap, Some(true));
 /// assert!(result.text.contains("graph TD"));
 /// ```
 pub fn extract_directives(text: &str) -> DirectiveResult {
@@ -153,9 +153,11 @@ pub fn extract_directives(text: &str) -> DirectiveResult {
     let mut init_configs: Vec<MermaidConfig> = Vec::new();
 
     let spans = find_directive_spans(text);
+    log::debug!("found {} directive(s)", spans.len());
 
     // Process each directive
-    for (_, _, content) in &spans {
+    for (start, end, content) in &spans {
+        let source = ConfigSource::Directive(Span::new(*start, *end));
         if let Some(directive) = parse_directive_content(content) {
             match directive.directive_type {
                 DirectiveType::Init => {
@@ -163,12 +165,19 @@ pub fn extract_directives(text: &str) -> DirectiveResult {
                         if let Ok(config) =
                             serde_json::from_value::<MermaidConfig>(JsonValue::Object(obj))
                         {
+                            result.trace.extend(ConfigChange::from_config(&config, source));
                             init_configs.push(config);
                         }
                     }
                 }
-                DirectiveType::Wrap => {
-                    result.wrap = true;
+                DirectiveType::Wrap | DirectiveType::NoWrap => {
+                    let wrap = directive.directive_type == DirectiveType::Wrap;
+                    result.wrap = Some(wrap);
+                    result.trace.push(ConfigChange {
+                        key: "wrap".to_string(),
+                        value: wrap.to_string(),
+                        source,
+                    });
                 }
                 DirectiveType::Unknown(_) => {
                     // Ignore unknown directives
@@ -185,11 +194,12 @@ pub fn extract_directives(text: &str) -> DirectiveResult {
     // Remove all directives from text
     let mut processed = text.to_string();
     // Remove from end to start to preserve positions
-    for (start, end, _) in spans.into_iter().rev() {
-        processed.replace_range(start..end, "");
+    for (start, end, _) in spans.iter().rev() {
+        processed.replace_range(*start..*end, "");
     }
 
     result.text = processed;
+    result.removed = spans.into_iter().map(|(start, end, _)| (start, end)).collect();
 
     result
 }
@@ -227,6 +237,9 @@ mod tests {
         let directive = parse_directive(text).unwrap();
 
         assert_eq!(directive.directive_type, DirectiveType::Wrap);
+
+        let directive = parse_directive("%%{ nowrap }%%").unwrap();
+        assert_eq!(directive.directive_type, DirectiveType::NoWrap);
     }
 
     #[test]
@@ -246,7 +259,7 @@ graph TD
 "#;
         let result = extract_directives(text);
 
-        assert!(result.wrap);
+        assert_eq!(result.wrap, Some(true));
         assert_eq!(
             result.config.flowchart.default_renderer,
             Some("elk".to_string())
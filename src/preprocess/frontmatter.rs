@@ -14,7 +14,7 @@ static FRONTMATTER_REGEX: Lazy<Regex> = Lazy::new(|| {
 });
 
 /// Result of frontmatter extraction.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct FrontmatterResult {
     /// Text with frontmatter removed.
     pub text: String,
@@ -26,17 +26,6 @@ pub struct FrontmatterResult {
     pub config: MermaidConfig,
 }
 
-impl Default for FrontmatterResult {
-    fn default() -> Self {
-        Self {
-            text: String::new(),
-            title: None,
-            display_mode: None,
-            config: MermaidConfig::default(),
-        }
-    }
-}
-
 /// Extracts and parses YAML frontmatter from text.
 ///
 /// Frontmatter is YAML bounded by `---` blocks at the start of the text.
@@ -62,7 +51,10 @@ impl Default for FrontmatterResult {
 /// assert!(result.text.starts_with("graph TD"));
 /// ```
 pub fn extract_frontmatter(text: &str) -> FrontmatterResult {
-    let Some(captures) = FRONTMATTER_REGEX.captures(text) else {
+    // Blank lines before the opening `---` are allowed; indentation is not.
+    let body = &text[leading_blank_lines_len(text)..];
+
+    let Some(captures) = FRONTMATTER_REGEX.captures(body) else {
         return FrontmatterResult {
             text: text.to_string(),
             ..Default::default()
@@ -75,8 +67,9 @@ pub fn extract_frontmatter(text: &str) -> FrontmatterResult {
     // Parse YAML
     let parsed: serde_yaml::Value = match serde_yaml::from_str(yaml_content) {
         Ok(v) => v,
-        Err(_) => {
+        Err(e) => {
             // If YAML parsing fails, return original text
+            log::debug!("frontmatter found but YAML is invalid, ignoring it: {}", e);
             return FrontmatterResult {
                 text: text.to_string(),
                 ..Default::default()
@@ -89,14 +82,16 @@ pub fn extract_frontmatter(text: &str) -> FrontmatterResult {
         serde_yaml::Value::Mapping(m) => m,
         _ => {
             return FrontmatterResult {
-                text: text[full_match.end()..].to_string(),
+                text: body[full_match.end()..].to_string(),
                 ..Default::default()
             };
         }
     };
 
+    log::debug!("frontmatter found ({} bytes of YAML)", yaml_content.len());
+
     let mut result = FrontmatterResult {
-        text: text[full_match.end()..].to_string(),
+        text: body[full_match.end()..].to_string(),
         ..Default::default()
     };
 
@@ -120,6 +115,18 @@ pub fn extract_frontmatter(text: &str) -> FrontmatterResult {
     result
 }
 
+/// Returns the byte length of the whitespace-only lines at the start of `text`.
+fn leading_blank_lines_len(text: &str) -> usize {
+    let mut len = 0;
+    for line in text.split_inclusive('\n') {
+        if !line.ends_with('\n') || !line.trim().is_empty() {
+            break;
+        }
+        len += line.len();
+    }
+    len
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -179,6 +186,24 @@ graph TD
         assert_eq!(result.text, text);
     }
 
+    #[test]
+    fn test_frontmatter_after_blank_lines() {
+        let text = "\n  \n---\ntitle: Test\n---\ngraph TD";
+        let result = extract_frontmatter(text);
+
+        assert_eq!(result.title, Some("Test".to_string()));
+        assert_eq!(result.text, "graph TD");
+    }
+
+    #[test]
+    fn test_indented_frontmatter_not_extracted() {
+        let text = "  ---\ntitle: Test\n---\ngraph TD";
+        let result = extract_frontmatter(text);
+
+        assert!(result.title.is_none());
+        assert_eq!(result.text, text);
+    }
+
     #[test]
     fn test_frontmatter_not_at_start() {
         let text = "some text\n---\ntitle: Test\n---\ngraph TD";
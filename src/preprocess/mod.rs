@@ -12,9 +12,12 @@ mod directive;
 mod frontmatter;
 mod normalize;
 pub mod preprocessor;
+mod source_map;
 
 pub use comments::remove_comments;
 pub use directive::{parse_directive, Directive, DirectiveType};
 pub use frontmatter::{extract_frontmatter, FrontmatterResult};
-pub use normalize::{encode_entities, normalize_text};
+pub use normalize::{decode_entities, encode_entities, normalize_text};
+pub(crate) use normalize::is_invisible;
 pub use preprocessor::{PreprocessResult, Preprocessor};
+pub use source_map::SourceMap;
@@ -107,20 +107,39 @@ pub fn encode_entities(text: &str) -> String {
 ///
 /// # Example
 ///
-/// ```ignore
-/// use mermaid_linter::preprocess::normalize::decode_entities;
+/// ```
+/// use mermaid_linter::preprocess::decode_entities;
 ///
 /// let encoded = "ﬂ°°123¶ß and ﬂ°nbsp¶ß";
 /// let decoded = decode_entities(encoded);
 /// assert_eq!(decoded, "&#123; and &nbsp;");
 /// ```
-#[allow(dead_code)]
 pub fn decode_entities(text: &str) -> String {
     text.replace("ﬂ°°", "&#")
         .replace("ﬂ°", "&")
         .replace("¶ß", ";")
 }
 
+/// Characters that render as nothing: zero-width spaces and joiners, the
+/// soft hyphen, the byte order mark and bidirectional controls.
+const INVISIBLE_CHARACTERS: &[(char, char)] = &[
+    ('\u{00ad}', '\u{00ad}'),
+    ('\u{034f}', '\u{034f}'),
+    ('\u{061c}', '\u{061c}'),
+    ('\u{180e}', '\u{180e}'),
+    ('\u{200b}', '\u{200f}'),
+    ('\u{202a}', '\u{202e}'),
+    ('\u{2060}', '\u{2064}'),
+    ('\u{2066}', '\u{2069}'),
+    ('\u{feff}', '\u{feff}'),
+];
+
+/// Checks whether `c` renders as nothing, so that two strings differing
+/// only by it look the same.
+pub(crate) fn is_invisible(c: char) -> bool {
+    INVISIBLE_CHARACTERS.iter().any(|&(first, last)| (first..=last).contains(&c))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2,11 +2,13 @@
 
 use thiserror::Error;
 
-use super::comments::remove_comments;
+use super::comments::remove_comment_lines;
 use super::directive::extract_directives;
 use super::frontmatter::extract_frontmatter;
 use super::normalize::normalize_text;
-use crate::config::MermaidConfig;
+use super::source_map::SourceMap;
+use crate::ast::Span;
+use crate::config::{ConfigChange, ConfigSource, MermaidConfig};
 
 /// Errors that can occur during preprocessing.
 #[derive(Debug, Error)]
@@ -29,6 +31,11 @@ pub struct PreprocessResult {
     pub title: Option<String>,
     /// Merged configuration from frontmatter and directives.
     pub config: MermaidConfig,
+    /// The fields the frontmatter and directives set, in merge order.
+    /// Spans index into the normalized text.
+    pub config_trace: Vec<ConfigChange>,
+    /// Maps offsets in `code` back to the text that was preprocessed.
+    pub source_map: SourceMap,
 }
 
 /// Preprocessor for Mermaid diagram text.
@@ -71,6 +78,9 @@ impl Preprocessor {
     pub fn preprocess(&self, text: &str) -> Result<PreprocessResult, PreprocessError> {
         // Step 1: Normalize text
         let normalized = normalize_text(text);
+        // Only the `\r` of a CRLF is dropped; other changes keep the length
+        let mut source_map = SourceMap::default();
+        source_map.push_removals(text.len(), text.match_indices("\r\n").map(|(i, _)| (i, i + 1)));
 
         // Step 2: Extract frontmatter
         let frontmatter_result = extract_frontmatter(&normalized);
@@ -81,24 +91,41 @@ impl Preprocessor {
             config.gantt.display_mode = Some(display_mode.clone());
         }
 
+        // Frontmatter is only ever removed from the start of the text
+        let frontmatter_len = normalized.len() - frontmatter_result.text.len();
+        source_map.push_removals(normalized.len(), [(0, frontmatter_len)]);
+        let mut config_trace =
+            ConfigChange::from_config(&config, ConfigSource::Frontmatter(Span::new(0, frontmatter_len)));
+
         // Step 3: Extract and process directives
         let directive_result = extract_directives(&frontmatter_result.text);
+        source_map.push_removals(frontmatter_result.text.len(), directive_result.removed.iter().copied());
 
         // Merge directive config into frontmatter config
         config.merge(&directive_result.config);
-
-        // Handle wrap directive
-        if directive_result.wrap {
-            config.wrap = true;
+        config_trace.extend(directive_result.trace.into_iter().map(|mut change| {
+            if let ConfigSource::Directive(span) = &mut change.source {
+                *span = Span::new(span.start + frontmatter_len, span.end + frontmatter_len);
+            }
+            change
+        }));
+
+        // A `wrap` or `nowrap` directive overrides the frontmatter and
+        // `init` directives
+        if let Some(wrap) = directive_result.wrap {
+            config.wrap = wrap;
         }
 
         // Step 4: Remove comments
-        let code = remove_comments(&directive_result.text);
+        let (code, segments) = remove_comment_lines(&directive_result.text);
+        source_map.push_step(segments);
 
         Ok(PreprocessResult {
             code,
             title: frontmatter_result.title,
             config,
+            config_trace,
+            source_map,
         })
     }
 }
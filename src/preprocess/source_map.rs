@@ -0,0 +1,121 @@
+//! Offsets in preprocessed code, mapped back to the source.
+//!
+//! Preprocessing removes frontmatter, directives, comment lines and the
+//! `\r` of CRLF line endings, so an offset into the preprocessed code points
+//! elsewhere in the source. Each step records the runs of its output that
+//! it copied from its input, and the map replays them in reverse.
+
+use crate::ast::Span;
+
+/// A run of a step's output copied from its input: the output offset, the
+/// input offset and the length.
+pub(crate) type Segment = (usize, usize, usize);
+
+/// Maps offsets between the source and the code preprocessing made of it.
+///
+/// The default map is the identity, for code that preprocessing left alone.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    /// The copied runs of each step, in the order the steps ran.
+    steps: Vec<Vec<Segment>>,
+}
+
+impl SourceMap {
+    /// Records a step that copied the `segments` of its input, sorted by
+    /// offset, and dropped or inserted everything else.
+    pub(crate) fn push_step(&mut self, segments: Vec<Segment>) {
+        self.steps.push(segments);
+    }
+
+    /// Records a step that removed `ranges` of its input of length `len`.
+    /// The ranges are sorted and don't overlap.
+    pub(crate) fn push_removals(&mut self, len: usize, ranges: impl IntoIterator<Item = (usize, usize)>) {
+        let mut segments = Vec::new();
+        let (mut input, mut removed) = (0, 0);
+        for (start, end) in ranges {
+            segments.push((input - removed, input, start - input));
+            removed += end - start;
+            input = end;
+        }
+        segments.push((input - removed, input, len - input));
+        self.push_step(segments);
+    }
+
+    /// Returns the source offset of `offset` in the preprocessed code.
+    /// Offsets in text a step inserted map to where it was inserted.
+    pub fn to_source(&self, offset: usize) -> usize {
+        self.steps.iter().rev().fold(offset, |offset, segments| {
+            match segments.iter().rev().find(|(output, _, _)| *output <= offset) {
+                Some(&(output, input, len)) => input + (offset - output).min(len),
+                None => 0,
+            }
+        })
+    }
+
+    /// Returns the offset in the preprocessed code of source `offset`.
+    /// Offsets in text a step removed map to where it was removed.
+    pub fn to_preprocessed(&self, offset: usize) -> usize {
+        self.steps.iter().fold(offset, |offset, segments| {
+            match segments.iter().rev().find(|(_, input, _)| *input <= offset) {
+                Some(&(output, input, len)) => output + (offset - input).min(len),
+                None => 0,
+            }
+        })
+    }
+
+    /// Returns the source span of `span` in the preprocessed code. The end
+    /// maps from the last character the span covers, so a span that ends a
+    /// line doesn't grow over the comment lines after it.
+    pub fn span_to_source(&self, span: Span) -> Span {
+        let start = self.to_source(span.start);
+        let end = match span.is_empty() {
+            true => start,
+            false => self.to_source(span.end - 1) + 1,
+        };
+        Span::new(start, end.max(start))
+    }
+
+    /// Returns whether the text of preprocessed `span` was copied from one
+    /// run of the source, unchanged in length, so an edit of it can be
+    /// made to the source.
+    pub fn is_copied(&self, span: Span) -> bool {
+        self.span_to_source(span).len() == span.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_removals_map_both_ways() {
+        // "ab%%c\nde" with "%%c\n" removed is "abde"
+        let mut map = SourceMap::default();
+        map.push_removals(8, [(2, 6)]);
+        assert_eq!((map.to_source(1), map.to_source(2), map.to_source(4)), (1, 6, 8));
+        assert_eq!((map.to_preprocessed(3), map.to_preprocessed(7)), (2, 3));
+        assert_eq!(map.span_to_source(Span::new(0, 2)), Span::new(0, 2));
+        assert!(map.is_copied(Span::new(2, 4)));
+        assert!(!map.is_copied(Span::new(1, 3)));
+    }
+
+    #[test]
+    fn test_steps_compose() {
+        // "\r\n" line endings, then a removed first line
+        let mut map = SourceMap::default();
+        map.push_removals(10, [(1, 2), (5, 6)]);
+        map.push_removals(8, [(0, 2)]);
+        assert_eq!(map.to_source(0), 3);
+        assert_eq!(map.to_source(4), 8);
+        assert_eq!(map.to_preprocessed(8), 4);
+    }
+
+    #[test]
+    fn test_inserted_text_maps_to_its_place() {
+        let mut map = SourceMap::default();
+        map.push_step(vec![(4, 0, 5)]);
+        assert_eq!((map.to_source(2), map.to_source(6)), (0, 2));
+        assert_eq!(map.to_preprocessed(2), 6);
+        assert_eq!(SourceMap::default().to_source(7), 7);
+    }
+}
@@ -0,0 +1,308 @@
+//! Renaming an identifier across a diagram.
+//!
+//! Occurrences are found in id positions only: node and subgraph ids, edge
+//! endpoints and `style`/`class`/`click` targets in flowcharts; participants,
+//! message endpoints, notes and activations in sequence diagrams; states,
+//! transitions and notes in state diagrams. Labels, messages and other text
+//! are never touched. The edits are found in the preprocessed code and
+//! mapped back to the source with [`ParseResult::source_map`](crate::ParseResult::source_map).
+
+use std::collections::HashSet;
+
+use crate::ast::{Ast, AstNode, NodeKind, Span};
+use crate::detector::DiagramType;
+use crate::diagnostic::{Diagnostic, DiagnosticCode, TextEdit};
+use crate::fix::apply_edits;
+use crate::parse;
+
+/// The result of [`rename_identifier`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenameResult {
+    /// The source with the identifier renamed.
+    pub code: String,
+    /// The spans of the renamed occurrences in the original source, in
+    /// source order.
+    pub occurrences: Vec<Span>,
+}
+
+/// Renames the identifier `old` to `new` in every id position of `code`.
+///
+/// `diagram_type_hint`, if given, must match the detected diagram type.
+/// The rename is rejected, with diagnostics explaining why, if `code` does
+/// not parse, if `old` is not an id of the diagram or `new` already is, or
+/// if the renamed diagram does not parse.
+pub fn rename_identifier(
+    code: &str,
+    diagram_type_hint: Option<DiagramType>,
+    old: &str,
+    new: &str,
+) -> Result<RenameResult, Vec<Diagnostic>> {
+    let result = parse(code, None);
+    if !result.ok {
+        return Err(result.diagnostics);
+    }
+    let (Some(ast), Some(diagram_type)) = (result.ast.as_ref(), result.diagram_type) else {
+        return Err(result.diagnostics);
+    };
+    let whole = Span::new(0, 0);
+    if let Some(hint) = diagram_type_hint.filter(|hint| hint.canonical() != diagram_type.canonical()) {
+        return Err(vec![Diagnostic::error(
+            DiagnosticCode::InvalidValue,
+            format!("Expected a {} diagram, found {}", hint, diagram_type),
+            whole,
+        )]);
+    }
+
+    let regions = id_regions(ast, diagram_type).ok_or_else(|| {
+        vec![Diagnostic::error(
+            DiagnosticCode::InvalidValue,
+            format!("Renaming is not supported in {} diagrams", diagram_type),
+            whole,
+        )]
+    })?;
+    let source = ast.source.as_str();
+    // Flowchart and state ids may hold a `-`, as in `order-svc`; in
+    // sequence diagrams `-` starts an arrow such as `-x`
+    let hyphens = diagram_type != DiagramType::Sequence;
+    let ids: HashSet<&str> =
+        regions.iter().flat_map(|region| words(source, *region, hyphens)).map(|w| w.text(source)).collect();
+    if !ids.contains(old) {
+        return Err(vec![Diagnostic::error(
+            DiagnosticCode::UndefinedReference,
+            format!("'{}' is not an id in this diagram", old),
+            whole,
+        )]);
+    }
+    if ids.contains(new) {
+        return Err(vec![Diagnostic::error(
+            DiagnosticCode::DuplicateDefinition,
+            format!("'{}' is already an id in this diagram", new),
+            whole,
+        )]);
+    }
+
+    let mut occurrences: Vec<Span> = regions
+        .iter()
+        .flat_map(|region| words(source, *region, hyphens))
+        .filter(|word| word.text(source) == old && result.source_map.is_copied(*word))
+        .map(|word| result.source_map.span_to_source(word))
+        .collect();
+    occurrences.sort_by_key(|span| span.start);
+    occurrences.dedup();
+
+    let edits: Vec<_> = occurrences.iter().map(|span| TextEdit::new(*span, new)).collect();
+    let renamed = apply_edits(code, &edits).map_err(|e| {
+        vec![Diagnostic::error(DiagnosticCode::InvalidValue, e.to_string(), whole)]
+    })?;
+    let check = parse(&renamed, None);
+    if !check.ok {
+        let mut diagnostics = vec![Diagnostic::error(
+            DiagnosticCode::InvalidValue,
+            format!("Renaming '{}' to '{}' makes the diagram invalid", old, new),
+            whole,
+        )];
+        diagnostics.extend(check.diagnostics);
+        return Err(diagnostics);
+    }
+
+    Ok(RenameResult { code: renamed, occurrences })
+}
+
+/// Returns the spans of the AST's text that hold ids, or `None` if the
+/// diagram type is not supported.
+fn id_regions(ast: &Ast, diagram_type: DiagramType) -> Option<Vec<Span>> {
+    let regions_of: fn(&AstNode, &str) -> Vec<Span> = match diagram_type {
+        DiagramType::Flowchart | DiagramType::FlowchartV2 | DiagramType::FlowchartElk => flowchart_regions,
+        DiagramType::Sequence => sequence_regions,
+        DiagramType::State | DiagramType::StateDiagram => state_regions,
+        _ => return None,
+    };
+    let mut regions = Vec::new();
+    ast.walk(|node, _| regions.extend(regions_of(node, &ast.source)));
+    Some(regions)
+}
+
+fn flowchart_regions(node: &AstNode, source: &str) -> Vec<Span> {
+    let text = node.span.text(source);
+    match (&node.kind, node.statement_type()) {
+        (NodeKind::Node, _) => node
+            .id()
+            .filter(|id| text.starts_with(id))
+            .map(|id| Span::from_len(node.span.start, id.len()))
+            .into_iter()
+            .collect(),
+        // The id comes before the `[label]`
+        (NodeKind::Subgraph, _) => {
+            let end = text.find(['[', '"']).unwrap_or(text.len());
+            after_words(node.span, &text[..end], 1).into_iter().collect()
+        }
+        (NodeKind::Style, _) | (NodeKind::Statement, Some("click")) => nth_word(node.span, text, 1).into_iter().collect(),
+        // `class a,b name`: every word but the keyword and the class name
+        (NodeKind::Statement, Some("class_assignment")) => {
+            let end = text.trim_end().rfind(char::is_whitespace).unwrap_or(0);
+            after_words(node.span, &text[..end], 1).into_iter().collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn sequence_regions(node: &AstNode, source: &str) -> Vec<Span> {
+    let text = node.span.text(source);
+    let head = text.split(':').next().unwrap_or_default();
+    match node.kind {
+        // The alias after `as` is a label
+        NodeKind::Participant => {
+            let keywords = if node.get_property("created").is_some() { 2 } else { 1 };
+            nth_word(node.span, text, keywords).into_iter().collect()
+        }
+        NodeKind::Message => vec![Span::from_len(node.span.start, head.len())],
+        NodeKind::Note => after_position(node, head).into_iter().collect(),
+        NodeKind::Activation => after_words(node.span, text, 1).into_iter().collect(),
+        NodeKind::Statement if matches!(node.statement_type(), Some("create" | "destroy")) => {
+            after_words(node.span, text, 1).into_iter().collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn state_regions(node: &AstNode, source: &str) -> Vec<Span> {
+    let text = node.span.text(source);
+    let head = text.split(':').next().unwrap_or_default();
+    match node.kind {
+        NodeKind::State if text.starts_with("state ") => {
+            // `state "Label" as Id`, `state Id {` or `state Id <<fork>>`
+            let declaration = &text[..text.find(['{', '<']).unwrap_or(text.len())];
+            match declaration.rfind(" as ") {
+                Some(at) => after_words(Span::new(node.span.start + at + 4, node.span.end), &declaration[at + 4..], 0),
+                None => nth_word(node.span, declaration, 1),
+            }
+            .into_iter()
+            .collect()
+        }
+        NodeKind::State => nth_word(node.span, text, 0).into_iter().collect(),
+        NodeKind::Transition => vec![Span::from_len(node.span.start, head.len())],
+        NodeKind::Note => after_position(node, head).into_iter().collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Returns the part of a note's head after its `left of`, `right of` or
+/// `over` keywords.
+fn after_position(node: &AstNode, head: &str) -> Option<Span> {
+    let position = node.get_property("position")?;
+    let keyword = position.split_whitespace().take_while(|w| matches!(*w, "left" | "right" | "of" | "over"));
+    after_words(node.span, head, 1 + keyword.count())
+}
+
+/// Returns the span of the `n`-th whitespace-separated word of `text`,
+/// which starts at `span.start`.
+fn nth_word(span: Span, text: &str, n: usize) -> Option<Span> {
+    let region = after_words(span, text, n)?;
+    let word = &text[region.start - span.start..];
+    let len = word.find(char::is_whitespace).unwrap_or(word.len());
+    Some(Span::from_len(region.start, len))
+}
+
+/// Returns the span of `text` after its first `n` whitespace-separated
+/// words, or `None` if nothing is left.
+fn after_words(span: Span, text: &str, n: usize) -> Option<Span> {
+    let mut rest = text;
+    for _ in 0..n {
+        let trimmed = rest.trim_start();
+        rest = &trimmed[trimmed.find(char::is_whitespace)?..];
+    }
+    let rest = rest.trim_start();
+    let start = span.start + text.len() - rest.len();
+    (!rest.trim_end().is_empty()).then(|| Span::from_len(start, rest.trim_end().len()))
+}
+
+/// Returns the spans of the identifiers in a region of `source`: runs of
+/// letters, digits and `_`, joined by single `-`s if `hyphens` is set.
+fn words(source: &str, region: Span, hyphens: bool) -> Vec<Span> {
+    let text = region.text(source);
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices().chain(std::iter::once((text.len(), ' '))) {
+        let joins = hyphens && c == '-' && start.is_some() && text[i + 1..].starts_with(is_word);
+        match (is_word(c) || joins, start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                words.push(Span::new(region.start + s, region.start + i));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rename_flowchart_skips_labels() {
+        let code = "flowchart TD\n    OrderSvc[OrderSvc API] --> Db\n    style OrderSvc fill:#f00\n    click OrderSvc \"https://example.com\" \"Open OrderSvc\"\n    Db --> OrderSvc";
+        let result = rename_identifier(code, Some(DiagramType::Flowchart), "OrderSvc", "OrderService").unwrap();
+        assert_eq!(
+            result.code,
+            "flowchart TD\n    OrderService[OrderSvc API] --> Db\n    style OrderService fill:#f00\n    click OrderService \"https://example.com\" \"Open OrderSvc\"\n    Db --> OrderService"
+        );
+        assert_eq!(result.occurrences.len(), 4);
+    }
+
+    #[test]
+    fn test_rename_sequence_and_state() {
+        let code = "sequenceDiagram\n    participant A as A team\n    A->>+B: A says hi\n    Note over A,B: A\n    deactivate B";
+        let result = rename_identifier(code, None, "A", "Alice").unwrap();
+        assert_eq!(
+            result.code,
+            "sequenceDiagram\n    participant Alice as A team\n    Alice->>+B: A says hi\n    Note over Alice,B: A\n    deactivate B"
+        );
+
+        let code = "sequenceDiagram\n    A->>B: hi\n    create participant C\n    B->>C: hi";
+        let result = rename_identifier(code, None, "C", "Carl").unwrap();
+        assert_eq!(result.code, "sequenceDiagram\n    A->>B: hi\n    create participant Carl\n    B->>Carl: hi");
+
+        let code = "stateDiagram-v2\n    [*] --> Idle\n    state \"Idle state\" as Idle\n    Idle --> Busy : leave Idle\n    note right of Idle : Idle";
+        let result = rename_identifier(code, None, "Idle", "Waiting").unwrap();
+        assert_eq!(
+            result.code,
+            "stateDiagram-v2\n    [*] --> Waiting\n    state \"Idle state\" as Waiting\n    Waiting --> Busy : leave Idle\n    note right of Waiting : Idle"
+        );
+    }
+
+    #[test]
+    fn test_rename_hyphenated_ids() {
+        let code = "flowchart TD\n    order-svc --> order\n    order --> db\n    style order-svc fill:#f00";
+        let result = rename_identifier(code, None, "order", "orders").unwrap();
+        assert_eq!(result.code, "flowchart TD\n    order-svc --> orders\n    orders --> db\n    style order-svc fill:#f00");
+        let result = rename_identifier(code, None, "order-svc", "order-service").unwrap();
+        assert_eq!(
+            result.code,
+            "flowchart TD\n    order-service --> order\n    order --> db\n    style order-service fill:#f00"
+        );
+        assert_eq!(result.occurrences.len(), 2);
+
+        let code = "stateDiagram-v2\n    order-svc --> order\n    order --> [*]";
+        let result = rename_identifier(code, None, "order", "done").unwrap();
+        assert_eq!(result.code, "stateDiagram-v2\n    order-svc --> done\n    done --> [*]");
+    }
+
+    #[test]
+    fn test_rename_rejections() {
+        let code = "graph TD\n    A --> B";
+        let message = |result: Result<RenameResult, Vec<Diagnostic>>| result.unwrap_err()[0].message.clone();
+        assert_eq!(message(rename_identifier(code, None, "C", "D")), "'C' is not an id in this diagram");
+        assert_eq!(message(rename_identifier(code, None, "A", "B")), "'B' is already an id in this diagram");
+        assert_eq!(
+            message(rename_identifier(code, None, "A", "end")),
+            "Renaming 'A' to 'end' makes the diagram invalid"
+        );
+        assert_eq!(
+            message(rename_identifier(code, Some(DiagramType::Sequence), "A", "C")),
+            "Expected a sequence diagram, found flowchart"
+        );
+    }
+}
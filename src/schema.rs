@@ -0,0 +1,35 @@
+//! The JSON Schema of the lint result envelope.
+//!
+//! The schema is maintained by hand in `schema/lint-result.schema.json` and
+//! describes [`crate::ParseResult::to_json_value`] and the CLI's `json` and
+//! `ndjson` output. It rejects unknown fields, so the golden tests, which
+//! check every fixture's output against it, fail when the envelope changes
+//! without the schema. Any change to the envelope bumps
+//! [`OUTPUT_SCHEMA_VERSION`] and the version in the schema's `$id`.
+
+/// The version of the output schema, also the last segment of its `$id`.
+pub const OUTPUT_SCHEMA_VERSION: u32 = 1;
+
+const OUTPUT_SCHEMA: &str = include_str!("../schema/lint-result.schema.json");
+
+/// Returns the JSON Schema (draft 2020-12) of the lint result envelope.
+///
+/// ```
+/// let schema = mermaid_linter::output_schema();
+/// assert_eq!(schema["$schema"], "https://json-schema.org/draft/2020-12/schema");
+/// ```
+pub fn output_schema() -> serde_json::Value {
+    serde_json::from_str(OUTPUT_SCHEMA).expect("the bundled output schema is valid JSON")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_id_carries_version() {
+        let schema = output_schema();
+        let id = schema["$id"].as_str().unwrap();
+        assert!(id.ends_with(&format!("/v{}", OUTPUT_SCHEMA_VERSION)), "{}", id);
+    }
+}
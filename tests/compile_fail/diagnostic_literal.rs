@@ -0,0 +1,15 @@
+use mermaid_linter::{Diagnostic, DiagnosticCode, Severity, Span};
+
+fn main() {
+    let _diagnostic = Diagnostic {
+        code: DiagnosticCode::InvalidSyntax,
+        message: "Unexpected token".to_string(),
+        severity: Severity::Error,
+        span: Span::new(0, 1),
+        diagram_type: None,
+        notes: Vec::new(),
+        related: Vec::new(),
+        fixes: Vec::new(),
+        range: None,
+    };
+}
@@ -0,0 +1,8 @@
+use mermaid_linter::MermaidConfig;
+
+fn main() {
+    let _config = MermaidConfig {
+        wrap: true,
+        ..Default::default()
+    };
+}
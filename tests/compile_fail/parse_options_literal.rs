@@ -0,0 +1,8 @@
+use mermaid_linter::ParseOptions;
+
+fn main() {
+    let _options = ParseOptions {
+        recover: true,
+        ..Default::default()
+    };
+}
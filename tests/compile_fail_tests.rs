@@ -0,0 +1,8 @@
+//! Compile-fail tests: public types that gain fields over time can't be
+//! built with struct literals outside the crate.
+
+#[test]
+fn test_non_exhaustive_literals_do_not_compile() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/compile_fail/*.rs");
+}
@@ -0,0 +1,89 @@
+//! Locating the first difference between two JSON values.
+//!
+//! Golden failures report the JSON pointer of the first differing value
+//! instead of two full documents. Spans (`{"start": .., "end": ..}` objects)
+//! are compared as a whole, so a shifted span shows both offsets.
+
+use serde_json::Value;
+
+/// The first place two JSON values differ.
+#[derive(Debug, PartialEq)]
+pub struct Difference {
+    /// The JSON pointer (RFC 6901) of the differing value; empty for the root.
+    pub pointer: String,
+    /// The expected value, or `None` if it is missing.
+    pub expected: Option<Value>,
+    /// The actual value, or `None` if it is missing.
+    pub actual: Option<Value>,
+}
+
+impl std::fmt::Display for Difference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let show = |value: &Option<Value>| value.as_ref().map_or("<missing>".to_string(), Value::to_string);
+        let pointer = if self.pointer.is_empty() { "<root>" } else { &self.pointer };
+        write!(
+            f,
+            "first difference at {}\n  expected: {}\n  actual:   {}",
+            pointer,
+            show(&self.expected),
+            show(&self.actual)
+        )
+    }
+}
+
+/// Returns the first difference between `expected` and `actual`, walking
+/// objects in key order and arrays in index order.
+pub fn first_difference(expected: &Value, actual: &Value) -> Option<Difference> {
+    difference_at(String::new(), Some(expected), Some(actual))
+}
+
+fn difference_at(pointer: String, expected: Option<&Value>, actual: Option<&Value>) -> Option<Difference> {
+    match (expected, actual) {
+        (Some(Value::Object(e)), Some(Value::Object(a))) if !is_span(e) || !is_span(a) => {
+            let mut keys: Vec<&String> = e.keys().chain(a.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            keys.into_iter().find_map(|key| {
+                let pointer = format!("{}/{}", pointer, key.replace('~', "~0").replace('/', "~1"));
+                difference_at(pointer, e.get(key), a.get(key))
+            })
+        }
+        (Some(Value::Array(e)), Some(Value::Array(a))) => (0..e.len().max(a.len()))
+            .find_map(|i| difference_at(format!("{}/{}", pointer, i), e.get(i), a.get(i))),
+        (expected, actual) if expected == actual => None,
+        (expected, actual) => Some(Difference {
+            pointer,
+            expected: expected.cloned(),
+            actual: actual.cloned(),
+        }),
+    }
+}
+
+fn is_span(object: &serde_json::Map<String, Value>) -> bool {
+    object.len() == 2 && object.contains_key("start") && object.contains_key("end")
+}
+
+#[test]
+fn test_first_difference_path() {
+    let expected = serde_json::json!({"ok": true, "diagnostics": [{"code": "E301"}, {"code": "E303"}]});
+    let actual = serde_json::json!({"ok": true, "diagnostics": [{"code": "E301"}, {"code": "E304"}]});
+    let difference = first_difference(&expected, &actual).unwrap();
+    assert_eq!(difference.pointer, "/diagnostics/1/code");
+    assert_eq!(difference.expected, Some(serde_json::json!("E303")));
+    assert_eq!(difference.actual, Some(serde_json::json!("E304")));
+    assert_eq!(first_difference(&expected, &expected), None);
+}
+
+#[test]
+fn test_first_difference_missing_and_spans() {
+    let expected = serde_json::json!({"a/b": [1], "span": {"start": 3, "end": 5}});
+    let actual = serde_json::json!({"a/b": [1, 2], "span": {"start": 4, "end": 5}});
+    let difference = first_difference(&expected, &actual).unwrap();
+    assert_eq!(difference.pointer, "/a~1b/1");
+    assert_eq!(difference.expected, None);
+
+    let actual = serde_json::json!({"a/b": [1], "span": {"start": 4, "end": 5}});
+    let difference = first_difference(&expected, &actual).unwrap();
+    assert_eq!(difference.pointer, "/span");
+    assert!(difference.to_string().contains("expected: {\"end\":5,\"start\":3}"));
+}
@@ -1,57 +1,69 @@
 //! Golden tests for the mermaid linter.
 //!
-//! These tests validate fixtures against expected JSON output.
-//! When the expected output doesn't exist, it will be created.
+//! These tests validate the fixture corpus (`mermaid_linter::corpus`, built
+//! from `tests/fixtures`) against expected JSON output.
+//! When the expected output doesn't exist, it will be created. Each
+//! fixture's JSON envelope is also checked against the output schema.
 
-use std::fs;
-use std::path::PathBuf;
-
-use mermaid_linter::parse;
-
-/// Test all fixture files in a directory
-fn test_fixtures_in_dir(dir_name: &str) {
-    let fixtures_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-        .join("tests")
-        .join("fixtures")
-        .join(dir_name);
-
-    let golden_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-        .join("tests")
-        .join("golden")
-        .join(dir_name);
+mod json_diff;
 
-    // Create golden dir if it doesn't exist
-    fs::create_dir_all(&golden_dir).expect("Failed to create golden directory");
+use std::fs;
+use std::path::{Path, PathBuf};
 
-    // Find all .mmd files
-    let entries = fs::read_dir(&fixtures_dir).expect("Failed to read fixtures directory");
+use mermaid_linter::corpus::{self, CorpusEntry};
+use mermaid_linter::{output_schema, parse};
 
-    for entry in entries {
-        let entry = entry.expect("Failed to read entry");
-        let path = entry.path();
+#[test]
+fn test_corpus_fixtures() {
+    let golden_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden");
+    let schema = jsonschema::validator_for(&output_schema()).expect("Invalid output schema");
+    for entry in corpus::all() {
+        test_single_fixture(entry, &golden_dir, &schema);
+    }
+}
 
-        if path.extension().map_or(false, |ext| ext == "mmd") {
-            test_single_fixture(&path, &golden_dir);
+#[test]
+fn test_corpus_matches_fixture_files() {
+    let fixtures_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+    let mut on_disk = 0;
+    for dir in fs::read_dir(&fixtures_dir).expect("Failed to read fixtures directory") {
+        let dir = dir.expect("Failed to read entry").path();
+        if dir.is_dir() {
+            on_disk += fs::read_dir(&dir)
+                .expect("Failed to read fixture directory")
+                .filter(|entry| {
+                    let path = entry.as_ref().unwrap().path();
+                    path.extension().is_some_and(|ext| ext == "mmd")
+                })
+                .count();
         }
     }
-}
+    assert_eq!(corpus::all().len(), on_disk);
 
-fn test_single_fixture(fixture_path: &PathBuf, golden_dir: &PathBuf) {
-    let fixture_name = fixture_path.file_stem().unwrap().to_str().unwrap();
-    let golden_path = golden_dir.join(format!("{}.json", fixture_name));
+    for entry in corpus::all() {
+        let result = std::panic::catch_unwind(|| parse(entry.source, None));
+        assert!(result.is_ok(), "Parsing {} panicked", entry.name);
+    }
+}
 
-    // Read fixture
-    let content = fs::read_to_string(fixture_path)
-        .unwrap_or_else(|_| panic!("Failed to read fixture: {:?}", fixture_path));
+fn test_single_fixture(entry: &CorpusEntry, golden_dir: &Path, schema: &jsonschema::Validator) {
+    let golden_path = golden_dir.join(format!("{}.json", entry.name));
 
     // Parse
-    let result = parse(&content, None);
+    let result = parse(entry.source, None);
+
+    let mut envelope = result.to_json_value(entry.source);
+    envelope["file"] = entry.name.into();
+    let errors: Vec<String> = schema.iter_errors(&envelope).map(|e| format!("{} at {}", e, e.instance_path)).collect();
+    assert!(errors.is_empty(), "Output of {:?} does not match the schema: {:?}", entry.name, errors);
 
     // Create a serializable result
     let output = serde_json::json!({
         "ok": result.ok,
         "diagram_type": result.diagram_type.map(|t| t.as_str()),
         "title": result.title,
+        "acc_title": result.acc_title,
+        "acc_descr": result.acc_descr,
         "has_ast": result.ast.is_some(),
         "diagnostic_count": result.diagnostics.len(),
         "diagnostics": result.diagnostics.iter().map(|d| {
@@ -70,45 +82,28 @@ fn test_single_fixture(fixture_path: &PathBuf, golden_dir: &PathBuf) {
         let expected = fs::read_to_string(&golden_path)
             .unwrap_or_else(|_| panic!("Failed to read golden file: {:?}", golden_path));
 
-        assert_eq!(
-            output_str.trim(),
-            expected.trim(),
-            "Golden test failed for {:?}\n\nExpected:\n{}\n\nActual:\n{}",
-            fixture_path,
-            expected,
-            output_str
-        );
+        if output_str.trim() != expected.trim() {
+            let expected_value: serde_json::Value = serde_json::from_str(&expected)
+                .unwrap_or_else(|e| panic!("Invalid golden file {:?}: {}", golden_path, e));
+            let difference = json_diff::first_difference(&expected_value, &output)
+                .map_or("only the formatting differs".to_string(), |d| d.to_string());
+            panic!(
+                "Golden test failed for {:?}: {}\n\nExpected:\n{}\n\nActual:\n{}",
+                entry.name, difference, expected, output_str
+            );
+        }
     } else {
         // Create golden file
+        fs::create_dir_all(golden_path.parent().unwrap()).expect("Failed to create golden directory");
         fs::write(&golden_path, &output_str)
             .unwrap_or_else(|_| panic!("Failed to write golden file: {:?}", golden_path));
         println!("Created golden file: {:?}", golden_path);
     }
 }
 
-#[test]
-fn test_flowchart_fixtures() {
-    test_fixtures_in_dir("flowchart");
-}
-
-#[test]
-fn test_sequence_fixtures() {
-    test_fixtures_in_dir("sequence");
-}
-
-#[test]
-fn test_class_fixtures() {
-    test_fixtures_in_dir("class");
-}
-
-#[test]
-fn test_state_fixtures() {
-    test_fixtures_in_dir("state");
-}
-
 /// Test error cases - diagrams that should fail
 mod error_cases {
-    use mermaid_linter::parse;
+    use mermaid_linter::{parse, DiagnosticCode};
 
     #[test]
     fn test_unknown_diagram() {
@@ -121,6 +116,9 @@ mod error_cases {
     fn test_empty_input() {
         let result = parse("", None);
         assert!(!result.ok);
+        let diagnostic = &result.diagnostics[0];
+        assert_eq!(diagnostic.code, DiagnosticCode::EmptyInput);
+        assert!(diagnostic.notes[0].contains("sequenceDiagram"));
     }
 
     #[test]
@@ -129,6 +127,14 @@ mod error_cases {
         assert!(!result.ok);
     }
 
+    #[test]
+    fn test_error_keyword() {
+        let result = parse("error", None);
+        assert!(!result.ok);
+        assert!(result.diagnostics[0].message.starts_with("'error' is Mermaid's internal directive"));
+        assert_eq!(result.diagnostics[0].notes.len(), 1);
+    }
+
     #[test]
     fn test_incomplete_flowchart() {
         let _result = parse("graph TD\n    A -->", None);
@@ -0,0 +1,126 @@
+//! Integration tests for C4 diagrams.
+
+use mermaid_linter::ast::NodeKind;
+use mermaid_linter::{parse, DiagramType};
+
+/// The C4Context example from the Mermaid documentation.
+const C4_CONTEXT: &str = r#"C4Context
+    title System Context diagram for Internet Banking System
+    Enterprise_Boundary(b0, "BankBoundary0") {
+        Person(customerA, "Banking Customer A", "A customer of the bank, with personal bank accounts.")
+        Person(customerB, "Banking Customer B")
+        Person_Ext(customerC, "Banking Customer C", "desc")
+
+        Person(customerD, "Banking Customer D", "A customer of the bank, <br/> with personal bank accounts.")
+
+        System(SystemAA, "Internet Banking System", "Allows customers to view information about their bank accounts, and make payments.")
+
+        Enterprise_Boundary(b1, "BankBoundary") {
+
+            SystemDb_Ext(SystemE, "Mainframe Banking System", "Stores all of the core banking information about customers, accounts, transactions, etc.")
+
+            System_Boundary(b2, "BankBoundary2") {
+                System(SystemA, "Banking System A")
+                System(SystemB, "Banking System B", "A system of the bank, with personal bank accounts. next line.")
+            }
+
+            System_Ext(SystemC, "E-mail system", "The internal Microsoft Exchange e-mail system.")
+            SystemDb(SystemD, "Banking System D Database", "A system of the bank, with personal bank accounts.")
+
+            Boundary(b3, "BankBoundary3", "boundary") {
+                SystemQueue(SystemF, "Banking System F Queue", "A system of the bank.")
+                SystemQueue_Ext(SystemG, "Banking System G Queue", "A system of the bank, with personal bank accounts.")
+            }
+        }
+    }
+
+    BiRel(customerA, SystemAA, "Uses")
+    BiRel(SystemAA, SystemE, "Uses")
+    Rel(SystemAA, SystemC, "Sends e-mails", "SMTP")
+    Rel(SystemC, customerA, "Sends e-mails to")
+
+    UpdateElementStyle(customerA, $fontColor="red", $bgColor="grey", $borderColor="red")
+    UpdateRelStyle(customerA, SystemAA, $textColor="blue", $lineColor="blue", $offsetX="5")
+    UpdateLayoutConfig($c4ShapeInRow="3", $c4BoundaryInRow="1")"#;
+
+#[test]
+fn test_c4_context_is_scanned() {
+    let result = parse(C4_CONTEXT, None);
+    assert!(result.ok, "Failed to scan C4 diagram: {:?}", result.diagnostics);
+    assert_eq!(result.diagram_type, Some(DiagramType::C4));
+
+    let ast = result.ast.unwrap();
+    assert_eq!(ast.root.get_property("status"), Some("scanned"));
+}
+
+#[test]
+fn test_c4_element_aliases_and_labels() {
+    let ast = parse(C4_CONTEXT, None).ast.unwrap();
+    let elements: Vec<_> = ast
+        .root
+        .children
+        .iter()
+        .filter(|n| n.kind == NodeKind::Node)
+        .map(|n| (n.get_property("alias").unwrap(), n.get_property("label").unwrap()))
+        .collect();
+
+    assert_eq!(
+        elements,
+        vec![
+            ("customerA", "Banking Customer A"),
+            ("customerB", "Banking Customer B"),
+            ("customerC", "Banking Customer C"),
+            ("customerD", "Banking Customer D"),
+            ("SystemAA", "Internet Banking System"),
+            ("SystemE", "Mainframe Banking System"),
+            ("SystemA", "Banking System A"),
+            ("SystemB", "Banking System B"),
+            ("SystemC", "E-mail system"),
+            ("SystemD", "Banking System D Database"),
+            ("SystemF", "Banking System F Queue"),
+            ("SystemG", "Banking System G Queue"),
+        ]
+    );
+
+    let system_e = ast
+        .root
+        .children
+        .iter()
+        .find(|n| n.text.as_deref() == Some("SystemE"))
+        .unwrap();
+    let span = system_e.span;
+    assert!(span.text(C4_CONTEXT).starts_with("SystemDb_Ext(SystemE,"));
+}
+
+#[test]
+fn test_c4_relationship_endpoints() {
+    let ast = parse(C4_CONTEXT, None).ast.unwrap();
+    let rels: Vec<_> = ast
+        .root
+        .children
+        .iter()
+        .filter(|n| n.kind == NodeKind::Edge)
+        .map(|n| (n.get_property("from").unwrap(), n.get_property("to").unwrap()))
+        .collect();
+
+    assert_eq!(
+        rels,
+        vec![
+            ("customerA", "SystemAA"),
+            ("SystemAA", "SystemE"),
+            ("SystemAA", "SystemC"),
+            ("SystemC", "customerA"),
+        ]
+    );
+}
+
+#[test]
+fn test_c4_quoted_label_with_commas() {
+    let code = r#"C4Context
+    System(SystemB, "Banking System B, with accounts", "A system of the bank, next line.")"#;
+
+    let ast = parse(code, None).ast.unwrap();
+    let system = &ast.root.children[1];
+    assert_eq!(system.get_property("alias"), Some("SystemB"));
+    assert_eq!(system.get_property("label"), Some("Banking System B, with accounts"));
+}
@@ -1,6 +1,6 @@
 //! Integration tests for class diagrams.
 
-use mermaid_linter::{parse, detect_type, DiagramType};
+use mermaid_linter::{parse, detect_type, DiagramType, ParseOptions};
 
 #[test]
 fn test_simple_class_diagram() {
@@ -284,3 +284,111 @@ fn test_class_inline_definition() {
     let result = parse(code, None);
     assert!(result.ok, "Failed to parse inline class definition: {:?}", result.diagnostics);
 }
+
+#[test]
+fn test_class_relation_orientation() {
+    use mermaid_linter::ast::{class_relations, HeadSide};
+
+    let left = parse("classDiagram\n    Animal <|-- Dog", None);
+    let right = parse("classDiagram\n    Dog --|> Animal", None);
+    let left = &class_relations(left.ast.as_ref().unwrap())[0];
+    let right = &class_relations(right.ast.as_ref().unwrap())[0];
+
+    assert_eq!((left.from.as_str(), left.to.as_str()), ("Dog", "Animal"));
+    assert_eq!((right.from.as_str(), right.to.as_str()), ("Dog", "Animal"));
+    assert_eq!(left.relation_type, right.relation_type);
+    assert_eq!(left.head_side, Some(HeadSide::Left));
+    assert_eq!(right.head_side, Some(HeadSide::Right));
+}
+
+#[test]
+fn test_class_relation_cardinalities_and_label() {
+    use mermaid_linter::ast::{class_relations, Cardinality};
+
+    let result = parse("classDiagram\n    Customer \"1\" o-- \"0..*\" Order : places\n    Order \"many\" --* \"1\" Shop", None);
+    assert!(result.ok, "Failed: {:?}", result.diagnostics);
+    let relations = class_relations(result.ast.as_ref().unwrap());
+    assert_eq!(relations.len(), 2);
+
+    let places = &relations[0];
+    assert_eq!((places.from.as_str(), places.to.as_str()), ("Order", "Customer"));
+    assert_eq!(places.label.as_deref(), Some("places"));
+    assert_eq!(places.from_cardinality, Some(Cardinality { min: Some("0".into()), max: Some("*".into()) }));
+    assert_eq!(places.to_cardinality, Some(Cardinality::parse("1")));
+
+    let owned = &relations[1];
+    assert_eq!((owned.from.as_str(), owned.to.as_str()), ("Order", "Shop"));
+    assert_eq!(owned.from_cardinality.as_ref().and_then(|c| c.max.as_deref()), Some("many"));
+    assert_eq!(owned.label, None);
+}
+
+#[test]
+fn test_class_click_actions() {
+    let code = r#"classDiagram
+    class Shape
+    click Shape call showInfo("Shape, base", 2) "Details"
+    click Shape href "https://example.com" _blank"#;
+
+    let result = parse(code, None);
+    assert!(result.ok, "Failed to parse click statements: {:?}", result.diagnostics);
+    let children = &result.ast.as_ref().unwrap().root.children;
+    let call = &children[2];
+    assert_eq!(call.get_property("callback"), Some("showInfo"));
+    assert_eq!(call.get_property("args"), Some(r#"["Shape, base","2"]"#));
+    assert_eq!(call.get_property("tooltip"), Some("Details"));
+    assert_eq!(children[3].get_property("link_target"), Some("_blank"));
+}
+
+#[test]
+fn test_class_click_on_backtick_name() {
+    let code = "classDiagram\n    class `Animal Class`\n    `Animal Class` <|-- Dog\n    click `Animal Class` href \"x\"";
+    let result = parse(code, None);
+    assert!(result.ok, "{:?}", result.diagnostics);
+    let children = &result.ast.as_ref().unwrap().root.children;
+    assert_eq!(children[1].get_property("name"), Some("Animal Class"));
+    assert_eq!(children[2].get_property("from"), Some("Animal Class"));
+    assert_eq!(children[3].get_property("target"), Some("Animal Class"));
+    assert_eq!(children[3].get_property("url"), Some("x"));
+}
+
+#[test]
+fn test_class_prefer_click_syntax() {
+    let code = r#"classDiagram
+    class Shape
+    class Circle
+    click Shape href "https://example.com/shape"
+    link Circle "https://example.com/circle" "Circle docs"
+    callback Shape "showShape" "Details""#;
+
+    let result = parse(code, None);
+    assert!(result.ok);
+    assert!(result.diagnostics.is_empty());
+
+    let options = ParseOptions::default().with_click_syntax_warnings();
+    let result = parse(code, Some(options.clone()));
+    assert!(result.ok);
+    let messages: Vec<_> = result.diagnostics.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        ["'link' is the older form of 'click'", "'callback' is the older form of 'click'"]
+    );
+
+    let fixed = mermaid_linter::fix::fix(code, Some(options)).unwrap();
+    assert!(fixed.code.contains(r#"click Circle href "https://example.com/circle" "Circle docs""#));
+    assert!(fixed.code.ends_with(r#"click Shape call showShape() "Details""#));
+}
+
+#[test]
+fn test_unknown_statement_is_skipped_with_a_warning() {
+    let code = "classDiagram\n    class Animal\n    style Animal fill:#f9f\n    Animal <|-- Dog\n    Dog : +bark()";
+    let result = parse(code, None);
+    assert!(result.ok, "Failed to parse: {:?}", result.diagnostics);
+    assert_eq!(result.diagnostics.len(), 1);
+    assert_eq!(
+        result.diagnostics[0].message,
+        "'style' statements are not supported by this version of mermaid-linter; the statement is skipped"
+    );
+    let root = &result.ast.as_ref().unwrap().root;
+    assert!(root.children.iter().any(|c| c.get_property("relation_type") == Some("Inheritance")));
+    assert!(root.children.iter().any(|c| c.get_property("type") == Some("class_member")));
+}
@@ -0,0 +1,526 @@
+//! Integration tests for the `mermaid-lint` binary.
+
+use std::process::Command;
+
+fn run(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_mermaid-lint"))
+        .args(args)
+        .output()
+        .expect("failed to run mermaid-lint");
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_rules_lists_known_rule() {
+    let output = run(&["rules"]);
+    let line = output.lines().find(|l| l.starts_with("E307")).expect("E307 not listed");
+    assert!(line.contains("warning"));
+    assert!(line.contains("defined more than once"));
+
+    let line = output.lines().find(|l| l.starts_with("unused-classdef")).expect("unused-classdef not listed");
+    assert!(line.contains(" off "));
+    assert!(line.contains("E401"));
+
+    let line = output
+        .lines()
+        .find(|l| l.starts_with("confusable-identifiers"))
+        .expect("confusable-identifiers not listed");
+    assert!(line.contains("with_confusable_identifier_warnings"));
+}
+
+#[test]
+fn test_rules_columns_aligned() {
+    let output = run(&["rules"]);
+    let columns: std::collections::HashSet<_> = output
+        .lines()
+        .map(|line| {
+            let name = line.find(' ').expect("rule line has no columns");
+            name + line[name..].find(|c: char| c != ' ').unwrap()
+        })
+        .collect();
+    assert_eq!(columns.len(), 1, "rule columns are misaligned:\n{}", output);
+}
+
+#[test]
+fn test_rules_json() {
+    let output = run(&["rules", "--format", "json"]);
+    let rules: serde_json::Value = serde_json::from_str(&output).unwrap();
+    let parser_error = rules
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|r| r["code"] == "E301")
+        .expect("E301 not listed");
+    assert_eq!(parser_error["name"], "ParserError");
+    assert_eq!(parser_error["severity"], "error");
+    assert_eq!(parser_error["enabled"], true);
+
+    let repeated_styles = rules
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|r| r["name"] == "repeated-styles")
+        .expect("repeated-styles not listed");
+    assert_eq!(repeated_styles["code"], "E307");
+    assert_eq!(repeated_styles["severity"], "hint");
+    assert_eq!(repeated_styles["enabled"], false);
+}
+
+#[test]
+fn test_severity_override_flag() {
+    let file = tempfile::Builder::new().suffix(".mmd").tempfile().unwrap();
+    std::fs::write(file.path(), "graph TD\n    A --> B\n    --> oops").unwrap();
+    let path = file.path().to_str().unwrap();
+
+    let failing = Command::new(env!("CARGO_BIN_EXE_mermaid-lint")).arg(path).output().unwrap();
+    assert!(!failing.status.success());
+
+    let output = run(&["--severity", "E305=warning", path]);
+    assert!(output.contains(": OK"));
+
+    let output = run(&["--severity", "e305=OFF", path]);
+    assert!(output.contains(": OK"));
+    assert!(!output.contains("E305"));
+}
+
+#[test]
+fn test_severity_override_rejects_unknown_code() {
+    let output = Command::new(env!("CARGO_BIN_EXE_mermaid-lint"))
+        .args(["--severity", "E999=warning", "rules"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown diagnostic code"));
+}
+
+#[test]
+fn test_json_diagnostics_link_docs() {
+    let file = tempfile::Builder::new().suffix(".mmd").tempfile().unwrap();
+    std::fs::write(file.path(), "flowchart TD\n    accTitle: One\n    accTitle: Two\n    A --> B").unwrap();
+
+    let path = file.path().to_str().unwrap();
+
+    let output = run(&["--format", "json", path]);
+    let result: serde_json::Value = serde_json::from_str(&output).unwrap();
+    let href = result["diagnostics"][0]["codeDescription"]["href"].as_str().unwrap();
+    assert!(href.ends_with("docs/rules.md#duplicate-definition"));
+    assert_eq!(result["diagnostics"][0]["url"], href);
+
+    let output = run(&["--docs-url", "https://lint.example.com/rules/", "--format", "json", path]);
+    let result: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(result["diagnostics"][0]["url"], "https://lint.example.com/rules/duplicate-definition");
+
+    std::fs::write(file.path(), "flowchart TD\n    A[ --> B").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_mermaid-lint"))
+        .args(["--docs-url", "https://lint.example.com/rules", path])
+        .output()
+        .unwrap();
+    let output = String::from_utf8(output.stdout).unwrap();
+    assert!(output.contains("\n  see: https://lint.example.com/rules/expected-token\n"), "{}", output);
+}
+
+#[test]
+fn test_fix_rewrites_fixture() {
+    let fixture = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/flowchart/empty_labels.mmd");
+    let original = std::fs::read_to_string(fixture).unwrap();
+    let file = tempfile::Builder::new().suffix(".mmd").tempfile().unwrap();
+    std::fs::write(file.path(), &original).unwrap();
+    let path = file.path().to_str().unwrap();
+
+    let output = run(&["fix", "--dry-run", path]);
+    assert_eq!(output.lines().count(), 2);
+    assert!(output.contains(":3:6: Empty node label is not allowed"));
+    assert_eq!(std::fs::read_to_string(path).unwrap(), original);
+
+    run(&["fix", path]);
+    let fixed = std::fs::read_to_string(path).unwrap();
+    assert!(fixed.contains("    A --> B(Step)\n    B --> C\n"));
+    assert!(fixed.starts_with("%% Empty labels"));
+    assert!(mermaid_linter::parse(&fixed, None).ok);
+
+    let output = run(&["fix", path]);
+    assert!(output.contains("nothing to fix"));
+}
+
+#[test]
+fn test_fix_reports_skipped_fixes() {
+    let file = tempfile::Builder::new().suffix(".mmd").tempfile().unwrap();
+    std::fs::write(file.path(), "graph TD\r\n    A -->\r\n    B\r\n").unwrap();
+    let path = file.path().to_str().unwrap();
+
+    let output = run(&["fix", "--dry-run", path]);
+    assert!(output.contains(": 1 fix(es) could not be applied to text preprocessing changed"), "{}", output);
+    assert!(!output.contains("nothing to fix"), "{}", output);
+}
+
+#[test]
+fn test_rename_identifier() {
+    let original = "%% Orders\ngraph TD\n    Api[Api gateway] --> Db\n    click Api \"https://example.com\"\n";
+    let file = tempfile::Builder::new().suffix(".mmd").tempfile().unwrap();
+    std::fs::write(file.path(), original).unwrap();
+    let path = file.path().to_str().unwrap();
+
+    let output = run(&["rename", "--from", "Api", "--to", "Gateway", path]);
+    assert_eq!(output.lines().count(), 2);
+    assert!(output.contains(":3:5: renamed 'Api' to 'Gateway'"), "{}", output);
+    assert!(output.contains(":4:11: renamed 'Api' to 'Gateway'"), "{}", output);
+    assert_eq!(std::fs::read_to_string(path).unwrap(), original);
+
+    run(&["rename", "--from", "Api", "--to", "Gateway", "--write", path]);
+    assert_eq!(
+        std::fs::read_to_string(path).unwrap(),
+        "%% Orders\ngraph TD\n    Gateway[Api gateway] --> Db\n    click Gateway \"https://example.com\"\n"
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mermaid-lint"))
+        .args(["rename", "--from", "Api", "--to", "Db", path])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr).unwrap().contains("not renamed, 'Api' is not an id"));
+}
+
+#[test]
+fn test_rename_hyphenated_identifier() {
+    let file = tempfile::Builder::new().suffix(".mmd").tempfile().unwrap();
+    std::fs::write(file.path(), "graph TD\n    order-svc --> order\n").unwrap();
+    let path = file.path().to_str().unwrap();
+
+    run(&["rename", "--from", "order-svc", "--to", "orders", "--write", path]);
+    assert_eq!(std::fs::read_to_string(path).unwrap(), "graph TD\n    orders --> order\n");
+}
+
+#[test]
+fn test_lint_embedded_documents() {
+    for (fixture, valid_line, broken_line) in [("guide.rst", 10, 21), ("guide.adoc", 7, 16)] {
+        let path = format!("{}/tests/fixtures/embedded/{}", env!("CARGO_MANIFEST_DIR"), fixture);
+        let output = Command::new(env!("CARGO_BIN_EXE_mermaid-lint"))
+            .args(["lint", &path])
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(stdout.contains(&format!("{}:{}: OK", fixture, valid_line)), "{}", stdout);
+        assert!(stdout.contains(&format!("{}:{}: FAIL", fixture, broken_line)), "{}", stdout);
+        assert_eq!(stdout.matches(": OK").count() + stdout.matches(": FAIL").count(), 2);
+    }
+}
+
+#[test]
+fn test_baseline_suppresses_recorded_diagnostics() {
+    let dir = tempfile::tempdir().unwrap();
+    let diagram = dir.path().join("legacy.mmd");
+    let baseline = dir.path().join("baseline.json");
+    std::fs::write(&diagram, "graph TD\n    A --> B\n    --> oops\n").unwrap();
+    let lint = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_mermaid-lint"))
+            .args(args)
+            .arg(&diagram)
+            .output()
+            .unwrap()
+    };
+
+    let output = lint(&["--baseline", baseline.to_str().unwrap(), "--write-baseline"]);
+    assert!(output.status.success());
+    let recorded: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&baseline).unwrap()).unwrap();
+    assert_eq!(recorded["entries"].as_array().unwrap().len(), 1);
+
+    // A line added above moves the old error, and a new error appears
+    std::fs::write(&diagram, "graph TD\n    C --> D\n    A --> B\n    --> oops\n    --> again\n").unwrap();
+    let output = lint(&["--baseline", baseline.to_str().unwrap()]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("'--> again'"));
+    assert!(!stdout.contains("'--> oops'"));
+
+    std::fs::write(&diagram, "graph TD\n    C --> D\n    A --> B\n    --> oops\n").unwrap();
+    let output = lint(&["lint", "--format", "json", "--baseline", baseline.to_str().unwrap(), "--show-baselined"]);
+    assert!(output.status.success());
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(result["ok"], true);
+    assert_eq!(result["diagnostics"][0]["baselined"], true);
+    assert_eq!(result["diagnostics"][0]["fingerprint"], recorded["entries"][0]["fingerprint"]);
+    assert!(jsonschema::is_valid(&mermaid_linter::output_schema(), &result));
+}
+
+#[test]
+fn test_baseline_takes_a_single_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let baseline = dir.path().join("baseline.json");
+    std::fs::write(&baseline, "{\"entries\": []}\n").unwrap();
+    let (bad, good) = (dir.path().join("bad.mmd"), dir.path().join("good.mmd"));
+    std::fs::write(&bad, "graph TD\n    --> oops\n").unwrap();
+    std::fs::write(&good, "graph TD\n    A --> B\n").unwrap();
+
+    // The file right after `--baseline FILE` is linted, in its place
+    let output = Command::new(env!("CARGO_BIN_EXE_mermaid-lint"))
+        .args(["check", "--baseline"])
+        .args([&baseline, &bad])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_mermaid-lint"))
+        .arg("--baseline")
+        .args([&baseline, &bad, &good])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let (bad, good) = (stdout.find("bad.mmd").unwrap(), stdout.find("good.mmd").unwrap());
+    assert!(bad < good, "{}", stdout);
+}
+
+#[test]
+fn test_baseline_matches_normalized_paths() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("docs")).unwrap();
+    std::fs::write(dir.path().join("docs/a.mmd"), "graph TD\n    --> oops\n").unwrap();
+    let lint = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_mermaid-lint"))
+            .current_dir(dir.path())
+            .args(["--baseline", "baseline.json"])
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    assert!(lint(&["--write-baseline", "./docs/a.mmd"]).status.success());
+    let recorded = std::fs::read_to_string(dir.path().join("baseline.json")).unwrap();
+    assert!(recorded.contains("\"file\": \"docs/a.mmd\""), "{}", recorded);
+    assert!(lint(&["docs/a.mmd"]).status.success());
+    assert!(lint(&["./docs/./a.mmd"]).status.success());
+}
+
+#[test]
+fn test_write_baseline_flag_and_new_warning() {
+    let dir = tempfile::tempdir().unwrap();
+    let diagram = dir.path().join("states.mmd");
+    let baseline = dir.path().join("baseline.json");
+    let baseline = baseline.to_str().unwrap();
+    let repeated = "stateDiagram-v2\n    direction LR\n    direction TB\n";
+    std::fs::write(&diagram, repeated).unwrap();
+    let lint = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_mermaid-lint"))
+            .args(["lint", "--format", "json"])
+            .args(args)
+            .arg(&diagram)
+            .output()
+            .unwrap();
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+
+    assert_eq!(lint(&["--baseline", baseline, "--write-baseline"])["diagnostics"].as_array().unwrap().len(), 1);
+    assert!(lint(&["--baseline", baseline])["diagnostics"].as_array().unwrap().is_empty());
+
+    std::fs::write(&diagram, format!("{}    direction RL\n", repeated)).unwrap();
+    let diagnostics = lint(&["--baseline", baseline])["diagnostics"].clone();
+    assert_eq!(diagnostics.as_array().unwrap().len(), 1);
+    assert_eq!(diagnostics[0]["code"], "E307");
+}
+
+#[test]
+fn test_parallel_lint_output_is_in_input_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let files: Vec<_> = (0..12)
+        .map(|i| {
+            let file = dir.path().join(format!("d{}.mmd", i));
+            let code = if i % 3 == 0 { "graph TD\n    --> oops\n".to_string() } else { format!("graph TD\n    A{} --> B\n", i) };
+            std::fs::write(&file, code).unwrap();
+            file
+        })
+        .collect();
+    let lint = |format: &str| {
+        let output = Command::new(env!("CARGO_BIN_EXE_mermaid-lint"))
+            .args(["lint", "--format", format, "--jobs", "8"])
+            .args(&files)
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let first = lint("json");
+    for _ in 0..4 {
+        assert_eq!(lint("json"), first);
+    }
+    let order: Vec<_> = files.iter().map(|f| first.find(f.to_str().unwrap()).unwrap()).collect();
+    assert!(order.windows(2).all(|w| w[0] < w[1]));
+
+    let mut sequences: Vec<u64> = lint("ndjson")
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["sequence"].as_u64().unwrap())
+        .collect();
+    sequences.sort();
+    assert_eq!(sequences, (0..12).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_explain_config() {
+    let file = tempfile::Builder::new().suffix(".mmd").tempfile().unwrap();
+    let code = "---\nconfig:\n  layout: dagre\n---\n%%{init: {\"layout\": \"elk\"}}%%\ngraph TD\n    A --> B";
+    std::fs::write(file.path(), code).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_mermaid-lint"))
+        .args(["--explain-config", file.path().to_str().unwrap()])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("  layout = dagre (frontmatter, line 1)\n  layout = elk (directive, line 5)"), "{}", stderr);
+}
+
+#[test]
+fn test_budget_summary() {
+    let file = tempfile::Builder::new().suffix(".mmd").tempfile().unwrap();
+    std::fs::write(file.path(), "sequenceDiagram\n    participant A\n    participant B\n    participant C\n    A->>B: hi\n    B->>C: hi\n    C->>A: hi").unwrap();
+    let path = file.path().to_str().unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_mermaid-lint"))
+        .args(["--budget", "max-participants=2", "--budget", "max-messages=3", "--format", "json", path])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["diagnostics"][0]["message"], "Diagram has 3 participants, over the max-participants budget of 2");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(stderr, format!("Over budget:\n  {}: max-participants (3 > 2)\n", path));
+}
+
+#[test]
+fn test_stats_reports_timing() {
+    let file = tempfile::Builder::new().suffix(".mmd").tempfile().unwrap();
+    std::fs::write(file.path(), "graph TD\n    A --> B --> C").unwrap();
+    let path = file.path().to_str().unwrap();
+
+    let output = run(&["stats", "--format", "json", path]);
+    let stats: serde_json::Value = serde_json::from_str(&output).unwrap();
+    let entry = &stats[0];
+    assert_eq!(entry["file"], path);
+    assert_eq!(entry["diagram_type"], "flowchart");
+    assert!(entry["duration_ms"].as_f64().unwrap() >= 0.0);
+    assert_eq!(entry["metrics"]["nodes"], 3);
+    assert_eq!(entry["metrics"]["edges"], 2);
+
+    let output = run(&["stats", path]);
+    assert!(output.contains("ms  flowchart"), "{}", output);
+    assert!(output.contains("3 nodes"), "{}", output);
+}
+
+#[test]
+fn test_completions_for_each_shell() {
+    for shell in ["bash", "zsh", "fish", "elvish", "powershell"] {
+        let script = run(&["completions", shell]);
+        assert!(script.contains("mermaid-lint"), "{}: {}", shell, script);
+        // `--format` completes to the formats it accepts; the elvish and
+        // powershell scripts only complete flags and subcommands
+        if matches!(shell, "bash" | "zsh" | "fish") {
+            assert!(script.contains("ndjson"), "{}: {}", shell, script);
+        }
+    }
+
+    // The bash script at least parses, where bash is installed
+    let script = run(&["completions", "bash"]);
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("mermaid-lint.bash");
+    std::fs::write(&path, script).unwrap();
+    if let Ok(status) = Command::new("bash").arg("-n").arg(&path).status() {
+        assert!(status.success());
+    }
+}
+
+#[test]
+fn test_generate_manpages() {
+    let dir = tempfile::tempdir().unwrap();
+    run(&["--generate-manpage", dir.path().to_str().unwrap()]);
+    let page = std::fs::read_to_string(dir.path().join("mermaid-lint.1")).unwrap();
+    assert!(page.contains(".TH mermaid-lint 1"), "{}", page);
+    for subcommand in ["lint", "detect", "check", "parse", "stats", "completions"] {
+        assert!(dir.path().join(format!("mermaid-lint-{}.1", subcommand)).exists(), "{}", subcommand);
+    }
+}
+
+#[test]
+fn test_json_output_matches_library_envelope() {
+    use mermaid_linter::parse;
+
+    let code = "flowchart TD\n    accTitle: One\n    accTitle: Two\n    A --> B";
+    let commented = "---\ntitle: T\n---\nflowchart TD\n    %% one\n    accTitle: One\n    %% two\n    accTitle: Two\n    A --> B";
+    for (code, line) in [(code, 3), (commented, 8)] {
+        let file = tempfile::Builder::new().suffix(".mmd").tempfile().unwrap();
+        std::fs::write(file.path(), code).unwrap();
+        let path = file.path().to_str().unwrap();
+
+        // Default options: no preprocessed code is kept, and spans still
+        // point into the source the CLI fingerprints
+        let cli: serde_json::Value = serde_json::from_str(&run(&["--format", "json", path])).unwrap();
+        let mut library = parse(code, None).to_json_value(code);
+        library["file"] = path.into();
+        assert_eq!(cli, library);
+        assert_eq!(library["diagnostics"][0]["location"]["start"]["line"], line);
+    }
+}
+
+#[test]
+fn test_schema_command_and_ndjson_output() {
+    let schema: serde_json::Value = serde_json::from_str(&run(&["schema"])).unwrap();
+    assert_eq!(schema, mermaid_linter::output_schema());
+    assert_eq!(
+        schema["$id"],
+        format!("https://github.com/0xd219b/mermaid-linter/schema/lint-result/v{}", mermaid_linter::OUTPUT_SCHEMA_VERSION)
+    );
+
+    let file = tempfile::Builder::new().suffix(".mmd").tempfile().unwrap();
+    std::fs::write(file.path(), "---\ntitle: Flow\n---\nflowchart TD\n    A --> B\n    --> oops").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_mermaid-lint"))
+        .args(["--format", "ndjson"])
+        .arg(file.path())
+        .output()
+        .unwrap();
+    let line: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(line["title"], "Flow");
+    assert_eq!(line["diagnostics"].as_array().unwrap().len(), 1);
+    let validator = jsonschema::validator_for(&schema).unwrap();
+    assert!(validator.is_valid(&line), "{}", line);
+
+    let mut drifted = line.clone();
+    drifted["summary"] = serde_json::json!({});
+    assert!(!validator.is_valid(&drifted));
+}
+
+#[test]
+fn test_list_types() {
+    use mermaid_linter::DiagramType;
+
+    let pseudo = DiagramType::all().iter().filter(|t| t.is_pseudo()).count();
+    let output = run(&["list-types"]);
+    assert_eq!(output.lines().count(), DiagramType::all().len() - pseudo);
+    assert!(output.lines().any(|l| l.starts_with("sankey") && l.contains("stub")), "{}", output);
+
+    let types: serde_json::Value = serde_json::from_str(&run(&["list-types", "--format", "json"])).unwrap();
+    let types = types.as_array().unwrap();
+    assert_eq!(types.len(), DiagramType::all().len() - pseudo);
+    let sequence = types.iter().find(|t| t["type"] == "sequence").unwrap();
+    assert_eq!(sequence["name"], "Sequence diagram");
+    assert_eq!(sequence["fully_parsed"], true);
+}
+
+#[test]
+fn test_count_prints_only_totals() {
+    let fixture = |name: &str| format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+    let warning = tempfile::Builder::new().suffix(".mmd").tempfile().unwrap();
+    std::fs::write(warning.path(), "flowchart TD\n    subgraph S\n    end\n    subgraph S\n    end").unwrap();
+    let files = [
+        fixture("flowchart/simple.mmd"),
+        fixture("flowchart/empty_labels.mmd"),
+        fixture("sequence/background_highlight_broken.mmd"),
+        warning.path().display().to_string(),
+    ];
+    let count = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_mermaid-lint")).args(args).args(&files).output().unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert_eq!(count(&["--count"]), "4 file(s): 2 ok, 2 failed, 5 error(s), 1 warning(s)\n");
+    let totals: serde_json::Value = serde_json::from_str(&count(&["lint", "--format", "json", "--count"])).unwrap();
+    assert_eq!(totals, serde_json::json!({"files": 4, "ok": 2, "failed": 2, "errors": 5, "warnings": 1}));
+}
@@ -1,6 +1,7 @@
 //! Integration tests for diagram type detection.
 
-use mermaid_linter::{detect_type, DiagramType};
+use mermaid_linter::ast::NodeKind;
+use mermaid_linter::{detect_type, parse, DiagramType, ParseOptions};
 
 #[test]
 fn test_detect_flowchart_variants() {
@@ -34,6 +35,26 @@ fn test_detect_state() {
     assert_eq!(detect_type("stateDiagram-v2\n[*] --> A"), Some(DiagramType::StateDiagram));
 }
 
+#[test]
+fn test_normalize_diagram_types() {
+    let cases = [
+        ("graph TD\nA-->B", DiagramType::Flowchart, DiagramType::FlowchartV2),
+        ("classDiagram\nclass Animal", DiagramType::Class, DiagramType::ClassDiagram),
+        ("stateDiagram\n[*] --> A", DiagramType::State, DiagramType::StateDiagram),
+        ("stateDiagram-v2\n[*] --> A", DiagramType::StateDiagram, DiagramType::StateDiagram),
+        ("sequenceDiagram\nAlice->>Bob: Hi", DiagramType::Sequence, DiagramType::Sequence),
+    ];
+
+    for (code, detected, canonical) in cases {
+        assert_eq!(parse(code, None).diagram_type, Some(detected));
+
+        let options = ParseOptions::default().with_normalized_types();
+        let result = parse(code, Some(options));
+        assert!(result.ok, "Failed to parse '{}': {:?}", code, result.diagnostics);
+        assert_eq!(result.diagram_type, Some(canonical));
+    }
+}
+
 #[test]
 fn test_detect_with_whitespace() {
     // Leading whitespace should be handled
@@ -158,3 +179,56 @@ fn test_detect_unimplemented_types() {
     let pie_result = detect_type("pie\ntitle Pets\n\"Dogs\" : 386");
     assert!(pie_result.is_none() || matches!(pie_result, Some(DiagramType::Pie)));
 }
+
+#[test]
+fn test_diagram_type_hint() {
+    let code = "---\nconfig:\n  diagramType: sequence\n---\nAlice->>Bob: hi";
+    assert_eq!(detect_type(code), Some(DiagramType::Sequence));
+    assert_eq!(detect_type("Alice->>Bob: hi"), None);
+    let result = parse(code, None);
+    assert!(result.ok, "{:?}", result.diagnostics);
+    assert_eq!(result.diagram_type, Some(DiagramType::Sequence));
+    let root = &result.ast.as_ref().unwrap().root;
+    assert_eq!(root.children[0].kind, NodeKind::DiagramDeclaration);
+    let message = &root.children[1];
+    assert_eq!(message.kind, NodeKind::Message);
+    assert_eq!((message.get_property("from"), message.get_property("to")), (Some("Alice"), Some("Bob")));
+
+    // The synthesized header shifts no source positions
+    let code = "%%{init: {\"diagramType\": \"flowchart\"}}%%\n    A --> B\n    B --> C";
+    let result = parse(code, None);
+    assert!(result.ok, "{:?}", result.diagnostics);
+    assert_eq!(result.ast.as_ref().unwrap().root.children.len(), 3);
+    let result = parse(&format!("{}\n    --> oops", code), None);
+    assert!(!result.ok);
+    assert_eq!(result.diagnostics[0].span.text(&format!("{}\n    --> oops", code)), "--> oops");
+
+    let result = parse("---\nconfig:\n  diagramType: pie\n---\n\"Dogs\" : 3\n\"Cats\" : 2", None);
+    assert!(result.ok, "{:?}", result.diagnostics);
+    assert_eq!(result.diagram_type, Some(DiagramType::Pie));
+    assert_eq!(result.ast.as_ref().unwrap().root.children.len(), 3);
+
+    let code = "%%{init: {\"diagramType\": \"pie\"}}%%\nflowchart TD\n    A --> B";
+    let result = parse(code, None);
+    assert!(result.ok);
+    assert_eq!(result.diagram_type, Some(DiagramType::FlowchartV2));
+    assert_eq!(
+        result.diagnostics[0].message,
+        "diagramType 'pie' conflicts with the diagram header, which starts a flowchart-v2 diagram"
+    );
+}
+
+#[test]
+fn test_stubbed_types_report_info() {
+    use mermaid_linter::{DiagnosticCode, Severity};
+
+    let result = parse("sankey-beta\n    A,B,10", None);
+    assert!(result.ok);
+    assert_eq!(result.diagnostics.len(), 1);
+    let diagnostic = &result.diagnostics[0];
+    assert_eq!(diagnostic.severity, Severity::Info);
+    assert_eq!(diagnostic.code, DiagnosticCode::UnvalidatedSyntax);
+    assert_eq!(diagnostic.message, format!("syntax not yet validated for {}", DiagramType::Sankey));
+
+    assert!(parse("flowchart TD\n    A --> B", None).diagnostics.is_empty());
+}
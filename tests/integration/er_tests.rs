@@ -1,6 +1,7 @@
 //! Integration tests for ER (Entity-Relationship) diagrams.
 
-use mermaid_linter::{parse, DiagramType};
+use mermaid_linter::ast::NodeKind;
+use mermaid_linter::{parse, DiagnosticCode, DiagramType};
 
 #[test]
 fn test_simple_er_diagram() {
@@ -226,3 +227,98 @@ fn test_er_accessibility() {
     let result = parse(code, None);
     assert!(result.ok, "Failed to parse accessibility statements: {:?}", result.diagnostics);
 }
+
+#[test]
+fn test_er_single_line_relationship_yields_one_node() {
+    let code = r#"erDiagram
+    CUSTOMER ||--o{ ORDER : places"#;
+
+    let result = parse(code, None);
+    assert!(result.ok, "Failed: {:?}", result.diagnostics);
+    let ast = result.ast.unwrap();
+    assert_eq!(ast.root.children_of_kind(&NodeKind::Relationship).len(), 1);
+    assert!(ast.root.children_of_kind(&NodeKind::Other("Entity".to_string())).is_empty());
+}
+
+#[test]
+fn test_er_relationship_wrapped_across_lines() {
+    let code = r#"erDiagram
+    CUSTOMER ||--o{
+    ORDER : places"#;
+
+    let result = parse(code, None);
+    assert!(!result.ok);
+    assert_eq!(result.diagnostics.len(), 1);
+    let diagnostic = &result.diagnostics[0];
+    assert_eq!(diagnostic.code, DiagnosticCode::InvalidSyntax);
+    assert!(diagnostic.message.contains("single line"));
+    assert_eq!(diagnostic.span.text(code), "CUSTOMER ||--o{");
+}
+
+#[test]
+fn test_er_malformed_attribute() {
+    let code = "erDiagram\n    CUSTOMER {\n        ( )\n        string name\n    }";
+    let result = parse(code, None);
+    assert!(!result.ok);
+    assert_eq!(result.diagnostics.len(), 1);
+    assert_eq!(result.diagnostics[0].code, DiagnosticCode::InvalidSyntax);
+    assert_eq!(result.diagnostics[0].span.text(code), "( )");
+}
+
+#[test]
+fn test_er_word_form_cardinalities() {
+    let code = r#"erDiagram
+    CUSTOMER only one to zero or more ORDER : places
+    ORDER one or many optionally to 0+ LINE-ITEM : contains
+    PERSON 1 to many(1) PASSPORT : holds"#;
+
+    let result = parse(code, None);
+    assert!(result.ok, "Failed to parse word-form cardinalities: {:?}", result.diagnostics);
+    let ast = result.ast.unwrap();
+    let relationships = ast.root.children_of_kind(&NodeKind::Relationship);
+    let cardinalities: Vec<_> = relationships
+        .iter()
+        .map(|r| {
+            (
+                r.get_property("cardinalityA").unwrap(),
+                r.get_property("identification").unwrap(),
+                r.get_property("cardinalityB").unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        cardinalities,
+        [
+            ("ONLY_ONE", "IDENTIFYING", "ZERO_OR_MORE"),
+            ("ONE_OR_MORE", "NON_IDENTIFYING", "ZERO_OR_MORE"),
+            ("ONLY_ONE", "IDENTIFYING", "ONE_OR_MORE"),
+        ]
+    );
+}
+
+#[test]
+fn test_er_unknown_cardinality_word() {
+    let code = "erDiagram\n    CUSTOMER only one to zero or lots ORDER : places";
+    let result = parse(code, None);
+    assert!(!result.ok);
+    assert_eq!(result.diagnostics.len(), 1);
+    assert_eq!(result.diagnostics[0].code, DiagnosticCode::InvalidValue);
+    assert_eq!(result.diagnostics[0].message, "Unknown cardinality 'zero or lots'");
+    assert_eq!(result.diagnostics[0].span.text(code), "zero or lots");
+}
+
+#[test]
+fn test_er_allowed_attribute_types() {
+    use mermaid_linter::{ParseOptions, Severity};
+
+    let code = "erDiagram\n    ORDER {\n        int id PK\n        money total\n        date placed\n    }";
+    assert!(parse(code, None).diagnostics.is_empty());
+
+    let options = ParseOptions::default().with_er_attribute_types(["string", "int", "date"]);
+    let result = parse(code, Some(options));
+    assert!(result.ok, "{:?}", result.diagnostics);
+    assert_eq!(result.diagnostics.len(), 1);
+    assert_eq!(result.diagnostics[0].code, DiagnosticCode::InvalidValue);
+    assert_eq!(result.diagnostics[0].severity, Severity::Warning);
+    assert_eq!(result.diagnostics[0].span.text(code), "money");
+}
@@ -1,6 +1,6 @@
 //! Integration tests for flowchart diagrams.
 
-use mermaid_linter::{parse, detect_type, DiagramType};
+use mermaid_linter::{parse, detect_type, DiagnosticCode, DiagramType, Severity};
 
 #[test]
 fn test_simple_flowchart_graph_td() {
@@ -72,6 +72,10 @@ fn test_flowchart_edge_types() {
 
     let result = parse(code, None);
     assert!(result.ok, "Failed to parse flowchart with edge types: {:?}", result.diagnostics);
+
+    let root = &result.ast.as_ref().unwrap().root;
+    let heads: Vec<_> = root.children[5..].iter().map(|edge| edge.children[1].get_property("arrow_head")).collect();
+    assert_eq!(heads, [Some("circle"), Some("cross")]);
 }
 
 #[test]
@@ -200,6 +204,35 @@ fn test_flowchart_click_event() {
     assert!(result.ok, "Failed to parse flowchart with click: {:?}", result.diagnostics);
 }
 
+#[test]
+fn test_flowchart_click_ends_at_semicolon() {
+    let result = parse("flowchart TD\n    A --> B\n    click A href \"https://x.com\" _blank;", None);
+    assert!(result.ok, "{:?}", result.diagnostics);
+    let click = &result.ast.as_ref().unwrap().root.children[2];
+    assert_eq!(click.get_property("link_target"), Some("_blank"));
+
+    let result = parse("flowchart TD\n    A --> B\n    click A href \"x\"; B --> C", None);
+    assert!(result.ok, "{:?}", result.diagnostics);
+    let children = &result.ast.as_ref().unwrap().root.children;
+    assert_eq!(children[2].get_property("url"), Some("x"));
+    assert_eq!(children[3].children[1].children[0].get_property("id"), Some("C"));
+}
+
+#[test]
+fn test_flowchart_click_misordered() {
+    let code = r#"graph TD
+    A --> B
+    click A "Open docs" href "https://example.com"
+    click B call callback() _blank"#;
+
+    let result = parse(code, None);
+    let messages: Vec<_> = result.diagnostics.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        ["Tooltip must come after the URL", "Link target '_blank' is only valid after a URL"]
+    );
+}
+
 #[test]
 fn test_detect_flowchart_graph() {
     assert_eq!(detect_type("graph TD\nA-->B"), Some(DiagramType::Flowchart));
@@ -239,3 +272,649 @@ fn test_flowchart_semicolons() {
     let result = parse(code, None);
     assert!(result.ok, "Failed to parse flowchart with semicolons: {:?}", result.diagnostics);
 }
+
+#[test]
+fn test_flowchart_declared_flag_for_bare_references() {
+    let code = r#"graph TD
+    A --> B
+    B{Choice} --> C
+    C --> D[Done]"#;
+
+    let result = parse(code, None);
+    assert!(result.ok, "Failed to parse mixed declarations: {:?}", result.diagnostics);
+
+    let mut flags = Vec::new();
+    result.ast.unwrap().walk(|node, _| {
+        if let (Some(id), Some(declared)) = (node.get_property("id"), node.get_property("declared")) {
+            flags.push(format!("{}={}", id, declared));
+        }
+    });
+    assert_eq!(
+        flags,
+        vec!["A=false", "B=true", "B=true", "C=false", "C=false", "D=true"]
+    );
+}
+
+#[test]
+fn test_flowchart_recovery_keeps_partial_ast() {
+    use mermaid_linter::ast::NodeKind;
+    use mermaid_linter::ParseOptions;
+
+    let code = r#"graph TD
+    A --> B
+    --> broken
+    C --> D"#;
+
+    let result = parse(code, None);
+    assert!(!result.ok);
+    assert!(result.ast.is_none());
+
+    let result = parse(code, Some(ParseOptions::default().with_recovery()));
+    assert!(!result.ok);
+    let ast = result.ast.expect("recovery mode should keep the partial AST");
+    let errors = ast.root.children_of_kind(&NodeKind::Error);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].text.as_deref(), Some("--> broken"));
+    assert_eq!(ast.root.children_of_kind(&NodeKind::Edge).len(), 2);
+}
+
+#[test]
+fn test_flowchart_recovery_reports_validation_warnings() {
+    use mermaid_linter::{ParseOptions, Severity};
+
+    let code = r#"graph TD
+    A --> B
+    B e1@--> C
+    --> broken
+    e1@{ animate: true }
+    linkStyle 0,1 stroke:red"#;
+
+    let warning = "Link 1 is also styled by its id 'e1'";
+    let result = parse(code, None);
+    assert!(!result.diagnostics.iter().any(|d| d.message == warning));
+
+    let result = parse(code, Some(ParseOptions::default().with_recovery()));
+    assert!(!result.ok);
+    assert_eq!(result.diagnostics.len(), 2);
+    assert_eq!(result.diagnostics[0].severity, Severity::Error);
+    assert_eq!(result.diagnostics[1].message, warning);
+    assert!(!result.diagnostics[1].severity.is_error());
+}
+
+fn subgraph_properties(code: &str) -> (String, Option<String>) {
+    use mermaid_linter::ast::NodeKind;
+
+    let result = parse(code, None);
+    assert!(result.ok, "Failed to parse subgraph: {:?}", result.diagnostics);
+    let ast = result.ast.unwrap();
+    let subgraph = ast.root.find_child(&NodeKind::Subgraph).expect("subgraph node");
+    (
+        subgraph.get_property("id").unwrap().to_string(),
+        subgraph.get_property("label").map(String::from),
+    )
+}
+
+#[test]
+fn test_flowchart_subgraph_id_with_bracketed_title() {
+    let code = r#"graph TD
+    subgraph sub1 [Display "Title" with end keyword]
+        A --> B
+    end
+    C --> sub1"#;
+
+    let (id, label) = subgraph_properties(code);
+    assert_eq!(id, "sub1");
+    assert_eq!(label.as_deref(), Some("Display Title with end keyword"));
+}
+
+#[test]
+fn test_flowchart_subgraph_quoted_title() {
+    let code = r#"graph TD
+    subgraph "Quoted end Title"
+        A --> B
+    end"#;
+
+    let (id, label) = subgraph_properties(code);
+    assert_eq!(id, "Quoted end Title");
+    assert_eq!(label.as_deref(), Some("Quoted end Title"));
+}
+
+#[test]
+fn test_flowchart_subgraph_bracketed_title_only() {
+    let code = r#"graph TD
+    subgraph [Bracketed title]
+        A --> B
+    end"#;
+
+    let (id, label) = subgraph_properties(code);
+    assert_eq!(id, "Bracketed title");
+    assert_eq!(label.as_deref(), Some("Bracketed title"));
+}
+
+#[test]
+fn test_quoted_label_keeps_html_and_entities() {
+    let code = r#"flowchart TD
+    A["line1<br/>line2"] --> B["Fish &amp; chips"]
+    B --> C["Say #quot;hi#quot;"]"#;
+
+    let result = parse(code, None);
+    assert!(result.ok, "Failed to parse: {:?}", result.diagnostics);
+
+    let nodes = mermaid_linter::ast::flow_nodes(result.ast.as_ref().unwrap());
+    let label_of = |id: &str| nodes.iter().find(|n| n.id == id).unwrap().label.clone();
+    assert_eq!(label_of("A").as_deref(), Some("line1<br/>line2"));
+    assert_eq!(label_of("B").as_deref(), Some("Fish &amp; chips"));
+    assert_eq!(label_of("C").as_deref(), Some("Say \"hi\""));
+}
+
+/// Returns the `(label, raw_label)` of a flowchart node.
+fn labels_of(code: &str, id: &str) -> (Option<String>, Option<String>) {
+    let result = parse(code, None);
+    assert!(result.ok, "Failed to parse: {:?}", result.diagnostics);
+    let nodes = mermaid_linter::ast::flow_nodes(result.ast.as_ref().unwrap());
+    let node = nodes.into_iter().find(|n| n.id == id).unwrap();
+    (node.label, node.raw_label)
+}
+
+#[test]
+fn test_label_escaped_quotes() {
+    let (label, raw) = labels_of(r#"graph TD
+    A["She said \"hi\""]"#, "A");
+    assert_eq!(label.as_deref(), Some(r#"She said "hi""#));
+    assert_eq!(raw.as_deref(), Some(r#""She said \"hi\"""#));
+}
+
+#[test]
+fn test_label_named_entity() {
+    let (label, raw) = labels_of("graph TD\n    A[Say #quot;hi#quot;]", "A");
+    assert_eq!(label.as_deref(), Some(r#"Say "hi""#));
+    assert_eq!(raw.as_deref(), Some("Say #quot;hi#quot;"));
+}
+
+#[test]
+fn test_label_numeric_entity() {
+    let (label, raw) = labels_of("graph TD\n    A(I #9829; Mermaid)", "A");
+    assert_eq!(label.as_deref(), Some("I \u{2665} Mermaid"));
+    assert_eq!(raw.as_deref(), Some("I #9829; Mermaid"));
+}
+
+#[test]
+fn test_label_unknown_entity_warns() {
+    let code = "graph TD\n    A[Say #quo;hi]";
+    let result = parse(code, None);
+    assert!(result.ok);
+
+    let warning = result
+        .diagnostics
+        .iter()
+        .find(|d| d.code == DiagnosticCode::InvalidEscape)
+        .expect("expected an escape warning");
+    assert_eq!(warning.severity, Severity::Warning);
+    assert_eq!(warning.span.text(code), "#quo;");
+
+    let (label, raw) = labels_of(code, "A");
+    assert_eq!(label.as_deref(), Some("Say #quo;hi"));
+    assert_eq!(raw.as_deref(), Some("Say #quo;hi"));
+}
+
+#[test]
+fn test_label_trailing_backslash_hint() {
+    let code = "graph TD\n    A(C:\\temp\\)";
+    let result = parse(code, None);
+    assert!(result.ok);
+
+    let hint = result
+        .diagnostics
+        .iter()
+        .find(|d| d.code == DiagnosticCode::InvalidEscape)
+        .expect("expected a trailing backslash hint");
+    assert_eq!(hint.severity, Severity::Hint);
+    assert_eq!(hint.span.text(code), "\\");
+    assert_eq!(hint.span.start, code.len() - 2);
+
+    let (label, raw) = labels_of(code, "A");
+    assert_eq!(label.as_deref(), Some("C:\\temp\\"));
+    assert_eq!(raw.as_deref(), Some("C:\\temp\\"));
+}
+
+#[test]
+fn test_entity_spans_match_source() {
+    // Entities no longer shift the spans of what follows them
+    let code = "graph TD\n    A[#quot;x#quot;] --> B[#quo;]";
+    let result = parse(code, None);
+    let warning = result
+        .diagnostics
+        .iter()
+        .find(|d| d.code == DiagnosticCode::InvalidEscape)
+        .unwrap();
+    assert_eq!(warning.span.text(code), "#quo;");
+}
+
+#[test]
+fn test_flowchart_metrics() {
+    let code = r#"flowchart LR
+    A[Start] --> B{Check}
+    B -->|Yes| C[Done]
+    B -->|No| A"#;
+
+    let result = parse(code, None);
+    assert!(result.ok, "Failed to parse: {:?}", result.diagnostics);
+
+    let metrics = result.metrics();
+    assert_eq!(metrics.nodes, 3);
+    assert_eq!(metrics.edges, 3);
+    assert_eq!(metrics.subgraphs, 0);
+}
+
+#[test]
+fn test_flowchart_accessibility() {
+    let code = r#"flowchart TD
+    accTitle: Checkout
+    accDescr {
+        Steps from cart
+        to payment
+    }
+    A[Cart] --> B[Pay]"#;
+
+    let result = parse(code, None);
+    assert!(result.ok, "Failed to parse: {:?}", result.diagnostics);
+    assert_eq!(result.acc_title.as_deref(), Some("Checkout"));
+    assert_eq!(result.acc_descr.as_deref(), Some("Steps from cart\nto payment"));
+}
+
+#[test]
+fn test_duplicate_acc_title_warns() {
+    let code = r#"flowchart TD
+    accTitle: First
+    A --> B
+    accTitle: Second"#;
+
+    let result = parse(code, None);
+    assert!(result.ok, "Failed to parse: {:?}", result.diagnostics);
+    assert_eq!(result.acc_title.as_deref(), Some("Second"));
+
+    let diagnostic = &result.diagnostics[0];
+    assert_eq!(diagnostic.code, DiagnosticCode::DuplicateDefinition);
+    assert_eq!(diagnostic.severity, Severity::Warning);
+    assert_eq!(diagnostic.span.text(code), "accTitle: Second");
+    assert_eq!(diagnostic.related[0].span.text(code), "accTitle: First");
+}
+
+#[test]
+fn test_duplicate_edge_check_is_opt_in() {
+    use mermaid_linter::ParseOptions;
+
+    let code = r#"flowchart TD
+    A -->|yes| B
+    A -->|no| B
+    A -->|yes| B"#;
+
+    let result = parse(code, None);
+    assert!(result.diagnostics.is_empty());
+
+    let result = parse(code, Some(ParseOptions::default().with_duplicate_edge_warnings()));
+    assert!(result.ok, "Failed to parse: {:?}", result.diagnostics);
+    assert_eq!(result.diagnostics.len(), 1);
+
+    let diagnostic = &result.diagnostics[0];
+    assert_eq!(diagnostic.code, DiagnosticCode::DuplicateDefinition);
+    assert_eq!(diagnostic.severity, Severity::Warning);
+    assert_eq!(diagnostic.span.start, code.rfind("A -->").unwrap());
+    assert_eq!(diagnostic.related[0].span.text(code), "A -->|yes| B");
+}
+
+#[test]
+fn test_complexity_budgets() {
+    use mermaid_linter::{ComplexityBudgets, ParseOptions};
+
+    // 11 nodes in a chain: 10 edges
+    let mut code = String::from("flowchart TD");
+    for i in 0..10 {
+        code.push_str(&format!("\n    N{} --> N{}", i, i + 1));
+    }
+
+    let mut budgets = ComplexityBudgets::default();
+    budgets.set("max-nodes", 10);
+    budgets.set("max-edges", 10);
+    budgets.set("max-subgraph-depth", 0);
+    let result = parse(&code, Some(ParseOptions::default().with_budgets(budgets)));
+    assert!(result.ok, "Failed to parse: {:?}", result.diagnostics);
+    assert_eq!(result.diagnostics.len(), 1);
+
+    let diagnostic = &result.diagnostics[0];
+    assert_eq!(diagnostic.code, DiagnosticCode::ConstraintViolation);
+    assert_eq!(diagnostic.severity, Severity::Warning);
+    assert_eq!(diagnostic.message, "Diagram has 11 nodes, over the max-nodes budget of 10");
+    assert_eq!(diagnostic.span.text(&code), "flowchart TD");
+
+    let exceeded = budgets.exceeded(&result.metrics());
+    assert_eq!(exceeded.len(), 1);
+    assert_eq!((exceeded[0].rule, exceeded[0].count, exceeded[0].limit), ("max-nodes", 11, 10));
+
+    assert!(parse(&code, None).diagnostics.is_empty());
+}
+
+#[test]
+fn test_edge_ids_and_indices() {
+    let code = r#"flowchart LR
+    A --> B --> C
+    C e1@==> D
+    e1@{ animate: true }
+    linkStyle 2 stroke:#f00"#;
+
+    let result = parse(code, None);
+    assert!(result.ok, "Failed to parse: {:?}", result.diagnostics);
+
+    let edges = mermaid_linter::ast::flow_edges(result.ast.as_ref().unwrap());
+    let links: Vec<_> = edges.iter().map(|e| (e.index, e.from.as_str(), e.to.as_str())).collect();
+    assert_eq!(links, vec![(0, "A", "B"), (1, "B", "C"), (2, "C", "D")]);
+    assert_eq!(edges[2].id.as_deref(), Some("e1"));
+
+    let diagnostic = &result.diagnostics[0];
+    assert_eq!(diagnostic.severity, Severity::Info);
+    assert_eq!(diagnostic.span.text(code), "linkStyle 2 stroke:#f00");
+}
+
+#[test]
+fn test_render_ids_are_opt_in() {
+    use mermaid_linter::{MermaidVersion, ParseOptions};
+
+    let code = "flowchart LR\n    A --> B\n    A --> B";
+    let render_id = |result: &mermaid_linter::ParseResult| {
+        let ast = result.ast.as_ref().unwrap();
+        ast.root.children[1].children[1].get_property("render_id").map(str::to_string)
+    };
+
+    assert_eq!(render_id(&parse(code, None)), None);
+
+    let v10 = parse(code, Some(ParseOptions::default().with_render_ids(MermaidVersion::V10)));
+    assert_eq!(render_id(&v10).as_deref(), Some("L-A-B-0"));
+    let source = &v10.ast.as_ref().unwrap().root.children[1].children[0];
+    assert_eq!(source.get_property("render_id"), Some("flowchart-A-0"));
+
+    let v11 = parse(code, Some(ParseOptions::default().with_render_ids(MermaidVersion::V11)));
+    assert_eq!(render_id(&v11).as_deref(), Some("L_A_B_0"));
+}
+
+#[test]
+fn test_repeated_style_hint_is_opt_in() {
+    use mermaid_linter::{ParseOptions, Severity};
+
+    let code = r#"flowchart TD
+    A --> B --> C
+    style A fill:#f00
+    style B fill:#f00
+    style C fill:#f00"#;
+
+    let result = parse(code, None);
+    assert!(result.diagnostics.is_empty());
+
+    let result = parse(code, Some(ParseOptions::default().with_repeated_style_hints()));
+    assert!(result.ok, "Failed to parse: {:?}", result.diagnostics);
+    assert_eq!(result.diagnostics.len(), 1);
+    assert_eq!(result.diagnostics[0].severity, Severity::Hint);
+}
+
+#[test]
+fn test_node_shape_metadata() {
+    let code = r#"flowchart LR
+    db@{ shape: database, label: "Orders DB" } --> svc@{ shape: rect }
+    svc --> out@{ shape: stop }"#;
+
+    let result = parse(code, None);
+    assert!(result.ok, "Failed to parse: {:?}", result.diagnostics);
+    let nodes = mermaid_linter::ast::flow_nodes(result.ast.as_ref().unwrap());
+    let db = nodes.iter().find(|n| n.id == "db").unwrap();
+    assert_eq!(db.label.as_deref(), Some("Orders DB"));
+    assert_eq!(db.shape, Some(mermaid_linter::ast::NodeShape::Cylindrical));
+
+    let result = parse("flowchart LR\n    A@{ shape: blob }", None);
+    assert!(!result.ok);
+    assert_eq!(result.diagnostics[0].code, DiagnosticCode::InvalidNodeShape);
+}
+
+#[test]
+fn test_diagnostic_range_is_resolved() {
+    let code = "---\ntitle: Flow\n---\ngraph TD\n    A[Ünïcödé] --> D[]";
+    let result = parse(code, None);
+    let source = code;
+    let diagnostic = &result.diagnostics[0];
+    let range = diagnostic.range.expect("parse resolves ranges");
+
+    let (start, end) = (diagnostic.span.start, diagnostic.span.end);
+    let line = source[..start].matches('\n').count() + 1;
+    let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+    assert_eq!(range.start.line, line);
+    assert_eq!(range.start.column, source[line_start..start].chars().count() + 1);
+    assert_eq!(range.start.offset, start);
+    assert_eq!(range.end.column, source[line_start..end].chars().count() + 1);
+    assert_eq!(range.end.offset, end);
+}
+
+#[test]
+fn test_diagnostic_positions_count_frontmatter_and_comments() {
+    let code = "---\ntitle: Flow\n---\r\n%% a comment\r\ngraph TD %%{wrap}%%\r\n    A --> B\r\n    %% --> C\r\n    --> oops\r\n";
+    let result = parse(code, None);
+    let diagnostic = &result.diagnostics[0];
+    assert_eq!(diagnostic.span.text(code), "--> oops");
+    let range = diagnostic.range.unwrap();
+    assert_eq!((range.start.line, range.start.column), (8, 5));
+
+    let json = result.to_json_value(code);
+    assert_eq!(json["diagnostics"][0]["location"]["start"]["line"], 8);
+    assert_eq!(json["diagnostics"][0]["range"]["start"], diagnostic.span.start);
+}
+
+#[test]
+fn test_graph_warns_about_flowchart_only_constructs() {
+    let body = "    A & B --> C@{ shape: rounded }\n    subgraph s\n    direction LR\n    C\n    end";
+    let warnings = |code: String| {
+        let result = parse(&code, None);
+        assert!(result.ok, "{:?}", result.diagnostics);
+        result.diagnostics.len()
+    };
+    assert_eq!(warnings(format!("graph TD\n{}", body)), 3);
+    assert_eq!(warnings(format!("flowchart TD\n{}", body)), 0);
+    let dagre = "---\nconfig:\n  flowchart:\n    defaultRenderer: dagre-wrapper\n---\n";
+    assert_eq!(warnings(format!("{}graph TD\n{}", dagre, body)), 0);
+}
+
+#[test]
+fn test_link_split_across_lines() {
+    let code = "flowchart TD\n    A -->\n    B --> C";
+    let result = parse(code, None);
+    assert!(!result.ok);
+    assert_eq!(result.diagnostics.len(), 1);
+    let diagnostic = &result.diagnostics[0];
+    assert_eq!(diagnostic.code, DiagnosticCode::UnexpectedEof);
+    assert_eq!(
+        diagnostic.message,
+        "Edge is missing its target; did you mean to continue on the same line?"
+    );
+    assert_eq!(diagnostic.span.text(code), "-->");
+    assert!(diagnostic.notes[0].contains("'B'"));
+    let joined = mermaid_linter::fix::apply_edits(code, &diagnostic.fixes).unwrap();
+    assert_eq!(joined, "flowchart TD\n    A --> B --> C");
+    assert!(parse(&joined, None).ok);
+
+    let result = parse("flowchart TD\n    A -->\n    end", None);
+    assert_eq!(result.diagnostics[0].code, DiagnosticCode::UnexpectedEof);
+    assert!(result.diagnostics[0].fixes.is_empty());
+}
+
+#[test]
+fn test_pipe_labels_are_read_raw() {
+    let labels = |code: &str| {
+        let result = parse(code, None);
+        assert!(result.ok, "{:?}", result.diagnostics);
+        let mut labels = Vec::new();
+        result.ast.unwrap().walk(|node, _| {
+            if let Some(label) = node.get_property("label").filter(|_| node.get_property("link_type").is_some()) {
+                labels.push(label.to_string());
+            }
+        });
+        labels
+    };
+    assert_eq!(labels(r#"flowchart TD
+    B -->|uses "v2" API \| fallback| C"#), vec![r#"uses "v2" API | fallback"#]);
+    assert_eq!(labels("flowchart TD\n    A -->|\"Yes\" please| B[x] -.->|a [b] (c)| D"), vec!["\"Yes\" please", "a [b] (c)"]);
+
+    let code = "flowchart TD\n    A -->|no end\n    B --> C";
+    let result = parse(code, None);
+    assert!(!result.ok);
+    assert_eq!(result.diagnostics.len(), 1);
+    assert_eq!(result.diagnostics[0].code, DiagnosticCode::UnterminatedString);
+    assert_eq!(result.diagnostics[0].message, "Unterminated edge label");
+    assert_eq!(result.diagnostics[0].span.text(code), "|no end");
+}
+
+#[test]
+fn test_nodes_are_numbered_in_source_order() {
+    let code = include_str!("../fixtures/flowchart/subgraph.mmd");
+    let ast = parse(code, None).ast.unwrap();
+
+    let nodes: Vec<_> = ast.iter_source_order().collect();
+    assert_eq!(nodes.len(), ast.node_count());
+    assert!(nodes.iter().enumerate().all(|(i, node)| node.order as usize == i));
+    assert!(nodes.windows(2).all(|pair| pair[0].span.start <= pair[1].span.start));
+
+    let mut statements: Vec<_> = ast.root.children.iter().map(|node| node.span).collect();
+    statements.sort_by_key(|span| span.start);
+    let in_order: Vec<_> = ast
+        .iter_source_order()
+        .filter(|node| ast.root.children.iter().any(|child| std::ptr::eq(child, *node)))
+        .map(|node| node.span)
+        .collect();
+    assert_eq!(in_order, statements);
+
+    let json = serde_json::to_value(&ast.root).unwrap();
+    let root: mermaid_linter::AstNode = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(root.children[1].order, ast.root.children[1].order);
+    assert_eq!(serde_json::to_value(&root).unwrap(), json);
+    let again = parse(code, None).ast.unwrap();
+    assert_eq!(serde_json::to_value(&again.root).unwrap(), json);
+}
+
+#[test]
+fn test_undeclared_endpoint_warnings() {
+    let options = || Some(mermaid_linter::ParseOptions::default().with_undeclared_endpoint_warnings());
+    let code = "flowchart LR\n    subgraph realSub\n    A[Start]\n    end\n    A --> missingSub";
+    let result = parse(code, options());
+    assert!(result.ok);
+    assert_eq!(result.diagnostics.len(), 1);
+    assert_eq!(result.diagnostics[0].code, DiagnosticCode::UndefinedReference);
+    assert_eq!(result.diagnostics[0].span.text(code), "missingSub");
+
+    let result = parse("flowchart LR\n    subgraph realSub\n    A[Start]\n    end\n    A --> realSub", options());
+    assert!(result.diagnostics.is_empty(), "{:?}", result.diagnostics);
+    assert!(parse(code, None).diagnostics.is_empty());
+}
+
+#[test]
+fn test_unused_class_def_warnings() {
+    let options = || Some(mermaid_linter::ParseOptions::default().with_unused_class_def_warnings());
+    let code = "flowchart TD\n    A:::ok --> B\n    class B done\n    classDef ok fill:#0f0\n    classDef done fill:#ccc\n    classDef warning fill:#ff0\n    classDef default fill:#fff";
+    let result = parse(code, options());
+    assert!(result.ok);
+    assert_eq!(result.diagnostics.len(), 1, "{:?}", result.diagnostics);
+    let diagnostic = &result.diagnostics[0];
+    assert_eq!(diagnostic.severity, Severity::Warning);
+    assert_eq!(diagnostic.message, "Class 'warning' is never applied");
+    assert_eq!(diagnostic.span.text(code), "classDef warning fill:#ff0");
+    assert!(diagnostic.notes[0].starts_with("nothing references it"));
+    assert!(parse(code, None).diagnostics.is_empty());
+}
+
+#[test]
+fn test_unknown_statement_is_skipped_with_a_warning() {
+    let code = "flowchart TD\n    A --> B\n    frobnicate A with B; B --> C\n    C --> D[]";
+    let result = parse(code, None);
+    let diagnostics: Vec<_> = result.diagnostics.iter().map(|d| (d.severity, d.code, d.message.as_str())).collect();
+    assert_eq!(
+        diagnostics[0],
+        (
+            Severity::Warning,
+            DiagnosticCode::InvalidSyntax,
+            "Unknown statement 'frobnicate' is not supported by this version of mermaid-linter; the statement is skipped"
+        )
+    );
+    // The statements after it are still parsed and checked
+    assert_eq!(diagnostics[1].2, "Empty node label is not allowed");
+    assert!(!result.ok);
+
+    let result = parse("flowchart TD\n    A --> B\n    frobnicate A with B\n    B --> C", None);
+    assert!(result.ok);
+    assert_eq!(result.ast.unwrap().root.children.len(), 4);
+}
+
+#[test]
+fn test_class_shorthand_on_invisible_links() {
+    let code = "flowchart LR\n    A:::foo ~~~ B:::bar\n    C:::my-class~~~~D[Label]:::foo-->E:::bar\n    classDef my-class fill:#f00\n    class E my-class";
+    let result = parse(code, None);
+    assert!(result.ok, "{:?}", result.diagnostics);
+    assert!(result.diagnostics.is_empty(), "{:?}", result.diagnostics);
+
+    let root = &result.ast.as_ref().unwrap().root;
+    let edge = &root.children[1];
+    let (source, link) = (&edge.children[0], &edge.children[1]);
+    assert_eq!(link.get_property("link_type"), Some("Invisible"));
+    assert_eq!((source.get_property("id"), source.get_property("class")), (Some("A"), Some("foo")));
+    let target = &link.children[0];
+    assert_eq!((target.get_property("id"), target.get_property("class")), (Some("B"), Some("bar")));
+
+    // A hyphenated class name stops at a link written right after it
+    let chain: Vec<_> = root.children[2].children[1..]
+        .iter()
+        .map(|link| {
+            let target = &link.children[0];
+            (
+                link.get_property("link_type").unwrap(),
+                target.get_property("id").unwrap(),
+                target.get_property("class").unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(chain, [("Invisible", "D", "foo"), ("Arrow", "E", "bar")]);
+    assert_eq!(root.children[2].children[0].get_property("class"), Some("my-class"));
+    assert_eq!(root.children[3].get_property("name"), Some("my-class"));
+    assert_eq!(root.children[4].get_property("class_name"), Some("my-class"));
+}
+
+#[test]
+fn test_long_links() {
+    let code = "flowchart TD\n    A ----> B\n    B ---- C\n    C -...-> D\n    D -..- E\n    E ====> F\n    F ==== G\n    G ---->|label| H\n    H -- label ----> I";
+    let result = parse(code, None);
+    assert!(result.ok, "{:?}", result.diagnostics);
+
+    let root = &result.ast.as_ref().unwrap().root;
+    let links: Vec<_> = root.children[1..]
+        .iter()
+        .map(|edge| edge.children[1].get_property("link_type").unwrap())
+        .collect();
+    assert_eq!(links, ["Arrow", "Open", "DottedArrow", "Dotted", "ThickArrow", "Thick", "Arrow", "Arrow"]);
+}
+
+#[test]
+fn test_labels_inside_links() {
+    let code = "flowchart TD\n    A-. dotted .->B\n    A== thick ==>B\n    A -- two words --> B\n    A -- open --- B";
+    let result = parse(code, None);
+    assert!(result.ok, "{:?}", result.diagnostics);
+
+    let root = &result.ast.as_ref().unwrap().root;
+    let links: Vec<_> = root.children[1..]
+        .iter()
+        .map(|edge| {
+            let link = &edge.children[1];
+            assert_eq!(link.children[0].get_property("id"), Some("B"));
+            (link.get_property("link_type").unwrap(), link.get_property("label").unwrap())
+        })
+        .collect();
+    assert_eq!(
+        links,
+        [("DottedArrow", "dotted"), ("ThickArrow", "thick"), ("Arrow", "two words"), ("Open", "open")]
+    );
+
+    let result = parse("flowchart TD\n    A == thick\n    B --> C", None);
+    assert!(!result.ok);
+    assert!(result.diagnostics.iter().any(|d| d.message == "Link '==' is not closed"), "{:?}", result.diagnostics);
+}
@@ -1,6 +1,7 @@
 //! Integration tests for Gantt charts.
 
-use mermaid_linter::{parse, DiagramType};
+use mermaid_linter::ast::gantt_graph;
+use mermaid_linter::{parse, DiagnosticCode, DiagramType, Severity};
 
 #[test]
 fn test_simple_gantt() {
@@ -225,3 +226,94 @@ fn test_gantt_invalid() {
     let result = parse(code, None);
     assert!(result.diagram_type != Some(DiagramType::Gantt) || !result.ok);
 }
+
+#[test]
+fn test_gantt_duplicate_task_id() {
+    let code = r#"gantt
+    dateFormat YYYY-MM-DD
+    section Design
+    Sketch :a1, 2024-01-01, 3d
+    section Build
+    Prototype :a1, 2024-01-05, 5d"#;
+
+    let result = parse(code, None);
+    assert!(result.ok, "Duplicate ids should only warn: {:?}", result.diagnostics);
+    assert_eq!(result.diagnostics.len(), 1);
+
+    let diagnostic = &result.diagnostics[0];
+    assert_eq!(diagnostic.code, DiagnosticCode::DuplicateDefinition);
+    assert_eq!(diagnostic.severity, Severity::Warning);
+    assert!(diagnostic.span.text(code).starts_with("Prototype"));
+    assert!(diagnostic.related[0].span.text(code).starts_with("Sketch"));
+}
+
+#[test]
+fn test_gantt_cross_section_dependency() {
+    let code = r#"gantt
+    dateFormat YYYY-MM-DD
+    section Build
+    Implement :impl, after spec, 10d
+    section Design
+    Write spec :spec, 2024-01-01, 5d
+    Review :after spec impl, 2d"#;
+
+    let result = parse(code, None);
+    assert!(result.ok, "Failed to parse: {:?}", result.diagnostics);
+
+    let graph = gantt_graph(result.ast.as_ref().unwrap());
+    let name = |index: usize| graph.tasks[index].name.as_str();
+    let after = |index: usize| graph.dependencies(index).map(name).collect::<Vec<_>>();
+
+    assert_eq!(after(0), vec!["Write spec"]);
+    assert_eq!(after(2), vec!["Write spec", "Implement"]);
+    assert_eq!(graph.tasks[2].id, None);
+    assert!(graph.unresolved.is_empty());
+}
+
+#[test]
+fn test_gantt_task_without_name() {
+    let result = parse("gantt\n    section S\n    : 2024-01-01, 1d", None);
+    assert!(!result.ok);
+    assert_eq!(result.diagnostics[0].code, DiagnosticCode::MissingElement);
+}
+
+#[test]
+fn test_gantt_vert_marker() {
+    let code = r#"gantt
+    dateFormat YYYY-MM-DD
+    section Release
+    Build :b1, 2024-01-01, 5d
+    Freeze :vert, f1, 2024-01-04, 0d"#;
+
+    let result = parse(code, None);
+    assert!(result.ok, "Failed to parse: {:?}", result.diagnostics);
+    assert!(result.diagnostics.is_empty());
+
+    let graph = gantt_graph(result.ast.as_ref().unwrap());
+    let freeze = &graph.tasks[1];
+    assert_eq!(freeze.id.as_deref(), Some("f1"));
+    assert_eq!(freeze.modifiers, vec!["vert"]);
+}
+
+#[test]
+fn test_gantt_unknown_modifier_hint() {
+    let code = "gantt\n    section S\n    Task :blocked, t1, 2024-01-01, 1d";
+    let result = parse(code, None);
+    assert!(result.ok, "Failed to parse: {:?}", result.diagnostics);
+
+    let hint = &result.diagnostics[0];
+    assert_eq!(hint.severity, Severity::Hint);
+    assert_eq!(hint.span.text(code), "blocked");
+    assert_eq!(gantt_graph(result.ast.as_ref().unwrap()).tasks[0].id.as_deref(), Some("t1"));
+}
+
+#[test]
+fn test_gantt_display_mode_from_config() {
+    let code = "---\ndisplayMode: compact\n---\ngantt\n    title Plan\n    Task :a1, 2024-01-01, 3d";
+    let result = parse(code, None);
+    assert!(result.ok, "{:?}", result.diagnostics);
+    assert_eq!(result.ast.unwrap().root.get_property("displayMode"), Some("compact"));
+
+    let result = parse("gantt\n    Task :a1, 2024-01-01, 3d", None);
+    assert_eq!(result.ast.unwrap().root.get_property("displayMode"), None);
+}
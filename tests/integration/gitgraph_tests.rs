@@ -0,0 +1,76 @@
+//! Integration tests for GitGraph diagrams.
+
+use mermaid_linter::{parse, validate, DiagnosticCode, DiagramType, ParseOptions, Severity};
+
+#[test]
+fn test_simple_gitgraph() {
+    let code = r#"gitGraph
+    commit
+    branch develop
+    checkout develop
+    commit
+    checkout main
+    merge develop"#;
+
+    let result = parse(code, None);
+    assert!(result.ok, "Failed to parse gitGraph: {:?}", result.diagnostics);
+    assert_eq!(result.diagram_type, Some(DiagramType::GitGraph));
+    assert!(result.diagnostics.is_empty());
+}
+
+#[test]
+fn test_gitgraph_cherry_pick_unknown_id() {
+    let code = r#"gitGraph
+    commit id: "one"
+    branch develop
+    commit id: "two"
+    checkout main
+    cherry-pick id: "three""#;
+
+    let result = parse(code, None);
+    assert!(!result.ok);
+    assert_eq!(result.diagnostics.len(), 1);
+    assert_eq!(result.diagnostics[0].code, DiagnosticCode::UndefinedReference);
+    assert!(result.diagnostics[0].message.contains("three"));
+}
+
+#[test]
+fn test_gitgraph_cherry_pick_known_id() {
+    let code = r#"gitGraph
+    commit id: "one"
+    branch develop
+    commit id: "two"
+    checkout main
+    cherry-pick id: "two""#;
+
+    let result = parse(code, None);
+    assert!(result.ok, "Failed: {:?}", result.diagnostics);
+}
+
+#[test]
+fn test_gitgraph_merge_before_main_commit_warns() {
+    let code = r#"gitGraph
+    branch feature
+    commit
+    checkout main
+    merge feature"#;
+
+    let result = parse(code, None);
+    assert!(result.ok, "Warnings must not fail the parse: {:?}", result.diagnostics);
+    assert_eq!(result.diagnostics.len(), 2);
+    assert!(result.diagnostics.iter().all(|d| d.severity == Severity::Warning));
+}
+
+#[test]
+fn test_gitgraph_syntax_only_skips_semantic_checks() {
+    let code = r#"gitGraph
+    commit id: "one"
+    checkout main
+    cherry-pick id: "three""#;
+
+    assert!(!validate(code, None));
+    let result = parse(code, Some(ParseOptions::default().with_syntax_only()));
+    assert!(result.ok, "Failed: {:?}", result.diagnostics);
+    assert!(result.diagnostics.is_empty());
+    assert!(validate(code, Some(ParseOptions::default().with_syntax_only())));
+}
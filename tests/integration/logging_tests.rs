@@ -0,0 +1,51 @@
+//! Integration tests for log instrumentation.
+
+use std::sync::Mutex;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use mermaid_linter::{parse, DiagramType, MermaidConfig, ParseOptions};
+
+/// Logger that records every message so tests can assert on them.
+struct CaptureLogger;
+
+static MESSAGES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+static LOGGER: CaptureLogger = CaptureLogger;
+
+impl Log for CaptureLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Trace
+    }
+
+    fn log(&self, record: &Record) {
+        if record.target().starts_with("mermaid_linter") {
+            MESSAGES.lock().unwrap().push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+fn install_logger() {
+    // Other tests in this binary may have installed it already.
+    let _ = log::set_logger(&LOGGER);
+    log::set_max_level(LevelFilter::Trace);
+}
+
+#[test]
+fn test_logs_detection_decision_for_graph_with_dagre_wrapper() {
+    install_logger();
+
+    let mut config = MermaidConfig::default();
+    config.flowchart.default_renderer = Some("dagre-wrapper".to_string());
+    let result = parse("graph TD\n    A --> B", Some(ParseOptions::with_config(config)));
+    assert_eq!(result.diagram_type, Some(DiagramType::FlowchartV2));
+
+    let messages = MESSAGES.lock().unwrap();
+    assert!(
+        messages
+            .iter()
+            .any(|m| m.contains("'graph' matched") && m.contains("dagre-wrapper")),
+        "detection decision not logged: {:?}",
+        *messages
+    );
+}
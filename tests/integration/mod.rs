@@ -12,3 +12,9 @@ mod journey_tests;
 mod pie_tests;
 mod preprocessing_tests;
 mod detector_tests;
+mod gitgraph_tests;
+mod c4_tests;
+mod logging_tests;
+mod cli_tests;
+mod robustness_tests;
+mod render_id_tests;
@@ -1,6 +1,6 @@
 //! Integration tests for preprocessing functionality.
 
-use mermaid_linter::{parse, DiagramType};
+use mermaid_linter::{parse, ConfigSource, DiagnosticCode, DiagramType, MermaidConfig, ParseOptions};
 
 #[test]
 fn test_frontmatter_title() {
@@ -68,6 +68,35 @@ graph TD
     assert_eq!(result.config.layout, Some("elk".to_string()));
 }
 
+#[test]
+fn test_frontmatter_layout_selects_elk_flowchart() {
+    let code = r#"---
+config:
+  layout: elk
+---
+flowchart TD
+    A --> B"#;
+
+    let result = parse(code, None);
+    assert!(result.ok, "Failed to parse with elk layout: {:?}", result.diagnostics);
+    assert_eq!(result.diagram_type, Some(DiagramType::FlowchartElk));
+    assert!(result.diagnostics.is_empty());
+}
+
+#[test]
+fn test_unknown_layout_warns() {
+    let code = r#"%%{init: {"layout": "grid"}}%%
+flowchart TD
+    A --> B"#;
+
+    let result = parse(code, None);
+    assert!(result.ok, "An unknown layout should not fail the parse: {:?}", result.diagnostics);
+    assert_eq!(result.diagram_type, Some(DiagramType::FlowchartV2));
+    assert_eq!(result.diagnostics.len(), 1);
+    assert_eq!(result.diagnostics[0].code, DiagnosticCode::InvalidValue);
+    assert_eq!(result.diagnostics[0].message, "Unknown layout 'grid'");
+}
+
 #[test]
 fn test_wrap_directive() {
     let code = r#"%%{wrap}%%
@@ -79,6 +108,17 @@ graph TD
     assert!(result.config.wrap);
 }
 
+#[test]
+fn test_nowrap_directive() {
+    let code = "---\nconfig:\n  wrap: true\n---\n%%{nowrap}%%\ngraph TD\n    A --> B";
+    let result = parse(code, None);
+    assert!(result.ok, "Failed to parse with nowrap directive: {:?}", result.diagnostics);
+    assert!(!result.config.wrap);
+
+    let result = parse("%%{nowrap}%%\n%%{wrap}%%\ngraph TD\n    A --> B", None);
+    assert!(result.config.wrap);
+}
+
 #[test]
 fn test_multiple_directives() {
     let code = r#"%%{init: {"layout": "elk"}}%%
@@ -250,3 +290,79 @@ graph TD
     let result = parse(code, None);
     assert!(result.ok, "Failed to ignore unknown directive: {:?}", result.diagnostics);
 }
+
+#[test]
+fn test_well_formed_frontmatter_is_not_bad_frontmatter() {
+    let code = "---\ntitle: x\n---\ngraph TD\n    A --> B";
+
+    let result = parse(code, None);
+    assert!(result.ok, "Failed: {:?}", result.diagnostics);
+    assert_eq!(result.diagram_type, Some(DiagramType::Flowchart));
+    assert_eq!(result.title, Some("x".to_string()));
+}
+
+#[test]
+fn test_frontmatter_followed_by_directive() {
+    let code = "---\ntitle: x\n---\n%%{init: {\"theme\": \"dark\"}}%%\ngraph TD\n    A --> B";
+
+    let result = parse(code, None);
+    assert!(result.ok, "Failed: {:?}", result.diagnostics);
+    assert_eq!(result.diagram_type, Some(DiagramType::Flowchart));
+}
+
+#[test]
+fn test_frontmatter_after_blank_lines() {
+    let code = "\n\n---\ntitle: x\n---\ngraph TD\n    A --> B";
+
+    let result = parse(code, None);
+    assert!(result.ok, "Failed: {:?}", result.diagnostics);
+    assert_eq!(result.diagram_type, Some(DiagramType::Flowchart));
+    assert_eq!(result.title, Some("x".to_string()));
+}
+
+#[test]
+fn test_unterminated_frontmatter_is_bad_frontmatter() {
+    let code = "---\ntitle: x\ngraph TD\n    A --> B";
+
+    let result = parse(code, None);
+    assert!(!result.ok);
+    assert_eq!(result.diagram_type, None);
+    assert_eq!(result.diagnostics.len(), 1);
+    assert_eq!(result.diagnostics[0].code, DiagnosticCode::FrontmatterParseError);
+}
+
+#[test]
+fn test_frontmatter_must_come_before_directives() {
+    // Mermaid only recognizes frontmatter at the very start of the diagram.
+    let code = "%%{init: {\"theme\": \"dark\"}}%%\n---\ntitle: x\n---\ngraph TD\n    A --> B";
+
+    let result = parse(code, None);
+    assert!(!result.ok);
+    assert_eq!(result.diagnostics[0].code, DiagnosticCode::FrontmatterParseError);
+}
+
+#[test]
+fn test_config_trace_records_each_source_in_order() {
+    let code = "---\nconfig:\n  layout: dagre\n---\n%%{init: {\"layout\": \"elk\"}}%%\ngraph TD\n    A --> B";
+    let mut base = MermaidConfig::default();
+    base.layout = Some("dagre".to_string());
+    let options = ParseOptions::with_config(base).with_config_trace();
+    let result = parse(code, Some(options));
+    assert!(result.ok, "{:?}", result.diagnostics);
+
+    let layouts: Vec<_> = result.config_trace.iter().filter(|c| c.key == "layout").collect();
+    assert_eq!(layouts.len(), 3);
+    assert_eq!(layouts[0].source, ConfigSource::Base);
+    let ConfigSource::Frontmatter(frontmatter) = layouts[1].source else {
+        panic!("expected frontmatter, found {:?}", layouts[1].source);
+    };
+    assert_eq!(frontmatter.text(code), "---\nconfig:\n  layout: dagre\n---\n");
+    let ConfigSource::Directive(directive) = layouts[2].source else {
+        panic!("expected a directive, found {:?}", layouts[2].source);
+    };
+    assert_eq!(directive.text(code), "%%{init: {\"layout\": \"elk\"}}%%");
+    assert_eq!(layouts[2].value, "elk");
+    assert_eq!(result.config.layout.as_deref(), Some("elk"));
+
+    assert!(parse(code, None).config_trace.is_empty());
+}
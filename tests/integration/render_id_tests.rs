@@ -0,0 +1,45 @@
+//! Render ids checked against the ids Mermaid put in its rendered SVG.
+//!
+//! Each `tests/render_ids/<name>.mmd` is compared with the ids recorded from
+//! Mermaid's output in `<name>.v10.json` and `<name>.v11.json`, written by
+//! `tests/render_ids/record.mjs`. A diagram without a recording for a
+//! release is not checked for it. The render-ids CI job records both releases
+//! and runs this test with `--ignored`.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+
+use mermaid_linter::{parse, MermaidVersion, ParseOptions};
+
+#[test]
+#[ignore = "needs the ids recorded from Mermaid with tests/render_ids/record.mjs"]
+fn test_render_ids_match_recorded_svg_ids() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("render_ids");
+    let mut checked = 0;
+    for entry in fs::read_dir(&dir).expect("Failed to read render_ids directory") {
+        let path = entry.expect("Failed to read entry").path();
+        if path.extension().is_none_or(|ext| ext != "mmd") {
+            continue;
+        }
+        let code = fs::read_to_string(&path).unwrap();
+        for (version, suffix) in [(MermaidVersion::V10, "v10"), (MermaidVersion::V11, "v11")] {
+            let Ok(recorded) = fs::read_to_string(path.with_extension(format!("{}.json", suffix))) else {
+                continue;
+            };
+            let recorded: BTreeSet<String> = serde_json::from_str(&recorded).unwrap();
+
+            let result = parse(&code, Some(ParseOptions::default().with_render_ids(version)));
+            assert!(result.ok, "{}: {:?}", path.display(), result.diagnostics);
+            let mut computed = BTreeSet::new();
+            result.ast.as_ref().unwrap().walk(|node, _| {
+                if let Some(ids) = node.get_property("render_id") {
+                    computed.extend(ids.split_whitespace().map(str::to_string));
+                }
+            });
+            assert_eq!(computed, recorded, "{} ({})", path.display(), suffix);
+            checked += 1;
+        }
+    }
+    assert!(checked > 0, "no ids recorded in {}", dir.display());
+}
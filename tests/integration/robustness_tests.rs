@@ -0,0 +1,95 @@
+//! Regression corpus of malformed inputs that must neither panic nor hang.
+
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use mermaid_linter::{parse, ParseOptions};
+
+/// Inputs that once made a parser stop advancing or that exercise
+/// degenerate token sequences.
+const CORPUS: &[&str] = &[
+    // Gantt task starting with a colon
+    "gantt\n    : task",
+    "gantt\n    section S\n    : :\n    :",
+    // ER attribute list that doesn't start with an identifier
+    "erDiagram\n    A {\n        1 x\n        ( )\n    }",
+    "erDiagram\n    A { : }",
+    "erDiagram\n    A {\n        string",
+    // Statements that stop on an unexpected token
+    "graph TD\n    A --> \n    e1@{\n    --> --> -->",
+    "flowchart LR\n    subgraph\n    end end end\n    ;;;;",
+    "sequenceDiagram\n    ->>\n    loop\n    else\n    end end",
+    "classDiagram\n    namespace N {\n    }\n    }\n    class {",
+    "stateDiagram-v2\n    state A {\n    }\n    }\n    [*] -->",
+    "pie\n    : 10\n    \"a\" :",
+    "journey\n    section\n    : 5 :",
+    "gitGraph\n    commit id:\n    branch\n    merge",
+    // Flowchart links with the label written inside them, closed or not
+    "flowchart TD\n    A-. dotted .->B",
+    "flowchart TD\n    A== thick ==>B",
+    "flowchart TD\n    A -. x\n    B == y\n    C -- z",
+];
+
+#[test]
+fn test_corpus_terminates() {
+    for &code in CORPUS {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for options in [None, Some(ParseOptions::default().with_recovery())] {
+                parse(code, options);
+            }
+            let _ = tx.send(());
+        });
+        rx.recv_timeout(Duration::from_secs(5))
+            .unwrap_or_else(|_| panic!("parsing did not finish within 5s: {:?}", code));
+    }
+}
+
+#[test]
+fn test_minified_one_line_diagram() {
+    // Over 100k characters with no newline and one bad statement
+    let code = format!("graph TD;{}X==>>;{}", "A-->B;".repeat(10_000), "C-->D;".repeat(7_000));
+    assert!(code.len() > 100_000);
+
+    let result = parse(&code, Some(ParseOptions::default().with_recovery()));
+    assert_eq!(result.diagnostics.len(), 1, "{:?}", result.diagnostics);
+    let diagnostic = &result.diagnostics[0];
+    assert_eq!(diagnostic.message, "Unable to parse statement '>'");
+    // Recovery stops at the next semicolon, so the statements after the
+    // error are still parsed
+    assert_eq!(result.metrics().edges, 17_001);
+
+    let formatted = diagnostic.format(&code);
+    assert!(formatted.len() < 400, "{}", formatted);
+    assert!(formatted.contains("X==>>;C-->D"), "{}", formatted);
+}
+
+#[test]
+fn test_formatting_many_diagnostics_on_one_long_line() {
+    use mermaid_linter::{Diagnostic, DiagnosticCode, LineIndex, Span};
+    use std::time::Instant;
+
+    let code = format!("graph TD;{}", "A-->B;".repeat(175_000));
+    assert!(code.len() > 1_000_000);
+    let diagnostics: Vec<Diagnostic> = (0..100)
+        .map(|i| {
+            let start = 9 + i * 6 * 1_750;
+            Diagnostic::error(DiagnosticCode::InvalidSyntax, "bad link", Span::new(start + 1, start + 4))
+        })
+        .collect();
+
+    let started = Instant::now();
+    let index = LineIndex::new(&code);
+    let formatted: Vec<String> = diagnostics.iter().map(|d| d.format_with_index(&code, &index)).collect();
+    assert!(started.elapsed() < Duration::from_secs(2), "took {:?}", started.elapsed());
+
+    for (i, text) in formatted.iter().enumerate() {
+        assert!(text.len() < 400, "{}", text);
+        assert!(text.contains(&format!("--> 1:{}", 9 + i * 6 * 1_750 + 2)), "{}", text);
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[3].ends_with("..."), "{}", lines[3]);
+        let caret = lines[4].find('^').unwrap();
+        assert_eq!(&lines[3][caret..caret + 4], "-->B");
+    }
+}
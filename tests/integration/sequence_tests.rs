@@ -1,6 +1,6 @@
 //! Integration tests for sequence diagrams.
 
-use mermaid_linter::{parse, detect_type, DiagramType};
+use mermaid_linter::{parse, detect_type, AstNode, DiagnosticCode, DiagramType, ParseResult, Severity};
 
 #[test]
 fn test_simple_sequence() {
@@ -141,7 +141,6 @@ fn test_sequence_opt() {
 }
 
 #[test]
-#[ignore = "par/and block syntax not yet implemented"]
 fn test_sequence_par() {
     let code = r#"sequenceDiagram
     Alice->>Bob: Hello
@@ -153,10 +152,13 @@ fn test_sequence_par() {
 
     let result = parse(code, None);
     assert!(result.ok, "Failed to parse sequence par: {:?}", result.diagnostics);
+    assert!(result.diagnostics.is_empty(), "{:?}", result.diagnostics);
+    let branch = &result.ast.as_ref().unwrap().root.children[4];
+    assert_eq!(branch.get_property("type"), Some("and"));
+    assert_eq!(branch.get_property("label"), Some("Parallel 2"));
 }
 
 #[test]
-#[ignore = "critical/option block syntax not yet implemented"]
 fn test_sequence_critical() {
     let code = r#"sequenceDiagram
     Alice->>Bob: Request
@@ -169,6 +171,10 @@ fn test_sequence_critical() {
 
     let result = parse(code, None);
     assert!(result.ok, "Failed to parse sequence critical: {:?}", result.diagnostics);
+    assert!(result.diagnostics.is_empty(), "{:?}", result.diagnostics);
+    let branch = &result.ast.as_ref().unwrap().root.children[5];
+    assert_eq!(branch.get_property("type"), Some("option"));
+    assert_eq!(branch.get_property("label"), Some("Timeout"));
 }
 
 #[test]
@@ -197,6 +203,30 @@ fn test_sequence_rect() {
     assert!(result.ok, "Failed to parse sequence rect: {:?}", result.diagnostics);
 }
 
+#[test]
+fn test_sequence_nested_rect_colors() {
+    let code = r#"sequenceDiagram
+    rect #ffcc00
+        Alice->>Bob: Hello
+        rect hsl(120, 50%, 50%)
+            Bob->>Charlie: Forward
+            rect hsla(200, 40%, 60%, 0.5)
+                Charlie-->>Bob: Reply
+            end
+        end
+    end
+    Bob-->>Alice: Response"#;
+
+    let result = parse(code, None);
+    assert!(result.ok, "Failed to parse nested rect: {:?}", result.diagnostics);
+    assert!(result.diagnostics.is_empty(), "{:?}", result.diagnostics);
+    let outer = &result.ast.as_ref().unwrap().root.children[1];
+    assert_eq!(outer.get_property("color"), Some("#ffcc00"));
+    let inner = &outer.children[1];
+    assert_eq!(inner.get_property("color"), Some("hsl(120, 50%, 50%)"));
+    assert_eq!(inner.children[1].get_property("color"), Some("hsla(200, 40%, 60%, 0.5)"));
+}
+
 #[test]
 fn test_sequence_autonumber() {
     let code = r#"sequenceDiagram
@@ -299,3 +329,236 @@ fn test_sequence_box() {
     let result = parse(code, None);
     assert!(result.ok, "Failed to parse sequence box: {:?}", result.diagnostics);
 }
+
+fn box_node(result: &ParseResult) -> &AstNode {
+    result
+        .ast
+        .as_ref()
+        .unwrap()
+        .root
+        .children
+        .iter()
+        .find(|c| c.get_property("type") == Some("box"))
+        .expect("box node")
+}
+
+#[test]
+fn test_sequence_box_colors() {
+    let cases = [
+        ("box Purple", Some("Purple"), ""),
+        ("box transparent Internal", Some("transparent"), "Internal"),
+        ("box rgb(33,66,99) Group name", Some("rgb(33,66,99)"), "Group name"),
+        ("box rgba(33, 66, 99, 0.5) Group", Some("rgba(33, 66, 99, 0.5)"), "Group"),
+        ("box Backend services", None, "Backend services"),
+    ];
+
+    for (header, color, label) in cases {
+        let code = format!("sequenceDiagram\n    {}\n        participant Alice\n    end", header);
+        let result = parse(&code, None);
+        assert!(result.ok, "Failed to parse '{}': {:?}", header, result.diagnostics);
+
+        let node = box_node(&result);
+        assert_eq!(node.get_property("color"), color, "color of '{}'", header);
+        assert_eq!(node.get_property("label"), Some(label), "label of '{}'", header);
+        assert_eq!(node.children.len(), 1);
+    }
+}
+
+#[test]
+fn test_sequence_box_label_starting_with_color_name() {
+    // Like Mermaid, a leading word that is a color is taken as the color
+    let code = r#"sequenceDiagram
+    box Red Team Members
+        participant Alice
+    end"#;
+
+    let result = parse(code, None);
+    assert!(result.ok);
+    let node = box_node(&result);
+    assert_eq!(node.get_property("color"), Some("Red"));
+    assert_eq!(node.get_property("label"), Some("Team Members"));
+}
+
+#[test]
+fn test_sequence_box_rgb_out_of_range_warns() {
+    let code = r#"sequenceDiagram
+    box rgb(300,66,99) Group
+        participant Alice
+    end"#;
+
+    let result = parse(code, None);
+    assert!(result.ok);
+    assert_eq!(result.diagnostics.len(), 1);
+    assert_eq!(result.diagnostics[0].severity, Severity::Warning);
+    assert_eq!(result.diagnostics[0].span.text(code), "rgb(300,66,99)");
+}
+
+#[test]
+fn test_sequence_box_rgb_wrong_argument_count() {
+    let code = r#"sequenceDiagram
+    box rgb(33,66) Group
+        participant Alice
+    end"#;
+
+    let result = parse(code, None);
+    assert!(!result.ok);
+    assert_eq!(result.diagnostics[0].code, DiagnosticCode::InvalidValue);
+}
+
+#[test]
+fn test_sequence_actor_via_metadata() {
+    let code = r#"sequenceDiagram
+    participant U@{ "type": "actor" }
+    participant S@{ "type": "collections" }
+    U->>S: Browse"#;
+
+    let result = parse(code, None);
+    assert!(result.ok, "Failed to parse participant metadata: {:?}", result.diagnostics);
+    let ast = result.ast.unwrap();
+    let types: Vec<_> = ast
+        .root
+        .children
+        .iter()
+        .filter_map(|c| c.get_property("metadata").and(c.get_property("type")))
+        .collect();
+    assert_eq!(types, ["actor", "collections"]);
+
+    let result = parse("sequenceDiagram\n    participant U@{ \"type\": actor }", None);
+    assert_eq!(result.diagnostics[0].code, DiagnosticCode::DirectiveJsonError);
+}
+
+#[test]
+fn test_message_split_across_lines() {
+    let code = "sequenceDiagram\n    Alice->>+\n    Bob: hi";
+    let result = parse(code, None);
+    assert!(!result.ok);
+    let diagnostic = &result.diagnostics[0];
+    assert_eq!(diagnostic.code, DiagnosticCode::UnexpectedEof);
+    assert_eq!(
+        diagnostic.message,
+        "Message is missing its receiver; did you mean to continue on the same line?"
+    );
+    assert_eq!(diagnostic.span.text(code), "->>+");
+    let joined = mermaid_linter::fix::apply_edits(code, &diagnostic.fixes).unwrap();
+    assert_eq!(joined, "sequenceDiagram\n    Alice->>+ Bob: hi");
+    assert!(parse(&joined, None).ok);
+
+    let result = parse("sequenceDiagram\n    Alice->>", None);
+    assert_eq!(result.diagnostics.len(), 1);
+    assert_eq!(result.diagnostics[0].code, DiagnosticCode::UnexpectedEof);
+}
+
+#[test]
+fn test_create_statements() {
+    let code = "sequenceDiagram\n    Alice->>Bob: hi\n    create participant Carl\n    Alice->>Carl: hi";
+    let result = parse(code, None);
+    assert!(result.ok);
+    assert!(result.diagnostics.is_empty(), "{:?}", result.diagnostics);
+    let carl = &result.ast.unwrap().root.children[2];
+    assert_eq!(carl.get_property("created"), Some("true"));
+    assert_eq!(carl.span.text(code), "create participant Carl");
+
+    let result = parse("sequenceDiagram\n    create\n    Alice->>Bob: hi", None);
+    assert!(!result.ok);
+    assert_eq!(result.diagnostics[0].code, DiagnosticCode::MissingElement);
+    assert_eq!(result.diagnostics[0].message, "'create' must be followed by the participant or actor it creates");
+
+    let code = "sequenceDiagram\n    create Carl\n    Alice->>Carl: hi";
+    let result = parse(code, None);
+    assert!(result.ok);
+    assert_eq!(result.diagnostics[0].severity, Severity::Warning);
+    let fixed = mermaid_linter::fix::apply_edits(code, &result.diagnostics[0].fixes).unwrap();
+    assert_eq!(fixed, "sequenceDiagram\n    create participant Carl\n    Alice->>Carl: hi");
+}
+
+#[test]
+fn test_wrap_config_reaches_messages() {
+    let fixture = include_str!("../fixtures/sequence/simple.mmd");
+    let wraps = |directive: &str| {
+        let result = parse(&format!("{}\n{}", directive, fixture), None);
+        assert!(result.ok, "{:?}", result.diagnostics);
+        let mut wraps = Vec::new();
+        result.ast.unwrap().walk(|node, _| {
+            if node.kind == mermaid_linter::ast::NodeKind::Message {
+                wraps.push(node.get_property("wrap").map(str::to_string));
+            }
+        });
+        wraps
+    };
+    let on = wraps("%%{wrap}%%");
+    assert!(!on.is_empty());
+    assert!(on.iter().all(|w| w.as_deref() == Some("true")));
+    assert!(wraps("%%{nowrap}%%").iter().all(Option::is_none));
+
+    let result = parse("%%{wrap}%%\nsequenceDiagram\n    A->>B: nowrap:short\n    A->>B: :wrap: long text", None);
+    let ast = result.ast.unwrap();
+    let messages = &ast.root.children[1..];
+    assert_eq!(messages[0].get_property("text"), Some("short"));
+    assert_eq!(messages[0].get_property("wrap"), Some("false"));
+    assert_eq!(messages[1].get_property("text"), Some("long text"));
+    assert_eq!(messages[1].get_property("wrap"), Some("true"));
+}
+
+#[test]
+fn test_unused_participant_warnings() {
+    let options = || Some(mermaid_linter::ParseOptions::default().with_unused_participant_warnings());
+    let code = "sequenceDiagram\n    participant Api\n    participant Db\n    participant Cache\n    actor User\n    User->>Api: get\n    Note over Cache: warm";
+    let result = parse(code, options());
+    assert!(result.ok);
+    assert_eq!(result.diagnostics.len(), 1, "{:?}", result.diagnostics);
+    let diagnostic = &result.diagnostics[0];
+    assert_eq!(diagnostic.severity, Severity::Warning);
+    assert_eq!(diagnostic.message, "Participant 'Db' never sends or receives a message");
+    assert_eq!(diagnostic.span.text(code), "participant Db");
+    assert!(parse(code, None).diagnostics.is_empty());
+}
+
+#[test]
+fn test_diagnostics_are_sorted_by_position() {
+    let code = include_str!("../fixtures/sequence/background_highlight_broken.mmd");
+    let result = parse(code, None);
+    let found: Vec<(usize, &str)> = result
+        .diagnostics
+        .iter()
+        .map(|d| (d.range.unwrap().start.line, d.message.as_str()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (5, "Missing 'end' for 'rect'"),
+            (5, "Missing ')' in 'rgb(191, 223, 255'"),
+            (7, "rgb() expects 3 arguments, found 2"),
+        ]
+    );
+    assert!(result.diagnostics.windows(2).all(|pair| pair[0].span.start <= pair[1].span.start));
+}
+
+#[test]
+fn test_unknown_statement_is_skipped_with_a_warning() {
+    let code = "sequenceDiagram\n    Alice->>Bob: Hi\n    frobnicate Alice with Bob\n    details Alice: Team lead\n    Bob->>Alice: Hello\n    ->> Bob: oops";
+    let result = parse(code, None);
+    assert!(!result.ok);
+    let messages: Vec<_> = result.diagnostics.iter().map(|d| (d.severity, d.message.as_str())).collect();
+    assert_eq!(
+        messages[..2],
+        [
+            (
+                Severity::Warning,
+                "Unknown statement 'frobnicate' is not supported by this version of mermaid-linter; the statement is skipped"
+            ),
+            (
+                Severity::Warning,
+                "'details' statements are not supported by this version of mermaid-linter; the statement is skipped"
+            ),
+        ]
+    );
+    // The lines after the skipped statements are still parsed and checked
+    assert_eq!(messages.len(), 3);
+    assert_eq!(messages[2].0, Severity::Error);
+
+    let result = parse("sequenceDiagram\n    frobnicate Alice\n    Alice->>Bob: Hi", None);
+    assert!(result.ok);
+    assert_eq!(result.diagnostics.len(), 1);
+    let root = &result.ast.as_ref().unwrap().root;
+    assert_eq!(root.children.iter().filter(|c| c.kind == mermaid_linter::ast::NodeKind::Message).count(), 1);
+}
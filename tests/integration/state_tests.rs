@@ -277,3 +277,58 @@ fn test_state_escaping() {
     let result = parse(code, None);
     assert!(result.ok, "Failed to parse escaped characters: {:?}", result.diagnostics);
 }
+
+#[test]
+fn test_composite_state_direction_scope() {
+    let code = r#"stateDiagram-v2
+    direction TB
+    state Outer {
+        direction LR
+        state Inner {
+            direction BT
+            [*] --> X
+        }
+        [*] --> Inner
+    }
+    [*] --> Outer"#;
+
+    let result = parse(code, None);
+    assert!(result.ok, "Failed to parse: {:?}", result.diagnostics);
+    assert!(result.diagnostics.is_empty());
+
+    let root = &result.ast.as_ref().unwrap().root;
+    let outer = root.children.iter().find(|c| c.get_property("id") == Some("Outer")).unwrap();
+    let inner = outer.children.iter().find(|c| c.get_property("id") == Some("Inner")).unwrap();
+    assert_eq!(root.get_property("direction"), Some("TB"));
+    assert_eq!(outer.get_property("direction"), Some("LR"));
+    assert_eq!(inner.get_property("direction"), Some("BT"));
+}
+
+#[test]
+fn test_unknown_statement_is_skipped_with_a_warning() {
+    let code = r#"stateDiagram-v2
+    [*] --> Idle
+    classDef hot fill:#f00
+    frobnicate Idle
+    Idle --> Busy : start
+    Busy --> [*]"#;
+
+    let result = parse(code, None);
+    assert!(result.ok, "Failed to parse: {:?}", result.diagnostics);
+    let messages: Vec<_> = result.diagnostics.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        [
+            "'classDef' statements are not supported by this version of mermaid-linter; the statement is skipped",
+            "Unknown statement 'frobnicate' is not supported by this version of mermaid-linter; the statement is skipped",
+        ]
+    );
+    let root = &result.ast.as_ref().unwrap().root;
+    let transitions: Vec<_> = root
+        .children
+        .iter()
+        .filter(|c| c.kind == mermaid_linter::ast::NodeKind::Transition)
+        .map(|c| (c.get_property("from").unwrap(), c.get_property("to").unwrap()))
+        .collect();
+    assert_eq!(transitions, [("[*]", "Idle"), ("Idle", "Busy"), ("Busy", "[*]")]);
+}
@@ -0,0 +1,43 @@
+// Records the element ids Mermaid renders for each diagram in this
+// directory, as `<name>.v<major>.json`, for the render id tests in
+// tests/integration/render_id_tests.rs.
+//
+// Run it with the Mermaid CLI of the release to record, e.g.
+//
+//     npx -p @mermaid-js/mermaid-cli@11 node tests/render_ids/record.mjs 11
+//
+// A diagram that release cannot render, such as edge ids before v11, is
+// skipped. The render id CI job runs it for both releases before the render
+// id tests.
+import { execFileSync } from "node:child_process";
+import { mkdtempSync, readFileSync, readdirSync, writeFileSync } from "node:fs";
+import { tmpdir } from "node:os";
+import { dirname, join } from "node:path";
+import { fileURLToPath } from "node:url";
+
+const major = process.argv[2];
+if (!["10", "11"].includes(major)) {
+  console.error("usage: record.mjs <10|11>");
+  process.exit(2);
+}
+
+const dir = dirname(fileURLToPath(import.meta.url));
+const out = mkdtempSync(join(tmpdir(), "render-ids-"));
+// CI runners don't allow Chrome's sandbox
+const puppeteerConfig = join(out, "puppeteer.json");
+writeFileSync(puppeteerConfig, JSON.stringify({ args: ["--no-sandbox"] }));
+// Node, link and actor ids, less the `svg-` prefix of the SVG's own id
+const ID = /\bid="(?:svg-)?((?:flowchart-|L[-_]|actor)[^"]*)"/g;
+
+for (const file of readdirSync(dir).filter((f) => f.endsWith(".mmd")).sort()) {
+  const svg = join(out, file.replace(/\.mmd$/, ".svg"));
+  try {
+    execFileSync("mmdc", ["--input", join(dir, file), "--output", svg, "--svgId", "svg", "--puppeteerConfigFile", puppeteerConfig], { stdio: "pipe" });
+  } catch (e) {
+    console.warn(`${file}: skipped, Mermaid ${major} cannot render it`);
+    continue;
+  }
+  const ids = [...new Set([...readFileSync(svg, "utf8").matchAll(ID)].map((m) => m[1]))];
+  writeFileSync(join(dir, file.replace(/\.mmd$/, `.v${major}.json`)), JSON.stringify(ids, null, 2) + "\n");
+  console.log(`${file}: ${ids.length} ids`);
+}